@@ -0,0 +1 @@
+ALTER TABLE budgets DROP COLUMN alert_thresholds;
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE budgets
+    ADD COLUMN alert_thresholds SMALLINT[] NOT NULL DEFAULT '{80,100}';
@@ -0,0 +1,3 @@
+ALTER TABLE notifications DROP CONSTRAINT fk_notification_user_id;
+ALTER TABLE notifications DROP CONSTRAINT fk_notification_budget_id;
+DROP TABLE notifications;
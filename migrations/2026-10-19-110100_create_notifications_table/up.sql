@@ -0,0 +1,19 @@
+CREATE TABLE notifications
+(
+    notification_id UUID         NOT NULL UNIQUE PRIMARY KEY,
+    user_id         UUID         NOT NULL,
+    budget_id       UUID         NULL     DEFAULT NULL,
+    kind            VARCHAR(50)  NOT NULL,
+    title           VARCHAR(150) NOT NULL,
+    message         VARCHAR(500) NOT NULL,
+    read_at         TIMESTAMP    NULL     DEFAULT NULL,
+    created_at      TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at      TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at      TIMESTAMP    NULL     DEFAULT NULL
+);
+
+ALTER TABLE notifications
+    ADD CONSTRAINT fk_notification_user_id FOREIGN KEY (user_id) REFERENCES users (user_id);
+
+ALTER TABLE notifications
+    ADD CONSTRAINT fk_notification_budget_id FOREIGN KEY (budget_id) REFERENCES budgets (budget_id);
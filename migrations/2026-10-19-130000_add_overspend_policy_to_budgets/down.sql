@@ -0,0 +1,2 @@
+ALTER TABLE budgets DROP COLUMN over_limit_at;
+ALTER TABLE budgets DROP COLUMN overspend_policy;
@@ -0,0 +1,5 @@
+ALTER TABLE budgets
+    ADD COLUMN overspend_policy VARCHAR(20) NOT NULL DEFAULT 'reject';
+
+ALTER TABLE budgets
+    ADD COLUMN over_limit_at TIMESTAMP NULL DEFAULT NULL;
@@ -0,0 +1,4 @@
+ALTER TABLE expense_labels DROP CONSTRAINT fk_expense_label_user_id;
+ALTER TABLE expense_labels DROP CONSTRAINT fk_expense_label_label_id;
+ALTER TABLE expense_labels DROP CONSTRAINT fk_expense_label_expense_id;
+DROP TABLE expense_labels;
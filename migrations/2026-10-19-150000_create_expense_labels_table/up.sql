@@ -0,0 +1,19 @@
+CREATE TABLE expense_labels
+(
+    expense_label_id UUID      NOT NULL UNIQUE PRIMARY KEY,
+    user_id          UUID      NOT NULL,
+    expense_id       UUID      NOT NULL,
+    label_id         UUID      NOT NULL,
+    created_at       TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at       TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at       TIMESTAMP          DEFAULT NULL
+);
+
+ALTER TABLE expense_labels
+    ADD CONSTRAINT fk_expense_label_user_id FOREIGN KEY (user_id) REFERENCES users (user_id);
+
+ALTER TABLE expense_labels
+    ADD CONSTRAINT fk_expense_label_expense_id FOREIGN KEY (expense_id) REFERENCES expenses (expense_id);
+
+ALTER TABLE expense_labels
+    ADD CONSTRAINT fk_expense_label_label_id FOREIGN KEY (label_id) REFERENCES labels (label_id);
@@ -0,0 +1,5 @@
+ALTER TABLE expenses DROP CONSTRAINT fk_expense_category_id;
+ALTER TABLE expenses DROP COLUMN category_id;
+ALTER TABLE categories DROP CONSTRAINT fk_category_parent_id;
+ALTER TABLE categories DROP CONSTRAINT fk_category_user_id;
+DROP TABLE categories;
\ No newline at end of file
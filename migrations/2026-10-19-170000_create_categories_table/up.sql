@@ -0,0 +1,64 @@
+CREATE TABLE categories
+(
+    category_id UUID         NOT NULL UNIQUE PRIMARY KEY,
+    user_id     UUID         NULL     DEFAULT NULL,
+    parent_id   UUID         NULL     DEFAULT NULL,
+    name        VARCHAR(150) NOT NULL,
+    created_at  TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at  TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at  TIMESTAMP    NULL     DEFAULT NULL
+);
+
+ALTER TABLE categories
+    ADD CONSTRAINT fk_category_user_id FOREIGN KEY (user_id) REFERENCES users (user_id);
+
+ALTER TABLE categories
+    ADD CONSTRAINT fk_category_parent_id FOREIGN KEY (parent_id) REFERENCES categories (category_id);
+
+ALTER TABLE expenses
+    ADD COLUMN category_id UUID NULL DEFAULT NULL;
+
+ALTER TABLE expenses
+    ADD CONSTRAINT fk_expense_category_id FOREIGN KEY (category_id) REFERENCES categories (category_id);
+
+-- default taxonomy shared by every user (categories without an owner)
+INSERT INTO categories (category_id, parent_id, name)
+VALUES
+       ('00000000-0000-4000-8000-000000000001', NULL, 'Food'),
+       ('00000000-0000-4000-8000-000000000002', '00000000-0000-4000-8000-000000000001', 'Groceries'),
+       ('00000000-0000-4000-8000-000000000003', '00000000-0000-4000-8000-000000000001', 'Restaurants'),
+       ('00000000-0000-4000-8000-000000000004', '00000000-0000-4000-8000-000000000001', 'Coffee & Snacks'),
+       ('00000000-0000-4000-8000-000000000005', NULL, 'Transport'),
+       ('00000000-0000-4000-8000-000000000006', '00000000-0000-4000-8000-000000000005', 'Fuel'),
+       ('00000000-0000-4000-8000-000000000007', '00000000-0000-4000-8000-000000000005', 'Public Transport'),
+       ('00000000-0000-4000-8000-000000000008', '00000000-0000-4000-8000-000000000005', 'Taxi & Ride Sharing'),
+       ('00000000-0000-4000-8000-000000000009', '00000000-0000-4000-8000-000000000005', 'Vehicle Maintenance'),
+       ('00000000-0000-4000-8000-000000000010', NULL, 'Housing'),
+       ('00000000-0000-4000-8000-000000000011', '00000000-0000-4000-8000-000000000010', 'Rent'),
+       ('00000000-0000-4000-8000-000000000012', '00000000-0000-4000-8000-000000000010', 'Utilities'),
+       ('00000000-0000-4000-8000-000000000013', '00000000-0000-4000-8000-000000000010', 'Internet & Phone'),
+       ('00000000-0000-4000-8000-000000000014', '00000000-0000-4000-8000-000000000010', 'Repairs'),
+       ('00000000-0000-4000-8000-000000000015', NULL, 'Health'),
+       ('00000000-0000-4000-8000-000000000016', '00000000-0000-4000-8000-000000000015', 'Medical'),
+       ('00000000-0000-4000-8000-000000000017', '00000000-0000-4000-8000-000000000015', 'Pharmacy'),
+       ('00000000-0000-4000-8000-000000000018', '00000000-0000-4000-8000-000000000015', 'Fitness'),
+       ('00000000-0000-4000-8000-000000000019', NULL, 'Entertainment'),
+       ('00000000-0000-4000-8000-000000000020', '00000000-0000-4000-8000-000000000019', 'Subscriptions'),
+       ('00000000-0000-4000-8000-000000000021', '00000000-0000-4000-8000-000000000019', 'Events'),
+       ('00000000-0000-4000-8000-000000000022', '00000000-0000-4000-8000-000000000019', 'Hobbies'),
+       ('00000000-0000-4000-8000-000000000023', NULL, 'Shopping'),
+       ('00000000-0000-4000-8000-000000000024', '00000000-0000-4000-8000-000000000023', 'Clothing'),
+       ('00000000-0000-4000-8000-000000000025', '00000000-0000-4000-8000-000000000023', 'Electronics'),
+       ('00000000-0000-4000-8000-000000000026', '00000000-0000-4000-8000-000000000023', 'Household'),
+       ('00000000-0000-4000-8000-000000000027', NULL, 'Education'),
+       ('00000000-0000-4000-8000-000000000028', '00000000-0000-4000-8000-000000000027', 'Tuition'),
+       ('00000000-0000-4000-8000-000000000029', '00000000-0000-4000-8000-000000000027', 'Books & Courses'),
+       ('00000000-0000-4000-8000-000000000030', NULL, 'Financial'),
+       ('00000000-0000-4000-8000-000000000031', '00000000-0000-4000-8000-000000000030', 'Fees & Charges'),
+       ('00000000-0000-4000-8000-000000000032', '00000000-0000-4000-8000-000000000030', 'Insurance'),
+       ('00000000-0000-4000-8000-000000000033', '00000000-0000-4000-8000-000000000030', 'Taxes'),
+       ('00000000-0000-4000-8000-000000000034', NULL, 'Gifts & Donations'),
+       ('00000000-0000-4000-8000-000000000035', NULL, 'Travel'),
+       ('00000000-0000-4000-8000-000000000036', '00000000-0000-4000-8000-000000000035', 'Flights'),
+       ('00000000-0000-4000-8000-000000000037', '00000000-0000-4000-8000-000000000035', 'Accommodation'),
+       ('00000000-0000-4000-8000-000000000038', NULL, 'Other');
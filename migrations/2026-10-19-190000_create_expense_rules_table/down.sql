@@ -0,0 +1,3 @@
+ALTER TABLE expense_rules DROP CONSTRAINT fk_expense_rule_category_id;
+ALTER TABLE expense_rules DROP CONSTRAINT fk_expense_rule_user_id;
+DROP TABLE expense_rules;
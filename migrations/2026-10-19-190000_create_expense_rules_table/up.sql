@@ -0,0 +1,22 @@
+CREATE TABLE expense_rules
+(
+    expense_rule_id    UUID         NOT NULL UNIQUE PRIMARY KEY,
+    user_id            UUID         NOT NULL,
+    name               VARCHAR(150) NOT NULL,
+    priority           INTEGER      NOT NULL DEFAULT 0,
+    narration_contains VARCHAR(250) NULL     DEFAULT NULL,
+    narration_pattern  VARCHAR(250) NULL     DEFAULT NULL,
+    min_amount         BIGINT       NULL     DEFAULT NULL,
+    max_amount         BIGINT       NULL     DEFAULT NULL,
+    category_id        UUID         NULL     DEFAULT NULL,
+    label_ids          UUID[]       NOT NULL DEFAULT '{}',
+    created_at         TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at         TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at         TIMESTAMP    NULL     DEFAULT NULL
+);
+
+ALTER TABLE expense_rules
+    ADD CONSTRAINT fk_expense_rule_user_id FOREIGN KEY (user_id) REFERENCES users (user_id);
+
+ALTER TABLE expense_rules
+    ADD CONSTRAINT fk_expense_rule_category_id FOREIGN KEY (category_id) REFERENCES categories (category_id);
@@ -0,0 +1,4 @@
+ALTER TABLE expenses DROP CONSTRAINT fk_expense_import_id;
+ALTER TABLE expenses DROP COLUMN import_id;
+ALTER TABLE imports DROP CONSTRAINT fk_import_user_id;
+DROP TABLE imports;
@@ -0,0 +1,22 @@
+CREATE TABLE imports
+(
+    import_id    UUID         NOT NULL UNIQUE PRIMARY KEY,
+    user_id      UUID         NOT NULL,
+    source       VARCHAR(20)  NOT NULL DEFAULT 'csv',
+    file_name    VARCHAR(250) NOT NULL,
+    content      TEXT         NOT NULL,
+    status       VARCHAR(20)  NOT NULL DEFAULT 'pending',
+    committed_at TIMESTAMP    NULL     DEFAULT NULL,
+    created_at   TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at   TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at   TIMESTAMP    NULL     DEFAULT NULL
+);
+
+ALTER TABLE imports
+    ADD CONSTRAINT fk_import_user_id FOREIGN KEY (user_id) REFERENCES users (user_id);
+
+ALTER TABLE expenses
+    ADD COLUMN import_id UUID NULL DEFAULT NULL;
+
+ALTER TABLE expenses
+    ADD CONSTRAINT fk_expense_import_id FOREIGN KEY (import_id) REFERENCES imports (import_id);
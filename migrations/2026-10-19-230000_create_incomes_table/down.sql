@@ -0,0 +1,5 @@
+DROP INDEX uq_income_external_id;
+ALTER TABLE incomes DROP CONSTRAINT fk_income_import_id;
+ALTER TABLE incomes DROP CONSTRAINT fk_income_project_id;
+ALTER TABLE incomes DROP CONSTRAINT fk_income_user_id;
+DROP TABLE incomes;
@@ -0,0 +1,27 @@
+CREATE TABLE incomes
+(
+    income_id   UUID         NOT NULL UNIQUE PRIMARY KEY,
+    user_id     UUID         NOT NULL,
+    project_id  UUID         NOT NULL,
+    amount      BIGINT       NOT NULL,
+    narration   VARCHAR(250) NOT NULL,
+    received_at TIMESTAMP    NOT NULL,
+    import_id   UUID         NULL     DEFAULT NULL,
+    external_id VARCHAR(250) NULL     DEFAULT NULL,
+    external_account VARCHAR(250) NULL DEFAULT NULL,
+    created_at  TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at  TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at  TIMESTAMP    NULL     DEFAULT NULL
+);
+
+ALTER TABLE incomes
+    ADD CONSTRAINT fk_income_user_id FOREIGN KEY (user_id) REFERENCES users (user_id);
+
+ALTER TABLE incomes
+    ADD CONSTRAINT fk_income_project_id FOREIGN KEY (project_id) REFERENCES projects (project_id);
+
+ALTER TABLE incomes
+    ADD CONSTRAINT fk_income_import_id FOREIGN KEY (import_id) REFERENCES imports (import_id);
+
+-- a bank transaction (FITID) can only be imported once per user and bank account (ACCTID)
+CREATE UNIQUE INDEX uq_income_external_id ON incomes (user_id, COALESCE(external_account, ''), external_id) WHERE external_id IS NOT NULL;
@@ -0,0 +1,3 @@
+ALTER TABLE bank_accounts DROP CONSTRAINT fk_bank_account_project_id;
+ALTER TABLE bank_accounts DROP CONSTRAINT fk_bank_account_user_id;
+DROP TABLE bank_accounts;
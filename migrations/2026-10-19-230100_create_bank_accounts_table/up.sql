@@ -0,0 +1,17 @@
+CREATE TABLE bank_accounts
+(
+    bank_account_id UUID         NOT NULL UNIQUE PRIMARY KEY,
+    user_id         UUID         NOT NULL,
+    project_id      UUID         NOT NULL,
+    account_number  VARCHAR(100) NOT NULL,
+    name            VARCHAR(150) NOT NULL,
+    created_at      TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at      TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at      TIMESTAMP    NULL     DEFAULT NULL
+);
+
+ALTER TABLE bank_accounts
+    ADD CONSTRAINT fk_bank_account_user_id FOREIGN KEY (user_id) REFERENCES users (user_id);
+
+ALTER TABLE bank_accounts
+    ADD CONSTRAINT fk_bank_account_project_id FOREIGN KEY (project_id) REFERENCES projects (project_id);
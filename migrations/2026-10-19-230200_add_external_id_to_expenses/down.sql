@@ -0,0 +1,3 @@
+DROP INDEX uq_expense_external_id;
+ALTER TABLE expenses DROP COLUMN external_account;
+ALTER TABLE expenses DROP COLUMN external_id;
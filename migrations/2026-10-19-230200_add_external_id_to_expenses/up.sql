@@ -0,0 +1,7 @@
+ALTER TABLE expenses
+    ADD COLUMN external_id VARCHAR(250) NULL DEFAULT NULL,
+    ADD COLUMN external_account VARCHAR(250) NULL DEFAULT NULL;
+
+-- a bank transaction (FITID) can only be imported once per user and bank account (ACCTID),
+-- transaction ids are only unique within the account that issued them
+CREATE UNIQUE INDEX uq_expense_external_id ON expenses (user_id, COALESCE(external_account, ''), external_id) WHERE external_id IS NOT NULL;
@@ -0,0 +1,3 @@
+ALTER TABLE attachments DROP CONSTRAINT fk_attachment_expense_id;
+ALTER TABLE attachments DROP CONSTRAINT fk_attachment_user_id;
+DROP TABLE attachments;
@@ -0,0 +1,20 @@
+CREATE TABLE attachments
+(
+    attachment_id UUID         NOT NULL UNIQUE PRIMARY KEY,
+    user_id       UUID         NOT NULL,
+    expense_id    UUID         NOT NULL,
+    file_name     VARCHAR(250) NOT NULL,
+    content_type  VARCHAR(100) NOT NULL,
+    size          BIGINT       NOT NULL,
+    storage_key   VARCHAR(250) NOT NULL,
+    thumbnail_key VARCHAR(250) NULL     DEFAULT NULL,
+    created_at    TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at    TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at    TIMESTAMP    NULL     DEFAULT NULL
+);
+
+ALTER TABLE attachments
+    ADD CONSTRAINT fk_attachment_user_id FOREIGN KEY (user_id) REFERENCES users (user_id);
+
+ALTER TABLE attachments
+    ADD CONSTRAINT fk_attachment_expense_id FOREIGN KEY (expense_id) REFERENCES expenses (expense_id);
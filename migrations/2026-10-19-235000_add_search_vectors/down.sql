@@ -0,0 +1,7 @@
+DROP INDEX idx_expenses_search_vector;
+DROP INDEX idx_budgets_search_vector;
+DROP INDEX idx_projects_search_vector;
+
+ALTER TABLE expenses DROP COLUMN search_vector;
+ALTER TABLE budgets DROP COLUMN search_vector;
+ALTER TABLE projects DROP COLUMN search_vector;
@@ -0,0 +1,21 @@
+-- weighted so that a match in a name or title ranks above one in a description or comment
+ALTER TABLE projects
+    ADD COLUMN search_vector TSVECTOR GENERATED ALWAYS AS (
+        setweight(to_tsvector('english', name), 'A') ||
+        setweight(to_tsvector('english', description), 'B')
+        ) STORED;
+
+ALTER TABLE budgets
+    ADD COLUMN search_vector TSVECTOR GENERATED ALWAYS AS (
+        setweight(to_tsvector('english', title), 'A') ||
+        setweight(to_tsvector('english', COALESCE(comment, '')), 'B')
+        ) STORED;
+
+ALTER TABLE expenses
+    ADD COLUMN search_vector TSVECTOR GENERATED ALWAYS AS (
+        to_tsvector('english', narration)
+        ) STORED;
+
+CREATE INDEX idx_projects_search_vector ON projects USING GIN (search_vector);
+CREATE INDEX idx_budgets_search_vector ON budgets USING GIN (search_vector);
+CREATE INDEX idx_expenses_search_vector ON expenses USING GIN (search_vector);
@@ -0,0 +1,15 @@
+UPDATE expenses
+SET spent_at = (expenses.spent_at AT TIME ZONE users.timezone) AT TIME ZONE current_setting('TimeZone')
+FROM users
+WHERE users.user_id = expenses.user_id
+  AND ABS(EXTRACT(EPOCH FROM
+                  (expenses.spent_at AT TIME ZONE users.timezone) AT TIME ZONE current_setting('TimeZone')
+                      - expenses.created_at)) < 1;
+
+UPDATE incomes
+SET received_at = (incomes.received_at AT TIME ZONE users.timezone) AT TIME ZONE current_setting('TimeZone')
+FROM users
+WHERE users.user_id = incomes.user_id
+  AND ABS(EXTRACT(EPOCH FROM
+                  (incomes.received_at AT TIME ZONE users.timezone) AT TIME ZONE current_setting('TimeZone')
+                      - incomes.created_at)) < 1;
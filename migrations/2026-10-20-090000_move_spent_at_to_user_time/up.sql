@@ -0,0 +1,16 @@
+-- spent_at and received_at hold the wall-clock time of the user's timezone. They used to
+-- default to the server's clock, so rows recorded without an explicit time carry the moment
+-- they were created in server time: those are moved to the wall-clock time of their user.
+-- The server's clock is taken to be in the database's TimeZone setting, run the migration
+-- with PGTZ set to the server's timezone when they differ.
+UPDATE expenses
+SET spent_at = (expenses.spent_at AT TIME ZONE current_setting('TimeZone')) AT TIME ZONE users.timezone
+FROM users
+WHERE users.user_id = expenses.user_id
+  AND ABS(EXTRACT(EPOCH FROM expenses.spent_at - expenses.created_at)) < 1;
+
+UPDATE incomes
+SET received_at = (incomes.received_at AT TIME ZONE current_setting('TimeZone')) AT TIME ZONE users.timezone
+FROM users
+WHERE users.user_id = incomes.user_id
+  AND ABS(EXTRACT(EPOCH FROM incomes.received_at - incomes.created_at)) < 1;
@@ -0,0 +1,26 @@
+WITH RECURSIVE category_roots AS (SELECT category_id, category_id AS root_id
+                                  FROM categories
+                                  WHERE parent_id IS NULL
+                                    AND (user_id IS NULL OR user_id = $1)
+                                  UNION ALL
+                                  SELECT categories.category_id, category_roots.root_id
+                                  FROM categories
+                                           INNER JOIN category_roots ON categories.parent_id = category_roots.category_id
+                                  WHERE categories.user_id IS NULL
+                                     OR categories.user_id = $1)
+SELECT roots.category_id                                                             AS category_id,
+       COALESCE(roots.name, 'Uncategorized')                                         AS name,
+       CAST(SUM(amount) FILTER (WHERE spent_at >= $3 AND spent_at < $4) AS VARCHAR)  AS year_expenses,
+       CAST(SUM(amount) FILTER (WHERE spent_at >= $5 AND spent_at < $6) AS VARCHAR)  AS month_expenses,
+       CAST(SUM(amount) FILTER (WHERE spent_at >= $7 AND spent_at < $8) AS VARCHAR)  AS week_expenses,
+       CAST(SUM(amount) FILTER (WHERE spent_at >= $9 AND spent_at < $10) AS VARCHAR) AS today_expenses
+FROM expenses
+         LEFT JOIN category_roots ON category_roots.category_id = expenses.category_id
+         LEFT JOIN categories AS roots ON roots.category_id = category_roots.root_id
+WHERE expenses.user_id = $1
+  AND ($2::UUID IS NULL OR expenses.project_id = $2)
+  AND expenses.deleted_at IS NULL
+GROUP BY roots.category_id, roots.name
+-- the week can start in the previous year, so it is checked on its own
+HAVING COUNT(*) FILTER (WHERE (spent_at >= $3 AND spent_at < $4) OR (spent_at >= $7 AND spent_at < $8)) > 0
+ORDER BY roots.name NULLS LAST
@@ -1,17 +1,8 @@
-SELECT (SELECT SUM(amount)
-        FROM expenses
-        WHERE EXTRACT(YEAR FROM expenses.spent_at) = ? AND expenses.user_id = ?)                                   AS year_expenses,
-       (SELECT SUM(amount)
-        FROM expenses
-        WHERE EXTRACT(MONTH FROM expenses.spent_at) = ?
-          AND expenses.user_id = ?)                                                                                AS month_expenses,
-       (SELECT SUM(amount)
-        FROM expenses
-        WHERE EXTRACT(DAY FROM expenses.spent_at) = ?
-          AND expenses.user_id = ?)                                                                                AS today_expenses,
-       (SELECT SUM(amount)
-        FROM expenses
-        WHERE EXTRACT(WEEK FROM NOW()) = EXTRACT(WEEK FROM NOW())
-          AND expenses.user_id = ?)                                                                                AS week_expenses;
-
-SELECT (SELECT SUM(amount) FROM expenses WHERE EXTRACT(YEAR FROM expenses.spent_at) = 2023)::VARCHAR AS year_expenses;
\ No newline at end of file
+SELECT CAST(SUM(amount) FILTER (WHERE spent_at >= $3 AND spent_at < $4) AS VARCHAR)  AS year_expenses,
+       CAST(SUM(amount) FILTER (WHERE spent_at >= $5 AND spent_at < $6) AS VARCHAR)  AS month_expenses,
+       CAST(SUM(amount) FILTER (WHERE spent_at >= $7 AND spent_at < $8) AS VARCHAR)  AS week_expenses,
+       CAST(SUM(amount) FILTER (WHERE spent_at >= $9 AND spent_at < $10) AS VARCHAR) AS today_expenses
+FROM expenses
+WHERE expenses.user_id = $1
+  AND ($2::UUID IS NULL OR expenses.project_id = $2)
+  AND expenses.deleted_at IS NULL
@@ -0,0 +1,13 @@
+WITH RECURSIVE descendants AS (SELECT category_id
+                               FROM categories
+                               WHERE category_id = ANY ($2)
+                                 AND (user_id IS NULL OR user_id = $1)
+                                 AND deleted_at IS NULL
+                               UNION
+                               SELECT categories.category_id
+                               FROM categories
+                                        INNER JOIN descendants ON categories.parent_id = descendants.category_id
+                               WHERE (categories.user_id IS NULL OR categories.user_id = $1)
+                                 AND categories.deleted_at IS NULL)
+SELECT category_id
+FROM descendants
@@ -0,0 +1,22 @@
+WITH previous AS (SELECT e.project_id,
+                         LOWER(e.narration)                   AS narration_key,
+                         MIN(e.narration)                     AS narration,
+                         CAST(ROUND(AVG(e.amount)) AS BIGINT) AS amount
+                  FROM expenses e
+                  WHERE e.user_id = $1
+                    AND e.deleted_at IS NULL
+                    AND e.spent_at >= $2
+                    AND e.spent_at < $3
+                  GROUP BY e.project_id, LOWER(e.narration)
+                  HAVING COUNT(DISTINCT date_trunc('month', e.spent_at)) = 2)
+SELECT previous.project_id, previous.narration, previous.amount
+FROM previous
+WHERE NOT EXISTS (SELECT 1
+                  FROM expenses c
+                  WHERE c.user_id = $1
+                    AND c.deleted_at IS NULL
+                    AND c.project_id = previous.project_id
+                    AND LOWER(c.narration) = previous.narration_key
+                    AND c.spent_at >= $3
+                    AND c.spent_at < $4)
+ORDER BY previous.amount DESC
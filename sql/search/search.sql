@@ -0,0 +1,55 @@
+WITH search AS (SELECT websearch_to_tsquery('english', $2) AS query),
+     hits AS (SELECT 'project'                                     AS kind,
+                     p.project_id                                  AS id,
+                     p.name                                        AS title,
+                     p.name || ' ' || p.description                AS body,
+                     ts_rank(p.search_vector, search.query)        AS rank,
+                     p.created_at
+              FROM projects p,
+                   search
+              WHERE p.user_id = $1
+                AND p.deleted_at IS NULL
+                AND 'project' = ANY ($3)
+                AND p.search_vector @@ search.query
+              UNION ALL
+              SELECT 'budget',
+                     b.budget_id,
+                     b.title,
+                     b.title || ' ' || COALESCE(b.comment, ''),
+                     ts_rank(b.search_vector, search.query),
+                     b.created_at
+              FROM budgets b,
+                   search
+              WHERE b.user_id = $1
+                AND b.deleted_at IS NULL
+                AND 'budget' = ANY ($3)
+                AND b.search_vector @@ search.query
+              UNION ALL
+              SELECT 'expense',
+                     e.expense_id,
+                     e.narration,
+                     e.narration,
+                     ts_rank(e.search_vector, search.query),
+                     e.created_at
+              FROM expenses e,
+                   search
+              WHERE e.user_id = $1
+                AND e.deleted_at IS NULL
+                AND 'expense' = ANY ($3)
+                AND e.search_vector @@ search.query),
+     top AS (SELECT *
+             FROM hits
+             ORDER BY rank DESC, created_at DESC
+             LIMIT $4)
+-- highlighting is the costly part, so only the returned hits get it
+SELECT top.kind,
+       top.id,
+       top.title,
+       ts_headline('english', top.body, search.query,
+                   'StartSel=' || chr(2) || ', StopSel=' || chr(3) ||
+                   ', MaxWords=20, MinWords=8, MaxFragments=2, FragmentDelimiter=" ... "') AS snippet,
+       top.rank,
+       top.created_at
+FROM top,
+     search
+ORDER BY top.rank DESC, top.created_at DESC
@@ -0,0 +1,40 @@
+WITH items AS (SELECT 'project'     AS kind,
+                      p.project_id  AS id,
+                      p.name        AS title,
+                      NULL::BIGINT  AS amount,
+                      p.deleted_at
+               FROM projects p
+               WHERE p.user_id = $1
+                 AND p.deleted_at IS NOT NULL
+                 AND 'project' = ANY ($2)
+               UNION ALL
+               SELECT 'budget',
+                      b.budget_id,
+                      b.title,
+                      b.amount,
+                      b.deleted_at
+               FROM budgets b
+               WHERE b.user_id = $1
+                 AND b.deleted_at IS NOT NULL
+                 AND 'budget' = ANY ($2)
+               UNION ALL
+               SELECT 'expense',
+                      e.expense_id,
+                      e.narration,
+                      e.amount,
+                      e.deleted_at
+               FROM expenses e
+               WHERE e.user_id = $1
+                 AND e.deleted_at IS NOT NULL
+                 AND 'expense' = ANY ($2))
+SELECT items.kind,
+       items.id,
+       items.title,
+       items.amount,
+       items.deleted_at,
+       -- when the retention job will purge it, never when retention is disabled
+       items.deleted_at + make_interval(days => $5) AS purge_at,
+       COUNT(*) OVER ()                            AS total_records
+FROM items
+ORDER BY items.deleted_at DESC, items.id
+LIMIT $3 OFFSET $4
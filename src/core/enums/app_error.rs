@@ -0,0 +1,257 @@
+use std::fmt::{Display, Formatter};
+
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use diesel::QueryResult;
+
+use validator::{ValidationErrors, ValidationErrorsKind};
+
+use crate::core::helpers::responder::{json_error_fields, FieldErrors};
+use crate::core::helpers::validation::describe_validation_error;
+
+/// Everything a request can fail with, rendered in the same envelope as
+/// successful responses along with a machine-readable code
+#[derive(Debug)]
+pub enum AppError {
+    /// the request cannot be carried out as asked, the message tells why
+    BadRequest(String),
+    InvalidId,
+    Unauthorized(String),
+    EntityNotFound(String),
+    RouteNotFound,
+    Conflict(String),
+    /// the payload was rejected, the map tells what is wrong with each field
+    Validation(FieldErrors),
+    /// details are logged, never sent to the client
+    Database(DieselError),
+    /// details are logged, never sent to the client
+    Internal(String),
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::BadRequest(_) => "bad_request",
+            AppError::InvalidId => "invalid_id",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::EntityNotFound(_) => "entity_not_found",
+            AppError::RouteNotFound => "route_not_found",
+            AppError::Conflict(_) => "conflict",
+            AppError::Validation(_) => "validation_failed",
+            AppError::Database(_) => "database_error",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            AppError::BadRequest(message)
+            | AppError::Unauthorized(message)
+            | AppError::Conflict(message) => message.clone(),
+            AppError::InvalidId => String::from("Your provided ID is invalid, please inspect it"),
+            AppError::EntityNotFound(entity) => format!("Such {} does not exists", entity),
+            AppError::RouteNotFound => String::from("Page Not Found"),
+            AppError::Validation(_) => String::from("The given data was invalid"),
+            AppError::Database(_) => String::from("Database operation failed"),
+            AppError::Internal(_) => String::from("Internal Server Error"),
+        }
+    }
+}
+
+impl Display for AppError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message().as_str())
+    }
+}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) | AppError::InvalidId => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::EntityNotFound(_) | AppError::RouteNotFound => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Database(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            AppError::Database(err) => log::error!("database operation failed: {:?}", err),
+            AppError::Internal(err) => log::error!("internal error: {}", err),
+            _ => {}
+        }
+
+        let errors = match self {
+            AppError::Validation(errors) => Some(errors.clone()),
+            _ => None,
+        };
+
+        json_error_fields(
+            self.message().as_str(),
+            self.code(),
+            self.status_code(),
+            errors,
+        )
+    }
+}
+
+impl From<DieselError> for AppError {
+    fn from(err: DieselError) -> Self {
+        match err {
+            DieselError::NotFound => AppError::EntityNotFound(String::from("record")),
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                AppError::Conflict(String::from("Such record already exists"))
+            }
+            DieselError::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
+                AppError::BadRequest(String::from(
+                    "Your request references a record that does not exist",
+                ))
+            }
+            err => AppError::Database(err),
+        }
+    }
+}
+
+/// Services report rule violations in the user input as plain messages, failures of the
+/// server itself must go through `OrInternal` instead
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::BadRequest(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::BadRequest(message.to_string())
+    }
+}
+
+impl From<ValidationErrors> for AppError {
+    fn from(errors: ValidationErrors) -> Self {
+        let mut fields = FieldErrors::new();
+        collect_field_errors(&mut fields, None, &errors);
+        AppError::Validation(fields)
+    }
+}
+
+/// Flattens nested struct and list errors into `parent.field` / `field[index]` keys
+fn collect_field_errors(fields: &mut FieldErrors, prefix: Option<&str>, errors: &ValidationErrors) {
+    for (field, kind) in errors.errors() {
+        let path = match prefix {
+            None => field.to_string(),
+            Some(prefix) => format!("{}.{}", prefix, field),
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                let messages = fields.entry(path).or_default();
+                for error in errors {
+                    messages.push(describe_validation_error(error));
+                }
+            }
+            ValidationErrorsKind::Struct(errors) => {
+                collect_field_errors(fields, Some(path.as_str()), errors)
+            }
+            ValidationErrorsKind::List(items) => {
+                for (index, errors) in items {
+                    let path = format!("{}[{}]", path, index);
+                    collect_field_errors(fields, Some(path.as_str()), errors);
+                }
+            }
+        }
+    }
+}
+
+impl From<uuid::Error> for AppError {
+    fn from(_: uuid::Error) -> Self {
+        AppError::InvalidId
+    }
+}
+
+pub trait OrNotFound<T> {
+    /// Reports a missing row as the given entity not being found
+    fn or_not_found(self, entity: &str) -> AppResult<T>;
+}
+
+impl<T> OrNotFound<T> for QueryResult<T> {
+    fn or_not_found(self, entity: &str) -> AppResult<T> {
+        self.map_err(|err| match err {
+            DieselError::NotFound => AppError::EntityNotFound(entity.to_string()),
+            err => AppError::from(err),
+        })
+    }
+}
+
+pub trait MissingAs<T> {
+    /// Reports a missing entity as a bad request with the given message, for entities
+    /// referenced by the payload, other failures are passed on as they are
+    fn missing_as(self, message: &str) -> AppResult<T>;
+}
+
+impl<T> MissingAs<T> for AppResult<T> {
+    fn missing_as(self, message: &str) -> AppResult<T> {
+        self.map_err(|err| match err {
+            AppError::EntityNotFound(_) => AppError::BadRequest(message.to_string()),
+            err => err,
+        })
+    }
+}
+
+pub trait OrInternal<T> {
+    /// Reports a failure of the server itself (storage, serialization, ...) as an internal
+    /// error, the details are logged and never sent to the client
+    fn or_internal(self, context: &str) -> AppResult<T>;
+}
+
+impl<T, E: Display> OrInternal<T> for Result<T, E> {
+    fn or_internal(self, context: &str) -> AppResult<T> {
+        self.map_err(|err| AppError::Internal(format!("{}: {}", context, err)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn internal_failures_hide_their_details() {
+        let failed: Result<(), String> = Err(String::from("disk full at /var/lib/nucleus"));
+        let err = failed.or_internal("Failed to store the file").unwrap_err();
+
+        assert_eq!(err.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(err.code(), "internal_error");
+        assert!(!err.message().contains("disk full"));
+    }
+
+    #[test]
+    fn validation_messages_are_bad_requests() {
+        let err = AppError::from("min_amount cannot be greater than max_amount");
+
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            err.message(),
+            "min_amount cannot be greater than max_amount"
+        );
+    }
+
+    #[test]
+    fn missing_references_are_bad_requests() {
+        let missing: AppResult<()> = Err(AppError::EntityNotFound(String::from("project")));
+        let err = missing
+            .missing_as("Such project does not exist")
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(err.message(), "Such project does not exist");
+
+        let failed: AppResult<()> = Err(AppError::Database(DieselError::BrokenTransactionManager));
+        let err = failed
+            .missing_as("Such project does not exist")
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -1 +1 @@
-pub mod http_error;
+pub mod app_error;
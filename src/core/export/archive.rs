@@ -0,0 +1,208 @@
+use std::io::{Cursor, Read, Write};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::models::account_archive::{AccountArchive, AccountArchiveManifest};
+
+/// Largest uncompressed entity file read from an uploaded archive, guards
+/// against entries that inflate far beyond the upload itself
+const MAX_ENTRY_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Largest uncompressed size of all the entity files together
+const MAX_ARCHIVE_BYTES: u64 = 512 * 1024 * 1024;
+
+/// How many times an entry may inflate its compressed size, JSON rarely goes
+/// past 20 while zip bombs go well beyond 1000. Entries smaller than
+/// [RATIO_FREE_BYTES] are not held to it
+const MAX_COMPRESSION_RATIO: u64 = 100;
+const RATIO_FREE_BYTES: u64 = 1024 * 1024;
+
+/// Zips the archive as one pretty printed JSON file per entity
+pub fn write_archive(archive: &AccountArchive) -> Result<Vec<u8>, String> {
+    let mut writer = ZipWriter::new(Cursor::new(vec![]));
+
+    write_entry(&mut writer, "manifest.json", &archive.manifest)?;
+    write_entry(&mut writer, "profile.json", &archive.profile)?;
+    write_entry(&mut writer, "projects.json", &archive.projects)?;
+    write_entry(&mut writer, "labels.json", &archive.labels)?;
+    write_entry(&mut writer, "project_labels.json", &archive.project_labels)?;
+    write_entry(&mut writer, "categories.json", &archive.categories)?;
+    write_entry(&mut writer, "budgets.json", &archive.budgets)?;
+    write_entry(&mut writer, "expenses.json", &archive.expenses)?;
+    write_entry(&mut writer, "expense_labels.json", &archive.expense_labels)?;
+    write_entry(&mut writer, "incomes.json", &archive.incomes)?;
+    write_entry(&mut writer, "bank_accounts.json", &archive.bank_accounts)?;
+    write_entry(&mut writer, "expense_rules.json", &archive.expense_rules)?;
+
+    let cursor = writer.finish().map_err(|e| e.to_string())?;
+    Ok(cursor.into_inner())
+}
+
+/// Archives of version 1 predate incomes, bank accounts and expense rules,
+/// they are read as having none
+pub fn read_archive(content: &[u8]) -> Result<AccountArchive, String> {
+    let mut reader = ZipArchive::new(Cursor::new(content))
+        .map_err(|_| String::from("The uploaded file is not a valid account archive"))?;
+
+    let mut remaining = MAX_ARCHIVE_BYTES;
+    let reader = &mut reader;
+    let remaining = &mut remaining;
+
+    let manifest: AccountArchiveManifest = read_entry(reader, "manifest.json", remaining)?;
+    let has_finances = manifest.version >= 2;
+
+    Ok(AccountArchive {
+        manifest,
+        profile: read_entry(reader, "profile.json", remaining)?,
+        projects: read_entry(reader, "projects.json", remaining)?,
+        labels: read_entry(reader, "labels.json", remaining)?,
+        project_labels: read_entry(reader, "project_labels.json", remaining)?,
+        categories: read_entry(reader, "categories.json", remaining)?,
+        budgets: read_entry(reader, "budgets.json", remaining)?,
+        expenses: read_entry(reader, "expenses.json", remaining)?,
+        expense_labels: read_entry(reader, "expense_labels.json", remaining)?,
+        incomes: match has_finances {
+            true => read_entry(reader, "incomes.json", remaining)?,
+            false => vec![],
+        },
+        bank_accounts: match has_finances {
+            true => read_entry(reader, "bank_accounts.json", remaining)?,
+            false => vec![],
+        },
+        expense_rules: match has_finances {
+            true => read_entry(reader, "expense_rules.json", remaining)?,
+            false => vec![],
+        },
+    })
+}
+
+fn write_entry<T: Serialize>(
+    writer: &mut ZipWriter<Cursor<Vec<u8>>>,
+    name: &str,
+    value: &T,
+) -> Result<(), String> {
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let json = serde_json::to_vec_pretty(value).map_err(|e| e.to_string())?;
+
+    writer
+        .start_file(name, options)
+        .map_err(|e| e.to_string())?;
+    writer.write_all(&json).map_err(|e| e.to_string())
+}
+
+/// Reads one entity file, `remaining` is what is left of [MAX_ARCHIVE_BYTES]
+fn read_entry<T: DeserializeOwned>(
+    reader: &mut ZipArchive<Cursor<&[u8]>>,
+    name: &str,
+    remaining: &mut u64,
+) -> Result<T, String> {
+    let file = reader
+        .by_name(name)
+        .map_err(|_| format!("The account archive is missing {}", name))?;
+
+    let ratio_limit = file
+        .compressed_size()
+        .saturating_mul(MAX_COMPRESSION_RATIO)
+        .max(RATIO_FREE_BYTES);
+    let (limit, too_large) = match *remaining < MAX_ENTRY_BYTES.min(ratio_limit) {
+        true => (
+            *remaining,
+            format!(
+                "The account archive is larger than {} MiB once uncompressed",
+                MAX_ARCHIVE_BYTES >> 20
+            ),
+        ),
+        false if ratio_limit < MAX_ENTRY_BYTES => (
+            ratio_limit,
+            format!(
+                "{} inflates more than {} times its compressed size",
+                name, MAX_COMPRESSION_RATIO
+            ),
+        ),
+        false => (
+            MAX_ENTRY_BYTES,
+            format!("{} is larger than {} MiB", name, MAX_ENTRY_BYTES >> 20),
+        ),
+    };
+
+    if file.size() > limit {
+        return Err(too_large);
+    }
+
+    // the declared sizes can lie, so reading stops one byte past the limit
+    let mut json = String::new();
+    file.take(limit + 1)
+        .read_to_string(&mut json)
+        .map_err(|_| format!("{} is not valid UTF-8 text", name))?;
+
+    if json.len() as u64 > limit {
+        return Err(too_large);
+    }
+
+    *remaining -= json.len() as u64;
+
+    serde_json::from_str(json.as_str()).map_err(|e| format!("{} is invalid: {}", name, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zip(name: &str, content: &[u8]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(vec![]));
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        writer.start_file(name, options).unwrap();
+        writer.write_all(content).unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn read(content: &[u8], remaining: &mut u64) -> Result<Vec<u32>, String> {
+        let mut reader = ZipArchive::new(Cursor::new(content)).unwrap();
+        read_entry(&mut reader, "entry.json", remaining)
+    }
+
+    #[test]
+    fn entries_count_towards_the_archive_size() {
+        let content = zip("entry.json", b"[1, 2, 3]");
+        let mut remaining = MAX_ARCHIVE_BYTES;
+
+        assert_eq!(read(&content, &mut remaining), Ok(vec![1, 2, 3]));
+        assert_eq!(remaining, MAX_ARCHIVE_BYTES - 9);
+
+        let mut remaining = 5;
+        assert_eq!(
+            read(&content, &mut remaining),
+            Err(String::from(
+                "The account archive is larger than 512 MiB once uncompressed"
+            ))
+        );
+    }
+
+    #[test]
+    fn entries_inflating_too_much_are_refused() {
+        let mut json = vec![b' '; 8 * 1024 * 1024];
+        json.extend_from_slice(b"[]");
+        let content = zip("entry.json", &json);
+        let mut remaining = MAX_ARCHIVE_BYTES;
+
+        assert_eq!(
+            read(&content, &mut remaining),
+            Err(String::from(
+                "entry.json inflates more than 100 times its compressed size"
+            ))
+        );
+    }
+
+    #[test]
+    fn small_entries_may_compress_well() {
+        let mut json = vec![b' '; 512 * 1024];
+        json.extend_from_slice(b"[7]");
+        let content = zip("entry.json", &json);
+        let mut remaining = MAX_ARCHIVE_BYTES;
+
+        assert_eq!(read(&content, &mut remaining), Ok(vec![7]));
+    }
+}
@@ -0,0 +1,174 @@
+use chrono::NaiveDateTime;
+use csv::WriterBuilder;
+use rust_xlsxwriter::{Format, Workbook};
+use serde_json::{Map, Number, Value};
+use uuid::Uuid;
+
+use crate::core::helpers::number::format_money;
+
+pub mod archive;
+pub mod pdf;
+
+const DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Leading characters spreadsheets treat as the start of a formula
+const FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+/// Position of an exported record, batches resume right after it
+pub type ExportCursor = (NaiveDateTime, Uuid);
+
+pub struct ExportColumn {
+    /// key of the value in JSON exports
+    pub key: &'static str,
+    /// header of the column in CSV and XLSX exports
+    pub title: &'static str,
+}
+
+pub enum ExportCell {
+    Text(String),
+    Number(i64),
+    Money(i64),
+    DateTime(NaiveDateTime),
+    Empty,
+}
+
+impl ExportCell {
+    pub fn text(&self) -> String {
+        match self {
+            ExportCell::Text(text) => text.clone(),
+            ExportCell::Number(number) => number.to_string(),
+            ExportCell::Money(amount) => format_money(*amount),
+            ExportCell::DateTime(date_time) => date_time.format(DATE_TIME_FORMAT).to_string(),
+            ExportCell::Empty => String::new(),
+        }
+    }
+
+    /// Text starting like a formula is prefixed with a quote, so spreadsheets
+    /// opening the CSV show it as is instead of evaluating it
+    pub fn csv_text(&self) -> String {
+        match self {
+            ExportCell::Text(text) if text.starts_with(FORMULA_PREFIXES) => format!("'{}", text),
+            _ => self.text(),
+        }
+    }
+
+    /// JSON keeps amounts as plain numbers so they can be computed with
+    pub fn json(&self) -> Value {
+        match self {
+            ExportCell::Number(number) | ExportCell::Money(number) => {
+                Value::Number(Number::from(*number))
+            }
+            ExportCell::Empty => Value::Null,
+            _ => Value::String(self.text()),
+        }
+    }
+}
+
+pub struct ExportRow {
+    pub cursor: ExportCursor,
+    pub cells: Vec<ExportCell>,
+}
+
+/// CSV lines of the rows, preceded by the header line when columns are given
+pub fn csv_chunk(columns: Option<&[ExportColumn]>, rows: &[ExportRow]) -> Result<Vec<u8>, String> {
+    let mut writer = WriterBuilder::new().from_writer(vec![]);
+
+    if let Some(columns) = columns {
+        writer
+            .write_record(columns.iter().map(|column| column.title))
+            .map_err(|e| e.to_string())?;
+    }
+
+    for row in rows {
+        writer
+            .write_record(row.cells.iter().map(|cell| cell.csv_text()))
+            .map_err(|e| e.to_string())?;
+    }
+
+    writer.into_inner().map_err(|e| e.to_string())
+}
+
+/// Comma separated JSON objects of the rows, `written` is the number of rows
+/// already sent so the separator before the first row can be left out
+pub fn json_chunk(columns: &[ExportColumn], rows: &[ExportRow], written: usize) -> Vec<u8> {
+    let mut chunk = vec![];
+
+    for (index, row) in rows.iter().enumerate() {
+        if written + index > 0 {
+            chunk.push(b',');
+        }
+
+        let object: Map<String, Value> = columns
+            .iter()
+            .zip(row.cells.iter())
+            .map(|(column, cell)| (column.key.to_string(), cell.json()))
+            .collect();
+
+        chunk.extend(Value::Object(object).to_string().into_bytes());
+    }
+
+    chunk
+}
+
+/// Single sheet workbook, amounts are stored as numbers formatted as money
+pub fn xlsx_workbook(
+    sheet_name: &str,
+    columns: &[ExportColumn],
+    rows: &[ExportRow],
+) -> Result<Vec<u8>, String> {
+    let mut workbook = Workbook::new();
+    let header_format = Format::new().set_bold();
+    let money_format = Format::new().set_num_format("#,##0.00");
+
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name(sheet_name).map_err(|e| e.to_string())?;
+    worksheet
+        .set_freeze_panes(1, 0)
+        .map_err(|e| e.to_string())?;
+
+    for (index, column) in columns.iter().enumerate() {
+        worksheet
+            .write_string_with_format(0, index as u16, column.title, &header_format)
+            .map_err(|e| e.to_string())?;
+    }
+
+    for (row_index, row) in rows.iter().enumerate() {
+        let row_num = row_index as u32 + 1;
+        for (col_index, cell) in row.cells.iter().enumerate() {
+            let col_num = col_index as u16;
+            let result = match cell {
+                ExportCell::Empty => continue,
+                ExportCell::Number(number) => {
+                    worksheet.write_number(row_num, col_num, *number as f64)
+                }
+                ExportCell::Money(amount) => worksheet.write_number_with_format(
+                    row_num,
+                    col_num,
+                    *amount as f64,
+                    &money_format,
+                ),
+                _ => worksheet.write_string(row_num, col_num, cell.text()),
+            };
+
+            result.map_err(|e| e.to_string())?;
+        }
+    }
+
+    workbook.save_to_buffer().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_text_escapes_formulas() {
+        for text in ["=SUM(A1:A2)", "+1", "-1", "@cmd", "\tx"] {
+            let cell = ExportCell::Text(text.to_string());
+            assert_eq!(cell.csv_text(), format!("'{}", text));
+        }
+
+        assert_eq!(ExportCell::Text(String::from("Lunch")).csv_text(), "Lunch");
+        assert_eq!(ExportCell::Money(-150).csv_text(), "-150.00");
+    }
+}
@@ -0,0 +1,257 @@
+use printpdf::{
+    BuiltinFont, IndirectFontRef, Line, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference,
+    Point,
+};
+
+use crate::core::helpers::number::format_money;
+use crate::models::statement::{BudgetStatement, StatementLine};
+
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 18.0;
+const LINE_HEIGHT: f32 = 5.0;
+const FONT_SIZE: f32 = 9.0;
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Lays text out top to bottom, starting a new A4 page whenever the current one is full
+struct PdfWriter {
+    document: PdfDocumentReference,
+    layer: PdfLayerReference,
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    pages: usize,
+    /// distance of the next line from the bottom of the page, in mm
+    cursor: f32,
+}
+
+impl PdfWriter {
+    fn new(title: &str) -> Result<PdfWriter, String> {
+        let (document, page, layer) =
+            PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Page 1");
+        let regular = document
+            .add_builtin_font(BuiltinFont::Helvetica)
+            .map_err(|e| e.to_string())?;
+        let bold = document
+            .add_builtin_font(BuiltinFont::HelveticaBold)
+            .map_err(|e| e.to_string())?;
+        let layer = document.get_page(page).get_layer(layer);
+
+        Ok(PdfWriter {
+            document,
+            layer,
+            regular,
+            bold,
+            pages: 1,
+            cursor: PAGE_HEIGHT - MARGIN,
+        })
+    }
+
+    /// Moves to the next line, breaking the page when there is no room left for it
+    fn next_line(&mut self, height: f32) {
+        self.cursor -= height;
+        if self.cursor < MARGIN {
+            self.pages += 1;
+            let (page, layer) = self.document.add_page(
+                Mm(PAGE_WIDTH),
+                Mm(PAGE_HEIGHT),
+                format!("Page {}", self.pages),
+            );
+            self.layer = self.document.get_page(page).get_layer(layer);
+            self.cursor = PAGE_HEIGHT - MARGIN - height;
+        }
+    }
+
+    fn text(&self, text: &str, size: f32, x: f32, bold: bool) {
+        let font = match bold {
+            true => &self.bold,
+            false => &self.regular,
+        };
+        self.layer
+            .use_text(text, size, Mm(x), Mm(self.cursor), font);
+    }
+
+    /// Writes the text so that it ends at the given position
+    fn text_right(&self, text: &str, size: f32, right: f32, bold: bool) {
+        self.text(text, size, right - text_width(text, size), bold);
+    }
+
+    fn rule(&self) {
+        let y = self.cursor - 1.5;
+        self.layer.set_outline_thickness(0.5);
+        self.layer.add_line(Line {
+            points: vec![
+                (Point::new(Mm(MARGIN), Mm(y)), false),
+                (Point::new(Mm(PAGE_WIDTH - MARGIN), Mm(y)), false),
+            ],
+            is_closed: false,
+        });
+    }
+
+    fn heading(&mut self, text: &str) {
+        self.next_line(LINE_HEIGHT * 2.0);
+        self.text(text, 12.0, MARGIN, true);
+        self.next_line(LINE_HEIGHT);
+    }
+
+    fn breakdown(&mut self, first_column: &str, lines: &[StatementLine]) {
+        self.next_line(LINE_HEIGHT);
+        self.text(first_column, FONT_SIZE, MARGIN, true);
+        self.text_right("Expenses", FONT_SIZE, 130.0, true);
+        self.text_right("Share", FONT_SIZE, 155.0, true);
+        self.text_right("Amount", FONT_SIZE, PAGE_WIDTH - MARGIN, true);
+        self.rule();
+        self.next_line(1.5);
+
+        for line in lines {
+            self.next_line(LINE_HEIGHT);
+            self.text(truncate(&line.name, 50).as_str(), FONT_SIZE, MARGIN, false);
+            self.text_right(line.expenses.to_string().as_str(), FONT_SIZE, 130.0, false);
+            self.text_right(
+                format!("{:.1}%", line.share).as_str(),
+                FONT_SIZE,
+                155.0,
+                false,
+            );
+            self.text_right(
+                format_money(line.amount).as_str(),
+                FONT_SIZE,
+                PAGE_WIDTH - MARGIN,
+                false,
+            );
+        }
+    }
+
+    fn finish(self) -> Result<Vec<u8>, String> {
+        self.document.save_to_bytes().map_err(|e| e.to_string())
+    }
+}
+
+/// Renders the statement as an A4 PDF document
+pub fn budget_statement(statement: &BudgetStatement) -> Result<Vec<u8>, String> {
+    let mut writer = PdfWriter::new(statement.title.as_str())?;
+
+    writer.text(statement.title.as_str(), 18.0, MARGIN, true);
+    writer.next_line(LINE_HEIGHT * 1.5);
+    writer.text(statement.account_name.as_str(), FONT_SIZE, MARGIN, false);
+    writer.text_right(
+        format!(
+            "Generated {}",
+            statement.generated_at.format("%Y-%m-%d %H:%M")
+        )
+        .as_str(),
+        FONT_SIZE,
+        PAGE_WIDTH - MARGIN,
+        false,
+    );
+    writer.rule();
+
+    writer.heading("Totals");
+    let totals = [
+        ("Budgeted", statement.total_budgeted),
+        ("Spent", statement.total_spent),
+        ("Available", statement.total_available),
+        ("Overspent", statement.total_overspent),
+    ];
+    for (name, amount) in totals {
+        writer.next_line(LINE_HEIGHT);
+        writer.text(name, FONT_SIZE, MARGIN, false);
+        writer.text_right(format_money(amount).as_str(), FONT_SIZE, 80.0, true);
+    }
+    writer.next_line(LINE_HEIGHT);
+    writer.text("Expenses", FONT_SIZE, MARGIN, false);
+    writer.text_right(
+        statement.expenses.len().to_string().as_str(),
+        FONT_SIZE,
+        80.0,
+        true,
+    );
+
+    writer.heading("By project");
+    writer.breakdown("Project", &statement.projects);
+
+    writer.heading("By label");
+    writer.breakdown("Label", &statement.labels);
+
+    writer.heading("Expenses");
+    writer.next_line(LINE_HEIGHT);
+    writer.text("Date", FONT_SIZE, MARGIN, true);
+    writer.text("Narration", FONT_SIZE, 40.0, true);
+    writer.text("Project", FONT_SIZE, 105.0, true);
+    writer.text("Labels", FONT_SIZE, 140.0, true);
+    writer.text_right("Amount", FONT_SIZE, PAGE_WIDTH - MARGIN, true);
+    writer.rule();
+    writer.next_line(1.5);
+
+    for expense in &statement.expenses {
+        writer.next_line(LINE_HEIGHT);
+        writer.text(
+            expense.spent_at.format(DATE_FORMAT).to_string().as_str(),
+            FONT_SIZE,
+            MARGIN,
+            false,
+        );
+        writer.text(
+            truncate(&expense.narration, 38).as_str(),
+            FONT_SIZE,
+            40.0,
+            false,
+        );
+        writer.text(
+            truncate(&expense.project, 20).as_str(),
+            FONT_SIZE,
+            105.0,
+            false,
+        );
+        writer.text(
+            truncate(&expense.labels.join(", "), 18).as_str(),
+            FONT_SIZE,
+            140.0,
+            false,
+        );
+        writer.text_right(
+            format_money(expense.amount).as_str(),
+            FONT_SIZE,
+            PAGE_WIDTH - MARGIN,
+            false,
+        );
+    }
+
+    if statement.expenses.is_empty() {
+        writer.next_line(LINE_HEIGHT);
+        writer.text(
+            "No expenses were recorded against this budget.",
+            FONT_SIZE,
+            MARGIN,
+            false,
+        );
+    }
+
+    writer.finish()
+}
+
+/// Approximate width in mm of the text set in Helvetica, good enough to right-align numbers
+fn text_width(text: &str, size: f32) -> f32 {
+    let em: f32 = text
+        .chars()
+        .map(|c| match c {
+            '0'..='9' => 0.556,
+            ',' | '.' | ' ' => 0.278,
+            '-' => 0.333,
+            '%' => 0.889,
+            _ => 0.6,
+        })
+        .sum();
+
+    // 1pt = 0.3528mm
+    em * size * 0.3528
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.chars().count() > max_chars {
+        true => format!(
+            "{}...",
+            text.chars().take(max_chars - 3).collect::<String>()
+        ),
+        false => String::from(text),
+    }
+}
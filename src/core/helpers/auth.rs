@@ -1,12 +1,33 @@
+use crate::core::helpers::cookie::CookieSettings;
 use crate::models::user::User;
+use actix_web::cookie::time::Duration as CookieDuration;
+use actix_web::cookie::Cookie;
 use actix_web::dev::Extensions;
 use std::cell::Ref;
 use uuid::Uuid;
 
 pub fn get_auth_id(ext: Ref<Extensions>) -> Uuid {
-    return *ext.get::<Uuid>().unwrap();
+    *ext.get::<Uuid>().unwrap()
 }
 
 pub fn get_auth_user(ext: Ref<Extensions>) -> User {
-    return ext.get::<User>().unwrap().clone();
+    ext.get::<User>().unwrap().clone()
+}
+
+/// Cookie carrying the access token of browser sessions
+pub const AUTH_COOKIE: &str = "token";
+
+pub fn make_auth_cookie(token: String, lifetime_in_minutes: i64) -> Cookie<'static> {
+    CookieSettings::from_env()
+        .apply(Cookie::build(AUTH_COOKIE, token))
+        .http_only(true)
+        .max_age(CookieDuration::minutes(lifetime_in_minutes))
+        .finish()
+}
+
+/// Expired copy of the auth cookie, makes browsers drop the session
+pub fn make_removal_auth_cookie() -> Cookie<'static> {
+    let mut cookie = make_auth_cookie(String::new(), 0);
+    cookie.make_removal();
+    cookie
 }
@@ -0,0 +1,52 @@
+use std::env;
+
+use actix_web::cookie::{CookieBuilder, SameSite};
+
+/// Attributes shared by the session and CSRF cookies, configured through
+/// AUTH_COOKIE_DOMAIN, AUTH_COOKIE_PATH, AUTH_COOKIE_SECURE and AUTH_COOKIE_SAME_SITE
+pub struct CookieSettings {
+    pub domain: Option<String>,
+    pub path: String,
+    pub secure: bool,
+    pub same_site: SameSite,
+}
+
+impl CookieSettings {
+    pub fn from_env() -> CookieSettings {
+        let same_site = match env::var("AUTH_COOKIE_SAME_SITE")
+            .unwrap_or_default()
+            .to_lowercase()
+            .as_str()
+        {
+            "strict" => SameSite::Strict,
+            "none" => SameSite::None,
+            _ => SameSite::Lax,
+        };
+
+        CookieSettings {
+            domain: env::var("AUTH_COOKIE_DOMAIN")
+                .ok()
+                .filter(|d| !d.is_empty()),
+            path: env::var("AUTH_COOKIE_PATH")
+                .ok()
+                .filter(|p| !p.is_empty())
+                .unwrap_or_else(|| String::from("/")),
+            // browsers refuse SameSite=None cookies that are not secure
+            secure: same_site == SameSite::None
+                || env::var("AUTH_COOKIE_SECURE").map_or(true, |s| s != "false"),
+            same_site,
+        }
+    }
+
+    pub fn apply<'c>(&self, builder: CookieBuilder<'c>) -> CookieBuilder<'c> {
+        let builder = builder
+            .path(self.path.clone())
+            .secure(self.secure)
+            .same_site(self.same_site);
+
+        match &self.domain {
+            Some(domain) => builder.domain(domain.clone()),
+            None => builder,
+        }
+    }
+}
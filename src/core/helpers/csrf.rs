@@ -0,0 +1,63 @@
+use actix_web::cookie::Cookie;
+use actix_web::HttpRequest;
+use uuid::Uuid;
+
+use crate::core::helpers::cookie::CookieSettings;
+
+/// Cookie holding the double-submit CSRF token, readable by scripts so they can echo it back
+pub const CSRF_COOKIE: &str = "csrf_token";
+
+pub fn make_csrf_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// Token of the current request's CSRF cookie, or a fresh one when it has none yet
+pub fn csrf_token_of(req: &HttpRequest) -> String {
+    req.cookie(CSRF_COOKIE)
+        .map(|c| c.value().to_string())
+        .filter(|token| !token.is_empty())
+        .unwrap_or_else(make_csrf_token)
+}
+
+pub fn make_csrf_cookie(token: String) -> Cookie<'static> {
+    CookieSettings::from_env()
+        .apply(Cookie::build(CSRF_COOKIE, token))
+        .http_only(false)
+        .finish()
+}
+
+pub fn make_removal_csrf_cookie() -> Cookie<'static> {
+    let mut cookie = make_csrf_cookie(String::new());
+    cookie.make_removal();
+    cookie
+}
+
+/// Header through which API clients echo the CSRF cookie back
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+
+/// Whether the submitted token is the one stored in the request's CSRF cookie
+pub fn csrf_token_matches(req: &HttpRequest, submitted: &str) -> bool {
+    match req.cookie(CSRF_COOKIE) {
+        None => false,
+        Some(cookie) => !submitted.is_empty() && constant_time_eq(cookie.value(), submitted),
+    }
+}
+
+/// Whether the request's CSRF header carries the token stored in its CSRF cookie
+pub fn csrf_header_matches(req: &HttpRequest) -> bool {
+    let submitted = req
+        .headers()
+        .get(CSRF_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    csrf_token_matches(req, submitted)
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
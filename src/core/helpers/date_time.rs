@@ -1,3 +1,6 @@
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+
 pub struct MonthItem<'n> {
     number: i16,
     name: &'n str,
@@ -75,3 +78,177 @@ impl Month<'_> {
         Err("Invalid month".to_string())
     }
 }
+
+/// Half-open `[start, end)` range of wall-clock timestamps
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DateRange {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+impl DateRange {
+    pub fn from_dates(start: NaiveDate, end: NaiveDate) -> DateRange {
+        DateRange {
+            start: start.and_hms_opt(0, 0, 0).unwrap(),
+            end: end.and_hms_opt(0, 0, 0).unwrap(),
+        }
+    }
+
+    /// Range covering the whole given month, `None` when the month is invalid
+    pub fn month(year: i32, month: u32) -> Option<DateRange> {
+        let start = NaiveDate::from_ymd_opt(year, month, 1)?;
+        let end = start.checked_add_months(Months::new(1))?;
+        Some(DateRange::from_dates(start, end))
+    }
+
+    pub fn days(&self) -> i64 {
+        (self.end - self.start).num_days()
+    }
+}
+
+/// Year, month, ISO week and day ranges containing the given date
+pub struct AggregatePeriods {
+    pub year: DateRange,
+    pub month: DateRange,
+    pub week: DateRange,
+    pub today: DateRange,
+}
+
+impl AggregatePeriods {
+    pub fn new(today: NaiveDate) -> AggregatePeriods {
+        let year = today.year();
+        let week_start = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+
+        AggregatePeriods {
+            year: DateRange::from_dates(
+                NaiveDate::from_ymd_opt(year, 1, 1).unwrap(),
+                NaiveDate::from_ymd_opt(year + 1, 1, 1).unwrap(),
+            ),
+            month: DateRange::month(year, today.month()).unwrap(),
+            week: DateRange::from_dates(week_start, week_start + Duration::days(7)),
+            today: DateRange::from_dates(today, today + Duration::days(1)),
+        }
+    }
+
+    /// Periods around the current date of the user's timezone. `spent_at` holds the
+    /// user's wall-clock time (see `get_nullable_time`), like the dates of the reports,
+    /// so the bounds are plain calendar dates splitting at the user's midnights
+    pub fn in_timezone(timezone: &str) -> AggregatePeriods {
+        AggregatePeriods::new(today_in_timezone(timezone))
+    }
+}
+
+/// Parses a `YYYY-MM-DD` date
+pub fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date \"{}\", expected YYYY-MM-DD", value))
+}
+
+pub fn parse_timezone(name: &str) -> Result<Tz, String> {
+    name.parse::<Tz>()
+        .map_err(|_| format!("Unknown timezone \"{}\"", name))
+}
+
+/// The named timezone, UTC for unknown names
+pub fn timezone_or_utc(timezone: &str) -> Tz {
+    parse_timezone(timezone).unwrap_or(Tz::UTC)
+}
+
+/// Current calendar date as seen in the given timezone, falling back to UTC for unknown zones
+pub fn today_in_timezone(timezone: &str) -> NaiveDate {
+    date_in_timezone(Utc::now(), timezone)
+}
+
+/// Calendar date of the instant as seen in the given timezone, falling back to UTC for unknown zones
+pub fn date_in_timezone(instant: DateTime<Utc>, timezone: &str) -> NaiveDate {
+    instant
+        .with_timezone(&timezone_or_utc(timezone))
+        .date_naive()
+}
+
+/// Current wall-clock time in the given timezone, falling back to UTC for unknown zones
+pub fn now_in_timezone(timezone: &str) -> NaiveDateTime {
+    Utc::now()
+        .with_timezone(&timezone_or_utc(timezone))
+        .naive_local()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn time(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        date(year, month, day).and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn instant(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.from_utc_datetime(&time(year, month, day, hour, minute))
+    }
+
+    fn contains(range: &DateRange, time: NaiveDateTime) -> bool {
+        range.start <= time && time < range.end
+    }
+
+    #[test]
+    fn month_splits_at_midnight() {
+        let periods = AggregatePeriods::new(date(2026, 7, 31));
+
+        assert_eq!(periods.month.start, time(2026, 7, 1, 0, 0));
+        assert_eq!(periods.month.end, time(2026, 8, 1, 0, 0));
+        assert!(contains(&periods.month, time(2026, 7, 31, 23, 30)));
+        assert!(contains(&periods.today, time(2026, 7, 31, 23, 30)));
+        assert!(!contains(&periods.month, time(2026, 8, 1, 0, 30)));
+        assert!(!contains(&periods.today, time(2026, 8, 1, 0, 30)));
+    }
+
+    #[test]
+    fn week_and_year_split_at_midnight() {
+        let periods = AggregatePeriods::new(date(2026, 12, 31));
+
+        assert_eq!(periods.week.start, time(2026, 12, 28, 0, 0));
+        assert_eq!(periods.week.end, time(2027, 1, 4, 0, 0));
+        assert_eq!(periods.year.end, time(2027, 1, 1, 0, 0));
+        assert!(!contains(&periods.year, time(2027, 1, 1, 0, 30)));
+        assert!(contains(&periods.week, time(2027, 1, 1, 0, 30)));
+    }
+
+    #[test]
+    fn periods_follow_the_users_calendar() {
+        // still July 31st in UTC and London, already August 1st in Sydney
+        let now = instant(2026, 7, 31, 14, 30);
+        assert_eq!(date_in_timezone(now, "Europe/London"), date(2026, 7, 31));
+
+        let today = date_in_timezone(now, "Australia/Sydney");
+        assert_eq!(today, date(2026, 8, 1));
+
+        // an expense the Sydney user records right now, at their wall-clock time
+        let spent_at = now
+            .with_timezone(&timezone_or_utc("Australia/Sydney"))
+            .naive_local();
+        let periods = AggregatePeriods::new(today);
+        assert!(contains(&periods.today, spent_at));
+        assert!(contains(&periods.month, spent_at));
+
+        // the spending report of that day covers the very same range
+        let report = DateRange::from_dates(today, today + Duration::days(1));
+        assert_eq!(report, periods.today);
+        assert!(contains(&report, spent_at));
+    }
+
+    #[test]
+    fn unknown_timezones_fall_back_to_utc() {
+        assert_eq!(timezone_or_utc("Mars/Olympus_Mons"), Tz::UTC);
+
+        let now = instant(2026, 7, 31, 23, 30);
+        assert_eq!(
+            date_in_timezone(now, "Mars/Olympus_Mons"),
+            date(2026, 7, 31)
+        );
+    }
+}
@@ -1,5 +1,112 @@
 use chrono::NaiveDateTime;
+use diesel::dsl::{And, Eq, IsNotNull, IsNull};
+use diesel::pg::Pg;
+use diesel::query_builder::{BoxedSqlQuery, SqlQuery};
+use diesel::sql_types::Uuid as SqlUuid;
+use diesel::{sql_query, BoolExpressionMethods, ExpressionMethods, QueryDsl};
+use uuid::Uuid;
+
+use crate::schema::{
+    attachments, bank_accounts, budgets, categories, expense_labels, expense_rules, expenses,
+    imports, incomes, labels, notifications, project_labels, projects,
+};
+
+/// Rows written per bulk insert, keeps statements well below the 65535 bind
+/// parameters Postgres accepts whatever the number of columns
+pub const INSERT_CHUNK_SIZE: usize = 1000;
 
 pub fn current_timestamp() -> NaiveDateTime {
     chrono::Local::now().naive_local()
 }
+
+/// Tables whose records belong to a user. Queries over a user's records start
+/// from `owned_by`, which puts the ownership condition first; everything added
+/// afterwards is ANDed to it (`or_filter` is disallowed through clippy.toml),
+/// so composed conditions can only ever narrow the user's records down.
+///
+/// Joins, updates and locking reads (`for_update`) cannot start from a boxed
+/// query, they filter on `owned_condition` / `trashed_condition` instead
+pub trait OwnedBy<'a> {
+    type Query;
+    type Condition;
+    type TrashedCondition;
+
+    /// The user's live (not deleted) records
+    fn owned_by(self, user_id: Uuid) -> Self::Query;
+
+    /// The user's soft-deleted records, i.e. their trash
+    fn trashed_by(self, user_id: Uuid) -> Self::Query;
+
+    /// The condition of `owned_by` on its own
+    fn owned_condition(&self, user_id: Uuid) -> Self::Condition;
+
+    /// The condition of `trashed_by` on its own
+    fn trashed_condition(&self, user_id: Uuid) -> Self::TrashedCondition;
+}
+
+macro_rules! impl_owned_by {
+    ($($table:ident),+ $(,)?) => {
+        $(
+            impl<'a> OwnedBy<'a> for $table::table {
+                type Query = $table::BoxedQuery<'a, Pg>;
+                type Condition = And<Eq<$table::user_id, Uuid>, IsNull<$table::deleted_at>>;
+                type TrashedCondition =
+                    And<Eq<$table::user_id, Uuid>, IsNotNull<$table::deleted_at>>;
+
+                fn owned_by(self, user_id: Uuid) -> Self::Query {
+                    self.filter(self.owned_condition(user_id)).into_boxed()
+                }
+
+                fn trashed_by(self, user_id: Uuid) -> Self::Query {
+                    self.filter(self.trashed_condition(user_id)).into_boxed()
+                }
+
+                fn owned_condition(&self, user_id: Uuid) -> Self::Condition {
+                    $table::user_id.eq(user_id).and($table::deleted_at.is_null())
+                }
+
+                fn trashed_condition(&self, user_id: Uuid) -> Self::TrashedCondition {
+                    $table::user_id.eq(user_id).and($table::deleted_at.is_not_null())
+                }
+            }
+        )+
+    };
+}
+
+impl_owned_by!(
+    attachments,
+    bank_accounts,
+    budgets,
+    categories,
+    expense_labels,
+    expense_rules,
+    expenses,
+    imports,
+    incomes,
+    labels,
+    notifications,
+    project_labels,
+    projects,
+);
+
+/// Categories the user can see: the shared default taxonomy along with their own live ones
+pub fn visible_categories<'a>(user_id: Uuid) -> categories::BoxedQuery<'a, Pg> {
+    categories::table
+        .filter(
+            categories::user_id
+                .is_null()
+                .or(categories::user_id.eq(user_id)),
+        )
+        .filter(categories::deleted_at.is_null())
+        .into_boxed()
+}
+
+/// Raw SQL over a user's records, the counterpart of `OwnedBy` for statements
+/// diesel's DSL cannot express. The user id is bound as `$1` and every tenant
+/// table the statement reads has to be filtered on `user_id = $1` along with
+/// its `deleted_at` condition; the caller's own parameters start at `$2`
+pub fn tenant_sql_query<'a>(sql: &str, user_id: Uuid) -> BoxedSqlQuery<'a, Pg, SqlQuery> {
+    sql_query(sql)
+        .into_boxed::<Pg>()
+        .bind::<SqlUuid, _>(user_id)
+}
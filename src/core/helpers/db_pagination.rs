@@ -1,8 +1,19 @@
+use std::env;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
 use diesel::pg::Pg;
 use diesel::prelude::*;
 use diesel::query_builder::*;
-use diesel::query_dsl::methods::LoadQuery;
+use diesel::query_dsl::methods::{LimitDsl, LoadQuery};
 use diesel::sql_types::BigInt;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::core::enums::app_error::{AppError, AppResult};
+use crate::core::helpers::http::QueryParams;
 
 pub trait Paginate: Sized {
     fn paginate(self, page: i64) -> Paginated<Self>;
@@ -10,8 +21,13 @@ pub trait Paginate: Sized {
 
 pub struct PaginationResult<U> {
     pub records: Vec<U>,
-    pub total_pages: i64,
-    pub total_records: i64,
+    /// counts are only known to offset pagination, cursor pages skip the extra work
+    pub total_pages: Option<i64>,
+    pub total_records: Option<i64>,
+    pub page: Option<i64>,
+    pub per_page: i64,
+    /// resumes the listing right after the last record, when there is more to list
+    pub next_cursor: Option<String>,
 }
 
 impl<T> Paginate for T {
@@ -20,12 +36,28 @@ impl<T> Paginate for T {
             query: self,
             per_page: DEFAULT_PER_PAGE,
             page,
-            offset: (page - 1) * DEFAULT_PER_PAGE,
+            offset: (page - 1).saturating_mul(DEFAULT_PER_PAGE),
         }
     }
 }
 
-const DEFAULT_PER_PAGE: i64 = 10;
+pub const DEFAULT_PER_PAGE: i64 = 10;
+
+/// Records skipped before the (1-based) page, refusing pages too far out to be addressed
+pub fn page_offset(page: i64, per_page: i64) -> AppResult<i64> {
+    (page - 1)
+        .checked_mul(per_page)
+        .ok_or_else(|| AppError::from("The requested page is out of range"))
+}
+
+/// Largest page a client may ask for, set through `PAGINATION_MAX_PER_PAGE` (100 by default)
+pub fn max_per_page() -> i64 {
+    env::var("PAGINATION_MAX_PER_PAGE")
+        .ok()
+        .and_then(|size| size.parse::<i64>().ok())
+        .filter(|size| *size > 0)
+        .unwrap_or(100)
+}
 
 #[derive(Debug, Clone, Copy, QueryId)]
 pub struct Paginated<T> {
@@ -39,7 +71,7 @@ impl<T> Paginated<T> {
     pub fn per_page(self, per_page: i64) -> Self {
         Paginated {
             per_page,
-            offset: (self.page - 1) * per_page,
+            offset: (self.page - 1).saturating_mul(per_page),
             ..self
         }
     }
@@ -51,16 +83,20 @@ impl<T> Paginated<T> {
     where
         Self: LoadQuery<'a, PgConnection, (U, i64)>,
     {
+        let page = self.page;
         let per_page = self.per_page;
         let results = self.load::<(U, i64)>(conn)?;
-        let total = results.get(0).map(|x| x.1).unwrap_or(0);
+        let total = results.first().map(|x| x.1).unwrap_or(0);
         let records = results.into_iter().map(|x| x.0).collect();
         let total_pages = (total as f64 / per_page as f64).ceil() as i64;
 
         Ok(PaginationResult {
             records,
-            total_pages,
-            total_records: total,
+            total_pages: Some(total_pages),
+            total_records: Some(total),
+            page: Some(page),
+            per_page,
+            next_cursor: None,
         })
     }
 }
@@ -85,3 +121,433 @@ where
         Ok(())
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone)]
+pub struct SortField {
+    pub name: &'static str,
+    pub direction: SortDirection,
+}
+
+/// Parses `sort=field,-field` against the fields an entity may be sorted by,
+/// a leading `-` sorts descending and no sort at all falls back to `default`
+pub fn parse_sort(
+    sort: Option<&str>,
+    allowed: &[&'static str],
+    default: &str,
+) -> AppResult<Vec<SortField>> {
+    let sort = match sort.map(str::trim).filter(|sort| !sort.is_empty()) {
+        Some(sort) => sort,
+        None => default,
+    };
+
+    let mut fields: Vec<SortField> = vec![];
+    for part in sort
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        let (name, direction) = match part.strip_prefix('-') {
+            Some(name) => (name, SortDirection::Desc),
+            None => (part.trim_start_matches('+'), SortDirection::Asc),
+        };
+
+        let name = allowed
+            .iter()
+            .find(|allowed| **allowed == name)
+            .ok_or_else(|| {
+                AppError::BadRequest(format!(
+                    "Cannot sort by \"{}\", sortable fields are: {}",
+                    name,
+                    allowed.join(", ")
+                ))
+            })?;
+
+        if !fields.iter().any(|field| field.name == *name) {
+            fields.push(SortField { name, direction });
+        }
+    }
+
+    Ok(fields)
+}
+
+fn format_sort(fields: &[SortField]) -> String {
+    fields
+        .iter()
+        .map(|field| match field.direction {
+            SortDirection::Asc => field.name.to_string(),
+            SortDirection::Desc => format!("-{}", field.name),
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Position of the last record of a page: the value of the sort field and the
+/// record id breaking ties, handed to clients as an opaque base64 string
+#[derive(Serialize, Deserialize)]
+pub struct Cursor {
+    #[serde(rename = "s")]
+    sort: String,
+    #[serde(rename = "v")]
+    value: Value,
+    #[serde(rename = "i")]
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(encoded: &str) -> AppResult<Cursor> {
+        URL_SAFE_NO_PAD
+            .decode(encoded)
+            .ok()
+            .and_then(|json| serde_json::from_slice::<Cursor>(&json).ok())
+            .ok_or_else(|| AppError::from("Invalid pagination cursor"))
+    }
+
+    pub fn value<T: DeserializeOwned>(&self) -> AppResult<T> {
+        serde_json::from_value(self.value.clone())
+            .map_err(|_| AppError::from("Invalid pagination cursor"))
+    }
+}
+
+/// The page a client asked for, by number unless a cursor is given
+pub struct PageRequest {
+    pub page: i64,
+    pub per_page: i64,
+    pub sort: Vec<SortField>,
+    pub cursor: Option<Cursor>,
+}
+
+impl PageRequest {
+    pub fn new(
+        query_params: &mut QueryParams,
+        allowed: &[&'static str],
+        default: &str,
+    ) -> AppResult<PageRequest> {
+        let sort = parse_sort(query_params.sort.as_deref(), allowed, default)?;
+
+        let cursor = match query_params.cursor.as_deref().filter(|c| !c.is_empty()) {
+            None => None,
+            Some(encoded) => {
+                let cursor = Cursor::decode(encoded)?;
+                if sort.len() != 1 || cursor.sort != format_sort(&sort) {
+                    return Err(AppError::from(
+                        "The cursor does not belong to the requested sort",
+                    ));
+                }
+
+                Some(cursor)
+            }
+        };
+
+        let page = query_params.get_page();
+        let per_page = query_params.get_per_page();
+        page_offset(page, per_page)?;
+
+        Ok(PageRequest {
+            page,
+            per_page,
+            sort,
+            cursor,
+        })
+    }
+
+    /// Cursors are only offered when sorting by a single field
+    pub fn keyset_field(&self) -> Option<&SortField> {
+        match self.sort.as_slice() {
+            [field] => Some(field),
+            _ => None,
+        }
+    }
+
+    /// Loads the page of an already sorted query, `key` gives the id and the
+    /// serialized record the next cursor is taken from
+    pub fn load<'a, Q, U>(
+        &self,
+        query: Q,
+        conn: &mut PgConnection,
+        key: impl Fn(&U) -> (Uuid, Value),
+    ) -> AppResult<PaginationResult<U>>
+    where
+        Q: LimitDsl,
+        Paginated<Q>: LoadQuery<'a, PgConnection, (U, i64)>,
+        <Q as LimitDsl>::Output: RunQueryDsl<PgConnection> + LoadQuery<'a, PgConnection, U>,
+    {
+        let (mut result, has_more) = match self.cursor {
+            Some(_) => {
+                let mut records = query.limit(self.per_page + 1).load::<U>(conn)?;
+                let has_more = records.len() as i64 > self.per_page;
+                records.truncate(self.per_page as usize);
+
+                let result = PaginationResult {
+                    records,
+                    total_pages: None,
+                    total_records: None,
+                    page: None,
+                    per_page: self.per_page,
+                    next_cursor: None,
+                };
+
+                (result, has_more)
+            }
+            None => {
+                let result = query
+                    .paginate(self.page)
+                    .per_page(self.per_page)
+                    .load_and_count_pages::<U>(conn)?;
+
+                let has_more = self.page < result.total_pages.unwrap_or(0);
+                (result, has_more)
+            }
+        };
+
+        if let (true, Some(field), Some(last)) =
+            (has_more, self.keyset_field(), result.records.last())
+        {
+            let (id, record) = key(last);
+            let cursor = Cursor {
+                sort: format_sort(&self.sort),
+                value: record.get(field.name).cloned().unwrap_or(Value::Null),
+                id,
+            };
+
+            result.next_cursor = Some(cursor.encode());
+        }
+
+        Ok(result)
+    }
+}
+
+/// Orders a boxed query by the requested sort, mapping each sortable field to
+/// its column and type, and when resuming from a cursor only keeps the records
+/// after it; `$id` breaks ties so that pages never overlap
+macro_rules! sort_query {
+    ($query:expr, $page:expr, $id:expr, { $($name:literal => $column:expr => $type:ty),+ $(,)? }) => {{
+        use diesel::{BoolExpressionMethods as _, ExpressionMethods as _, QueryDsl as _};
+        use $crate::core::helpers::db_pagination::{PageRequest, SortDirection};
+
+        let page: &PageRequest = $page;
+        let mut query = $query;
+
+        if let (Some(cursor), Some(field)) = (&page.cursor, page.keyset_field()) {
+            query = match (field.name, field.direction) {
+                $(
+                    ($name, SortDirection::Asc) => {
+                        let value: $type = cursor.value()?;
+                        query.filter(
+                            $column
+                                .gt(value.clone())
+                                .or($column.eq(value).and($id.gt(cursor.id))),
+                        )
+                    }
+                    ($name, SortDirection::Desc) => {
+                        let value: $type = cursor.value()?;
+                        query.filter(
+                            $column
+                                .lt(value.clone())
+                                .or($column.eq(value).and($id.lt(cursor.id))),
+                        )
+                    }
+                )+
+                _ => query,
+            };
+        }
+
+        for field in &page.sort {
+            query = match (field.name, field.direction) {
+                $(
+                    ($name, SortDirection::Asc) => query.then_order_by($column.asc()),
+                    ($name, SortDirection::Desc) => query.then_order_by($column.desc()),
+                )+
+                _ => query,
+            };
+        }
+
+        match page.sort.first().map(|field| field.direction) {
+            Some(SortDirection::Asc) => query.then_order_by($id.asc()),
+            _ => query.then_order_by($id.desc()),
+        }
+    }};
+}
+
+pub(crate) use sort_query;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::ops::DerefMut;
+
+    use chrono::NaiveDate;
+    use serde_json::json;
+
+    use super::*;
+    use crate::core::helpers::get_db_conn;
+    use crate::core::helpers::testing::{create_budget, create_user, test_pool};
+    use crate::models::budget::OverspendPolicy;
+    use crate::models::expense::{ExpenseFilter, ExpenseForm};
+    use crate::models::project::ProjectForm;
+    use crate::repositories::expense_repository::ExpenseRepository;
+    use crate::repositories::project_repository::ProjectRepository;
+    use crate::schema::expenses;
+
+    const SORTS: [&str; 3] = ["created_at", "amount", "narration"];
+
+    fn sort_of(sort: Option<&str>) -> AppResult<String> {
+        parse_sort(sort, &SORTS, "-created_at").map(|fields| format_sort(&fields))
+    }
+
+    fn params(sort: &str, cursor: Option<String>) -> QueryParams {
+        QueryParams {
+            sort: Some(String::from(sort)),
+            per_page: Some(3),
+            cursor,
+            ..QueryParams::default()
+        }
+    }
+
+    #[test]
+    fn parse_sort_keeps_to_the_allowed_fields() {
+        assert_eq!(sort_of(None).unwrap(), "-created_at");
+        assert_eq!(sort_of(Some("  ")).unwrap(), "-created_at");
+        assert_eq!(
+            sort_of(Some("-amount, narration")).unwrap(),
+            "-amount,narration"
+        );
+        assert_eq!(sort_of(Some("+amount,-amount")).unwrap(), "amount");
+
+        for sort in [
+            "password",
+            "amount,user_id",
+            "-amount; DROP TABLE expenses",
+            "Amount",
+        ] {
+            assert!(sort_of(Some(sort)).is_err(), "{}", sort);
+        }
+    }
+
+    #[test]
+    fn cursors_survive_the_round_trip() {
+        let cursor = Cursor {
+            sort: String::from("-amount"),
+            value: json!(250),
+            id: Uuid::new_v4(),
+        };
+
+        let decoded = Cursor::decode(&cursor.encode()).unwrap();
+
+        assert_eq!(decoded.sort, cursor.sort);
+        assert_eq!(decoded.id, cursor.id);
+        assert_eq!(decoded.value::<i64>().unwrap(), 250);
+        assert!(decoded.value::<NaiveDate>().is_err());
+    }
+
+    #[test]
+    fn tampered_cursors_are_refused() {
+        let encoded = Cursor {
+            sort: String::from("-amount"),
+            value: json!(250),
+            id: Uuid::new_v4(),
+        }
+        .encode();
+
+        let mut flipped = encoded.clone().into_bytes();
+        flipped[3] ^= 0x01;
+        let flipped = String::from_utf8(flipped).unwrap();
+        let foreign = URL_SAFE_NO_PAD.encode(br#"{"s":"-amount","v":250,"i":"not-an-id"}"#);
+
+        for cursor in [
+            flipped.as_str(),
+            &encoded[1..],
+            "not base64!",
+            foreign.as_str(),
+        ] {
+            assert!(Cursor::decode(cursor).is_err(), "{}", cursor);
+        }
+
+        // a cursor only resumes the sort it was made for
+        let mut query_params = params("amount", Some(encoded));
+        assert!(PageRequest::new(&mut query_params, &SORTS, "-created_at").is_err());
+    }
+
+    #[test]
+    fn page_offset_refuses_overflowing_pages() {
+        assert_eq!(page_offset(1, 10).unwrap(), 0);
+        assert_eq!(page_offset(3, 25).unwrap(), 50);
+        assert!(page_offset(i64::MAX, 100).is_err());
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn cursors_page_through_equal_timestamps() {
+        let pool = test_pool();
+        let user = create_user(&pool);
+        let budget = create_budget(&pool, user.user_id, 10_000, OverspendPolicy::Allow);
+        let project = ProjectRepository
+            .create(
+                &pool,
+                user.user_id,
+                ProjectForm {
+                    name: String::from("Household"),
+                    description: String::new(),
+                },
+            )
+            .unwrap();
+
+        let mut created = HashSet::new();
+        for amount in 1..=8 {
+            let form = ExpenseForm {
+                project_id: project.project_id.to_string(),
+                amount,
+                narration: String::from("Groceries"),
+                spent_at: None,
+                category_id: None,
+            };
+            let (expense, _, _) = ExpenseRepository
+                .create(&pool, user.user_id, "UTC", budget.budget_id, form)
+                .unwrap();
+            created.insert(expense.expense_id);
+        }
+
+        let same_time = NaiveDate::from_ymd_opt(2031, 1, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        diesel::update(expenses::table.filter(expenses::user_id.eq(user.user_id)))
+            .set(expenses::created_at.eq(same_time))
+            .execute(get_db_conn(&pool).deref_mut())
+            .unwrap();
+
+        for sort in ["created_at", "-created_at"] {
+            let mut seen = vec![];
+            let mut cursor = None;
+            loop {
+                let (page, _) = ExpenseRepository
+                    .list(
+                        &pool,
+                        user.user_id,
+                        &ExpenseFilter::default(),
+                        params(sort, cursor),
+                    )
+                    .unwrap();
+                seen.extend(page.records.iter().map(|(expense, _)| expense.expense_id));
+
+                cursor = page.next_cursor;
+                if cursor.is_none() {
+                    break;
+                }
+            }
+
+            let unique: HashSet<Uuid> = seen.iter().copied().collect();
+            assert_eq!(seen.len(), created.len(), "{}", sort);
+            assert_eq!(unique, created, "{}", sort);
+        }
+    }
+}
@@ -1,15 +1,44 @@
-use crate::core::helpers::db::current_timestamp;
-use chrono::NaiveDateTime;
+use crate::core::enums::app_error::AppResult;
+use crate::core::helpers::date_time::now_in_timezone;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use std::str::FromStr;
 use uuid::Uuid;
 
-pub fn get_nullable_time(spent_at: Option<String>) -> NaiveDateTime {
+pub const DEFAULT_DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Parses a `spent_at` like wall-clock time of the user, defaulting to the current
+/// wall-clock time of their timezone
+pub fn get_nullable_time(spent_at: Option<String>, timezone: &str) -> AppResult<NaiveDateTime> {
     match spent_at {
-        None => current_timestamp(),
-        Some(val) => NaiveDateTime::parse_from_str(val.as_str(), "%Y-%m-%d %H:%M:%S").unwrap(),
+        None => Ok(now_in_timezone(timezone)),
+        Some(val) => Ok(parse_date_time(val.as_str(), DEFAULT_DATE_TIME_FORMAT)?),
     }
 }
 
-pub fn get_uuid_from_string(uuid: String) -> Uuid {
-    Uuid::from_str(uuid.as_str()).unwrap()
+/// Parses the value with the given chrono format, date-only formats resolve to midnight
+pub fn parse_date_time(value: &str, format: &str) -> Result<NaiveDateTime, String> {
+    let value = value.trim();
+    NaiveDateTime::parse_from_str(value, format)
+        .or_else(|_| {
+            NaiveDate::parse_from_str(value, format).map(|date| date.and_time(NaiveTime::MIN))
+        })
+        .map_err(|_| {
+            format!(
+                "\"{}\" does not match the date format \"{}\"",
+                value, format
+            )
+        })
+}
+
+pub fn get_uuid_from_string(uuid: String) -> AppResult<Uuid> {
+    Ok(Uuid::from_str(uuid.as_str())?)
+}
+
+pub fn parse_optional_uuid(uuid: Option<String>, entity: &str) -> Result<Option<Uuid>, String> {
+    match uuid {
+        None => Ok(None),
+        Some(val) => Uuid::from_str(val.as_str())
+            .map(Some)
+            .map_err(|_| format!("Your provided {} ID is invalid, please inspect it", entity)),
+    }
 }
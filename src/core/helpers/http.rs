@@ -1,13 +1,33 @@
-use serde::Deserialize;
+use crate::core::helpers::db_pagination::{max_per_page, DEFAULT_PER_PAGE};
+use serde::{Deserialize, Deserializer};
 use std::str::FromStr;
 use uuid::{Error, Uuid};
 
-#[derive(Deserialize)]
+tokio::task_local! {
+    /// Id of the request being handled, set by the request id middleware
+    pub static REQUEST_ID: String;
+}
+
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+#[derive(Deserialize, Clone, Default)]
 pub struct QueryParams {
     pub search: Option<String>,
     pub limit: Option<i64>,
     pub page: Option<i64>,
     pub per_page: Option<i64>,
+    /// comma separated fields, descending when prefixed with `-`, e.g. `-amount,narration`
+    pub sort: Option<String>,
+    /// `next_cursor` of the previous page, switches the listing to cursor pagination
+    pub cursor: Option<String>,
+    /// comma separated label ids, records carrying any of them are matched
+    #[serde(default, deserialize_with = "deserialize_uuid_list")]
+    pub labels: Vec<Uuid>,
+    /// comma separated category ids, expenses in any of them or their sub-categories are matched
+    #[serde(default, deserialize_with = "deserialize_uuid_list")]
+    pub categories: Vec<Uuid>,
 }
 
 #[derive(Deserialize)]
@@ -21,12 +41,39 @@ impl IdPathParam {
     }
 }
 
+#[derive(Deserialize)]
+pub struct LabelPathParam {
+    pub id: String,
+    pub label_id: String,
+}
+
+impl LabelPathParam {
+    pub fn get_uuids(&mut self) -> Result<(Uuid, Uuid), Error> {
+        Ok((
+            Uuid::from_str(self.id.as_str())?,
+            Uuid::from_str(self.label_id.as_str())?,
+        ))
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AttachmentPathParam {
+    pub id: String,
+    pub attachment_id: String,
+}
+
+impl AttachmentPathParam {
+    pub fn get_uuids(&mut self) -> Result<(Uuid, Uuid), Error> {
+        Ok((
+            Uuid::from_str(self.id.as_str())?,
+            Uuid::from_str(self.attachment_id.as_str())?,
+        ))
+    }
+}
+
 impl QueryParams {
     pub fn get_search_query(&mut self) -> String {
-        match self.search.clone() {
-            None => String::from(""),
-            Some(q) => q,
-        }
+        self.search.clone().unwrap_or_default()
     }
 
     #[allow(dead_code)]
@@ -35,10 +82,24 @@ impl QueryParams {
     }
 
     pub fn get_page(&mut self) -> i64 {
-        self.page.unwrap_or(1)
+        self.page.unwrap_or(1).max(1)
     }
 
     pub fn get_per_page(&mut self) -> i64 {
-        self.per_page.unwrap_or(10)
+        self.per_page
+            .unwrap_or(DEFAULT_PER_PAGE)
+            .clamp(1, max_per_page())
     }
 }
+
+/// Comma separated ids, e.g. `?labels=<id>,<id>`
+pub fn deserialize_uuid_list<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Uuid>, D::Error> {
+    String::deserialize(deserializer)?
+        .split(',')
+        .map(|id| id.trim())
+        .filter(|id| !id.is_empty())
+        .map(|id| Uuid::from_str(id).map_err(serde::de::Error::custom))
+        .collect()
+}
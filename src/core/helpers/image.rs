@@ -0,0 +1,68 @@
+use std::io::Cursor;
+
+use image::ImageOutputFormat;
+
+/// Longest side of generated thumbnails, in pixels
+pub const THUMBNAIL_SIZE: u32 = 320;
+
+/// Content type of a file judged by its leading bytes, only the types that
+/// can be attached to expenses are recognised
+pub fn sniff_content_type(data: &[u8]) -> Option<&'static str> {
+    match data {
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => Some("image/png"),
+        [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        [b'%', b'P', b'D', b'F', b'-', ..] => Some("application/pdf"),
+        _ => None,
+    }
+}
+
+/// PNG thumbnail of the image, keeping its aspect ratio
+pub fn make_thumbnail(data: &[u8]) -> Result<Vec<u8>, String> {
+    let image = image::load_from_memory(data).map_err(|e| e.to_string())?;
+    let mut thumbnail = Cursor::new(vec![]);
+
+    image
+        .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        .write_to(&mut thumbnail, ImageOutputFormat::Png)
+        .map_err(|e| e.to_string())?;
+
+    Ok(thumbnail.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, ImageOutputFormat};
+
+    use super::*;
+
+    #[test]
+    fn sniffs_supported_types_only() {
+        assert_eq!(sniff_content_type(b"%PDF-1.7"), Some("application/pdf"));
+        assert_eq!(sniff_content_type(b"GIF89a"), Some("image/gif"));
+        assert_eq!(
+            sniff_content_type(&[0xFF, 0xD8, 0xFF, 0xE0]),
+            Some("image/jpeg")
+        );
+        assert_eq!(sniff_content_type(b"<html></html>"), None);
+        assert_eq!(sniff_content_type(b""), None);
+    }
+
+    #[test]
+    fn thumbnails_keep_the_aspect_ratio() {
+        let mut png = Cursor::new(vec![]);
+        DynamicImage::new_rgb8(1000, 500)
+            .write_to(&mut png, ImageOutputFormat::Png)
+            .unwrap();
+
+        let thumbnail = make_thumbnail(png.get_ref()).unwrap();
+        let thumbnail = image::load_from_memory(&thumbnail).unwrap();
+
+        assert_eq!(
+            (thumbnail.width(), thumbnail.height()),
+            (THUMBNAIL_SIZE, 160)
+        );
+        assert!(make_thumbnail(b"not an image").is_err());
+    }
+}
@@ -4,15 +4,22 @@ use diesel::PgConnection;
 use r2d2::PooledConnection;
 
 pub mod auth;
+pub mod cookie;
+pub mod csrf;
 pub mod date_time;
 pub mod db;
 pub mod db_pagination;
 pub mod form;
 pub mod http;
+pub mod image;
 pub mod misc;
 pub mod number;
 pub mod responder;
 pub mod string;
+pub mod template;
+#[cfg(test)]
+pub mod testing;
+pub mod validation;
 
 pub fn get_db_conn(pool: &DBPool) -> PooledConnection<ConnectionManager<PgConnection>> {
     pool.get()
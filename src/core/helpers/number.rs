@@ -7,3 +7,20 @@ pub fn to_cent(num: i64) -> i64 {
 pub fn from_cent(num: i64) -> i64 {
     num / 100
 }
+
+/// Renders a whole amount the way it is shown to people, e.g. 1250000 as "1,250,000.00"
+pub fn format_money(amount: i64) -> String {
+    let digits = amount.unsigned_abs().to_string();
+    let mut grouped = String::new();
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+
+    match amount < 0 {
+        true => format!("-{}.00", grouped),
+        false => format!("{}.00", grouped),
+    }
+}
@@ -1,8 +1,11 @@
-use actix_web::http::StatusCode;
-use actix_web::HttpResponse;
+use actix_web::http::{header, StatusCode};
+use actix_web::{HttpRequest, HttpResponse};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use url::Url;
 
 use crate::core::helpers::db_pagination::PaginationResult;
+use crate::core::helpers::http::current_request_id;
 
 #[derive(Serialize, Deserialize)]
 pub struct JsonResponse<T: Serialize> {
@@ -12,11 +15,21 @@ pub struct JsonResponse<T: Serialize> {
 }
 
 #[derive(Serialize, Deserialize)]
-pub struct JsonPaginationResponse<T: Serialize> {
+pub struct JsonPaginationResponse<T: Serialize, S: Serialize = ()> {
     success: bool,
     data: T,
-    total_pages: i64,
-    total_records: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_pages: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_records: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page: Option<i64>,
+    per_page: i64,
+    next_page: Option<i64>,
+    next_cursor: Option<String>,
+    /// figures about every matching record, e.g. the total amount of listed expenses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<S>,
     status: u16,
 }
 
@@ -25,6 +38,21 @@ pub struct JsonSuccessMessageResponse {
     message: String,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct JsonErrorResponse {
+    code: String,
+    message: String,
+    /// lets a failure be matched with the server logs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+    /// messages per offending field of a rejected payload
+    #[serde(skip_serializing_if = "Option::is_none")]
+    errors: Option<FieldErrors>,
+}
+
+/// Field name (or path, e.g. `label_ids[1]`) to the messages describing what is wrong with it
+pub type FieldErrors = BTreeMap<String, Vec<String>>;
+
 pub fn json<T: Serialize>(data: T, status: StatusCode) -> HttpResponse {
     HttpResponse::build(status).json(data)
 }
@@ -40,17 +68,93 @@ pub fn json_success<T: Serialize>(data: T) -> HttpResponse {
     )
 }
 
-pub fn json_pagination<T: Serialize>(data: PaginationResult<T>) -> HttpResponse {
-    json(
-        JsonPaginationResponse {
-            success: true,
-            status: 200,
-            data: data.records,
-            total_pages: data.total_pages,
-            total_records: data.total_records,
-        },
-        StatusCode::OK,
-    )
+/// Paginated list, with a `Link` header pointing at the neighbouring pages
+pub fn json_pagination<T: Serialize>(req: &HttpRequest, data: PaginationResult<T>) -> HttpResponse {
+    json_pagination_summary(req, data, None::<()>)
+}
+
+pub fn json_pagination_summary<T: Serialize, S: Serialize>(
+    req: &HttpRequest,
+    data: PaginationResult<T>,
+    summary: Option<S>,
+) -> HttpResponse {
+    let next_page = match (data.page, data.total_pages) {
+        (Some(page), Some(total_pages)) if page < total_pages => Some(page + 1),
+        _ => None,
+    };
+
+    let mut response = HttpResponse::Ok();
+    if let Some(links) = pagination_links(req, &data, next_page) {
+        response.insert_header((header::LINK, links));
+    }
+
+    response.json(JsonPaginationResponse {
+        success: true,
+        status: 200,
+        data: data.records,
+        total_pages: data.total_pages,
+        total_records: data.total_records,
+        page: data.page,
+        per_page: data.per_page,
+        next_page,
+        next_cursor: data.next_cursor,
+        summary,
+    })
+}
+
+fn pagination_links<T>(
+    req: &HttpRequest,
+    data: &PaginationResult<T>,
+    next_page: Option<i64>,
+) -> Option<String> {
+    let info = req.connection_info();
+    let url = Url::parse(&format!("{}://{}{}", info.scheme(), info.host(), req.uri())).ok()?;
+
+    let mut links = vec![];
+    if let (None, Some(cursor)) = (data.page, &data.next_cursor) {
+        links.push(page_link(&url, "cursor", cursor, "next"));
+    }
+
+    if let (Some(page), Some(total_pages)) = (data.page, data.total_pages) {
+        if let Some(next_page) = next_page {
+            links.push(page_link(&url, "page", &next_page.to_string(), "next"));
+        }
+
+        if page > 1 {
+            let prev_page = page.min(total_pages + 1) - 1;
+            links.push(page_link(&url, "page", &prev_page.to_string(), "prev"));
+        }
+
+        links.push(page_link(&url, "page", "1", "first"));
+        links.push(page_link(
+            &url,
+            "page",
+            &total_pages.max(1).to_string(),
+            "last",
+        ));
+    }
+
+    match links.is_empty() {
+        true => None,
+        false => Some(links.join(", ")),
+    }
+}
+
+/// The request url with its page (or cursor) swapped, as a `Link` header entry
+fn page_link(url: &Url, param: &str, value: &str, rel: &str) -> String {
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| key != "page" && key != "cursor")
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+
+    let mut url = url.clone();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .append_pair(param, value);
+
+    format!("<{}>; rel=\"{}\"", url, rel)
 }
 
 pub fn json_error<T: Serialize>(data: T, status: StatusCode) -> HttpResponse {
@@ -64,14 +168,22 @@ pub fn json_error<T: Serialize>(data: T, status: StatusCode) -> HttpResponse {
     )
 }
 
-pub fn json_error_message(message: &str) -> HttpResponse {
-    json_error_message_status(message, StatusCode::BAD_REQUEST)
+pub fn json_error_code(message: &str, code: &str, status: StatusCode) -> HttpResponse {
+    json_error_fields(message, code, status, None)
 }
 
-pub fn json_error_message_status(message: &str, status: StatusCode) -> HttpResponse {
+pub fn json_error_fields(
+    message: &str,
+    code: &str,
+    status: StatusCode,
+    errors: Option<FieldErrors>,
+) -> HttpResponse {
     json_error(
-        JsonSuccessMessageResponse {
+        JsonErrorResponse {
+            code: code.to_string(),
             message: message.to_string(),
+            request_id: current_request_id(),
+            errors,
         },
         status,
     )
@@ -83,18 +195,73 @@ pub fn json_success_message(message: &str) -> HttpResponse {
     })
 }
 
-pub fn json_unauthorized_message(message: &str) -> HttpResponse {
-    json_error_message_status(message, StatusCode::UNAUTHORIZED)
-}
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
 
-pub fn json_invalid_uuid_response() -> HttpResponse {
-    json_error_message("Your provided ID is invalid, please inspect it")
-}
+    use super::*;
 
-pub fn json_not_found_response(message: Option<&str>) -> HttpResponse {
-    json_error_message_status(message.unwrap_or("Not Found"), StatusCode::NOT_FOUND)
-}
+    fn links_of(uri: &str, page: Option<i64>, next_cursor: Option<&str>) -> Vec<String> {
+        let req = TestRequest::get().uri(uri).to_http_request();
+        let data = PaginationResult::<()> {
+            records: vec![],
+            total_pages: page.map(|_| 3),
+            total_records: page.map(|_| 9),
+            page,
+            per_page: 3,
+            next_cursor: next_cursor.map(String::from),
+        };
+
+        let response = json_pagination(&req, data);
+        response
+            .headers()
+            .get(header::LINK)
+            .map(|links| links.to_str().unwrap().to_string())
+            .unwrap_or_default()
+            .split(", ")
+            .filter(|link| !link.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn links_to_the_neighbouring_pages() {
+        let links = links_of(
+            "/api/v1/expenses?page=2&per_page=3&sort=-amount",
+            Some(2),
+            None,
+        );
+
+        assert_eq!(
+            links,
+            [
+                "<http://localhost:8080/api/v1/expenses?per_page=3&sort=-amount&page=3>; rel=\"next\"",
+                "<http://localhost:8080/api/v1/expenses?per_page=3&sort=-amount&page=1>; rel=\"prev\"",
+                "<http://localhost:8080/api/v1/expenses?per_page=3&sort=-amount&page=1>; rel=\"first\"",
+                "<http://localhost:8080/api/v1/expenses?per_page=3&sort=-amount&page=3>; rel=\"last\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn first_and_last_pages_have_one_neighbour() {
+        let first = links_of("/api/v1/expenses", Some(1), None);
+        assert!(first[0].ends_with("page=2>; rel=\"next\""));
+        assert!(!first.iter().any(|link| link.contains("rel=\"prev\"")));
+
+        let last = links_of("/api/v1/expenses?page=3", Some(3), None);
+        assert!(last[0].ends_with("page=2>; rel=\"prev\""));
+        assert!(!last.iter().any(|link| link.contains("rel=\"next\"")));
+    }
+
+    #[test]
+    fn cursor_pages_link_to_the_next_cursor_only() {
+        let links = links_of("/api/v1/expenses?cursor=abc&page=4", None, Some("def"));
 
-pub fn json_entity_not_found_response(entity: &str) -> HttpResponse {
-    json_not_found_response(Some(format!("Such {} does not exists", entity).as_str()))
+        assert_eq!(
+            links,
+            ["<http://localhost:8080/api/v1/expenses?cursor=def>; rel=\"next\""]
+        );
+        assert!(links_of("/api/v1/expenses?cursor=abc", None, None).is_empty());
+    }
 }
@@ -0,0 +1,29 @@
+use std::collections::HashMap;
+
+use tera::{to_value, Tera, Value};
+
+use crate::core::helpers::number::format_money;
+
+/// Registers the filters shared by the server-rendered pages
+pub fn register_template_filters(tera: &mut Tera) {
+    tera.register_filter("money", money_filter);
+}
+
+/// `{{ amount | money }}` renders a whole amount as "1,250,000.00", sums
+/// fetched as text and missing sums are accepted as well
+fn money_filter(value: &Value, _: &HashMap<String, Value>) -> tera::Result<Value> {
+    let amount = match value {
+        Value::Null => Some(0),
+        Value::Number(number) => number.as_i64(),
+        Value::String(text) => text.parse::<i64>().ok(),
+        _ => None,
+    };
+
+    match amount {
+        Some(amount) => Ok(to_value(format_money(amount))?),
+        None => Err(tera::Error::msg(format!(
+            "Filter `money` received a value that is not an amount: {}",
+            value
+        ))),
+    }
+}
@@ -0,0 +1,150 @@
+use std::env;
+use std::path::PathBuf;
+
+use chrono::{Datelike, Duration, Utc};
+use diesel::r2d2::ConnectionManager;
+use diesel::PgConnection;
+use jsonwebtoken::{encode, EncodingKey, Header};
+use uuid::Uuid;
+
+use crate::models::budget::{Budget, BudgetForm, OverspendPolicy};
+use crate::models::expense::Expense;
+use crate::models::user::{RegisterForm, User};
+use crate::models::DBPool;
+use crate::repositories::budget_repository::BudgetRepository;
+use crate::repositories::user_repository::UserRepository;
+use crate::services::auth_service::TokenClaims;
+
+/// Key tokens are signed with in tests, set before any token is made or checked
+const TEST_APP_KEY: &str = "nucleus-test-key";
+
+pub fn use_test_app_key() {
+    env::set_var("APP_KEY", TEST_APP_KEY);
+}
+
+/// Directory of the local storage uploads go to once this is called
+pub fn use_test_storage() -> PathBuf {
+    let root = env::temp_dir().join("nucleus-test-storage");
+    env::set_var("STORAGE_DRIVER", "local");
+    env::set_var("STORAGE_LOCAL_DIR", &root);
+    root
+}
+
+/// Token of the given subject, expiring `lifetime` from now (in the past when negative)
+pub fn make_token(subject: Uuid, lifetime: Duration) -> String {
+    use_test_app_key();
+
+    let now = Utc::now();
+    let claims = TokenClaims {
+        sub: subject.to_string(),
+        iat: now.timestamp() as usize,
+        exp: (now + lifetime).timestamp() as usize,
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(TEST_APP_KEY.as_ref()),
+    )
+    .unwrap()
+}
+
+/// Pool of the database set up through the DB_* variables, like the server's.
+/// Tests using it are ignored by default, run them with `cargo test -- --ignored`
+pub fn test_pool() -> DBPool {
+    use_test_app_key();
+    dotenv::dotenv().ok();
+
+    let var = |name: &str| env::var(name).unwrap_or_else(|_| panic!("{} is not set", name));
+    let db_url = format!(
+        "{}://{}:{}@{}:{}/{}",
+        var("DB_DRIVER"),
+        var("DB_USERNAME"),
+        var("DB_PASSWORD"),
+        var("DB_HOST"),
+        var("DB_PORT"),
+        var("DB_DATABASE"),
+    );
+
+    r2d2::Pool::builder()
+        .max_size(2)
+        .build(ConnectionManager::<PgConnection>::new(db_url))
+        .expect("Failed to create the test pool")
+}
+
+/// Freshly registered user with a unique email address
+pub fn create_user(pool: &DBPool) -> User {
+    UserRepository
+        .create(
+            pool,
+            RegisterForm {
+                first_name: String::from("Test"),
+                last_name: String::from("User"),
+                email: format!("{}@nucleus.test", Uuid::new_v4()),
+                password: String::from("password1"),
+                timezone: None,
+            },
+        )
+        .expect("Failed to create the test user")
+}
+
+/// Budget of the given month that only lives in memory, rejecting overspending
+pub fn make_budget(amount: i64, amount_used: i64, month: i16, year: i16) -> Budget {
+    let now = Utc::now().naive_utc();
+    Budget {
+        budget_id: Uuid::new_v4(),
+        user_id: Uuid::new_v4(),
+        amount,
+        amount_used,
+        month,
+        year,
+        title: format!("{}-{}", year, month),
+        comment: None,
+        created_at: now,
+        updated_at: now,
+        deleted_at: None,
+        alert_thresholds: vec![80, 100],
+        overspend_policy: String::from("reject"),
+        over_limit_at: None,
+    }
+}
+
+/// Expense of the budget that only lives in memory
+pub fn make_expense(budget: &Budget, amount: i64) -> Expense {
+    let now = Utc::now().naive_utc();
+    Expense {
+        expense_id: Uuid::new_v4(),
+        user_id: budget.user_id,
+        project_id: Uuid::new_v4(),
+        budget_id: budget.budget_id,
+        amount,
+        narration: String::from("Groceries"),
+        spent_at: now,
+        created_at: now,
+        updated_at: now,
+        deleted_at: None,
+        category_id: None,
+        import_id: None,
+        external_id: None,
+        external_account: None,
+    }
+}
+
+/// Stored budget of the current month, alerting at 80% and 100%
+pub fn create_budget(pool: &DBPool, user_id: Uuid, amount: i64, policy: OverspendPolicy) -> Budget {
+    let today = Utc::now().date_naive();
+    BudgetRepository
+        .create(
+            pool,
+            user_id,
+            BudgetForm {
+                amount,
+                month: today.month() as i16,
+                year: today.year() as i16,
+                comment: None,
+                alert_thresholds: None,
+                overspend_policy: Some(policy),
+            },
+        )
+        .expect("Failed to create the test budget")
+}
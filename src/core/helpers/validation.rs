@@ -0,0 +1,109 @@
+use std::borrow::Cow;
+use std::str::FromStr;
+
+use serde_json::Value;
+use uuid::Uuid;
+use validator::ValidationError;
+
+use crate::core::helpers::date_time::parse_timezone;
+use crate::core::helpers::form::{parse_date_time, DEFAULT_DATE_TIME_FORMAT};
+use crate::models::label::LabelModule;
+
+/// Human readable message for the built-in rules, custom rules carry their own
+pub fn describe_validation_error(error: &ValidationError) -> String {
+    if let Some(message) = &error.message {
+        return message.to_string();
+    }
+
+    let min = error.params.get("min").map(display_param);
+    let max = error.params.get("max").map(display_param);
+
+    match error.code.as_ref() {
+        "length" => match (min, max) {
+            (Some(min), Some(max)) => format!("Must be between {} and {} characters", min, max),
+            (Some(min), None) => format!("Must be at least {} characters", min),
+            (None, Some(max)) => format!("Must not be longer than {} characters", max),
+            (None, None) => String::from("Has an invalid length"),
+        },
+        "range" => match (min, max) {
+            (Some(min), Some(max)) => format!("Must be between {} and {}", min, max),
+            (Some(min), None) => format!("Must be at least {}", min),
+            (None, Some(max)) => format!("Must not be greater than {}", max),
+            (None, None) => String::from("Is out of range"),
+        },
+        "email" => String::from("Must be a valid email address"),
+        "required" => String::from("This field is required"),
+        _ => String::from("Is invalid"),
+    }
+}
+
+/// Range bounds are kept as floats, whole numbers read better without the fraction
+fn display_param(value: &Value) -> String {
+    match value.as_f64() {
+        Some(number) if number.fract() == 0.0 => format!("{}", number as i64),
+        _ => value.to_string(),
+    }
+}
+
+fn invalid(code: &'static str, message: &'static str) -> ValidationError {
+    let mut error = ValidationError::new(code);
+    error.message = Some(Cow::Borrowed(message));
+    error
+}
+
+pub fn validate_not_blank(value: &str) -> Result<(), ValidationError> {
+    match value.trim().is_empty() {
+        true => Err(invalid("blank", "Must not be blank")),
+        false => Ok(()),
+    }
+}
+
+pub fn validate_uuid(value: &str) -> Result<(), ValidationError> {
+    Uuid::from_str(value)
+        .map(|_| ())
+        .map_err(|_| invalid("uuid", "Must be a valid ID"))
+}
+
+pub fn validate_uuids(values: &[String]) -> Result<(), ValidationError> {
+    match values.iter().all(|value| Uuid::from_str(value).is_ok()) {
+        true => Ok(()),
+        false => Err(invalid("uuid", "Must only contain valid IDs")),
+    }
+}
+
+pub fn validate_date_time(value: &str) -> Result<(), ValidationError> {
+    parse_date_time(value, DEFAULT_DATE_TIME_FORMAT)
+        .map(|_| ())
+        .map_err(|_| {
+            invalid(
+                "date_time",
+                "Must be a date formatted as YYYY-MM-DD or YYYY-MM-DD HH:MM:SS",
+            )
+        })
+}
+
+pub fn validate_timezone(value: &str) -> Result<(), ValidationError> {
+    parse_timezone(value)
+        .map(|_| ())
+        .map_err(|_| invalid("timezone", "Must be an IANA timezone such as Africa/Lagos"))
+}
+
+/// Passwords need at least a letter and a digit, length is checked separately
+pub fn validate_password(value: &str) -> Result<(), ValidationError> {
+    let has_letter = value.chars().any(|char| char.is_alphabetic());
+    let has_digit = value.chars().any(|char| char.is_ascii_digit());
+
+    match has_letter && has_digit {
+        true => Ok(()),
+        false => Err(invalid(
+            "password",
+            "Must contain at least one letter and one digit",
+        )),
+    }
+}
+
+pub fn validate_label_module(value: &str) -> Result<(), ValidationError> {
+    LabelModule::parse(value)
+        .map(|_| ())
+        .map_err(|_| invalid("label_module", "Must be either projects or expenses"))
+}
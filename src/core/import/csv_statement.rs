@@ -0,0 +1,226 @@
+use csv::{ReaderBuilder, StringRecord};
+
+use crate::core::helpers::form::parse_date_time;
+use crate::core::import::{parse_amount, AmountFormat};
+use crate::models::import::{ImportMapping, ImportRow};
+
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Header row of the statement
+pub fn read_headers(content: &str, delimiter: Option<char>) -> Result<Vec<String>, String> {
+    let mut reader = ReaderBuilder::new()
+        .delimiter(delimiter_byte(delimiter)?)
+        .from_reader(content.as_bytes());
+
+    let headers = reader.headers().map_err(|e| e.to_string())?;
+    Ok(headers
+        .iter()
+        .map(|header| header.trim().to_string())
+        .collect())
+}
+
+/// Reads every statement line into a row, problems with individual values are
+/// collected on the row instead of failing the whole file
+pub fn parse(
+    content: &str,
+    mapping: &ImportMapping,
+    format: AmountFormat,
+) -> Result<Vec<ImportRow>, String> {
+    let has_headers = mapping.has_headers.unwrap_or(true);
+    let mut reader = ReaderBuilder::new()
+        .delimiter(delimiter_byte(mapping.delimiter)?)
+        .has_headers(has_headers)
+        .flexible(true)
+        .from_reader(content.as_bytes());
+
+    let headers = match has_headers {
+        true => reader.headers().map_err(|e| e.to_string())?.clone(),
+        false => StringRecord::new(),
+    };
+
+    let amount_index = column_index(&headers, &mapping.amount_column, "amount_column")?;
+    let narration_index = column_index(&headers, &mapping.narration_column, "narration_column")?;
+    let date_index = column_index(&headers, &mapping.date_column, "date_column")?;
+    let date_format = mapping
+        .date_format
+        .clone()
+        .unwrap_or(DEFAULT_DATE_FORMAT.to_string());
+
+    let mut rows = vec![];
+    for record in reader.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        let line = record
+            .position()
+            .map(|pos| pos.line() as usize)
+            .unwrap_or(0);
+
+        // blank lines at the end of exported statements are common
+        if record.iter().all(|value| value.trim().is_empty()) {
+            continue;
+        }
+
+        let mut row = ImportRow::new(line);
+
+        match record.get(narration_index).map(|value| value.trim()) {
+            Some(narration) if !narration.is_empty() => row.narration = Some(narration.to_string()),
+            _ => row.errors.push(String::from("Narration is missing")),
+        }
+
+        match record
+            .get(amount_index)
+            .map(|value| parse_amount(value, format))
+        {
+            Some(Ok(amount)) => row.amount = Some(amount),
+            Some(Err(err)) => row.errors.push(err),
+            None => row.errors.push(String::from("Amount is missing")),
+        }
+
+        match record
+            .get(date_index)
+            .map(|value| parse_date_time(value, date_format.as_str()))
+        {
+            Some(Ok(spent_at)) => row.spent_at = Some(spent_at),
+            Some(Err(err)) => row.errors.push(err),
+            None => row.errors.push(String::from("Date is missing")),
+        }
+
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+fn column_index(
+    headers: &StringRecord,
+    column: &Option<String>,
+    field: &str,
+) -> Result<usize, String> {
+    let column = match column {
+        Some(column) => column.trim(),
+        None => return Err(format!("{} is required for CSV imports", field)),
+    };
+
+    if let Some(index) = headers
+        .iter()
+        .position(|header| header.trim().eq_ignore_ascii_case(column))
+    {
+        return Ok(index);
+    }
+
+    column
+        .parse::<usize>()
+        .map_err(|_| format!("Column \"{}\" does not exist in this file", column))
+}
+
+fn delimiter_byte(delimiter: Option<char>) -> Result<u8, String> {
+    match delimiter.unwrap_or(',') {
+        char if char.is_ascii() => Ok(char as u8),
+        _ => Err(String::from("Delimiter must be a single ASCII character")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn mapping(amount: &str, narration: &str, date: &str) -> ImportMapping {
+        ImportMapping {
+            amount_column: Some(String::from(amount)),
+            narration_column: Some(String::from(narration)),
+            date_column: Some(String::from(date)),
+            ..ImportMapping::default()
+        }
+    }
+
+    #[test]
+    fn columns_are_found_by_header_name() {
+        let content =
+            "Date,Description,Amount\n2026-10-01,Coffee,\"1,250.00\"\n\n2026-10-02,Rent,-300\n";
+
+        let rows = parse(
+            content,
+            &mapping("amount", "DESCRIPTION", "Date"),
+            AmountFormat::default(),
+        )
+        .unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].line, 2);
+        assert_eq!(rows[0].narration.as_deref(), Some("Coffee"));
+        assert_eq!(rows[0].amount, Some(1250));
+        assert_eq!(
+            rows[0].spent_at,
+            NaiveDate::from_ymd_opt(2026, 10, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+        );
+        assert_eq!(rows[1].amount, Some(300));
+        assert!(rows.iter().all(|row| row.is_valid()));
+    }
+
+    #[test]
+    fn columns_are_found_by_position_without_headers() {
+        let content = "01/10/2026;Bakery;1.250,50\n";
+        let mapping = ImportMapping {
+            date_format: Some(String::from("%d/%m/%Y")),
+            delimiter: Some(';'),
+            has_headers: Some(false),
+            decimal_separator: Some(','),
+            ..mapping("2", "1", "0")
+        };
+
+        let rows = parse(
+            content,
+            &mapping,
+            AmountFormat::from_mapping(&mapping).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].amount, Some(1251));
+        assert!(rows[0].is_valid());
+    }
+
+    #[test]
+    fn bad_values_are_reported_on_their_row() {
+        let content = "date,narration,amount\nyesterday,,\"1,25\"\n";
+
+        let rows = parse(
+            content,
+            &mapping("amount", "narration", "date"),
+            AmountFormat::default(),
+        )
+        .unwrap();
+
+        assert_eq!(rows[0].errors.len(), 3);
+        assert_eq!(rows[0].errors[0], "Narration is missing");
+    }
+
+    #[test]
+    fn unknown_columns_fail_the_file() {
+        let content = "date,narration,amount\n";
+
+        let result = parse(
+            content,
+            &mapping("total", "narration", "date"),
+            AmountFormat::default(),
+        );
+
+        assert_eq!(
+            result.err().as_deref(),
+            Some("Column \"total\" does not exist in this file")
+        );
+        assert!(parse(content, &ImportMapping::default(), AmountFormat::default()).is_err());
+    }
+
+    #[test]
+    fn headers_are_trimmed() {
+        assert_eq!(
+            read_headers(" date | amount \n", Some('|')).unwrap(),
+            vec!["date", "amount"]
+        );
+        assert!(read_headers("a,b", Some('€')).is_err());
+    }
+}
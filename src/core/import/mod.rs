@@ -0,0 +1,285 @@
+use crate::models::import::{
+    Import, ImportMapping, ImportRow, IMPORT_SOURCE_CSV, IMPORT_SOURCE_OFX, IMPORT_SOURCE_QIF,
+};
+
+pub mod csv_statement;
+pub mod ofx_statement;
+pub mod qif_statement;
+
+/// Guesses the statement format from the file extension, falling back to its content
+pub fn detect_source(file_name: &str, content: &str) -> &'static str {
+    let file_name = file_name.to_lowercase();
+    if file_name.ends_with(".ofx") || file_name.ends_with(".qfx") {
+        return IMPORT_SOURCE_OFX;
+    }
+
+    if file_name.ends_with(".qif") {
+        return IMPORT_SOURCE_QIF;
+    }
+
+    let head = content.trim_start().to_uppercase();
+    if head.starts_with("OFXHEADER") || (head.starts_with("<?XML") && head.contains("<OFX>")) {
+        return IMPORT_SOURCE_OFX;
+    }
+
+    if head.starts_with("!TYPE:") || head.starts_with("!ACCOUNT") {
+        return IMPORT_SOURCE_QIF;
+    }
+
+    IMPORT_SOURCE_CSV
+}
+
+const DECIMAL_SEPARATORS: [char; 2] = ['.', ','];
+const THOUSANDS_SEPARATORS: [char; 4] = [',', '.', ' ', '\''];
+
+/// How the statement writes its amounts, "1,250.00" or "1.250,00" for instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmountFormat {
+    pub decimal_separator: char,
+    pub thousands_separator: char,
+}
+
+impl Default for AmountFormat {
+    fn default() -> Self {
+        AmountFormat {
+            decimal_separator: '.',
+            thousands_separator: ',',
+        }
+    }
+}
+
+impl AmountFormat {
+    pub fn from_mapping(mapping: &ImportMapping) -> Result<AmountFormat, String> {
+        let decimal_separator = mapping.decimal_separator.unwrap_or('.');
+        let thousands_separator = mapping
+            .thousands_separator
+            .unwrap_or(match decimal_separator {
+                ',' => '.',
+                _ => ',',
+            });
+
+        if !DECIMAL_SEPARATORS.contains(&decimal_separator) {
+            return Err(String::from(
+                "decimal_separator must be either \".\" or \",\"",
+            ));
+        }
+
+        if !THOUSANDS_SEPARATORS.contains(&thousands_separator) {
+            return Err(String::from(
+                "thousands_separator must be one of \",\", \".\", \" \" or \"'\"",
+            ));
+        }
+
+        if decimal_separator == thousands_separator {
+            return Err(String::from(
+                "decimal_separator and thousands_separator must differ",
+            ));
+        }
+
+        Ok(AmountFormat {
+            decimal_separator,
+            thousands_separator,
+        })
+    }
+}
+
+/// Reads the uploaded statement into rows according to its format
+pub fn parse_statement(import: &Import, mapping: &ImportMapping) -> Result<Vec<ImportRow>, String> {
+    let content = import.content.as_str();
+    let format = AmountFormat::from_mapping(mapping)?;
+    match import.source.as_str() {
+        IMPORT_SOURCE_OFX => ofx_statement::parse(content, format),
+        IMPORT_SOURCE_QIF => qif_statement::parse(content, mapping.date_format.as_deref(), format),
+        _ => csv_statement::parse(content, mapping, format),
+    }
+}
+
+/// Turns a statement amount ("1,250.00", "-300", "(45.50)", "₦2,000") into a
+/// whole amount, fractions are rounded to the nearest unit and parentheses mean negative.
+/// Amounts that don't fit the format, like "1,25" when "," groups thousands, are refused
+/// rather than guessed
+pub fn parse_signed_amount(value: &str, format: AmountFormat) -> Result<i64, String> {
+    let value = value.trim();
+    let invalid = || format!("\"{}\" is not a valid amount", value);
+    let ambiguous = || {
+        format!(
+            "\"{}\" does not match the decimal separator \"{}\" and thousands separator \"{}\"",
+            value, format.decimal_separator, format.thousands_separator
+        )
+    };
+
+    // currency symbols and signs around the number
+    let outside = |char: char| !char.is_ascii_digit() && char != format.decimal_separator;
+    let rest = value.trim_start_matches(outside);
+    let number = rest.trim_end_matches(outside);
+    if number.is_empty() {
+        return Err(invalid());
+    }
+    let prefix = &value[..value.len() - rest.len()];
+    let suffix = &rest[number.len()..];
+    let negative = prefix.contains('-')
+        || suffix.contains('-')
+        || (prefix.contains('(') && suffix.contains(')'));
+
+    let mut parts = number.split(format.decimal_separator);
+    let whole = parts.next().unwrap_or_default();
+    let fraction = parts.next().unwrap_or_default();
+    if parts.next().is_some() {
+        return Err(ambiguous());
+    }
+
+    if !fraction.chars().all(|char| char.is_ascii_digit()) {
+        return Err(match fraction.contains(format.thousands_separator) {
+            true => ambiguous(),
+            false => invalid(),
+        });
+    }
+
+    let groups: Vec<&str> = whole.split(format.thousands_separator).collect();
+    if groups
+        .iter()
+        .any(|group| !group.chars().all(|char| char.is_ascii_digit()))
+    {
+        let separated = THOUSANDS_SEPARATORS
+            .iter()
+            .any(|separator| whole.contains(*separator));
+        return Err(match separated {
+            true => ambiguous(),
+            false => invalid(),
+        });
+    }
+
+    if groups.len() > 1
+        && (groups[0].is_empty()
+            || groups[0].len() > 3
+            || groups[1..].iter().any(|group| group.len() != 3))
+    {
+        return Err(ambiguous());
+    }
+
+    let amount = format!("{}.{}", groups.concat(), fraction)
+        .trim_end_matches('.')
+        .parse::<f64>()
+        .map_err(|_| invalid())?
+        .round() as i64;
+
+    match negative {
+        true => Ok(-amount),
+        false => Ok(amount),
+    }
+}
+
+/// Same as [parse_signed_amount] but for amounts that are expenses whatever their sign
+pub fn parse_amount(value: &str, format: AmountFormat) -> Result<i64, String> {
+    let amount = parse_signed_amount(value, format)?.abs();
+
+    match amount > 0 {
+        true => Ok(amount),
+        false => Err(String::from("Amount must be greater than zero")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EUROPEAN: AmountFormat = AmountFormat {
+        decimal_separator: ',',
+        thousands_separator: '.',
+    };
+
+    #[test]
+    fn amounts_follow_the_default_format() {
+        let format = AmountFormat::default();
+
+        assert_eq!(parse_signed_amount("1,250.00", format), Ok(1250));
+        assert_eq!(parse_signed_amount("-300", format), Ok(-300));
+        assert_eq!(parse_signed_amount("(45.50)", format), Ok(-46));
+        assert_eq!(parse_signed_amount("₦2,000", format), Ok(2000));
+        assert_eq!(parse_signed_amount("-$1,234,567.49", format), Ok(-1234567));
+        assert_eq!(parse_signed_amount("12.5 USD", format), Ok(13));
+    }
+
+    #[test]
+    fn amounts_follow_the_configured_separators() {
+        assert_eq!(parse_signed_amount("1.250,00", EUROPEAN), Ok(1250));
+        assert_eq!(parse_signed_amount("-12,50", EUROPEAN), Ok(-13));
+        assert_eq!(parse_signed_amount("€ 2.000", EUROPEAN), Ok(2000));
+
+        let swiss = AmountFormat {
+            decimal_separator: '.',
+            thousands_separator: '\'',
+        };
+        assert_eq!(parse_signed_amount("CHF 1'250.75", swiss), Ok(1251));
+    }
+
+    #[test]
+    fn amounts_not_matching_the_format_are_refused() {
+        let format = AmountFormat::default();
+
+        for value in ["1.250,00", "1,25", "12,50", "1 250", "1.2.3", "1,2345.00"] {
+            let err = parse_signed_amount(value, format).unwrap_err();
+            assert!(
+                err.contains("does not match the decimal separator"),
+                "{value}: {err}"
+            );
+        }
+
+        for value in ["1,250.00", "12.50"] {
+            assert!(parse_signed_amount(value, EUROPEAN).is_err(), "{value}");
+        }
+    }
+
+    #[test]
+    fn amounts_without_digits_are_invalid() {
+        let format = AmountFormat::default();
+
+        assert_eq!(
+            parse_signed_amount("n/a", format),
+            Err(String::from("\"n/a\" is not a valid amount"))
+        );
+        assert!(parse_signed_amount("", format).is_err());
+        assert!(parse_signed_amount("12-34", format).is_err());
+    }
+
+    #[test]
+    fn expense_amounts_must_be_positive() {
+        let format = AmountFormat::default();
+
+        assert_eq!(parse_amount("-300", format), Ok(300));
+        assert_eq!(
+            parse_amount("0.20", format),
+            Err(String::from("Amount must be greater than zero"))
+        );
+    }
+
+    #[test]
+    fn formats_come_from_the_mapping() {
+        let mut mapping = ImportMapping::default();
+        assert_eq!(
+            AmountFormat::from_mapping(&mapping),
+            Ok(AmountFormat::default())
+        );
+
+        mapping.decimal_separator = Some(',');
+        assert_eq!(AmountFormat::from_mapping(&mapping), Ok(EUROPEAN));
+
+        mapping.thousands_separator = Some(',');
+        assert!(AmountFormat::from_mapping(&mapping).is_err());
+
+        mapping.decimal_separator = Some('x');
+        assert!(AmountFormat::from_mapping(&mapping).is_err());
+    }
+
+    #[test]
+    fn sources_are_detected_by_name_then_content() {
+        assert_eq!(detect_source("bank.QFX", ""), IMPORT_SOURCE_OFX);
+        assert_eq!(detect_source("bank.qif", ""), IMPORT_SOURCE_QIF);
+        assert_eq!(
+            detect_source("bank.txt", "OFXHEADER:100"),
+            IMPORT_SOURCE_OFX
+        );
+        assert_eq!(detect_source("bank.txt", "!Type:Bank\n"), IMPORT_SOURCE_QIF);
+        assert_eq!(detect_source("bank.txt", "date,amount"), IMPORT_SOURCE_CSV);
+    }
+}
@@ -0,0 +1,213 @@
+use std::sync::OnceLock;
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use regex::Regex;
+
+use crate::core::import::{parse_signed_amount, AmountFormat};
+use crate::models::import::{ImportRow, TransactionKind};
+
+#[derive(Default)]
+struct Transaction {
+    posted_at: Option<String>,
+    amount: Option<String>,
+    fitid: Option<String>,
+    name: Option<String>,
+    memo: Option<String>,
+}
+
+/// Reads the STMTTRN entries of both SGML (1.x) and XML (2.x) statements,
+/// rows are numbered in the order the transactions appear
+pub fn parse(content: &str, format: AmountFormat) -> Result<Vec<ImportRow>, String> {
+    if !content.to_uppercase().contains("<OFX>") {
+        return Err(String::from(
+            "The uploaded file is not a valid OFX statement",
+        ));
+    }
+
+    // SGML statements leave elements unclosed, so a value runs until the next tag
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    let tag = TAG.get_or_init(|| Regex::new(r"<(/?)([A-Za-z0-9.]+)>([^<]*)").unwrap());
+
+    let mut rows = vec![];
+    let mut account: Option<String> = None;
+    let mut current: Option<Transaction> = None;
+
+    for captures in tag.captures_iter(content) {
+        let closing = &captures[1] == "/";
+        let name = captures[2].to_uppercase();
+        let value = captures[3].trim().to_string();
+
+        match (closing, name.as_str()) {
+            (false, "ACCTID") => account = Some(value).filter(|value| !value.is_empty()),
+            (false, "STMTTRN") => current = Some(Transaction::default()),
+            (true, "STMTTRN") => {
+                if let Some(transaction) = current.take() {
+                    rows.push(into_row(
+                        rows.len() + 1,
+                        transaction,
+                        account.clone(),
+                        format,
+                    ));
+                }
+            }
+            (false, field) => {
+                if let Some(transaction) = current.as_mut() {
+                    let value = Some(value).filter(|value| !value.is_empty());
+                    match field {
+                        "DTPOSTED" => transaction.posted_at = value,
+                        "TRNAMT" => transaction.amount = value,
+                        "FITID" => transaction.fitid = value,
+                        "NAME" => transaction.name = value,
+                        "MEMO" => transaction.memo = value,
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(rows)
+}
+
+fn into_row(
+    line: usize,
+    transaction: Transaction,
+    account: Option<String>,
+    format: AmountFormat,
+) -> ImportRow {
+    let mut row = ImportRow::new(line);
+    row.account = account;
+    row.external_id = transaction.fitid;
+
+    match transaction.name.or(transaction.memo) {
+        Some(narration) => row.narration = Some(decode_entities(narration.as_str())),
+        None => row.errors.push(String::from("Narration is missing")),
+    }
+
+    match transaction
+        .amount
+        .as_deref()
+        .map(|amount| parse_signed_amount(amount, format))
+    {
+        Some(Ok(0)) => row
+            .errors
+            .push(String::from("Amount must be greater than zero")),
+        Some(Ok(amount)) => {
+            row.kind = match amount < 0 {
+                true => TransactionKind::Expense,
+                false => TransactionKind::Income,
+            };
+            row.amount = Some(amount.abs());
+        }
+        Some(Err(err)) => row.errors.push(err),
+        None => row.errors.push(String::from("Amount is missing")),
+    }
+
+    match transaction.posted_at.as_deref().map(parse_date) {
+        Some(Some(posted_at)) => row.spent_at = Some(posted_at),
+        Some(None) => row.errors.push(String::from("Posting date is invalid")),
+        None => row.errors.push(String::from("Posting date is missing")),
+    }
+
+    row
+}
+
+/// OFX dates look like "20261019", "20261019120000" or "20261019120000.000[-5:EST]"
+fn parse_date(value: &str) -> Option<NaiveDateTime> {
+    let digits: String = value
+        .chars()
+        .take_while(|char| char.is_ascii_digit())
+        .collect();
+    let date = NaiveDate::parse_from_str(digits.get(0..8)?, "%Y%m%d").ok()?;
+    let time = digits
+        .get(8..14)
+        .and_then(|time| NaiveTime::parse_from_str(time, "%H%M%S").ok())
+        .unwrap_or(NaiveTime::MIN);
+
+    Some(date.and_time(time))
+}
+
+fn decode_entities(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SGML: &str = "OFXHEADER:100
+DATA:OFXSGML
+
+<OFX>
+<BANKMSGSRSV1><STMTTRNRS><STMTRS>
+<BANKACCTFROM><BANKID>011<ACCTID>12345678<ACCTTYPE>CHECKING</BANKACCTFROM>
+<BANKTRANLIST>
+<STMTTRN><TRNTYPE>DEBIT<DTPOSTED>20261019120000.000[-5:EST]<TRNAMT>-45.50<FITID>T1<NAME>Ben &amp; Jerry's</STMTTRN>
+<STMTTRN><TRNTYPE>CREDIT<DTPOSTED>20261020<TRNAMT>1250.00<FITID>T2<MEMO>Salary</STMTTRN>
+<STMTTRN><TRNTYPE>DEBIT<DTPOSTED>2026<TRNAMT>0<FITID>T3</STMTTRN>
+</BANKTRANLIST>
+</STMTRS></STMTTRNRS></BANKMSGSRSV1>
+</OFX>";
+
+    #[test]
+    fn sgml_transactions_are_read() {
+        let rows = parse(SGML, AmountFormat::default()).unwrap();
+
+        assert_eq!(rows.len(), 3);
+
+        assert_eq!(rows[0].line, 1);
+        assert_eq!(rows[0].account.as_deref(), Some("12345678"));
+        assert_eq!(rows[0].external_id.as_deref(), Some("T1"));
+        assert_eq!(rows[0].narration.as_deref(), Some("Ben & Jerry's"));
+        assert_eq!(rows[0].amount, Some(46));
+        assert!(rows[0].kind == TransactionKind::Expense);
+        assert_eq!(
+            rows[0].spent_at,
+            NaiveDate::from_ymd_opt(2026, 10, 19)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+        );
+
+        assert_eq!(rows[1].narration.as_deref(), Some("Salary"));
+        assert_eq!(rows[1].amount, Some(1250));
+        assert!(rows[1].kind == TransactionKind::Income);
+        assert!(rows[1].is_valid());
+
+        assert_eq!(
+            rows[2].errors,
+            vec![
+                "Narration is missing",
+                "Amount must be greater than zero",
+                "Posting date is invalid"
+            ]
+        );
+    }
+
+    #[test]
+    fn xml_transactions_are_read() {
+        let content = "<?xml version=\"1.0\"?><OFX><BANKACCTFROM><ACCTID>DE89</ACCTID></BANKACCTFROM>
+            <STMTTRN><DTPOSTED>20261001</DTPOSTED><TRNAMT>-1.250,75</TRNAMT><FITID>X1</FITID><NAME>Miete</NAME></STMTTRN></OFX>";
+        let format = AmountFormat {
+            decimal_separator: ',',
+            thousands_separator: '.',
+        };
+
+        let rows = parse(content, format).unwrap();
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].account.as_deref(), Some("DE89"));
+        assert_eq!(rows[0].amount, Some(1251));
+        assert!(rows[0].is_valid());
+    }
+
+    #[test]
+    fn other_files_are_refused() {
+        assert!(parse("date,amount\n", AmountFormat::default()).is_err());
+    }
+}
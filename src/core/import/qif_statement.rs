@@ -0,0 +1,245 @@
+use chrono::NaiveDateTime;
+
+use crate::core::helpers::form::parse_date_time;
+use crate::core::import::{parse_signed_amount, AmountFormat};
+use crate::models::import::{ImportRow, TransactionKind};
+
+/// QIF has no standard date layout, US style dates are the most common one.
+/// Two-digit years go first as "%Y" would happily read "26" as the year 26
+const DATE_FORMATS: [&str; 5] = ["%m/%d/%y", "%m/%d/%Y", "%d/%m/%y", "%d/%m/%Y", "%Y-%m-%d"];
+
+#[derive(Default)]
+struct Record {
+    line: usize,
+    date: Option<String>,
+    amount: Option<String>,
+    payee: Option<String>,
+    memo: Option<String>,
+}
+
+/// Reads the non-investment records of the statement, `!Account` blocks name
+/// the account the following records belong to
+pub fn parse(
+    content: &str,
+    date_format: Option<&str>,
+    format: AmountFormat,
+) -> Result<Vec<ImportRow>, String> {
+    let mut rows = vec![];
+    let mut account: Option<String> = None;
+    let mut in_account_block = false;
+    let mut skip_records = false;
+    let mut record = Record::default();
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with('!') {
+            let header = line.to_lowercase();
+            in_account_block = header.starts_with("!account");
+            skip_records = header.starts_with("!type:invst") || header.starts_with("!option");
+            continue;
+        }
+
+        // the code is one character, which is not always one byte
+        let mut chars = line.chars();
+        let code = match chars.next() {
+            Some(code) => code,
+            None => continue,
+        };
+        let value = chars.as_str().trim().to_string();
+
+        if in_account_block {
+            match code {
+                'N' => account = Some(value),
+                '^' => in_account_block = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        if record.line == 0 {
+            record.line = index + 1;
+        }
+
+        match code {
+            'D' => record.date = Some(value),
+            'T' => record.amount = Some(value),
+            'U' if record.amount.is_none() => record.amount = Some(value),
+            'P' => record.payee = Some(value),
+            'M' => record.memo = Some(value),
+            '^' => {
+                let finished = std::mem::take(&mut record);
+                if !skip_records {
+                    rows.push(into_row(finished, account.clone(), date_format, format));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if rows.is_empty() && !content.trim_start().starts_with('!') {
+        return Err(String::from(
+            "The uploaded file is not a valid QIF statement",
+        ));
+    }
+
+    Ok(rows)
+}
+
+fn into_row(
+    record: Record,
+    account: Option<String>,
+    date_format: Option<&str>,
+    format: AmountFormat,
+) -> ImportRow {
+    let mut row = ImportRow::new(record.line);
+    row.account = account;
+
+    match record
+        .payee
+        .or(record.memo)
+        .filter(|value| !value.is_empty())
+    {
+        Some(narration) => row.narration = Some(narration),
+        None => row.errors.push(String::from("Narration is missing")),
+    }
+
+    match record
+        .amount
+        .as_deref()
+        .map(|amount| parse_signed_amount(amount, format))
+    {
+        Some(Ok(0)) => row
+            .errors
+            .push(String::from("Amount must be greater than zero")),
+        Some(Ok(amount)) => {
+            row.kind = match amount < 0 {
+                true => TransactionKind::Expense,
+                false => TransactionKind::Income,
+            };
+            row.amount = Some(amount.abs());
+        }
+        Some(Err(err)) => row.errors.push(err),
+        None => row.errors.push(String::from("Amount is missing")),
+    }
+
+    match record
+        .date
+        .as_deref()
+        .map(|date| parse_date(date, date_format))
+    {
+        Some(Ok(spent_at)) => row.spent_at = Some(spent_at),
+        Some(Err(err)) => row.errors.push(err),
+        None => row.errors.push(String::from("Date is missing")),
+    }
+
+    row
+}
+
+/// Quicken writes dates like "10/19'26" or "1/ 5/2026"
+fn parse_date(value: &str, date_format: Option<&str>) -> Result<NaiveDateTime, String> {
+    let value = value.replace('\'', "/").replace(' ', "");
+
+    match date_format {
+        Some(format) => parse_date_time(value.as_str(), format),
+        None => DATE_FORMATS
+            .iter()
+            .find_map(|format| parse_date_time(value.as_str(), format).ok())
+            .ok_or(format!("\"{}\" is not a recognised QIF date", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    #[test]
+    fn bank_records_are_read_with_their_account() {
+        let content = "!Account
+NChecking
+TBank
+^
+!Type:Bank
+D10/19'26
+T-1,250.00
+PLandlord
+^
+D10/20/2026
+U300
+MRefund
+^
+";
+
+        let rows = parse(content, None, AmountFormat::default()).unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].line, 6);
+        assert_eq!(rows[0].account.as_deref(), Some("Checking"));
+        assert_eq!(rows[0].narration.as_deref(), Some("Landlord"));
+        assert_eq!(rows[0].amount, Some(1250));
+        assert!(rows[0].kind == TransactionKind::Expense);
+        assert_eq!(
+            rows[0].spent_at,
+            NaiveDate::from_ymd_opt(2026, 10, 19)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+        );
+
+        assert_eq!(rows[1].narration.as_deref(), Some("Refund"));
+        assert_eq!(rows[1].amount, Some(300));
+        assert!(rows[1].kind == TransactionKind::Income);
+    }
+
+    #[test]
+    fn non_ascii_lines_are_read() {
+        let content = "!Account\nNCompte courant\n^\n!Type:Bank\nD10/19/2026\nT-12.50\nPCafé Façade\nMÜberweisung\n€ stray line\n^\n";
+
+        let rows = parse(content, None, AmountFormat::default()).unwrap();
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].account.as_deref(), Some("Compte courant"));
+        assert_eq!(rows[0].narration.as_deref(), Some("Café Façade"));
+        assert_eq!(rows[0].amount, Some(13));
+        assert!(rows[0].is_valid());
+    }
+
+    #[test]
+    fn investment_records_are_skipped() {
+        let content =
+            "!Type:Invst\nD10/19/2026\nT100\nPShares\n^\n!Type:Cash\nD10/19/2026\nT-5\nPTaxi\n^\n";
+
+        let rows = parse(content, None, AmountFormat::default()).unwrap();
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].narration.as_deref(), Some("Taxi"));
+    }
+
+    #[test]
+    fn the_date_format_can_be_forced() {
+        let content = "!Type:Bank\nD05/10/2026\nT-5\nPTaxi\n^\n";
+
+        let rows = parse(content, Some("%d/%m/%Y"), AmountFormat::default()).unwrap();
+
+        assert_eq!(
+            rows[0].spent_at,
+            NaiveDate::from_ymd_opt(2026, 10, 5)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn bad_values_are_reported_on_their_row() {
+        let content = "!Type:Bank\nDsomeday\nT1,25\n^\n";
+
+        let rows = parse(content, None, AmountFormat::default()).unwrap();
+
+        assert_eq!(rows[0].errors.len(), 3);
+        assert!(parse("date,amount\n", None, AmountFormat::default()).is_err());
+    }
+}
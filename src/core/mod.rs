@@ -1,2 +1,6 @@
 pub mod enums;
+pub mod export;
 pub mod helpers;
+pub mod import;
+pub mod notification;
+pub mod storage;
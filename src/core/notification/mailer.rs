@@ -0,0 +1,126 @@
+use std::env;
+
+use crate::core::notification::NotificationChannel;
+use crate::models::notification::Notification;
+use crate::models::user::User;
+
+pub trait Mailer {
+    fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), String>;
+}
+
+/// Local stand-in that writes outgoing mails to the application log instead of sending them
+pub struct LogMailer;
+
+impl Mailer for LogMailer {
+    fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), String> {
+        log::info!("[mail] to: {}, subject: {}\n{}", to, subject, body);
+        Ok(())
+    }
+}
+
+/// Mailer selected by the `MAIL_DRIVER` variable, only "log" is currently supported
+pub fn make_mailer() -> Box<dyn Mailer> {
+    let driver = env::var("MAIL_DRIVER").unwrap_or(String::from("log"));
+    if driver != "log" {
+        log::warn!(
+            "Unknown mail driver \"{}\", falling back to \"log\"",
+            driver
+        );
+    }
+
+    Box::new(LogMailer)
+}
+
+pub struct EmailChannel {
+    mailer: Box<dyn Mailer>,
+}
+
+impl EmailChannel {
+    pub fn new(mailer: Box<dyn Mailer>) -> EmailChannel {
+        EmailChannel { mailer }
+    }
+}
+
+impl NotificationChannel for EmailChannel {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    fn deliver(&self, user: &User, notification: &Notification) -> Result<(), String> {
+        let body = format!(
+            "Hi {},\n\n{}\n\n- Nucleus",
+            user.first_name, notification.message
+        );
+
+        self.mailer.send(
+            user.email.as_str(),
+            notification.title.as_str(),
+            body.as_str(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::core::helpers::db::current_timestamp;
+
+    type Outbox = Arc<Mutex<Vec<(String, String, String)>>>;
+
+    struct RecordingMailer(Outbox);
+
+    impl Mailer for RecordingMailer {
+        fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), String> {
+            self.0
+                .lock()
+                .unwrap()
+                .push((to.to_string(), subject.to_string(), body.to_string()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn email_channel_mails_the_notification_to_its_user() {
+        let outbox = Outbox::default();
+        let channel = EmailChannel::new(Box::new(RecordingMailer(outbox.clone())));
+
+        let user = User {
+            user_id: Uuid::new_v4(),
+            first_name: String::from("Ada"),
+            last_name: String::from("Lovelace"),
+            email: String::from("ada@nucleus.test"),
+            password: String::new(),
+            status: String::from("active"),
+            created_at: current_timestamp(),
+            updated_at: current_timestamp(),
+            deleted_at: None,
+            timezone: String::from("UTC"),
+        };
+        let notification = Notification {
+            notification_id: Uuid::new_v4(),
+            user_id: user.user_id,
+            budget_id: None,
+            kind: String::from("budget_threshold_crossed"),
+            title: String::from("July, 2026 Budget reached 80%"),
+            message: String::from("You have used 800 of 1000"),
+            read_at: None,
+            created_at: current_timestamp(),
+            updated_at: current_timestamp(),
+            deleted_at: None,
+        };
+
+        channel.deliver(&user, &notification).unwrap();
+
+        let sent = outbox.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        let (to, subject, body) = &sent[0];
+        assert_eq!(to, "ada@nucleus.test");
+        assert_eq!(subject, "July, 2026 Budget reached 80%");
+        assert!(body.starts_with("Hi Ada,"));
+        assert!(body.contains("You have used 800 of 1000"));
+    }
+}
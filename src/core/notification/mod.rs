@@ -0,0 +1,81 @@
+use std::env;
+use std::sync::mpsc::{self, Sender};
+use std::sync::OnceLock;
+use std::thread;
+
+use crate::core::notification::mailer::{make_mailer, EmailChannel};
+use crate::core::notification::webhook::WebhookChannel;
+use crate::models::notification::Notification;
+use crate::models::user::User;
+
+pub mod mailer;
+pub mod webhook;
+
+/// Delivers an already persisted (in-app) notification through an external medium
+pub trait NotificationChannel {
+    fn name(&self) -> &'static str;
+
+    fn deliver(&self, user: &User, notification: &Notification) -> Result<(), String>;
+}
+
+/// Channels enabled through the comma separated `NOTIFICATION_CHANNELS` variable ("email,webhook")
+pub fn notification_channels() -> Vec<Box<dyn NotificationChannel>> {
+    let enabled = env::var("NOTIFICATION_CHANNELS").unwrap_or_default();
+    let mut channels: Vec<Box<dyn NotificationChannel>> = vec![];
+
+    for name in enabled.split(',').map(|name| name.trim()) {
+        match name {
+            "" => {}
+            "email" => channels.push(Box::new(EmailChannel::new(make_mailer()))),
+            "webhook" => match env::var("NOTIFICATION_WEBHOOK_URL") {
+                Ok(url) => channels.push(Box::new(WebhookChannel::new(url))),
+                Err(_) => log::warn!("Webhook notifications require NOTIFICATION_WEBHOOK_URL"),
+            },
+            _ => log::warn!("Unknown notification channel \"{}\"", name),
+        }
+    }
+
+    channels
+}
+
+struct Delivery {
+    user: User,
+    notification: Notification,
+}
+
+/// Hands the notification over to the enabled channels on a background thread, so
+/// that slow mail servers or webhook endpoints never hold up the request raising it
+pub fn queue_delivery(user: User, notification: Notification) {
+    if let Err(err) = delivery_queue().send(Delivery { user, notification }) {
+        log::error!("Failed to queue notification delivery: {}", err);
+    }
+}
+
+fn delivery_queue() -> &'static Sender<Delivery> {
+    static QUEUE: OnceLock<Sender<Delivery>> = OnceLock::new();
+
+    QUEUE.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<Delivery>();
+
+        thread::spawn(move || {
+            for delivery in receiver {
+                deliver(&delivery.user, &delivery.notification);
+            }
+        });
+
+        sender
+    })
+}
+
+fn deliver(user: &User, notification: &Notification) {
+    for channel in notification_channels() {
+        if let Err(err) = channel.deliver(user, notification) {
+            log::error!(
+                "Failed to deliver notification {} via {}: {}",
+                notification.notification_id,
+                channel.name(),
+                err
+            );
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::core::notification::NotificationChannel;
+use crate::models::notification::Notification;
+use crate::models::user::User;
+
+/// Posts notifications as JSON to a single configured URL
+pub struct WebhookChannel {
+    url: String,
+}
+
+impl WebhookChannel {
+    pub fn new(url: String) -> WebhookChannel {
+        WebhookChannel { url }
+    }
+}
+
+impl NotificationChannel for WebhookChannel {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn deliver(&self, user: &User, notification: &Notification) -> Result<(), String> {
+        ureq::post(self.url.as_str())
+            .timeout(Duration::from_secs(5))
+            .send_json(json!({
+                "event": notification.kind,
+                "user_id": user.user_id,
+                "notification": notification,
+            }))
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,77 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::core::storage::{check_key, Storage};
+
+/// Keeps files under the directory set by `STORAGE_LOCAL_DIR` ("storage" by default)
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: PathBuf) -> LocalStorage {
+        LocalStorage { root }
+    }
+
+    pub fn from_env() -> LocalStorage {
+        let root = env::var("STORAGE_LOCAL_DIR").unwrap_or(String::from("storage"));
+        LocalStorage::new(PathBuf::from(root))
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, String> {
+        check_key(key)?;
+        Ok(self.root.join(key))
+    }
+}
+
+impl Storage for LocalStorage {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn put(&self, key: &str, _content_type: &str, data: &[u8]) -> Result<(), String> {
+        let path = self.path(key)?;
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(|e| e.to_string())?;
+        }
+
+        fs::write(path, data).map_err(|e| e.to_string())
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>, String> {
+        fs::read(self.path(key)?).map_err(|e| e.to_string())
+    }
+
+    fn delete(&self, key: &str) -> Result<(), String> {
+        match fs::remove_file(self.path(key)?) {
+            Ok(_) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stores_reads_and_deletes_files() {
+        let root = env::temp_dir().join(format!("nucleus-storage-{}", uuid::Uuid::new_v4()));
+        let storage = LocalStorage::new(root.clone());
+
+        storage
+            .put("attachments/user/file", "text/plain", b"receipt")
+            .unwrap();
+        assert_eq!(storage.get("attachments/user/file").unwrap(), b"receipt");
+
+        storage.delete("attachments/user/file").unwrap();
+        assert!(storage.get("attachments/user/file").is_err());
+        // deleting a missing file is not an error
+        assert!(storage.delete("attachments/user/file").is_ok());
+        assert!(storage.put("../outside", "text/plain", b"x").is_err());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,72 @@
+use std::env;
+
+use crate::core::storage::local::LocalStorage;
+use crate::core::storage::s3::S3Storage;
+
+pub mod local;
+pub mod s3;
+
+/// Blob store for uploaded files, keys are generated by the application and
+/// look like "attachments/{user_id}/{attachment_id}"
+pub trait Storage {
+    fn name(&self) -> &'static str;
+
+    fn put(&self, key: &str, content_type: &str, data: &[u8]) -> Result<(), String>;
+
+    fn get(&self, key: &str) -> Result<Vec<u8>, String>;
+
+    fn delete(&self, key: &str) -> Result<(), String>;
+}
+
+/// Storage selected by the `STORAGE_DRIVER` variable, "local" (default) or "s3"
+pub fn make_storage() -> Box<dyn Storage> {
+    let driver = env::var("STORAGE_DRIVER").unwrap_or(String::from("local"));
+    match driver.as_str() {
+        "s3" => match S3Storage::from_env() {
+            Ok(storage) => return Box::new(storage),
+            Err(err) => log::warn!("{}, falling back to \"local\" storage", err),
+        },
+        "local" => {}
+        _ => log::warn!(
+            "Unknown storage driver \"{}\", falling back to \"local\"",
+            driver
+        ),
+    }
+
+    Box::new(LocalStorage::from_env())
+}
+
+/// Keys never leave the storage root, whatever backend they are used with
+fn check_key(key: &str) -> Result<(), String> {
+    let is_valid = !key.is_empty()
+        && !key.starts_with('/')
+        && key
+            .split('/')
+            .all(|part| !part.is_empty() && part != "." && part != "..");
+
+    match is_valid {
+        true => Ok(()),
+        false => Err(format!("Invalid storage key \"{}\"", key)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_key_rejects_escaping_keys() {
+        assert!(check_key("attachments/user/file").is_ok());
+
+        for key in [
+            "",
+            "/etc/passwd",
+            "attachments/../secret",
+            "a//b",
+            "./a",
+            "a/",
+        ] {
+            assert!(check_key(key).is_err(), "{} should be rejected", key);
+        }
+    }
+}
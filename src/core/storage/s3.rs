@@ -0,0 +1,182 @@
+use std::env;
+use std::io::Read;
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+use crate::core::storage::{check_key, Storage};
+
+/// S3 compatible object store (AWS, MinIO, Ceph...) addressed with path-style
+/// URLs and AWS Signature Version 4
+pub struct S3Storage {
+    /// scheme and host, e.g. "http://127.0.0.1:9000"
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+}
+
+impl S3Storage {
+    pub fn new(
+        endpoint: String,
+        bucket: String,
+        region: String,
+        access_key: String,
+        secret_key: String,
+    ) -> S3Storage {
+        S3Storage {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            bucket,
+            region,
+            access_key,
+            secret_key,
+        }
+    }
+
+    /// Reads `S3_ENDPOINT`, `S3_BUCKET`, `S3_REGION` (defaults to "us-east-1"),
+    /// `S3_ACCESS_KEY` and `S3_SECRET_KEY`
+    pub fn from_env() -> Result<S3Storage, String> {
+        let required = |name: &str| {
+            env::var(name).map_err(|_| format!("S3 storage requires the {} variable", name))
+        };
+
+        Ok(S3Storage::new(
+            required("S3_ENDPOINT")?,
+            required("S3_BUCKET")?,
+            env::var("S3_REGION").unwrap_or(String::from("us-east-1")),
+            required("S3_ACCESS_KEY")?,
+            required("S3_SECRET_KEY")?,
+        ))
+    }
+
+    fn send(
+        &self,
+        method: &str,
+        key: &str,
+        content_type: Option<&str>,
+        body: &[u8],
+    ) -> Result<ureq::Response, String> {
+        check_key(key)?;
+
+        let host = self
+            .endpoint
+            .split("://")
+            .nth(1)
+            .unwrap_or(self.endpoint.as_str());
+        let path = format!("/{}/{}", self.bucket, uri_encode(key));
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex::encode(Sha256::digest(body));
+
+        // canonical headers have to be sorted by name
+        let mut headers: Vec<(&str, &str)> = vec![
+            ("host", host),
+            ("x-amz-content-sha256", payload_hash.as_str()),
+            ("x-amz-date", amz_date.as_str()),
+        ];
+        if let Some(content_type) = content_type {
+            headers.insert(0, ("content-type", content_type));
+        }
+
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+            .collect();
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<&str>>()
+            .join(";");
+
+        let canonical_request = format!(
+            "{}\n{}\n\n{}\n{}\n{}",
+            method, path, canonical_headers, signed_headers, payload_hash
+        );
+
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let signing_key = [self.region.as_str(), "s3", "aws4_request"].iter().fold(
+            hmac_sha256(
+                format!("AWS4{}", self.secret_key).as_bytes(),
+                date.as_bytes(),
+            ),
+            |key, part| hmac_sha256(&key, part.as_bytes()),
+        );
+        let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key, scope, signed_headers, signature
+        );
+
+        let mut request = ureq::request(method, format!("{}{}", self.endpoint, path).as_str())
+            .set("Authorization", authorization.as_str());
+        for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+            request = request.set(name, value);
+        }
+
+        request.send_bytes(body).map_err(|err| match err {
+            ureq::Error::Status(status, _) => {
+                format!("S3 {} of \"{}\" failed with status {}", method, key, status)
+            }
+            err => err.to_string(),
+        })
+    }
+}
+
+impl Storage for S3Storage {
+    fn name(&self) -> &'static str {
+        "s3"
+    }
+
+    fn put(&self, key: &str, content_type: &str, data: &[u8]) -> Result<(), String> {
+        self.send("PUT", key, Some(content_type), data)?;
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>, String> {
+        let response = self.send("GET", key, None, &[])?;
+
+        let mut data = vec![];
+        response
+            .into_reader()
+            .read_to_end(&mut data)
+            .map_err(|e| e.to_string())?;
+
+        Ok(data)
+    }
+
+    fn delete(&self, key: &str) -> Result<(), String> {
+        self.send("DELETE", key, None, &[])?;
+        Ok(())
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encodes everything but unreserved characters and path separators
+fn uri_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
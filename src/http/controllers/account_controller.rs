@@ -0,0 +1,58 @@
+use actix_multipart::form::{MultipartForm, MultipartFormConfig};
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::web::{Data, ServiceConfig};
+use actix_web::{get, post, HttpMessage, HttpRequest, HttpResponse};
+
+use crate::core::enums::app_error::AppResult;
+use crate::core::helpers::auth::get_auth_user;
+use crate::core::helpers::responder::json_success;
+use crate::http::middlewares::auth_middleware::AuthMiddleware;
+use crate::models::account_archive::AccountImportForm;
+use crate::models::DBPool;
+use crate::services::account_service::AccountService;
+
+pub fn account_controller(cfg: &mut ServiceConfig) {
+    // archives are read into memory whole, well beyond the 2MiB default
+    cfg.app_data(
+        MultipartFormConfig::default()
+            .total_limit(50 * 1024 * 1024)
+            .memory_limit(50 * 1024 * 1024),
+    );
+    cfg.service(export);
+    cfg.service(import);
+}
+
+#[get("export")]
+async fn export(
+    pool: Data<DBPool>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let user = get_auth_user(req.extensions());
+    let archive = AccountService.export(pool.get_ref(), &user)?;
+
+    let file_name = format!(
+        "nucleus-account-{}.zip",
+        chrono::Utc::now().format("%Y%m%d%H%M%S")
+    );
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/zip")
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(file_name)],
+        })
+        .body(archive))
+}
+
+#[post("import")]
+async fn import(
+    pool: Data<DBPool>,
+    form: MultipartForm<AccountImportForm>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let user = get_auth_user(req.extensions());
+    let imported = AccountService.import(pool.get_ref(), &user, form.into_inner())?;
+    Ok(json_success(imported))
+}
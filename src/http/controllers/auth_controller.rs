@@ -1,18 +1,18 @@
-use crate::core::helpers::auth::get_auth_user;
+use crate::core::enums::app_error::{AppError, AppResult};
+use crate::core::helpers::auth::{get_auth_user, make_auth_cookie, make_removal_auth_cookie};
+use crate::core::helpers::csrf::{make_csrf_cookie, make_csrf_token, make_removal_csrf_cookie};
 use actix_web::http::StatusCode;
-use actix_web::web::{Data, Json, ServiceConfig};
+use actix_web::web::{Data, ServiceConfig};
 use actix_web::{get, post, HttpMessage, HttpRequest, HttpResponse};
-use diesel::result::DatabaseErrorInformation;
 
-use crate::core::helpers::responder::{
-    json, json_error_message, json_success, json_success_message, json_unauthorized_message,
-};
+use crate::core::helpers::responder::{json, json_success, json_success_message};
+use crate::http::extractors::json_extractor::Json;
 use crate::http::middlewares::auth_middleware::AuthMiddleware;
 use crate::models::DBPool;
 
 use crate::models::user::{LoginForm, RegisterForm};
 use crate::repositories::user_repository::UserRepository;
-use crate::services::auth_service::AuthService;
+use crate::services::auth_service::{AuthService, CookieAuthData};
 
 pub fn auth_controller(cfg: &mut ServiceConfig) {
     cfg.service(login);
@@ -22,14 +22,26 @@ pub fn auth_controller(cfg: &mut ServiceConfig) {
 }
 
 #[post("login")]
-async fn login(pool: Data<DBPool>, data: Json<LoginForm>) -> HttpResponse {
-    let result = AuthService.login(pool.get_ref(), data.email.clone(), data.password.clone());
+async fn login(pool: Data<DBPool>, data: Json<LoginForm>) -> AppResult<HttpResponse> {
+    let access = AuthService.login(pool.get_ref(), data.email.clone(), data.password.clone())?;
 
-    if result.is_err() {
-        return json_unauthorized_message(result.err().unwrap().as_str());
+    if !data.use_cookie {
+        return Ok(json(access, StatusCode::OK));
     }
 
-    json(result.unwrap(), StatusCode::OK)
+    let csrf_token = make_csrf_token();
+    let mut response = json(
+        CookieAuthData {
+            token_type: String::from("cookie"),
+            expires_in: access.expires_in,
+            csrf_token: csrf_token.clone(),
+        },
+        StatusCode::OK,
+    );
+
+    let _ = response.add_cookie(&make_auth_cookie(access.access_token, access.expires_in));
+    let _ = response.add_cookie(&make_csrf_cookie(csrf_token));
+    Ok(response)
 }
 
 #[get("me")]
@@ -41,22 +53,154 @@ async fn me(req: HttpRequest, _: AuthMiddleware) -> HttpResponse {
 #[post("logout")]
 async fn logout() -> HttpResponse {
     AuthService.logout();
-    json_success_message("Logged out successfully")
+
+    let mut response = json_success_message("Logged out successfully");
+    let _ = response.add_cookie(&make_removal_auth_cookie());
+    let _ = response.add_cookie(&make_removal_csrf_cookie());
+    response
 }
 
 #[post("register")]
-async fn register(pool: Data<DBPool>, form: Json<RegisterForm>) -> HttpResponse {
-    let result =
+async fn register(pool: Data<DBPool>, form: Json<RegisterForm>) -> AppResult<HttpResponse> {
+    let user =
         actix_web::web::block(move || UserRepository.create(pool.get_ref(), form.into_inner()))
             .await
-            .expect("Failed to create user");
+            .map_err(|e| AppError::Internal(e.to_string()))??;
+
+    Ok(json_success(user))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use actix_web::cookie::time::Duration as CookieDuration;
+    use actix_web::cookie::{Cookie, SameSite};
+    use actix_web::dev::ServiceResponse;
+    use actix_web::http::header;
+    use actix_web::{test, App};
+    use chrono::Duration;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::core::helpers::auth::AUTH_COOKIE;
+    use crate::core::helpers::csrf::{CSRF_COOKIE, CSRF_HEADER};
+    use crate::core::helpers::testing::{create_user, make_token, test_pool, use_test_app_key};
+    use crate::http::kernel::{register_extractor_configs, register_routes};
+
+    fn cookie_named(res: &ServiceResponse, name: &str) -> Cookie<'static> {
+        res.response()
+            .cookies()
+            .find(|cookie| cookie.name() == name)
+            .map(|cookie| cookie.into_owned())
+            .unwrap_or_else(|| panic!("no {} cookie was set", name))
+    }
+
+    fn project_form() -> Value {
+        json!({"name": "Household", "description": ""})
+    }
 
-    if result.is_err() {
-        return json_error_message(result.err().unwrap().message());
+    #[actix_web::test]
+    #[ignore = "needs the migrated database configured in .env"]
+    async fn cookie_sessions_need_the_csrf_token_to_change_anything() {
+        use_test_app_key();
+        env::set_var("AUTH_TOKEN_LIFETIME", "60");
+        let pool = test_pool();
+        let user = create_user(&pool);
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(pool.clone()))
+                .configure(register_extractor_configs)
+                .configure(register_routes),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/v1/auth/login")
+            .set_json(json!({"email": user.email, "password": "password1", "use_cookie": true}))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let session = cookie_named(&res, AUTH_COOKIE);
+        assert_eq!(session.http_only(), Some(true));
+        assert_eq!(session.same_site(), Some(SameSite::Lax));
+        assert!(!session.value().is_empty());
+
+        let csrf = cookie_named(&res, CSRF_COOKIE);
+        // scripts read it to echo it back
+        assert_ne!(csrf.http_only(), Some(true));
+        assert_eq!(csrf.same_site(), Some(SameSite::Lax));
+
+        // the token itself never shows up in the body
+        let body: Value = test::read_body_json(res).await;
+        assert_eq!(body["csrf_token"], csrf.value());
+        assert!(body.get("access_token").is_none());
+
+        let post_project = |csrf_header: Option<&str>| {
+            let mut req = test::TestRequest::post()
+                .uri("/api/v1/projects")
+                .cookie(session.clone())
+                .cookie(csrf.clone())
+                .set_json(project_form());
+            if let Some(token) = csrf_header {
+                req = req.insert_header((CSRF_HEADER, token.to_string()));
+            }
+            req.to_request()
+        };
+
+        for csrf_header in [None, Some("forged"), Some("")] {
+            let res = test::call_service(&app, post_project(csrf_header)).await;
+            assert_eq!(res.status(), StatusCode::FORBIDDEN, "{:?}", csrf_header);
+            let body: Value = test::read_body_json(res).await;
+            assert_eq!(body["data"]["code"], "csrf_token_mismatch");
+        }
+
+        let res = test::call_service(&app, post_project(Some(csrf.value()))).await;
+        assert!(res.status().is_success(), "{}", res.status());
+
+        // reads need no token
+        let req = test::TestRequest::get()
+            .uri("/api/v1/projects")
+            .cookie(session.clone())
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+        let req = test::TestRequest::post()
+            .uri("/api/v1/auth/logout")
+            .cookie(session.clone())
+            .cookie(csrf.clone())
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        for name in [AUTH_COOKIE, CSRF_COOKIE] {
+            let removal = cookie_named(&res, name);
+            assert_eq!(removal.value(), "", "{}", name);
+            assert_eq!(removal.max_age(), Some(CookieDuration::ZERO), "{}", name);
+        }
     }
 
-    let mut user = result.unwrap();
+    #[actix_web::test]
+    #[ignore = "needs the migrated database configured in .env"]
+    async fn bearer_requests_need_no_csrf_token() {
+        let pool = test_pool();
+        let user = create_user(&pool);
+        let token = make_token(user.user_id, Duration::hours(1));
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(pool.clone()))
+                .configure(register_extractor_configs)
+                .configure(register_routes),
+        )
+        .await;
 
-    user.password = String::from("");
-    json_success(user)
+        let req = test::TestRequest::post()
+            .uri("/api/v1/projects")
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+            .set_json(project_form())
+            .to_request();
+        let res = test::call_service(&app, req).await;
+
+        assert!(res.status().is_success(), "{}", res.status());
+    }
 }
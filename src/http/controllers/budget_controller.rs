@@ -1,23 +1,35 @@
-use crate::core::enums::http_error::ErroneousOption;
-use crate::core::helpers::auth::get_auth_id;
+use crate::core::enums::app_error::{AppError, AppResult};
+use crate::core::helpers::auth::{get_auth_id, get_auth_user};
 use crate::core::helpers::http::{IdPathParam, QueryParams};
 use crate::core::helpers::responder::{
-    json_entity_not_found_response, json_error_message, json_invalid_uuid_response,
-    json_pagination, json_success, json_success_message,
+    json_pagination, json_pagination_summary, json_success, json_success_message,
 };
+use crate::http::extractors::json_extractor::Json;
 use crate::http::middlewares::auth_middleware::AuthMiddleware;
 use crate::models::budget::BudgetForm;
+use crate::models::expense::{ExpenseCascadeParams, ExpenseFilter};
+use crate::models::report::BudgetYearReportParams;
+use crate::models::statement::BudgetStatement;
 use crate::models::DBPool;
 use crate::repositories::budget_repository::BudgetRepository;
 use crate::repositories::expense_repository::ExpenseRepository;
 use crate::services::budget_service::BudgetService;
-use actix_web::web::{Data, Json, Path, Query, ServiceConfig};
+use crate::services::report_service::ReportService;
+use crate::services::statement_service::StatementService;
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::web::{Data, Path, Query, ServiceConfig};
 use actix_web::{delete, get, post, put, HttpMessage, HttpRequest, HttpResponse};
+use tera::Tera;
 
 pub fn budget_controller(cfg: &mut ServiceConfig) {
     cfg.service(index);
     cfg.service(create);
+    cfg.service(year_report);
+    cfg.service(current_budget);
     cfg.service(show);
+    cfg.service(report);
+    cfg.service(statement_pdf);
+    cfg.service(statement_html);
     cfg.service(expenses);
     cfg.service(update);
     cfg.service(delete);
@@ -29,10 +41,10 @@ async fn index(
     req: HttpRequest,
     q: Query<QueryParams>,
     _: AuthMiddleware,
-) -> HttpResponse {
+) -> AppResult<HttpResponse> {
     let user_id = get_auth_id(req.extensions());
-    let budgets = BudgetRepository.list_by_user_id(pool.get_ref(), user_id, q.into_inner());
-    json_pagination(budgets.unwrap())
+    let budgets = BudgetRepository.list_by_user_id(pool.get_ref(), user_id, q.into_inner())?;
+    Ok(json_pagination(&req, budgets))
 }
 
 #[post("")]
@@ -41,28 +53,28 @@ async fn create(
     form: Json<BudgetForm>,
     req: HttpRequest,
     _: AuthMiddleware,
-) -> HttpResponse {
+) -> AppResult<HttpResponse> {
     let budget = BudgetService.create(
         pool.get_ref(),
         get_auth_id(req.extensions()),
         form.into_inner(),
-    );
+    )?;
 
-    json_success(budget)
+    Ok(json_success(budget))
 }
 
 #[get("current-budget")]
-async fn current_budget(pool: Data<DBPool>, req: HttpRequest, _: AuthMiddleware) -> HttpResponse {
-    let user_id = get_auth_id(req.extensions());
+async fn current_budget(
+    pool: Data<DBPool>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let user = get_auth_user(req.extensions());
     let budget = BudgetRepository
-        .find_owned_current_month_budget(pool.get_ref(), user_id)
-        .unwrap();
+        .find_owned_current_month_budget(pool.get_ref(), user.user_id, user.timezone.as_str())?
+        .ok_or_else(|| AppError::EntityNotFound(String::from("budget")))?;
 
-    if budget.is_none() {
-        return json_entity_not_found_response("expense");
-    }
-
-    json_success(budget.unwrap())
+    Ok(json_success(budget))
 }
 
 #[get("{id}")]
@@ -71,23 +83,14 @@ async fn show(
     mut param: Path<IdPathParam>,
     req: HttpRequest,
     _: AuthMiddleware,
-) -> HttpResponse {
-    let id = param.get_uuid();
-    if id.is_err() {
-        return json_invalid_uuid_response();
-    }
-
-    let result = BudgetRepository.find_owned_by_id(
+) -> AppResult<HttpResponse> {
+    let budget = BudgetRepository.find_owned_by_id(
         pool.get_ref(),
-        id.unwrap(),
+        param.get_uuid()?,
         get_auth_id(req.extensions()),
-    );
+    )?;
 
-    if result.is_error_or_empty() {
-        return result.send_error();
-    }
-
-    json_success(result.unwrap_entity())
+    Ok(json_success(budget))
 }
 
 #[put("{id}")]
@@ -97,57 +100,230 @@ async fn update(
     mut param: Path<IdPathParam>,
     req: HttpRequest,
     _: AuthMiddleware,
-) -> HttpResponse {
-    let id = param.get_uuid();
-    if id.is_err() {
-        return json_invalid_uuid_response();
-    }
-
-    let result = BudgetService.update(
+) -> AppResult<HttpResponse> {
+    let budget = BudgetService.update(
         pool.get_ref(),
-        id.unwrap(),
+        param.get_uuid()?,
         get_auth_id(req.extensions()),
         form.into_inner(),
-    );
+    )?;
 
-    if result.is_err() {
-        return json_error_message(result.err().unwrap().to_string().as_str());
-    }
-
-    json_success(result.unwrap())
+    Ok(json_success(budget))
 }
 
 #[delete("{id}")]
 async fn delete(
     pool: Data<DBPool>,
     mut param: Path<IdPathParam>,
+    q: Query<ExpenseCascadeParams>,
     req: HttpRequest,
     _: AuthMiddleware,
-) -> HttpResponse {
-    let id = param.get_uuid();
-    if id.is_err() {
-        return json_invalid_uuid_response();
-    }
-
-    BudgetService
-        .delete(pool.get_ref(), id.unwrap(), get_auth_id(req.extensions()))
-        .expect("Failed to delete budget");
-
-    json_success_message("budget deleted")
+) -> AppResult<HttpResponse> {
+    BudgetService.delete(
+        pool.get_ref(),
+        param.get_uuid()?,
+        get_auth_id(req.extensions()),
+        q.get_cascade()?,
+    )?;
+    Ok(json_success_message("budget deleted"))
 }
 
 #[get("{id}/expenses")]
 async fn expenses(
     pool: Data<DBPool>,
+    req: HttpRequest,
     mut param: Path<IdPathParam>,
     q: Query<QueryParams>,
+    filter: Query<ExpenseFilter>,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let user_id = get_auth_id(req.extensions());
+    let budget = BudgetRepository.find_owned_by_id(pool.get_ref(), param.get_uuid()?, user_id)?;
+    let filter = ExpenseFilter {
+        budget_id: Some(budget.budget_id),
+        ..filter.into_inner()
+    };
+
+    let (expenses, summary) =
+        ExpenseRepository.list(pool.get_ref(), user_id, &filter, q.into_inner())?;
+    Ok(json_pagination_summary(&req, expenses, Some(summary)))
+}
+
+#[get("report")]
+async fn year_report(
+    pool: Data<DBPool>,
+    req: HttpRequest,
+    q: Query<BudgetYearReportParams>,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let user = get_auth_user(req.extensions());
+    let year_report = ReportService.budget_year(pool.get_ref(), &user, q.year)?;
+    Ok(json_success(year_report))
+}
+
+#[get("{id}/report")]
+async fn report(
+    pool: Data<DBPool>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
     _: AuthMiddleware,
-) -> HttpResponse {
-    let id = param.get_uuid();
-    if id.is_err() {
-        return json_invalid_uuid_response();
+) -> AppResult<HttpResponse> {
+    let user = get_auth_user(req.extensions());
+    let budget =
+        BudgetRepository.find_owned_by_id(pool.get_ref(), param.get_uuid()?, user.user_id)?;
+    let budget_report = ReportService.budget(pool.get_ref(), &user, budget)?;
+    Ok(json_success(budget_report))
+}
+
+#[get("{id}/statement.pdf")]
+async fn statement_pdf(
+    pool: Data<DBPool>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let statement = build_statement(pool.get_ref(), &mut param, &req)?;
+    let pdf = StatementService
+        .render_pdf(&statement)
+        .map_err(AppError::Internal)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/pdf")
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Inline,
+            parameters: vec![DispositionParam::Filename(format!(
+                "budget-statement-{}-{:02}.pdf",
+                statement.budget.year, statement.budget.month
+            ))],
+        })
+        .body(pdf))
+}
+
+#[get("{id}/statement.html")]
+async fn statement_html(
+    pool: Data<DBPool>,
+    tera: Data<Tera>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let statement = build_statement(pool.get_ref(), &mut param, &req)?;
+    let html = StatementService
+        .render_html(tera.get_ref(), &statement)
+        .map_err(AppError::Internal)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(html))
+}
+
+fn build_statement(
+    pool: &DBPool,
+    param: &mut Path<IdPathParam>,
+    req: &HttpRequest,
+) -> AppResult<BudgetStatement> {
+    let user = get_auth_user(req.extensions());
+    let budget = BudgetRepository.find_owned_by_id(pool, param.get_uuid()?, user.user_id)?;
+    StatementService.build(pool, &user, budget)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::{header, StatusCode};
+    use actix_web::{test, App};
+    use chrono::Duration;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::core::helpers::template::register_template_filters;
+    use crate::core::helpers::testing::{create_budget, create_user, make_token, test_pool};
+    use crate::http::kernel::register_routes;
+    use crate::models::budget::OverspendPolicy;
+    use crate::models::expense::ExpenseForm;
+    use crate::models::project::ProjectForm;
+    use crate::repositories::project_repository::ProjectRepository;
+
+    fn spend(pool: &DBPool, user_id: Uuid, budget_id: Uuid, project: &str, amount: i64) {
+        let project = ProjectRepository
+            .create(
+                pool,
+                user_id,
+                ProjectForm {
+                    name: String::from(project),
+                    description: String::new(),
+                },
+            )
+            .unwrap();
+        let form = ExpenseForm {
+            project_id: project.project_id.to_string(),
+            amount,
+            narration: format!("{} costs", project.name),
+            spent_at: None,
+            category_id: None,
+        };
+        ExpenseRepository
+            .create(pool, user_id, "UTC", budget_id, form)
+            .unwrap();
     }
 
-    let projects = ExpenseRepository.list_by_budget_id(pool.get_ref(), id.unwrap(), q.into_inner());
-    json_pagination(projects.unwrap())
+    #[actix_web::test]
+    #[ignore = "needs the migrated database configured in .env"]
+    async fn statements_add_up_the_budget() {
+        let pool = test_pool();
+        let user = create_user(&pool);
+        let other = create_user(&pool);
+        let budget = create_budget(&pool, user.user_id, 1000, OverspendPolicy::Allow);
+        spend(&pool, user.user_id, budget.budget_id, "Household", 1000);
+        spend(&pool, user.user_id, budget.budget_id, "Car", 250);
+
+        let mut tera = Tera::new(concat!(env!("CARGO_MANIFEST_DIR"), "/templates/**/*")).unwrap();
+        register_template_filters(&mut tera);
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(pool.clone()))
+                .app_data(Data::new(tera))
+                .configure(register_routes),
+        )
+        .await;
+        let get = |user_id: Uuid, format: &str| {
+            test::TestRequest::get()
+                .uri(&format!(
+                    "/api/v1/budgets/{}/statement.{}",
+                    budget.budget_id, format
+                ))
+                .insert_header((
+                    header::AUTHORIZATION,
+                    format!("Bearer {}", make_token(user_id, Duration::hours(1))),
+                ))
+                .to_request()
+        };
+
+        let res = test::call_service(&app, get(user.user_id, "pdf")).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/pdf"
+        );
+        assert!(test::read_body(res).await.starts_with(b"%PDF-"));
+
+        let res = test::call_service(&app, get(user.user_id, "html")).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let html = String::from_utf8(test::read_body(res).await.to_vec()).unwrap();
+        for (row, amount) in [
+            ("Budgeted", "1,000.00"),
+            ("Spent", "1,250.00"),
+            ("Available", "0.00"),
+            ("Overspent", "250.00"),
+            ("Expenses", "2"),
+        ] {
+            let cells = format!("<td>{}</td><td class=\"amount\">{}</td>", row, amount);
+            assert!(html.contains(&cells), "{}", cells);
+        }
+        assert!(html.contains("80%") && html.contains("20%"));
+
+        for format in ["pdf", "html"] {
+            let res = test::call_service(&app, get(other.user_id, format)).await;
+            assert_eq!(res.status(), StatusCode::NOT_FOUND, "{}", format);
+        }
+    }
 }
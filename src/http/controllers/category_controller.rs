@@ -0,0 +1,93 @@
+use actix_web::web::{Data, Path, ServiceConfig};
+use actix_web::{delete, get, post, put, HttpMessage, HttpRequest, HttpResponse};
+
+use crate::core::enums::app_error::AppResult;
+use crate::core::helpers::auth::get_auth_id;
+use crate::core::helpers::http::IdPathParam;
+use crate::core::helpers::responder::{json_success, json_success_message};
+use crate::http::extractors::json_extractor::Json;
+use crate::http::middlewares::auth_middleware::AuthMiddleware;
+use crate::models::category::{CategoryForm, CategoryNode};
+use crate::models::DBPool;
+use crate::repositories::category_repository::CategoryRepository;
+use crate::services::category_service::CategoryService;
+
+pub fn category_controller(cfg: &mut ServiceConfig) {
+    cfg.service(index);
+    cfg.service(create);
+    cfg.service(show);
+    cfg.service(update);
+    cfg.service(delete);
+}
+
+#[get("")]
+async fn index(pool: Data<DBPool>, req: HttpRequest, _: AuthMiddleware) -> AppResult<HttpResponse> {
+    let user_id = get_auth_id(req.extensions());
+    let categories = CategoryRepository.list_by_user_id(pool.get_ref(), user_id)?;
+    Ok(json_success(CategoryNode::build_tree(categories)))
+}
+
+#[post("")]
+async fn create(
+    pool: Data<DBPool>,
+    form: Json<CategoryForm>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let category = CategoryService.create(
+        pool.get_ref(),
+        get_auth_id(req.extensions()),
+        form.into_inner(),
+    )?;
+
+    Ok(json_success(category))
+}
+
+#[get("{id}")]
+async fn show(
+    pool: Data<DBPool>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let category = CategoryRepository.find_visible_by_id(
+        pool.get_ref(),
+        param.get_uuid()?,
+        get_auth_id(req.extensions()),
+    )?;
+
+    Ok(json_success(category))
+}
+
+#[put("{id}")]
+async fn update(
+    pool: Data<DBPool>,
+    form: Json<CategoryForm>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let category = CategoryService.update(
+        pool.get_ref(),
+        param.get_uuid()?,
+        get_auth_id(req.extensions()),
+        form.into_inner(),
+    )?;
+
+    Ok(json_success(category))
+}
+
+#[delete("{id}")]
+async fn delete(
+    pool: Data<DBPool>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    CategoryRepository.delete(
+        pool.get_ref(),
+        param.get_uuid()?,
+        get_auth_id(req.extensions()),
+    )?;
+    Ok(json_success_message("category deleted"))
+}
@@ -0,0 +1,260 @@
+use actix_web::http::header::{ContentType, LOCATION};
+use actix_web::web::{Data, Form, ServiceConfig};
+use actix_web::{get, post, HttpMessage, HttpRequest, HttpResponse};
+use tera::{Context, Tera};
+
+use crate::core::enums::app_error::AppError;
+use crate::core::helpers::auth::{get_auth_user, make_auth_cookie, make_removal_auth_cookie};
+use crate::core::helpers::csrf::{
+    csrf_token_matches, csrf_token_of, make_csrf_cookie, make_csrf_token,
+};
+use crate::http::middlewares::auth_middleware::AuthMiddleware;
+use crate::models::dashboard::{DashboardLoginForm, DashboardLogoutForm};
+use crate::models::DBPool;
+use crate::services::auth_service::AuthService;
+use crate::services::dashboard_service::DashboardService;
+
+pub fn dashboard_controller(cfg: &mut ServiceConfig) {
+    cfg.service(index);
+    cfg.service(login_page);
+    cfg.service(login);
+    cfg.service(logout);
+}
+
+const CSRF_MISMATCH: &str = "Your session form has expired, please try again";
+
+#[get("")]
+async fn index(
+    pool: Data<DBPool>,
+    tera: Data<Tera>,
+    req: HttpRequest,
+    auth: Option<AuthMiddleware>,
+) -> HttpResponse {
+    if auth.is_none() {
+        return redirect("/dashboard/login");
+    }
+
+    let user = get_auth_user(req.extensions());
+    let dashboard = match DashboardService.build(pool.get_ref(), &user) {
+        Ok(dashboard) => dashboard,
+        Err(err) => {
+            log::error!("failed to build dashboard: {:?}", err);
+            return render_error(
+                &tera,
+                &req,
+                "Failed to load your dashboard, please try again",
+            );
+        }
+    };
+
+    let mut context = Context::new();
+    context.insert("dashboard", &dashboard);
+    render(&tera, &req, "dashboard/index.tera.html", context)
+}
+
+#[get("login")]
+async fn login_page(
+    tera: Data<Tera>,
+    req: HttpRequest,
+    auth: Option<AuthMiddleware>,
+) -> HttpResponse {
+    if auth.is_some() {
+        return redirect("/dashboard");
+    }
+
+    render_login(&tera, &req, None, None)
+}
+
+#[post("login")]
+async fn login(
+    pool: Data<DBPool>,
+    tera: Data<Tera>,
+    req: HttpRequest,
+    form: Form<DashboardLoginForm>,
+) -> HttpResponse {
+    let form = form.into_inner();
+    if !csrf_token_matches(&req, form.csrf_token.as_str()) {
+        return render_login(&tera, &req, Some(form.email), Some(CSRF_MISMATCH));
+    }
+
+    let access = match AuthService.login(pool.get_ref(), form.email.clone(), form.password) {
+        Ok(access) => access,
+        Err(err) => {
+            if !matches!(err, AppError::Unauthorized(_)) {
+                log::error!("failed to log in to the dashboard: {:?}", err);
+            }
+
+            return render_login(
+                &tera,
+                &req,
+                Some(form.email),
+                Some(err.to_string().as_str()),
+            );
+        }
+    };
+
+    // a token planted before signing in must not outlive the session change
+    let mut response = redirect("/dashboard");
+    let _ = response.add_cookie(&make_auth_cookie(access.access_token, access.expires_in));
+    let _ = response.add_cookie(&make_csrf_cookie(make_csrf_token()));
+    response
+}
+
+#[post("logout")]
+async fn logout(req: HttpRequest, form: Form<DashboardLogoutForm>) -> HttpResponse {
+    if !csrf_token_matches(&req, form.csrf_token.as_str()) {
+        return redirect("/dashboard");
+    }
+
+    AuthService.logout();
+
+    let mut response = redirect("/dashboard/login");
+    let _ = response.add_cookie(&make_removal_auth_cookie());
+    response
+}
+
+fn render_login(
+    tera: &Tera,
+    req: &HttpRequest,
+    email: Option<String>,
+    error: Option<&str>,
+) -> HttpResponse {
+    let mut context = Context::new();
+    context.insert("email", &email.unwrap_or_default());
+    context.insert("error", &error);
+    render(tera, req, "dashboard/login.tera.html", context)
+}
+
+fn render_error(tera: &Tera, req: &HttpRequest, message: &str) -> HttpResponse {
+    let mut context = Context::new();
+    context.insert("message", message);
+    render(tera, req, "dashboard/error.tera.html", context)
+}
+
+/// Renders the page with the request's CSRF token, issuing the token cookie when it has none yet
+fn render(tera: &Tera, req: &HttpRequest, template: &str, mut context: Context) -> HttpResponse {
+    let csrf_token = csrf_token_of(req);
+    context.insert("csrf_token", &csrf_token);
+
+    match tera.render(template, &context) {
+        Ok(html) => HttpResponse::Ok()
+            .content_type(ContentType::html())
+            .cookie(make_csrf_cookie(csrf_token))
+            .body(html),
+        Err(err) => {
+            log::error!("failed to render {}: {:?}", template, err);
+            HttpResponse::InternalServerError()
+                .content_type(ContentType::plaintext())
+                .body("Failed to render the page")
+        }
+    }
+}
+
+fn redirect(location: &str) -> HttpResponse {
+    HttpResponse::SeeOther()
+        .insert_header((LOCATION, location))
+        .finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use actix_web::cookie::Cookie;
+    use actix_web::dev::ServiceResponse;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+
+    use super::*;
+    use crate::core::helpers::auth::AUTH_COOKIE;
+    use crate::core::helpers::csrf::CSRF_COOKIE;
+    use crate::core::helpers::template::register_template_filters;
+    use crate::core::helpers::testing::{create_user, test_pool, use_test_app_key};
+    use crate::http::kernel::register_routes;
+
+    fn cookie_named(res: &ServiceResponse, name: &str) -> Option<Cookie<'static>> {
+        res.response()
+            .cookies()
+            .find(|cookie| cookie.name() == name)
+            .map(|cookie| cookie.into_owned())
+    }
+
+    fn location(res: &ServiceResponse) -> &str {
+        res.headers()
+            .get(LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs the migrated database configured in .env"]
+    async fn forms_are_refused_without_the_csrf_token() {
+        use_test_app_key();
+        env::set_var("AUTH_TOKEN_LIFETIME", "60");
+        let pool = test_pool();
+        let user = create_user(&pool);
+        let mut tera = Tera::new(concat!(env!("CARGO_MANIFEST_DIR"), "/templates/**/*")).unwrap();
+        register_template_filters(&mut tera);
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(pool.clone()))
+                .app_data(Data::new(tera))
+                .configure(register_routes),
+        )
+        .await;
+
+        // the login page hands out the token, both as a cookie and in the form
+        let req = test::TestRequest::get()
+            .uri("/dashboard/login")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        let csrf = cookie_named(&res, CSRF_COOKIE).unwrap();
+        let page = String::from_utf8(test::read_body(res).await.to_vec()).unwrap();
+        assert!(page.contains(csrf.value()));
+
+        let login_with = |csrf_field: &str| {
+            test::TestRequest::post()
+                .uri("/dashboard/login")
+                .cookie(csrf.clone())
+                .set_form([
+                    ("email", user.email.as_str()),
+                    ("password", "password1"),
+                    ("csrf_token", csrf_field),
+                ])
+                .to_request()
+        };
+
+        for csrf_field in ["", "forged"] {
+            let res = test::call_service(&app, login_with(csrf_field)).await;
+            assert_eq!(res.status(), StatusCode::OK, "{:?}", csrf_field);
+            assert!(cookie_named(&res, AUTH_COOKIE).is_none());
+            let page = String::from_utf8(test::read_body(res).await.to_vec()).unwrap();
+            assert!(page.contains(CSRF_MISMATCH));
+        }
+
+        let res = test::call_service(&app, login_with(csrf.value())).await;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert_eq!(location(&res), "/dashboard");
+        let session = cookie_named(&res, AUTH_COOKIE).unwrap();
+        // signing in rotates the token
+        let csrf = cookie_named(&res, CSRF_COOKIE).unwrap();
+
+        let logout_with = |csrf_field: &str| {
+            test::TestRequest::post()
+                .uri("/dashboard/logout")
+                .cookie(session.clone())
+                .cookie(csrf.clone())
+                .set_form([("csrf_token", csrf_field)])
+                .to_request()
+        };
+
+        let res = test::call_service(&app, logout_with("forged")).await;
+        assert_eq!(location(&res), "/dashboard");
+        assert!(cookie_named(&res, AUTH_COOKIE).is_none());
+
+        let res = test::call_service(&app, logout_with(csrf.value())).await;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert_eq!(location(&res), "/dashboard/login");
+        assert_eq!(cookie_named(&res, AUTH_COOKIE).unwrap().value(), "");
+    }
+}
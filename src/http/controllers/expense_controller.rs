@@ -1,18 +1,26 @@
-use crate::core::enums::http_error::ErroneousOption;
-use actix_web::web::{Data, Json, Path, Query, ServiceConfig};
+use actix_multipart::form::{MultipartForm, MultipartFormConfig};
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::web::{self, Data, Path, Query, ServiceConfig};
 use actix_web::{delete, get, post, put, HttpMessage, HttpRequest, HttpResponse};
 
-use crate::core::helpers::auth::get_auth_id;
-use crate::core::helpers::http::{IdPathParam, QueryParams};
+use crate::core::enums::app_error::{AppError, AppResult};
+use crate::core::helpers::auth::{get_auth_id, get_auth_user};
+use crate::core::helpers::http::{AttachmentPathParam, IdPathParam, LabelPathParam, QueryParams};
 use crate::core::helpers::responder::{
-    json_error_message, json_invalid_uuid_response, json_pagination, json_success,
-    json_success_message,
+    json_pagination_summary, json_success, json_success_message,
 };
+use crate::http::extractors::json_extractor::Json;
 use crate::http::middlewares::auth_middleware::AuthMiddleware;
-use crate::models::expense::ExpenseForm;
+use crate::models::attachment::{Attachment, AttachmentDownloadParams, AttachmentUploadForm};
+use crate::models::expense::{ExpenseFilter, ExpenseForm};
 use crate::models::DBPool;
+use crate::repositories::attachment_repository::AttachmentRepository;
 use crate::repositories::expense_repository::ExpenseRepository;
+use crate::repositories::label_repository::LabelRepository;
+use crate::services::attachment_service::{max_attachment_size, AttachmentService};
 use crate::services::expense_service::ExpenseService;
+use crate::services::label_service::LabelService;
+use uuid::Uuid;
 
 pub fn expense_controller(cfg: &mut ServiceConfig) {
     cfg.service(index);
@@ -21,6 +29,21 @@ pub fn expense_controller(cfg: &mut ServiceConfig) {
     cfg.service(show);
     cfg.service(update);
     cfg.service(delete);
+    cfg.service(labels);
+    cfg.service(attach_label);
+    cfg.service(detach_label);
+    cfg.service(attachments);
+    cfg.service(upload_attachment);
+    cfg.service(download_attachment);
+    cfg.service(delete_attachment);
+
+    // leave a little room for the multipart framing around the file
+    let upload_limit = max_attachment_size() + 64 * 1024;
+    cfg.app_data(
+        MultipartFormConfig::default()
+            .total_limit(upload_limit)
+            .memory_limit(upload_limit),
+    );
 }
 
 #[get("")]
@@ -28,23 +51,29 @@ async fn index(
     pool: Data<DBPool>,
     req: HttpRequest,
     q: Query<QueryParams>,
+    filter: Query<ExpenseFilter>,
     _: AuthMiddleware,
-) -> HttpResponse {
+) -> AppResult<HttpResponse> {
     let user_id = get_auth_id(req.extensions());
-    let expenses = ExpenseRepository.list_by_user_id(pool.get_ref(), user_id, q.into_inner());
-    json_pagination(expenses.unwrap())
+    let (expenses, summary) =
+        ExpenseRepository.list(pool.get_ref(), user_id, &filter, q.into_inner())?;
+    Ok(json_pagination_summary(&req, expenses, Some(summary)))
 }
 
 #[get("aggregates")]
-async fn aggregate(pool: Data<DBPool>, req: HttpRequest, _: AuthMiddleware) -> HttpResponse {
-    let user_id = get_auth_id(req.extensions());
-    let result = ExpenseRepository.fetch_aggregate_by_user_id(pool.get_ref(), user_id);
-
-    if result.is_err() {
-        return json_error_message(result.err().unwrap().to_string().as_str());
-    }
+async fn aggregate(
+    pool: Data<DBPool>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let user = get_auth_user(req.extensions());
+    let aggregate = ExpenseRepository.fetch_aggregate_by_user_id(
+        pool.get_ref(),
+        user.user_id,
+        user.timezone.as_str(),
+    )?;
 
-    json_success(result.unwrap().first().unwrap())
+    Ok(json_success(aggregate))
 }
 
 #[post("")]
@@ -53,15 +82,10 @@ async fn create(
     form: Json<ExpenseForm>,
     req: HttpRequest,
     _: AuthMiddleware,
-) -> HttpResponse {
-    let user_id = get_auth_id(req.extensions());
-    let result = ExpenseService.create(pool.get_ref(), user_id, form.into_inner());
-
-    if result.is_err() {
-        return json_error_message(result.err().unwrap());
-    }
-
-    json_success(result.unwrap())
+) -> AppResult<HttpResponse> {
+    let user = get_auth_user(req.extensions());
+    let expense = ExpenseService.create(pool.get_ref(), &user, form.into_inner())?;
+    Ok(json_success(expense))
 }
 
 #[get("{id}")]
@@ -70,23 +94,14 @@ async fn show(
     mut param: Path<IdPathParam>,
     req: HttpRequest,
     _: AuthMiddleware,
-) -> HttpResponse {
-    let id = param.get_uuid();
-    if id.is_err() {
-        return json_invalid_uuid_response();
-    }
-
-    let result = ExpenseRepository.find_owned_by_id(
+) -> AppResult<HttpResponse> {
+    let expense = ExpenseRepository.find_owned_by_id(
         pool.get_ref(),
-        id.unwrap(),
+        param.get_uuid()?,
         get_auth_id(req.extensions()),
-    );
+    )?;
 
-    if result.is_error_or_empty() {
-        return result.send_error();
-    }
-
-    json_success(result.unwrap())
+    Ok(json_success(expense))
 }
 
 #[put("{id}")]
@@ -96,24 +111,15 @@ async fn update(
     mut param: Path<IdPathParam>,
     req: HttpRequest,
     _: AuthMiddleware,
-) -> HttpResponse {
-    let id = param.get_uuid();
-    if id.is_err() {
-        return json_invalid_uuid_response();
-    }
-
-    let result = ExpenseService.update(
+) -> AppResult<HttpResponse> {
+    let expense = ExpenseService.update(
         pool.get_ref(),
-        id.unwrap(),
-        get_auth_id(req.extensions()),
+        param.get_uuid()?,
+        &get_auth_user(req.extensions()),
         form.into_inner(),
-    );
+    )?;
 
-    if result.is_err() {
-        return json_error_message(result.err().unwrap().to_string().as_str());
-    }
-
-    json_success(result.unwrap())
+    Ok(json_success(expense))
 }
 
 #[delete("{id}")]
@@ -122,15 +128,149 @@ async fn delete(
     mut param: Path<IdPathParam>,
     req: HttpRequest,
     _: AuthMiddleware,
-) -> HttpResponse {
-    let id = param.get_uuid();
-    if id.is_err() {
-        return json_invalid_uuid_response();
-    }
+) -> AppResult<HttpResponse> {
+    ExpenseService.delete(
+        pool.get_ref(),
+        param.get_uuid()?,
+        get_auth_id(req.extensions()),
+    )?;
+    Ok(json_success_message("expense deleted"))
+}
 
-    ExpenseService
-        .delete(pool.get_ref(), id.unwrap(), get_auth_id(req.extensions()))
-        .expect("Failed to delete expense");
+#[get("{id}/labels")]
+async fn labels(
+    pool: Data<DBPool>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let user_id = get_auth_id(req.extensions());
+    let expense = ExpenseRepository.find_owned_by_id(pool.get_ref(), param.get_uuid()?, user_id)?;
+
+    let labels = LabelRepository.list_by_expense_id(pool.get_ref(), expense.expense_id, user_id)?;
+    Ok(json_success(labels))
+}
+
+#[post("{id}/labels/{label_id}")]
+async fn attach_label(
+    pool: Data<DBPool>,
+    mut param: Path<LabelPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let (id, label_id) = param.get_uuids()?;
+    let user_id = get_auth_id(req.extensions());
+
+    ExpenseRepository.find_owned_by_id(pool.get_ref(), id, user_id)?;
+    let label = LabelRepository.find_owned_by_id(pool.get_ref(), label_id, user_id)?;
+
+    let expense_label = LabelService.attach_to_expense(pool.get_ref(), user_id, id, &label)?;
+    Ok(json_success(expense_label))
+}
+
+#[delete("{id}/labels/{label_id}")]
+async fn detach_label(
+    pool: Data<DBPool>,
+    mut param: Path<LabelPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let (id, label_id) = param.get_uuids()?;
+    let user_id = get_auth_id(req.extensions());
+
+    ExpenseRepository.find_owned_by_id(pool.get_ref(), id, user_id)?;
+    LabelRepository.detach_from_expense(pool.get_ref(), user_id, id, label_id)?;
+    Ok(json_success_message("label detached"))
+}
+
+#[get("{id}/attachments")]
+async fn attachments(
+    pool: Data<DBPool>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let user_id = get_auth_id(req.extensions());
+    let expense = ExpenseRepository.find_owned_by_id(pool.get_ref(), param.get_uuid()?, user_id)?;
+
+    let attachments =
+        AttachmentRepository.list_by_expense_id(pool.get_ref(), expense.expense_id, user_id)?;
+    Ok(json_success(attachments))
+}
+
+#[post("{id}/attachments")]
+async fn upload_attachment(
+    pool: Data<DBPool>,
+    mut param: Path<IdPathParam>,
+    form: MultipartForm<AttachmentUploadForm>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let expense = ExpenseRepository.find_owned_by_id(
+        pool.get_ref(),
+        param.get_uuid()?,
+        get_auth_id(req.extensions()),
+    )?;
+
+    let attachment =
+        web::block(move || AttachmentService.upload(pool.get_ref(), &expense, form.into_inner()))
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))??;
+
+    Ok(json_success(attachment))
+}
+
+#[get("{id}/attachments/{attachment_id}")]
+async fn download_attachment(
+    pool: Data<DBPool>,
+    mut param: Path<AttachmentPathParam>,
+    q: Query<AttachmentDownloadParams>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let (id, attachment_id) = param.get_uuids()?;
+    let attachment = find_expense_attachment(pool.get_ref(), &req, id, attachment_id)?;
+
+    let (data, content_type) =
+        AttachmentService.download(&attachment, q.thumbnail.unwrap_or(false))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Inline,
+            parameters: vec![DispositionParam::Filename(attachment.file_name)],
+        })
+        .body(data))
+}
+
+#[delete("{id}/attachments/{attachment_id}")]
+async fn delete_attachment(
+    pool: Data<DBPool>,
+    mut param: Path<AttachmentPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let (id, attachment_id) = param.get_uuids()?;
+    let attachment = find_expense_attachment(pool.get_ref(), &req, id, attachment_id)?;
+
+    AttachmentService.delete(pool.get_ref(), &attachment)?;
+    Ok(json_success_message("attachment deleted"))
+}
+
+/// Attachment of the user's expense, attachments of other expenses are reported as missing
+fn find_expense_attachment(
+    pool: &DBPool,
+    req: &HttpRequest,
+    id: Uuid,
+    attachment_id: Uuid,
+) -> AppResult<Attachment> {
+    let user_id = get_auth_id(req.extensions());
+    ExpenseRepository.find_owned_by_id(pool, id, user_id)?;
+
+    let attachment = AttachmentRepository.find_owned_by_id(pool, attachment_id, user_id)?;
+    if attachment.expense_id != id {
+        return Err(AppError::EntityNotFound(String::from("attachment")));
+    }
 
-    json_success_message("expense deleted")
+    Ok(attachment)
 }
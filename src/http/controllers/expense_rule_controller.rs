@@ -0,0 +1,127 @@
+use actix_web::web::{Data, Path, Query, ServiceConfig};
+use actix_web::{delete, get, post, put, HttpMessage, HttpRequest, HttpResponse};
+
+use crate::core::enums::app_error::AppResult;
+use crate::core::helpers::auth::get_auth_id;
+use crate::core::helpers::http::IdPathParam;
+use crate::core::helpers::responder::{json_success, json_success_message};
+use crate::http::extractors::json_extractor::Json;
+use crate::http::middlewares::auth_middleware::AuthMiddleware;
+use crate::models::expense_rule::{ExpenseRuleApplyForm, ExpenseRuleForm, ExpenseRuleSample};
+use crate::models::DBPool;
+use crate::repositories::expense_rule_repository::ExpenseRuleRepository;
+use crate::services::expense_rule_service::ExpenseRuleService;
+
+pub fn expense_rule_controller(cfg: &mut ServiceConfig) {
+    cfg.service(index);
+    cfg.service(create);
+    cfg.service(dry_run);
+    cfg.service(apply);
+    cfg.service(show);
+    cfg.service(update);
+    cfg.service(delete);
+}
+
+#[get("")]
+async fn index(pool: Data<DBPool>, req: HttpRequest, _: AuthMiddleware) -> AppResult<HttpResponse> {
+    let user_id = get_auth_id(req.extensions());
+    let rules = ExpenseRuleRepository.list_by_user_id(pool.get_ref(), user_id)?;
+    Ok(json_success(rules))
+}
+
+#[post("")]
+async fn create(
+    pool: Data<DBPool>,
+    form: Json<ExpenseRuleForm>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let rule = ExpenseRuleService.create(
+        pool.get_ref(),
+        get_auth_id(req.extensions()),
+        form.into_inner(),
+    )?;
+
+    Ok(json_success(rule))
+}
+
+#[post("dry-run")]
+async fn dry_run(
+    pool: Data<DBPool>,
+    sample: Json<ExpenseRuleSample>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let dry_run = ExpenseRuleService.dry_run(
+        pool.get_ref(),
+        get_auth_id(req.extensions()),
+        sample.into_inner(),
+    )?;
+
+    Ok(json_success(dry_run))
+}
+
+#[post("apply")]
+async fn apply(
+    pool: Data<DBPool>,
+    q: Query<ExpenseRuleApplyForm>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let result = ExpenseRuleService.apply(
+        pool.get_ref(),
+        get_auth_id(req.extensions()),
+        q.into_inner().overwrite.unwrap_or(false),
+    )?;
+
+    Ok(json_success(result))
+}
+
+#[get("{id}")]
+async fn show(
+    pool: Data<DBPool>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let rule = ExpenseRuleRepository.find_owned_by_id(
+        pool.get_ref(),
+        param.get_uuid()?,
+        get_auth_id(req.extensions()),
+    )?;
+
+    Ok(json_success(rule))
+}
+
+#[put("{id}")]
+async fn update(
+    pool: Data<DBPool>,
+    form: Json<ExpenseRuleForm>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let rule = ExpenseRuleService.update(
+        pool.get_ref(),
+        param.get_uuid()?,
+        get_auth_id(req.extensions()),
+        form.into_inner(),
+    )?;
+
+    Ok(json_success(rule))
+}
+
+#[delete("{id}")]
+async fn delete(
+    pool: Data<DBPool>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    ExpenseRuleRepository.delete(
+        pool.get_ref(),
+        param.get_uuid()?,
+        get_auth_id(req.extensions()),
+    )?;
+    Ok(json_success_message("expense rule deleted"))
+}
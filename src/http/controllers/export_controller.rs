@@ -0,0 +1,68 @@
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::web::{Data, Path, Query, ServiceConfig};
+use actix_web::{get, HttpMessage, HttpRequest, HttpResponse};
+
+use crate::core::enums::app_error::{AppError, AppResult};
+use crate::core::helpers::auth::get_auth_id;
+use crate::core::helpers::http::QueryParams;
+use crate::http::middlewares::auth_middleware::AuthMiddleware;
+use crate::models::expense::ExpenseFilter;
+use crate::models::export::{ExportEntity, ExportForm, ExportFormat, ExportPathParam};
+use crate::models::DBPool;
+use crate::services::export_service::ExportService;
+
+pub fn export_controller(cfg: &mut ServiceConfig) {
+    cfg.service(export);
+}
+
+#[get("{entity}")]
+async fn export(
+    pool: Data<DBPool>,
+    param: Path<ExportPathParam>,
+    form: Query<ExportForm>,
+    filter: Query<ExpenseFilter>,
+    q: Query<QueryParams>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let entity = ExportEntity::parse(param.entity.as_str())
+        .ok_or_else(|| AppError::from("You can only export expenses, budgets or projects"))?;
+
+    let format = ExportFormat::parse(form.format.as_deref().unwrap_or("csv"))
+        .ok_or_else(|| AppError::from("Export format must be one of csv, xlsx or json"))?;
+
+    let filter = filter.into_inner();
+    filter.check_ranges()?;
+
+    let user_id = get_auth_id(req.extensions());
+    let disposition = ContentDisposition {
+        disposition: DispositionType::Attachment,
+        parameters: vec![DispositionParam::Filename(format!(
+            "{}.{}",
+            entity.as_str(),
+            format.extension()
+        ))],
+    };
+
+    if format == ExportFormat::Xlsx {
+        let workbook =
+            ExportService.xlsx(pool.get_ref(), user_id, entity, filter, q.into_inner())?;
+
+        return Ok(HttpResponse::Ok()
+            .content_type(format.content_type())
+            .insert_header(disposition)
+            .body(workbook));
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header(disposition)
+        .streaming(ExportService.stream(
+            pool.get_ref().clone(),
+            user_id,
+            entity,
+            format,
+            filter,
+            q.into_inner(),
+        )))
+}
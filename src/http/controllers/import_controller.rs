@@ -0,0 +1,118 @@
+use actix_multipart::form::MultipartForm;
+use actix_web::web::{Data, Path, Query, ServiceConfig};
+use actix_web::{delete, get, post, HttpMessage, HttpRequest, HttpResponse};
+
+use crate::core::enums::app_error::AppResult;
+use crate::core::helpers::auth::get_auth_id;
+use crate::core::helpers::http::{IdPathParam, QueryParams};
+use crate::core::helpers::responder::{json_pagination, json_success, json_success_message};
+use crate::http::extractors::json_extractor::Json;
+use crate::http::middlewares::auth_middleware::AuthMiddleware;
+use crate::models::import::{ImportCommitForm, ImportMapping, ImportUploadForm};
+use crate::models::DBPool;
+use crate::repositories::import_repository::ImportRepository;
+use crate::services::import_service::ImportService;
+
+pub fn import_controller(cfg: &mut ServiceConfig) {
+    cfg.service(index);
+    cfg.service(upload);
+    cfg.service(show);
+    cfg.service(preview);
+    cfg.service(commit);
+    cfg.service(delete);
+}
+
+#[get("")]
+async fn index(
+    pool: Data<DBPool>,
+    req: HttpRequest,
+    q: Query<QueryParams>,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let user_id = get_auth_id(req.extensions());
+    let imports = ImportRepository.list_by_user_id(pool.get_ref(), user_id, q.into_inner())?;
+    Ok(json_pagination(&req, imports))
+}
+
+#[post("")]
+async fn upload(
+    pool: Data<DBPool>,
+    form: MultipartForm<ImportUploadForm>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let uploaded = ImportService.upload(
+        pool.get_ref(),
+        get_auth_id(req.extensions()),
+        form.into_inner(),
+    )?;
+
+    Ok(json_success(uploaded))
+}
+
+#[get("{id}")]
+async fn show(
+    pool: Data<DBPool>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let import = ImportRepository.find_owned_by_id(
+        pool.get_ref(),
+        param.get_uuid()?,
+        get_auth_id(req.extensions()),
+    )?;
+
+    Ok(json_success(import))
+}
+
+#[post("{id}/preview")]
+async fn preview(
+    pool: Data<DBPool>,
+    mapping: Json<ImportMapping>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let import = ImportRepository.find_owned_by_id(
+        pool.get_ref(),
+        param.get_uuid()?,
+        get_auth_id(req.extensions()),
+    )?;
+
+    let preview = ImportService.preview(pool.get_ref(), &import, &mapping)?;
+    Ok(json_success(preview))
+}
+
+#[post("{id}/commit")]
+async fn commit(
+    pool: Data<DBPool>,
+    form: Json<ImportCommitForm>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let import = ImportRepository.find_owned_by_id(
+        pool.get_ref(),
+        param.get_uuid()?,
+        get_auth_id(req.extensions()),
+    )?;
+
+    let result = ImportService.commit(pool.get_ref(), &import, form.into_inner())?;
+    Ok(json_success(result))
+}
+
+#[delete("{id}")]
+async fn delete(
+    pool: Data<DBPool>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    ImportRepository.delete(
+        pool.get_ref(),
+        param.get_uuid()?,
+        get_auth_id(req.extensions()),
+    )?;
+    Ok(json_success_message("import deleted"))
+}
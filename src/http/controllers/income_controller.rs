@@ -0,0 +1,98 @@
+use actix_web::web::{Data, Path, Query, ServiceConfig};
+use actix_web::{delete, get, post, put, HttpMessage, HttpRequest, HttpResponse};
+
+use crate::core::enums::app_error::AppResult;
+use crate::core::helpers::auth::{get_auth_id, get_auth_user};
+use crate::core::helpers::http::{IdPathParam, QueryParams};
+use crate::core::helpers::responder::{json_pagination, json_success, json_success_message};
+use crate::http::extractors::json_extractor::Json;
+use crate::http::middlewares::auth_middleware::AuthMiddleware;
+use crate::models::income::IncomeForm;
+use crate::models::DBPool;
+use crate::repositories::income_repository::IncomeRepository;
+use crate::services::income_service::IncomeService;
+
+pub fn income_controller(cfg: &mut ServiceConfig) {
+    cfg.service(index);
+    cfg.service(create);
+    cfg.service(show);
+    cfg.service(update);
+    cfg.service(delete);
+}
+
+#[get("")]
+async fn index(
+    pool: Data<DBPool>,
+    req: HttpRequest,
+    q: Query<QueryParams>,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let user_id = get_auth_id(req.extensions());
+    let incomes = IncomeRepository.list_by_user_id(pool.get_ref(), user_id, q.into_inner())?;
+    Ok(json_pagination(&req, incomes))
+}
+
+#[post("")]
+async fn create(
+    pool: Data<DBPool>,
+    form: Json<IncomeForm>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let income = IncomeService.create(
+        pool.get_ref(),
+        &get_auth_user(req.extensions()),
+        form.into_inner(),
+    )?;
+
+    Ok(json_success(income))
+}
+
+#[get("{id}")]
+async fn show(
+    pool: Data<DBPool>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let income = IncomeRepository.find_owned_by_id(
+        pool.get_ref(),
+        param.get_uuid()?,
+        get_auth_id(req.extensions()),
+    )?;
+
+    Ok(json_success(income))
+}
+
+#[put("{id}")]
+async fn update(
+    pool: Data<DBPool>,
+    form: Json<IncomeForm>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let income = IncomeService.update(
+        pool.get_ref(),
+        param.get_uuid()?,
+        &get_auth_user(req.extensions()),
+        form.into_inner(),
+    )?;
+
+    Ok(json_success(income))
+}
+
+#[delete("{id}")]
+async fn delete(
+    pool: Data<DBPool>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    IncomeRepository.delete(
+        pool.get_ref(),
+        param.get_uuid()?,
+        get_auth_id(req.extensions()),
+    )?;
+    Ok(json_success_message("income deleted"))
+}
@@ -0,0 +1,105 @@
+use actix_web::web::{Data, Path, Query, ServiceConfig};
+use actix_web::{delete, get, post, put, HttpMessage, HttpRequest, HttpResponse};
+
+use crate::core::enums::app_error::AppResult;
+use crate::core::helpers::auth::get_auth_id;
+use crate::core::helpers::http::{IdPathParam, QueryParams};
+use crate::core::helpers::responder::{json_pagination, json_success, json_success_message};
+use crate::http::extractors::json_extractor::Json;
+use crate::http::middlewares::auth_middleware::AuthMiddleware;
+use crate::models::label::{LabelFilter, LabelForm};
+use crate::models::DBPool;
+use crate::repositories::label_repository::LabelRepository;
+use crate::services::label_service::LabelService;
+
+pub fn label_controller(cfg: &mut ServiceConfig) {
+    cfg.service(index);
+    cfg.service(create);
+    cfg.service(show);
+    cfg.service(update);
+    cfg.service(delete);
+}
+
+#[get("")]
+async fn index(
+    pool: Data<DBPool>,
+    req: HttpRequest,
+    q: Query<QueryParams>,
+    filter: Query<LabelFilter>,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let user_id = get_auth_id(req.extensions());
+    let labels = LabelRepository.list_by_user_id(
+        pool.get_ref(),
+        user_id,
+        filter.into_inner().module,
+        q.into_inner(),
+    )?;
+
+    Ok(json_pagination(&req, labels))
+}
+
+#[post("")]
+async fn create(
+    pool: Data<DBPool>,
+    form: Json<LabelForm>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let label = LabelService.create(
+        pool.get_ref(),
+        get_auth_id(req.extensions()),
+        form.into_inner(),
+    )?;
+
+    Ok(json_success(label))
+}
+
+#[get("{id}")]
+async fn show(
+    pool: Data<DBPool>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let label = LabelRepository.find_owned_by_id(
+        pool.get_ref(),
+        param.get_uuid()?,
+        get_auth_id(req.extensions()),
+    )?;
+
+    Ok(json_success(label))
+}
+
+#[put("{id}")]
+async fn update(
+    pool: Data<DBPool>,
+    form: Json<LabelForm>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let label = LabelService.update(
+        pool.get_ref(),
+        param.get_uuid()?,
+        get_auth_id(req.extensions()),
+        form.into_inner(),
+    )?;
+
+    Ok(json_success(label))
+}
+
+#[delete("{id}")]
+async fn delete(
+    pool: Data<DBPool>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    LabelRepository.delete(
+        pool.get_ref(),
+        param.get_uuid()?,
+        get_auth_id(req.extensions()),
+    )?;
+    Ok(json_success_message("label deleted"))
+}
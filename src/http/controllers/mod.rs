@@ -1,15 +1,41 @@
+use crate::http::controllers::account_controller::account_controller;
 use crate::http::controllers::auth_controller::auth_controller;
+use crate::http::controllers::bank_account_controller::bank_account_controller;
 use crate::http::controllers::budget_controller::budget_controller;
+use crate::http::controllers::category_controller::category_controller;
+use crate::http::controllers::dashboard_controller::dashboard_controller;
 use crate::http::controllers::expense_controller::expense_controller;
+use crate::http::controllers::expense_rule_controller::expense_rule_controller;
+use crate::http::controllers::export_controller::export_controller;
+use crate::http::controllers::import_controller::import_controller;
+use crate::http::controllers::income_controller::income_controller;
+use crate::http::controllers::label_controller::label_controller;
 use crate::http::controllers::main_controller::main_controller;
+use crate::http::controllers::notification_controller::notification_controller;
 use crate::http::controllers::project_controller::project_controller;
+use crate::http::controllers::report_controller::report_controller;
+use crate::http::controllers::search_controller::search_controller;
+use crate::http::controllers::trash_controller::trash_controller;
 use actix_web::web::ServiceConfig;
 
+mod account_controller;
 mod auth_controller;
+mod bank_account_controller;
 mod budget_controller;
+mod category_controller;
+mod dashboard_controller;
 mod expense_controller;
+mod expense_rule_controller;
+mod export_controller;
+mod import_controller;
+mod income_controller;
+mod label_controller;
 mod main_controller;
+mod notification_controller;
 mod project_controller;
+mod report_controller;
+mod search_controller;
+mod trash_controller;
 
 pub struct Controller {
     pub path: String,
@@ -30,6 +56,13 @@ pub fn routes() -> Vec<Route> {
                 handler: main_controller,
             }],
         },
+        Route {
+            prefix: String::from("/dashboard"),
+            controllers: vec![Controller {
+                path: String::from(""),
+                handler: dashboard_controller,
+            }],
+        },
         Route {
             prefix: String::from("/api/v1"),
             controllers: vec![
@@ -37,6 +70,10 @@ pub fn routes() -> Vec<Route> {
                     path: String::from("/auth"),
                     handler: auth_controller,
                 },
+                Controller {
+                    path: String::from("/account"),
+                    handler: account_controller,
+                },
                 Controller {
                     path: String::from("/projects"),
                     handler: project_controller,
@@ -49,9 +86,624 @@ pub fn routes() -> Vec<Route> {
                     path: String::from("/expenses"),
                     handler: expense_controller,
                 },
+                Controller {
+                    path: String::from("/categories"),
+                    handler: category_controller,
+                },
+                Controller {
+                    path: String::from("/expense-rules"),
+                    handler: expense_rule_controller,
+                },
+                Controller {
+                    path: String::from("/imports"),
+                    handler: import_controller,
+                },
+                Controller {
+                    path: String::from("/incomes"),
+                    handler: income_controller,
+                },
+                Controller {
+                    path: String::from("/bank-accounts"),
+                    handler: bank_account_controller,
+                },
+                Controller {
+                    path: String::from("/exports"),
+                    handler: export_controller,
+                },
+                Controller {
+                    path: String::from("/labels"),
+                    handler: label_controller,
+                },
+                Controller {
+                    path: String::from("/notifications"),
+                    handler: notification_controller,
+                },
+                Controller {
+                    path: String::from("/reports"),
+                    handler: report_controller,
+                },
+                Controller {
+                    path: String::from("/search"),
+                    handler: search_controller,
+                },
+                Controller {
+                    path: String::from("/trash"),
+                    handler: trash_controller,
+                },
             ],
         },
     ];
 
     routes
 }
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::{header, Method, StatusCode};
+    use actix_web::web::Data;
+    use actix_web::{test, App};
+    use chrono::Duration;
+    use serde_json::{json, Value};
+    use tera::Tera;
+    use uuid::Uuid;
+
+    use crate::core::export::archive::read_archive;
+    use crate::core::helpers::db::current_timestamp;
+    use crate::core::helpers::template::register_template_filters;
+    use crate::core::helpers::testing::{create_budget, create_user, make_token, test_pool};
+    use crate::http::kernel::{register_extractor_configs, register_routes};
+    use crate::models::attachment::Attachment;
+    use crate::models::bank_account::BankAccountForm;
+    use crate::models::budget::{BudgetForm, OverspendPolicy};
+    use crate::models::expense::{ExpenseCascade, ExpenseForm};
+    use crate::models::expense_rule::ExpenseRuleForm;
+    use crate::models::import::IMPORT_SOURCE_CSV;
+    use crate::models::income::IncomeForm;
+    use crate::models::label::LabelForm;
+    use crate::models::notification::NotificationEvent;
+    use crate::models::project::ProjectForm;
+    use crate::models::DBPool;
+    use crate::repositories::attachment_repository::AttachmentRepository;
+    use crate::repositories::bank_account_repository::BankAccountRepository;
+    use crate::repositories::budget_repository::BudgetRepository;
+    use crate::repositories::category_repository::CategoryRepository;
+    use crate::repositories::expense_repository::ExpenseRepository;
+    use crate::repositories::expense_rule_repository::ExpenseRuleRepository;
+    use crate::repositories::import_repository::ImportRepository;
+    use crate::repositories::income_repository::IncomeRepository;
+    use crate::repositories::label_repository::LabelRepository;
+    use crate::repositories::notification_repository::NotificationRepository;
+    use crate::repositories::project_repository::ProjectRepository;
+
+    /// Narration of the owner's records, no response to another user may mention it
+    const MARKER: &str = "quokka";
+    /// Amount of the owner's expense, no report of another user may add it up
+    const AMOUNT: i64 = 4321;
+    const UPLOAD_BOUNDARY: &str = "nucleus-isolation";
+
+    /// Everything the owner has, live and trashed
+    struct Owned {
+        project_id: Uuid,
+        budget_id: Uuid,
+        expense_id: Uuid,
+        label_id: Uuid,
+        category_id: Uuid,
+        income_id: Uuid,
+        bank_account_id: Uuid,
+        expense_rule_id: Uuid,
+        import_id: Uuid,
+        notification_id: Uuid,
+        attachment_id: Uuid,
+        trashed_project_id: Uuid,
+        trashed_budget_id: Uuid,
+        trashed_expense_id: Uuid,
+    }
+
+    fn project(pool: &DBPool, user_id: Uuid) -> Uuid {
+        let form = ProjectForm {
+            name: format!("{} household", MARKER),
+            description: String::new(),
+        };
+        ProjectRepository
+            .create(pool, user_id, form)
+            .unwrap()
+            .project_id
+    }
+
+    fn expense(pool: &DBPool, user_id: Uuid, budget_id: Uuid, project_id: Uuid) -> Uuid {
+        let form = ExpenseForm {
+            project_id: project_id.to_string(),
+            amount: AMOUNT,
+            narration: format!("{} food", MARKER),
+            spent_at: None,
+            category_id: None,
+        };
+        let (expense, _, _) = ExpenseRepository
+            .create(pool, user_id, "UTC", budget_id, form)
+            .unwrap();
+        expense.expense_id
+    }
+
+    fn create_owned(pool: &DBPool, user_id: Uuid) -> Owned {
+        let project_id = project(pool, user_id);
+        let budget = create_budget(pool, user_id, 100_000, OverspendPolicy::Allow);
+        let expense_id = expense(pool, user_id, budget.budget_id, project_id);
+
+        let label = LabelRepository
+            .create(
+                pool,
+                user_id,
+                LabelForm {
+                    name: format!("{} label", MARKER),
+                    module: String::from("expenses"),
+                },
+            )
+            .unwrap();
+        LabelRepository
+            .attach_to_expense(pool, user_id, expense_id, label.label_id)
+            .unwrap();
+
+        let category = CategoryRepository
+            .create(pool, user_id, None, format!("{} category", MARKER))
+            .unwrap();
+
+        let income = IncomeRepository
+            .create(
+                pool,
+                user_id,
+                "UTC",
+                IncomeForm {
+                    project_id: project_id.to_string(),
+                    amount: AMOUNT,
+                    narration: format!("{} salary", MARKER),
+                    received_at: None,
+                },
+            )
+            .unwrap();
+
+        let bank_account = BankAccountRepository
+            .create(
+                pool,
+                user_id,
+                BankAccountForm {
+                    project_id: project_id.to_string(),
+                    account_number: format!("{}-0001", MARKER),
+                    name: None,
+                },
+            )
+            .unwrap();
+
+        let expense_rule = ExpenseRuleRepository
+            .create(pool, user_id, rule_form(MARKER, None))
+            .unwrap();
+
+        let import = ImportRepository
+            .create(
+                pool,
+                user_id,
+                IMPORT_SOURCE_CSV,
+                String::from("statement.csv"),
+                format!("date,amount,narration\n2026-01-02,12.00,{}\n", MARKER),
+            )
+            .unwrap();
+
+        let notification = NotificationRepository
+            .create(
+                pool,
+                &NotificationEvent::BudgetThresholdCrossed {
+                    budget: budget.clone(),
+                    threshold: 80,
+                },
+            )
+            .unwrap();
+
+        let attachment = AttachmentRepository
+            .create(
+                pool,
+                Attachment {
+                    attachment_id: Uuid::new_v4(),
+                    user_id,
+                    expense_id,
+                    file_name: format!("{}.txt", MARKER),
+                    content_type: String::from("text/plain"),
+                    size: 1,
+                    storage_key: format!("{}/missing", user_id),
+                    thumbnail_key: None,
+                    created_at: current_timestamp(),
+                    updated_at: current_timestamp(),
+                    deleted_at: None,
+                },
+            )
+            .unwrap();
+
+        let trashed_project_id = project(pool, user_id);
+        ProjectRepository
+            .delete(pool, trashed_project_id, user_id, ExpenseCascade::Refuse)
+            .unwrap();
+
+        let trashed_budget = BudgetRepository
+            .create(
+                pool,
+                user_id,
+                BudgetForm {
+                    amount: 1000,
+                    month: 1,
+                    year: 2031,
+                    comment: None,
+                    alert_thresholds: None,
+                    overspend_policy: None,
+                },
+            )
+            .unwrap();
+        BudgetRepository
+            .delete(
+                pool,
+                trashed_budget.budget_id,
+                user_id,
+                ExpenseCascade::Refuse,
+            )
+            .unwrap();
+
+        let trashed_expense_id = expense(pool, user_id, budget.budget_id, project_id);
+        ExpenseRepository
+            .delete(pool, trashed_expense_id, user_id)
+            .unwrap();
+
+        Owned {
+            project_id,
+            budget_id: budget.budget_id,
+            expense_id,
+            label_id: label.label_id,
+            category_id: category.category_id,
+            income_id: income.income_id,
+            bank_account_id: bank_account.bank_account_id,
+            expense_rule_id: expense_rule.expense_rule_id,
+            import_id: import.import_id,
+            notification_id: notification.notification_id,
+            attachment_id: attachment.attachment_id,
+            trashed_project_id,
+            trashed_budget_id: trashed_budget.budget_id,
+            trashed_expense_id,
+        }
+    }
+
+    fn rule_form(narration_contains: &str, category_id: Option<Uuid>) -> ExpenseRuleForm {
+        ExpenseRuleForm {
+            name: String::from("Pets"),
+            priority: None,
+            narration_contains: Some(String::from(narration_contains)),
+            narration_pattern: None,
+            min_amount: None,
+            max_amount: None,
+            category_id: category_id.map(|id| id.to_string()),
+            label_ids: None,
+        }
+    }
+
+    fn upload_body() -> String {
+        format!(
+            "--{0}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"receipt.txt\"\r\n\
+             Content-Type: text/plain\r\n\r\nreceipt\r\n--{0}--\r\n",
+            UPLOAD_BOUNDARY
+        )
+    }
+
+    /// Status and body of every request, made by the holder of the token
+    async fn call_all(
+        pool: &DBPool,
+        token: &str,
+        requests: Vec<(Method, String, Option<Value>)>,
+    ) -> Vec<(String, StatusCode, String)> {
+        let mut tera = Tera::new(concat!(env!("CARGO_MANIFEST_DIR"), "/templates/**/*")).unwrap();
+        register_template_filters(&mut tera);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(pool.clone()))
+                .app_data(Data::new(tera))
+                .configure(register_extractor_configs)
+                .configure(register_routes),
+        )
+        .await;
+
+        let mut responses = vec![];
+        for (method, uri, body) in requests {
+            let mut req = test::TestRequest::default()
+                .method(method.clone())
+                .uri(uri.as_str())
+                .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)));
+
+            req = match body {
+                Some(Value::String(multipart)) => req
+                    .insert_header((
+                        header::CONTENT_TYPE,
+                        format!("multipart/form-data; boundary={}", UPLOAD_BOUNDARY),
+                    ))
+                    .set_payload(multipart),
+                Some(json) => req.set_json(json),
+                None => req,
+            };
+
+            let res = test::call_service(&app, req.to_request()).await;
+            let status = res.status();
+            let body = test::read_body(res).await;
+            responses.push((
+                format!("{} {}", method, uri),
+                status,
+                String::from_utf8_lossy(&body).to_string(),
+            ));
+        }
+
+        responses
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs the migrated database configured in .env"]
+    async fn other_users_get_404_for_every_owned_route() {
+        let pool = test_pool();
+        let owner = create_user(&pool);
+        let other = create_user(&pool);
+        let owned = create_owned(&pool, owner.user_id);
+        let own_project_id = project(&pool, other.user_id);
+        let own_category = CategoryRepository
+            .create(&pool, other.user_id, None, String::from("Mine"))
+            .unwrap();
+        let token = make_token(other.user_id, Duration::hours(1));
+
+        let (p, b, e) = (owned.project_id, owned.budget_id, owned.expense_id);
+        let (l, a) = (owned.label_id, owned.attachment_id);
+        let project_form = json!({"name": "Mine", "description": ""});
+        let budget_form = json!({"amount": 10, "month": 1, "year": 2031});
+        let expense_form = json!({"project_id": own_project_id, "amount": 10, "narration": "Mine"});
+        let income_form = expense_form.clone();
+        let bank_account_form = json!({"project_id": own_project_id, "account_number": "1"});
+        let label_form = json!({"name": "Mine", "module": "expenses"});
+        let rule_form = json!({
+            "name": "Mine",
+            "narration_contains": "mine",
+            "category_id": own_category.category_id,
+        });
+        let get = |uri: String| (Method::GET, uri, None);
+        let delete = |uri: String| (Method::DELETE, uri, None);
+        let put = |uri: String, body: &Value| (Method::PUT, uri, Some(body.clone()));
+        let post = |uri: String, body: Option<Value>| (Method::POST, uri, body);
+
+        let mut requests = vec![
+            get(format!("/api/v1/projects/{}", p)),
+            put(format!("/api/v1/projects/{}", p), &project_form),
+            get(format!("/api/v1/projects/{}/aggregates", p)),
+            get(format!("/api/v1/projects/{}/expenses", p)),
+            get(format!("/api/v1/projects/{}/labels", p)),
+            post(format!("/api/v1/projects/{}/labels/{}", p, l), None),
+            delete(format!("/api/v1/projects/{}/labels/{}", p, l)),
+            get(format!("/api/v1/budgets/{}", b)),
+            put(format!("/api/v1/budgets/{}", b), &budget_form),
+            get(format!("/api/v1/budgets/{}/expenses", b)),
+            get(format!("/api/v1/budgets/{}/report", b)),
+            get(format!("/api/v1/budgets/{}/statement.pdf", b)),
+            get(format!("/api/v1/budgets/{}/statement.html", b)),
+            get(format!("/api/v1/expenses/{}", e)),
+            put(format!("/api/v1/expenses/{}", e), &expense_form),
+            get(format!("/api/v1/expenses/{}/labels", e)),
+            post(format!("/api/v1/expenses/{}/labels/{}", e, l), None),
+            delete(format!("/api/v1/expenses/{}/labels/{}", e, l)),
+            get(format!("/api/v1/expenses/{}/attachments", e)),
+            post(
+                format!("/api/v1/expenses/{}/attachments", e),
+                Some(Value::String(upload_body())),
+            ),
+            get(format!("/api/v1/expenses/{}/attachments/{}", e, a)),
+            delete(format!("/api/v1/expenses/{}/attachments/{}", e, a)),
+            get(format!("/api/v1/labels/{}", l)),
+            put(format!("/api/v1/labels/{}", l), &label_form),
+            get(format!("/api/v1/categories/{}", owned.category_id)),
+            put(
+                format!("/api/v1/categories/{}", owned.category_id),
+                &json!({"name": "Mine"}),
+            ),
+            get(format!("/api/v1/incomes/{}", owned.income_id)),
+            put(format!("/api/v1/incomes/{}", owned.income_id), &income_form),
+            get(format!("/api/v1/bank-accounts/{}", owned.bank_account_id)),
+            put(
+                format!("/api/v1/bank-accounts/{}", owned.bank_account_id),
+                &bank_account_form,
+            ),
+            get(format!("/api/v1/expense-rules/{}", owned.expense_rule_id)),
+            put(
+                format!("/api/v1/expense-rules/{}", owned.expense_rule_id),
+                &rule_form,
+            ),
+            get(format!("/api/v1/imports/{}", owned.import_id)),
+            post(
+                format!("/api/v1/imports/{}/preview", owned.import_id),
+                Some(json!({})),
+            ),
+            post(
+                format!("/api/v1/imports/{}/commit", owned.import_id),
+                Some(json!({})),
+            ),
+            put(
+                format!("/api/v1/notifications/{}/read", owned.notification_id),
+                &json!({}),
+            ),
+        ];
+
+        for (kind, id) in [
+            ("projects", owned.trashed_project_id),
+            ("budgets", owned.trashed_budget_id),
+            ("expenses", owned.trashed_expense_id),
+        ] {
+            requests.push(post(format!("/api/v1/trash/{}/{}/restore", kind, id), None));
+            requests.push(delete(format!("/api/v1/trash/{}/{}", kind, id)));
+        }
+
+        // deletes last, so that a leak can't hide behind an earlier one
+        requests.extend([
+            delete(format!("/api/v1/expenses/{}", e)),
+            delete(format!("/api/v1/budgets/{}?expenses=cascade", b)),
+            delete(format!("/api/v1/projects/{}?expenses=cascade", p)),
+            delete(format!("/api/v1/labels/{}", l)),
+            delete(format!("/api/v1/categories/{}", owned.category_id)),
+            delete(format!("/api/v1/incomes/{}", owned.income_id)),
+            delete(format!("/api/v1/bank-accounts/{}", owned.bank_account_id)),
+            delete(format!("/api/v1/expense-rules/{}", owned.expense_rule_id)),
+            delete(format!("/api/v1/imports/{}", owned.import_id)),
+        ]);
+
+        for (request, status, body) in call_all(&pool, &token, requests).await {
+            assert_eq!(
+                status,
+                StatusCode::NOT_FOUND,
+                "{} answered {}",
+                request,
+                body
+            );
+        }
+
+        // nothing of the owner was touched
+        let user_id = owner.user_id;
+        let budget = BudgetRepository
+            .find_owned_by_id(&pool, b, user_id)
+            .unwrap();
+        assert_eq!(budget.amount_used, AMOUNT);
+        ProjectRepository
+            .find_owned_by_id(&pool, p, user_id)
+            .unwrap();
+        ExpenseRepository
+            .find_owned_by_id(&pool, e, user_id)
+            .unwrap();
+        LabelRepository.find_owned_by_id(&pool, l, user_id).unwrap();
+        AttachmentRepository
+            .find_owned_by_id(&pool, a, user_id)
+            .unwrap();
+        ImportRepository
+            .find_owned_by_id(&pool, owned.import_id, user_id)
+            .unwrap();
+        ProjectRepository
+            .find_trashed_by_id(&pool, owned.trashed_project_id, user_id)
+            .unwrap();
+        BudgetRepository
+            .find_trashed_by_id(&pool, owned.trashed_budget_id, user_id)
+            .unwrap();
+        ExpenseRepository
+            .find_trashed_by_id(&pool, owned.trashed_expense_id, user_id)
+            .unwrap();
+        let labels = LabelRepository
+            .list_by_expense_id(&pool, e, user_id)
+            .unwrap();
+        assert_eq!(labels.len(), 1);
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs the migrated database configured in .env"]
+    async fn other_users_never_see_owned_records() {
+        let pool = test_pool();
+        let owner = create_user(&pool);
+        let other = create_user(&pool);
+        let owned = create_owned(&pool, owner.user_id);
+        let token = make_token(other.user_id, Duration::hours(1));
+
+        // a rule of the other user that would match the owner's expense
+        let category = CategoryRepository
+            .create(&pool, other.user_id, None, String::from("Mine"))
+            .unwrap();
+        ExpenseRuleRepository
+            .create(
+                &pool,
+                other.user_id,
+                rule_form("food", Some(category.category_id)),
+            )
+            .unwrap();
+
+        let (p, b, l) = (owned.project_id, owned.budget_id, owned.label_id);
+        let requests: Vec<(Method, String, Option<Value>)> = [
+            "/api/v1/projects",
+            "/api/v1/budgets",
+            "/api/v1/expenses?include_deleted=true",
+            "/api/v1/expenses/aggregates",
+            "/api/v1/labels",
+            "/api/v1/incomes",
+            "/api/v1/bank-accounts",
+            "/api/v1/imports",
+            "/api/v1/notifications",
+            "/api/v1/trash",
+            // the search echoes its query, which must not be the marker itself
+            "/api/v1/search?q=food",
+            &format!("/api/v1/expenses?project_id={}&budget_id={}", p, b),
+            &format!("/api/v1/projects?labels={}", l),
+            "/api/v1/exports/expenses?format=json",
+            "/api/v1/exports/budgets?format=json",
+            "/api/v1/exports/projects?format=json",
+            "/api/v1/reports/spending",
+            &format!("/api/v1/reports/spending?project_id={}", p),
+            &format!("/api/v1/reports/spending?label_id={}", l),
+            "/api/v1/budgets/report",
+        ]
+        .into_iter()
+        .map(|uri| (Method::GET, uri.to_string(), None))
+        .chain([
+            (
+                Method::POST,
+                String::from("/api/v1/expense-rules/dry-run"),
+                Some(json!({"narration": MARKER, "amount": AMOUNT})),
+            ),
+            (
+                Method::POST,
+                String::from("/api/v1/expense-rules/apply?overwrite=true"),
+                None,
+            ),
+            (Method::DELETE, String::from("/api/v1/trash"), None),
+        ])
+        .collect();
+
+        for (request, status, body) in call_all(&pool, &token, requests).await {
+            assert!(
+                !body.contains(MARKER) && !body.contains(&AMOUNT.to_string()),
+                "{} answered {} with {}",
+                request,
+                status,
+                body
+            );
+        }
+
+        let categories = call_all(
+            &pool,
+            &token,
+            vec![(Method::GET, String::from("/api/v1/categories"), None)],
+        )
+        .await;
+        assert!(!categories[0].2.contains(&owned.category_id.to_string()));
+
+        let rules = call_all(
+            &pool,
+            &token,
+            vec![(Method::GET, String::from("/api/v1/expense-rules"), None)],
+        )
+        .await;
+        assert!(!rules[0].2.contains(&owned.expense_rule_id.to_string()));
+
+        let export = test::init_service(
+            App::new()
+                .app_data(Data::new(pool.clone()))
+                .configure(register_routes),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/api/v1/account/export")
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+            .to_request();
+        let archive = read_archive(&test::call_and_read_body(&export, req).await).unwrap();
+        assert!(archive.projects.is_empty() && archive.expenses.is_empty());
+
+        // applying the other user's rules left the owner's expense alone
+        let expense = ExpenseRepository
+            .find_owned_by_id(&pool, owned.expense_id, owner.user_id)
+            .unwrap();
+        assert_eq!(expense.category_id, None);
+
+        // emptying the other user's trash left the owner's one alone
+        ExpenseRepository
+            .find_trashed_by_id(&pool, owned.trashed_expense_id, owner.user_id)
+            .unwrap();
+    }
+}
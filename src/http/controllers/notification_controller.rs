@@ -0,0 +1,63 @@
+use actix_web::web::{Data, Path, Query, ServiceConfig};
+use actix_web::{get, put, HttpMessage, HttpRequest, HttpResponse};
+
+use crate::core::enums::app_error::AppResult;
+use crate::core::helpers::auth::get_auth_id;
+use crate::core::helpers::http::{IdPathParam, QueryParams};
+use crate::core::helpers::responder::{json_pagination, json_success, json_success_message};
+use crate::http::middlewares::auth_middleware::AuthMiddleware;
+use crate::models::notification::NotificationFilter;
+use crate::models::DBPool;
+use crate::repositories::notification_repository::NotificationRepository;
+
+pub fn notification_controller(cfg: &mut ServiceConfig) {
+    cfg.service(index);
+    cfg.service(mark_all_as_read);
+    cfg.service(mark_as_read);
+}
+
+#[get("")]
+async fn index(
+    pool: Data<DBPool>,
+    req: HttpRequest,
+    q: Query<QueryParams>,
+    filter: Query<NotificationFilter>,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let user_id = get_auth_id(req.extensions());
+    let notifications = NotificationRepository.list_by_user_id(
+        pool.get_ref(),
+        user_id,
+        filter.unread.unwrap_or(false),
+        q.into_inner(),
+    )?;
+
+    Ok(json_pagination(&req, notifications))
+}
+
+#[put("read")]
+async fn mark_all_as_read(
+    pool: Data<DBPool>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let user_id = get_auth_id(req.extensions());
+    NotificationRepository.mark_all_as_read(pool.get_ref(), user_id)?;
+    Ok(json_success_message("notifications marked as read"))
+}
+
+#[put("{id}/read")]
+async fn mark_as_read(
+    pool: Data<DBPool>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let notification = NotificationRepository.mark_as_read(
+        pool.get_ref(),
+        param.get_uuid()?,
+        get_auth_id(req.extensions()),
+    )?;
+
+    Ok(json_success(notification))
+}
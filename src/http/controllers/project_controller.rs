@@ -1,17 +1,20 @@
-use crate::core::enums::http_error::ErroneousOption;
-use crate::core::helpers::auth::get_auth_id;
-use crate::core::helpers::http::{IdPathParam, QueryParams};
+use crate::core::enums::app_error::AppResult;
+use crate::core::helpers::auth::{get_auth_id, get_auth_user};
+use crate::core::helpers::http::{IdPathParam, LabelPathParam, QueryParams};
 use crate::core::helpers::responder::{
-    json_entity_not_found_response, json_error_message, json_invalid_uuid_response,
-    json_pagination, json_success, json_success_message,
+    json_pagination, json_pagination_summary, json_success, json_success_message,
 };
+use crate::http::extractors::json_extractor::Json;
 use crate::http::middlewares::auth_middleware::AuthMiddleware;
+use crate::models::expense::{ExpenseCascadeParams, ExpenseFilter};
 use crate::models::project::ProjectForm;
 use crate::models::DBPool;
 use crate::repositories::expense_repository::ExpenseRepository;
+use crate::repositories::label_repository::LabelRepository;
 use crate::repositories::project_repository::ProjectRepository;
+use crate::services::label_service::LabelService;
 use crate::services::project_service::ProjectService;
-use actix_web::web::{Data, Json, Path, Query, ServiceConfig};
+use actix_web::web::{Data, Path, Query, ServiceConfig};
 use actix_web::{delete, get, post, put, HttpMessage, HttpRequest, HttpResponse};
 
 pub fn project_controller(cfg: &mut ServiceConfig) {
@@ -21,6 +24,9 @@ pub fn project_controller(cfg: &mut ServiceConfig) {
     cfg.service(aggregate);
     cfg.service(update);
     cfg.service(delete);
+    cfg.service(labels);
+    cfg.service(attach_label);
+    cfg.service(detach_label);
     cfg.service(expenses);
 }
 
@@ -30,10 +36,10 @@ async fn index(
     req: HttpRequest,
     q: Query<QueryParams>,
     _: AuthMiddleware,
-) -> HttpResponse {
+) -> AppResult<HttpResponse> {
     let user_id = get_auth_id(req.extensions());
-    let projects = ProjectRepository.list_by_user_id(pool.get_ref(), user_id, q.into_inner());
-    json_pagination(projects.unwrap())
+    let projects = ProjectRepository.list_by_user_id(pool.get_ref(), user_id, q.into_inner())?;
+    Ok(json_pagination(&req, projects))
 }
 
 #[post("")]
@@ -42,14 +48,14 @@ async fn create(
     form: Json<ProjectForm>,
     req: HttpRequest,
     _: AuthMiddleware,
-) -> HttpResponse {
+) -> AppResult<HttpResponse> {
     let project = ProjectService.create(
         pool.get_ref(),
         get_auth_id(req.extensions()),
         form.into_inner(),
-    );
+    )?;
 
-    json_success(project)
+    Ok(json_success(project))
 }
 
 #[get("{id}")]
@@ -58,43 +64,34 @@ async fn show(
     mut param: Path<IdPathParam>,
     req: HttpRequest,
     _: AuthMiddleware,
-) -> HttpResponse {
-    let id = param.get_uuid();
-    if id.is_err() {
-        return json_invalid_uuid_response();
-    }
-
-    let result = ProjectRepository.find_owned_by_id(
+) -> AppResult<HttpResponse> {
+    let project = ProjectRepository.find_owned_by_id(
         pool.get_ref(),
-        id.unwrap(),
+        param.get_uuid()?,
         get_auth_id(req.extensions()),
-    );
-
-    if result.is_err() {
-        return json_entity_not_found_response("project");
-    }
+    )?;
 
-    json_success(result.unwrap_entity())
+    Ok(json_success(project))
 }
 
 #[get("{id}/aggregates")]
 async fn aggregate(
     pool: Data<DBPool>,
     mut param: Path<IdPathParam>,
+    req: HttpRequest,
     _: AuthMiddleware,
-) -> HttpResponse {
-    let id = param.get_uuid();
-    if id.is_err() {
-        return json_invalid_uuid_response();
-    }
-
-    let result = ProjectRepository.fetch_aggregate_by_project_id(pool.get_ref(), id.unwrap());
-
-    if result.is_err() {
-        return json_error_message(result.err().unwrap().to_string().as_str());
-    }
+) -> AppResult<HttpResponse> {
+    let user = get_auth_user(req.extensions());
+    let project =
+        ProjectRepository.find_owned_by_id(pool.get_ref(), param.get_uuid()?, user.user_id)?;
+    let aggregate = ProjectRepository.fetch_aggregate_by_project_id(
+        pool.get_ref(),
+        project.project_id,
+        user.user_id,
+        user.timezone.as_str(),
+    )?;
 
-    json_success(result.unwrap().first().unwrap())
+    Ok(json_success(aggregate))
 }
 
 #[put("{id}")]
@@ -104,58 +101,97 @@ async fn update(
     mut param: Path<IdPathParam>,
     req: HttpRequest,
     _: AuthMiddleware,
-) -> HttpResponse {
-    let id = param.get_uuid();
-    if id.is_err() {
-        return json_invalid_uuid_response();
-    }
-
-    let result = ProjectService.update(
+) -> AppResult<HttpResponse> {
+    let project = ProjectService.update(
         pool.get_ref(),
-        id.unwrap(),
+        param.get_uuid()?,
         get_auth_id(req.extensions()),
         form.into_inner(),
-    );
-
-    if result.is_err() {
-        return json_error_message(result.err().unwrap().to_string().as_str());
-    }
+    )?;
 
-    json_success(result.unwrap())
+    Ok(json_success(project))
 }
 
 #[delete("{id}")]
 async fn delete(
     pool: Data<DBPool>,
     mut param: Path<IdPathParam>,
+    q: Query<ExpenseCascadeParams>,
     req: HttpRequest,
     _: AuthMiddleware,
-) -> HttpResponse {
-    let id = param.get_uuid();
-    if id.is_err() {
-        return json_invalid_uuid_response();
-    }
-
-    ProjectService
-        .delete(pool.get_ref(), id.unwrap(), get_auth_id(req.extensions()))
-        .expect("Failed to delete project");
-
-    json_success_message("project deleted")
+) -> AppResult<HttpResponse> {
+    ProjectService.delete(
+        pool.get_ref(),
+        param.get_uuid()?,
+        get_auth_id(req.extensions()),
+        q.get_cascade()?,
+    )?;
+    Ok(json_success_message("project deleted"))
 }
 
 #[get("{id}/expenses")]
 async fn expenses(
     pool: Data<DBPool>,
+    req: HttpRequest,
     mut param: Path<IdPathParam>,
     q: Query<QueryParams>,
+    filter: Query<ExpenseFilter>,
     _: AuthMiddleware,
-) -> HttpResponse {
-    let id = param.get_uuid();
-    if id.is_err() {
-        return json_invalid_uuid_response();
-    }
-
-    let projects =
-        ExpenseRepository.list_by_project_id(pool.get_ref(), id.unwrap(), q.into_inner());
-    json_pagination(projects.unwrap())
+) -> AppResult<HttpResponse> {
+    let user_id = get_auth_id(req.extensions());
+    let project = ProjectRepository.find_owned_by_id(pool.get_ref(), param.get_uuid()?, user_id)?;
+    let filter = ExpenseFilter {
+        project_id: vec![project.project_id],
+        ..filter.into_inner()
+    };
+
+    let (expenses, summary) =
+        ExpenseRepository.list(pool.get_ref(), user_id, &filter, q.into_inner())?;
+    Ok(json_pagination_summary(&req, expenses, Some(summary)))
+}
+
+#[get("{id}/labels")]
+async fn labels(
+    pool: Data<DBPool>,
+    mut param: Path<IdPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let user_id = get_auth_id(req.extensions());
+    let project = ProjectRepository.find_owned_by_id(pool.get_ref(), param.get_uuid()?, user_id)?;
+
+    let labels = LabelRepository.list_by_project_id(pool.get_ref(), project.project_id, user_id)?;
+    Ok(json_success(labels))
+}
+
+#[post("{id}/labels/{label_id}")]
+async fn attach_label(
+    pool: Data<DBPool>,
+    mut param: Path<LabelPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let (id, label_id) = param.get_uuids()?;
+    let user_id = get_auth_id(req.extensions());
+
+    ProjectRepository.find_owned_by_id(pool.get_ref(), id, user_id)?;
+    let label = LabelRepository.find_owned_by_id(pool.get_ref(), label_id, user_id)?;
+
+    let project_label = LabelService.attach_to_project(pool.get_ref(), user_id, id, &label)?;
+    Ok(json_success(project_label))
+}
+
+#[delete("{id}/labels/{label_id}")]
+async fn detach_label(
+    pool: Data<DBPool>,
+    mut param: Path<LabelPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let (id, label_id) = param.get_uuids()?;
+    let user_id = get_auth_id(req.extensions());
+
+    ProjectRepository.find_owned_by_id(pool.get_ref(), id, user_id)?;
+    LabelRepository.detach_from_project(pool.get_ref(), user_id, id, label_id)?;
+    Ok(json_success_message("label detached"))
 }
@@ -0,0 +1,26 @@
+use actix_web::web::{Data, Query, ServiceConfig};
+use actix_web::{get, HttpMessage, HttpRequest, HttpResponse};
+
+use crate::core::enums::app_error::AppResult;
+use crate::core::helpers::auth::get_auth_user;
+use crate::core::helpers::responder::json_success;
+use crate::http::middlewares::auth_middleware::AuthMiddleware;
+use crate::models::report::SpendingReportParams;
+use crate::models::DBPool;
+use crate::services::report_service::ReportService;
+
+pub fn report_controller(cfg: &mut ServiceConfig) {
+    cfg.service(spending);
+}
+
+#[get("spending")]
+async fn spending(
+    pool: Data<DBPool>,
+    req: HttpRequest,
+    q: Query<SpendingReportParams>,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let user = get_auth_user(req.extensions());
+    let report = ReportService.spending(pool.get_ref(), &user, q.into_inner())?;
+    Ok(json_success(report))
+}
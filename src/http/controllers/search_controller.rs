@@ -0,0 +1,26 @@
+use actix_web::web::{Data, Query, ServiceConfig};
+use actix_web::{get, HttpMessage, HttpRequest, HttpResponse};
+
+use crate::core::enums::app_error::AppResult;
+use crate::core::helpers::auth::get_auth_id;
+use crate::core::helpers::responder::json_success;
+use crate::http::middlewares::auth_middleware::AuthMiddleware;
+use crate::models::search::SearchParams;
+use crate::models::DBPool;
+use crate::services::search_service::SearchService;
+
+pub fn search_controller(cfg: &mut ServiceConfig) {
+    cfg.service(search);
+}
+
+#[get("")]
+async fn search(
+    pool: Data<DBPool>,
+    req: HttpRequest,
+    q: Query<SearchParams>,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let user_id = get_auth_id(req.extensions());
+    let results = SearchService.search(pool.get_ref(), user_id, q.into_inner())?;
+    Ok(json_success(results))
+}
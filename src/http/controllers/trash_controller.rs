@@ -0,0 +1,74 @@
+use actix_web::web::{Data, Path, Query, ServiceConfig};
+use actix_web::{delete, get, post, HttpMessage, HttpRequest, HttpResponse};
+
+use crate::core::enums::app_error::AppResult;
+use crate::core::helpers::auth::get_auth_id;
+use crate::core::helpers::http::QueryParams;
+use crate::core::helpers::responder::{json_pagination, json_success, json_success_message};
+use crate::http::middlewares::auth_middleware::AuthMiddleware;
+use crate::models::trash::{TrashParams, TrashPathParam};
+use crate::models::DBPool;
+use crate::services::trash_service::TrashService;
+
+pub fn trash_controller(cfg: &mut ServiceConfig) {
+    cfg.service(index);
+    cfg.service(empty);
+    cfg.service(restore);
+    cfg.service(purge);
+}
+
+#[get("")]
+async fn index(
+    pool: Data<DBPool>,
+    req: HttpRequest,
+    q: Query<QueryParams>,
+    params: Query<TrashParams>,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let user_id = get_auth_id(req.extensions());
+    let items = TrashService.list(pool.get_ref(), user_id, params.into_inner(), q.into_inner())?;
+    Ok(json_pagination(&req, items))
+}
+
+#[delete("")]
+async fn empty(pool: Data<DBPool>, req: HttpRequest, _: AuthMiddleware) -> AppResult<HttpResponse> {
+    let summary = TrashService.empty(pool.get_ref(), get_auth_id(req.extensions()))?;
+    Ok(json_success(summary))
+}
+
+#[post("{kind}/{id}/restore")]
+async fn restore(
+    pool: Data<DBPool>,
+    param: Path<TrashPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let kind = param.get_kind()?;
+    TrashService.restore(
+        pool.get_ref(),
+        get_auth_id(req.extensions()),
+        kind,
+        param.get_uuid()?,
+    )?;
+    Ok(json_success_message(&format!("{} restored", kind.as_str())))
+}
+
+#[delete("{kind}/{id}")]
+async fn purge(
+    pool: Data<DBPool>,
+    param: Path<TrashPathParam>,
+    req: HttpRequest,
+    _: AuthMiddleware,
+) -> AppResult<HttpResponse> {
+    let kind = param.get_kind()?;
+    TrashService.purge(
+        pool.get_ref(),
+        get_auth_id(req.extensions()),
+        kind,
+        param.get_uuid()?,
+    )?;
+    Ok(json_success_message(&format!(
+        "{} permanently deleted",
+        kind.as_str()
+    )))
+}
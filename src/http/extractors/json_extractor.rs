@@ -0,0 +1,240 @@
+use std::ops::Deref;
+
+use actix_web::dev::Payload;
+use actix_web::web::Bytes;
+use actix_web::{Error, FromRequest, HttpMessage, HttpRequest};
+use futures_util::future::LocalBoxFuture;
+use serde::de::DeserializeOwned;
+use serde_json::error::Category;
+use validator::Validate;
+
+use crate::core::enums::app_error::AppError;
+use crate::core::helpers::responder::FieldErrors;
+
+/// Drop-in replacement for actix's `Json` that validates the payload, both
+/// malformed and invalid payloads are answered with a 422 and a per-field error map
+pub struct Json<T>(pub T);
+
+impl<T> Json<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Json<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> FromRequest for Json<T>
+where
+    T: DeserializeOwned + Validate + 'static,
+{
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let content_type = req.content_type().to_lowercase();
+        let body = Bytes::from_request(req, payload);
+
+        Box::pin(async move {
+            if content_type != "application/json" && !content_type.ends_with("+json") {
+                return Err(AppError::from("Content-Type must be application/json").into());
+            }
+
+            let body = body
+                .await
+                .map_err(|err| AppError::BadRequest(err.to_string()))?;
+
+            let value: T = parse(&body)?;
+            value.validate().map_err(AppError::from)?;
+            Ok(Json(value))
+        })
+    }
+}
+
+fn parse<T: DeserializeOwned>(body: &[u8]) -> Result<T, AppError> {
+    let mut deserializer = serde_json::Deserializer::from_slice(body);
+    let value = serde_path_to_error::deserialize(&mut deserializer).map_err(deserialize_error)?;
+
+    deserializer
+        .end()
+        .map_err(|err| field_error("body", describe_json_error(&err)))?;
+
+    Ok(value)
+}
+
+fn deserialize_error(err: serde_path_to_error::Error<serde_json::Error>) -> AppError {
+    let path = match err.path().iter().len() {
+        0 => None,
+        _ => Some(err.path().to_string()),
+    };
+
+    let err = err.into_inner();
+    if err.classify() != Category::Data {
+        return field_error("body", describe_json_error(&err));
+    }
+
+    // missing fields are reported against the struct holding them
+    let message = describe_json_error(&err);
+    if let Some(field) = message
+        .strip_prefix("Missing field `")
+        .and_then(|rest| rest.strip_suffix('`'))
+    {
+        let field = match path {
+            None => field.to_string(),
+            Some(path) => format!("{}.{}", path, field),
+        };
+
+        return field_error(field.as_str(), String::from("This field is required"));
+    }
+
+    field_error(path.as_deref().unwrap_or("body"), message)
+}
+
+/// serde_json's message without the position, which means little to API clients
+fn describe_json_error(err: &serde_json::Error) -> String {
+    let message = err.to_string();
+    let message = match message.rsplit_once(" at line ") {
+        Some((message, _)) => message.to_string(),
+        None => message,
+    };
+
+    let mut chars = message.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => message,
+    }
+}
+
+fn field_error(field: &str, message: String) -> AppError {
+    let mut errors = FieldErrors::new();
+    errors.insert(field.to_string(), vec![message]);
+    AppError::Validation(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::{test, web, App, HttpResponse};
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::models::budget::BudgetForm;
+    use crate::models::expense::ExpenseForm;
+    use crate::models::project::ProjectForm;
+    use crate::models::user::RegisterForm;
+
+    #[derive(Deserialize, Validate)]
+    struct Payload {
+        #[allow(dead_code)]
+        amount: i64,
+    }
+
+    async fn accept<T>(_: Json<T>) -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    /// Status and sorted error keys of posting the raw body as JSON
+    async fn post<T>(body: &str) -> (StatusCode, Vec<String>)
+    where
+        T: DeserializeOwned + Validate + 'static,
+    {
+        let app = test::init_service(App::new().route("/", web::post().to(accept::<T>))).await;
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header(("Content-Type", "application/json"))
+            .set_payload(body.to_string())
+            .to_request();
+
+        let res = test::call_service(&app, req).await;
+        let status = res.status();
+        let body: Value = serde_json::from_slice(&test::read_body(res).await).unwrap_or_default();
+        let mut fields: Vec<String> = body["data"]["errors"]
+            .as_object()
+            .map(|errors| errors.keys().cloned().collect())
+            .unwrap_or_default();
+        fields.sort();
+
+        (status, fields)
+    }
+
+    #[actix_web::test]
+    async fn valid_payload() {
+        let (status, fields) = post::<Payload>(r#"{"amount": 10}"#).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(fields.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn malformed_body() {
+        for body in [r#"{"amount": "#, "not json", r#"{"amount": 10} trailing"#] {
+            let (status, fields) = post::<Payload>(body).await;
+
+            assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+            assert_eq!(fields, ["body"], "{}", body);
+        }
+    }
+
+    #[actix_web::test]
+    async fn wrong_type() {
+        let (status, fields) = post::<Payload>(r#"{"amount": "ten"}"#).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(fields, ["amount"]);
+    }
+
+    #[actix_web::test]
+    async fn missing_field() {
+        let (status, fields) = post::<Payload>("{}").await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(fields, ["amount"]);
+    }
+
+    #[actix_web::test]
+    async fn project_form() {
+        let body = json!({"name": " ", "description": "d".repeat(151)});
+        let (status, fields) = post::<ProjectForm>(&body.to_string()).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(fields, ["description", "name"]);
+    }
+
+    #[actix_web::test]
+    async fn budget_form() {
+        let body = json!({"amount": 100, "month": 13, "year": 2026});
+        let (status, fields) = post::<BudgetForm>(&body.to_string()).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(fields, ["month"]);
+    }
+
+    #[actix_web::test]
+    async fn expense_form() {
+        let body = json!({"project_id": "not-a-uuid", "amount": 10, "narration": "Groceries"});
+        let (status, fields) = post::<ExpenseForm>(&body.to_string()).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(fields, ["project_id"]);
+    }
+
+    #[actix_web::test]
+    async fn register_form() {
+        let body = json!({
+            "first_name": "f".repeat(151),
+            "last_name": "l".repeat(151),
+            "email": "jane@example.com",
+            "password": "correct-Horse-battery-9",
+        });
+        let (status, fields) = post::<RegisterForm>(&body.to_string()).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(fields, ["first_name", "last_name"]);
+    }
+}
@@ -1,8 +1,12 @@
+use crate::core::enums::app_error::AppError;
+use crate::core::helpers::csrf::CSRF_HEADER;
 use crate::http::controllers::routes;
+use crate::http::middlewares::request_id_middleware::REQUEST_ID_HEADER;
 use actix_cors::Cors;
 use actix_web::http::header;
+use actix_web::http::header::HeaderName;
 use actix_web::web;
-use actix_web::web::ServiceConfig;
+use actix_web::web::{FormConfig, JsonConfig, PathConfig, QueryConfig, ServiceConfig};
 use std::env;
 
 struct Route {
@@ -43,9 +47,28 @@ pub fn setup_cors() -> Cors {
         .allowed_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE"])
         .allowed_headers(vec![header::AUTHORIZATION, header::ACCEPT])
         .allowed_header(header::CONTENT_TYPE)
+        .allowed_header(CSRF_HEADER)
+        .expose_headers(vec![
+            header::WWW_AUTHENTICATE,
+            HeaderName::from_static(REQUEST_ID_HEADER),
+        ])
+        .supports_credentials()
         .max_age(3600)
 }
 
+/// Malformed paths, queries and bodies are answered in the usual error envelope
+pub fn register_extractor_configs(actix_config: &mut ServiceConfig) {
+    actix_config
+        .app_data(PathConfig::default().error_handler(|err, _| bad_request(err.to_string())))
+        .app_data(QueryConfig::default().error_handler(|err, _| bad_request(err.to_string())))
+        .app_data(JsonConfig::default().error_handler(|err, _| bad_request(err.to_string())))
+        .app_data(FormConfig::default().error_handler(|err, _| bad_request(err.to_string())));
+}
+
+fn bad_request(message: String) -> actix_web::Error {
+    AppError::BadRequest(message).into()
+}
+
 pub fn register_middlewares(_actix_config: &mut ServiceConfig) {
     // for middleware in middlewares() {
     // }
@@ -2,90 +2,359 @@ use core::fmt;
 use std::env;
 use std::future::{ready, Ready};
 
-use crate::models::user::User;
+use crate::core::helpers::auth::AUTH_COOKIE;
+use crate::core::helpers::csrf::csrf_header_matches;
+use crate::core::helpers::responder::json_error_code;
+use crate::models::user::{User, UserStatus};
 use crate::models::DBPool;
-use crate::repositories::user_repository::UserRepository;
-use actix_web::error::ErrorUnauthorized;
+use crate::repositories::user_repository::{user_status_is, UserRepository};
+use actix_web::http::header::{HeaderValue, WWW_AUTHENTICATE};
+use actix_web::http::StatusCode;
 use actix_web::web::Data;
 use actix_web::{dev::Payload, Error as ActixWebError};
-use actix_web::{http, FromRequest, HttpMessage, HttpRequest};
+use actix_web::{http, FromRequest, HttpMessage, HttpRequest, HttpResponse, ResponseError};
+use jsonwebtoken::errors::ErrorKind;
 use jsonwebtoken::{decode, DecodingKey, Validation};
-use serde::Serialize;
 
 use crate::services::auth_service::TokenClaims;
 
-#[derive(Debug, Serialize)]
-struct ErrorResponse<'a> {
-    success: bool,
-    status: i32,
-    message: &'a str,
+/// Authorization schemes the token may be sent with, matched case-insensitively
+const TOKEN_SCHEMES: [&str; 2] = ["bearer", "token"];
+
+/// Why a request could not be authenticated
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuthError {
+    /// neither an Authorization header nor a session cookie was sent
+    MissingToken,
+    /// the Authorization header is not "<scheme> <token>" with a supported scheme
+    MalformedHeader,
+    ExpiredToken,
+    /// bad signature, unreadable claims or a token that is not a JWT at all
+    InvalidToken,
+    /// the token is genuine, but its account is gone or deactivated
+    RevokedToken,
+    /// cookie session used for a state-changing request without the CSRF token
+    CsrfMismatch,
+    /// the account could not be looked up
+    Unavailable,
 }
 
-impl fmt::Display for ErrorResponse<'_> {
+impl AuthError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AuthError::MissingToken => "auth_token_missing",
+            AuthError::MalformedHeader => "auth_header_malformed",
+            AuthError::ExpiredToken => "auth_token_expired",
+            AuthError::InvalidToken => "auth_token_invalid",
+            AuthError::RevokedToken => "auth_token_revoked",
+            AuthError::CsrfMismatch => "csrf_token_mismatch",
+            AuthError::Unavailable => "auth_unavailable",
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            AuthError::MissingToken => "You are not logged in, please provide token",
+            AuthError::MalformedHeader => {
+                "Malformed Authorization header, expected \"Bearer <token>\""
+            }
+            AuthError::ExpiredToken => "Your auth token has expired, please log in again",
+            AuthError::InvalidToken => "Invalid auth token",
+            AuthError::RevokedToken => "Your auth token has been revoked, please log in again",
+            AuthError::CsrfMismatch => "Missing or invalid CSRF token",
+            AuthError::Unavailable => "Failed to verify your auth token, please try again",
+        }
+    }
+
+    /// Challenge sent along with 401 responses, following RFC 6750
+    fn challenge(&self) -> Option<String> {
+        let error = match self {
+            AuthError::MissingToken => return Some(String::from("Bearer realm=\"nucleus\"")),
+            AuthError::MalformedHeader => "invalid_request",
+            AuthError::ExpiredToken | AuthError::InvalidToken | AuthError::RevokedToken => {
+                "invalid_token"
+            }
+            AuthError::CsrfMismatch | AuthError::Unavailable => return None,
+        };
+
+        // quoted-strings cannot carry unescaped quotes
+        Some(format!(
+            "Bearer realm=\"nucleus\", error=\"{}\", error_description=\"{}\"",
+            error,
+            self.message().replace('"', "'")
+        ))
+    }
+}
+
+impl fmt::Display for AuthError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", serde_json::to_string(&self).unwrap())
+        f.write_str(self.message())
     }
 }
 
-pub struct AuthMiddleware {
-    pub user_id: uuid::Uuid,
+impl ResponseError for AuthError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AuthError::CsrfMismatch => StatusCode::FORBIDDEN,
+            AuthError::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::UNAUTHORIZED,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = json_error_code(self.message(), self.code(), self.status_code());
+        let challenge = self.challenge();
+        if let Some(challenge) = challenge.and_then(|value| HeaderValue::from_str(&value).ok()) {
+            response.headers_mut().insert(WWW_AUTHENTICATE, challenge);
+        }
+
+        response
+    }
 }
 
+/// Guard of the authenticated routes, the user it lets through is put in the request extensions
+pub struct AuthMiddleware;
+
 impl FromRequest for AuthMiddleware {
     type Error = ActixWebError;
     type Future = Ready<Result<Self, Self::Error>>;
     fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
-        let token = req
-            .cookie("token")
-            .map(|c| c.value().to_string())
-            .or_else(|| {
-                req.headers()
-                    .get(http::header::AUTHORIZATION)
-                    .map(|h| h.to_str().unwrap().split_at(7).1.to_string())
-            });
-
-        if token.is_none() {
-            return ready(Err(ErrorUnauthorized(make_unauthorized_response(
-                "You are not logged in, please provide token",
-            ))));
+        ready(authenticate(req).map_err(ActixWebError::from))
+    }
+}
+
+fn authenticate(req: &HttpRequest) -> Result<AuthMiddleware, AuthError> {
+    let token = request_token(req)?;
+    let claims = decode_token(token.as_str())?;
+
+    let user_id =
+        uuid::Uuid::parse_str(claims.sub.as_str()).map_err(|_| AuthError::InvalidToken)?;
+    let pool = req
+        .app_data::<Data<DBPool>>()
+        .ok_or(AuthError::Unavailable)?;
+
+    let user = match UserRepository.find_by_id(pool, user_id) {
+        Ok(Some(user)) => user,
+        Ok(None) => return Err(AuthError::RevokedToken),
+        Err(err) => {
+            log::error!("failed to look up authenticated user: {:?}", err);
+            return Err(AuthError::Unavailable);
         }
+    };
 
-        let claims = match decode::<TokenClaims>(
-            &token.unwrap(),
-            &DecodingKey::from_secret(env::var("APP_KEY").unwrap().as_ref()),
-            &Validation::default(),
-        ) {
-            Ok(c) => c.claims,
-            Err(_) => {
-                return ready(Err(ErrorUnauthorized(make_unauthorized_response(
-                    "Invalid auth token",
-                ))));
-            }
-        };
+    if user.deleted_at.is_some() || user_status_is(user.status.to_owned(), UserStatus::Inactive) {
+        return Err(AuthError::RevokedToken);
+    }
+
+    req.extensions_mut()
+        .insert::<uuid::Uuid>(user_id.to_owned());
 
-        let user_id = uuid::Uuid::parse_str(claims.sub.as_str()).unwrap();
-        let pool = req.app_data::<Data<DBPool>>().unwrap();
-        let user_lookup = UserRepository.find_by_id(pool, user_id).unwrap();
+    req.extensions_mut().insert::<User>(user);
+
+    Ok(AuthMiddleware)
+}
 
-        if user_lookup.is_none() {
-            return ready(Err(ErrorUnauthorized(make_unauthorized_response(
-                "Invalid auth token, user not found",
-            ))));
+/// Token of the Authorization header, falling back to the session cookie
+fn request_token(req: &HttpRequest) -> Result<String, AuthError> {
+    if let Some(header) = req.headers().get(http::header::AUTHORIZATION) {
+        return parse_authorization(header);
+    }
+
+    match req.cookie(AUTH_COOKIE) {
+        None => Err(AuthError::MissingToken),
+        Some(cookie) => {
+            // browsers attach cookies to cross-site requests on their own, the
+            // token they carry only counts once the CSRF token is echoed back
+            if !req.method().is_safe() && !csrf_header_matches(req) {
+                return Err(AuthError::CsrfMismatch);
+            }
+
+            match cookie.value().is_empty() {
+                true => Err(AuthError::MissingToken),
+                false => Ok(cookie.value().to_string()),
+            }
         }
+    }
+}
 
-        req.extensions_mut()
-            .insert::<uuid::Uuid>(user_id.to_owned());
+/// Reads "<scheme> <token>" where the scheme is any casing of Bearer or Token
+fn parse_authorization(header: &HeaderValue) -> Result<String, AuthError> {
+    let value = header
+        .to_str()
+        .map_err(|_| AuthError::MalformedHeader)?
+        .trim();
+    let (scheme, token) = value
+        .split_once(char::is_whitespace)
+        .ok_or(AuthError::MalformedHeader)?;
 
-        req.extensions_mut().insert::<User>(user_lookup.unwrap());
+    if !TOKEN_SCHEMES.contains(&scheme.to_ascii_lowercase().as_str()) {
+        return Err(AuthError::MalformedHeader);
+    }
 
-        ready(Ok(AuthMiddleware { user_id }))
+    match token.trim() {
+        "" => Err(AuthError::MissingToken),
+        token => Ok(token.to_string()),
     }
 }
 
-fn make_unauthorized_response(message: &str) -> ErrorResponse {
-    ErrorResponse {
-        success: false,
-        status: 401,
-        message,
+fn decode_token(token: &str) -> Result<TokenClaims, AuthError> {
+    let key = env::var("APP_KEY").map_err(|_| AuthError::Unavailable)?;
+
+    decode::<TokenClaims>(
+        token,
+        &DecodingKey::from_secret(key.as_ref()),
+        &Validation::default(),
+    )
+    .map(|data| data.claims)
+    .map_err(|err| match err.kind() {
+        ErrorKind::ExpiredSignature => AuthError::ExpiredToken,
+        _ => AuthError::InvalidToken,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, web, App};
+    use chrono::Duration;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::core::helpers::testing::{create_user, make_token, test_pool, use_test_app_key};
+
+    async fn protected(_: AuthMiddleware) -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    /// Status, error code and WWW-Authenticate challenge of a request to a
+    /// protected route carrying the given Authorization header
+    async fn call(
+        authorization: Option<String>,
+        pool: Option<DBPool>,
+    ) -> (StatusCode, String, String) {
+        let header = authorization.map(|value| HeaderValue::from_str(&value).unwrap());
+        call_with_header(header, pool).await
+    }
+
+    async fn call_with_header(
+        authorization: Option<HeaderValue>,
+        pool: Option<DBPool>,
+    ) -> (StatusCode, String, String) {
+        use_test_app_key();
+
+        let mut app = App::new().route("/", web::get().to(protected));
+        if let Some(pool) = pool {
+            app = app.app_data(Data::new(pool));
+        }
+
+        let app = test::init_service(app).await;
+        let mut req = test::TestRequest::get().uri("/");
+        if let Some(authorization) = authorization {
+            req = req.insert_header((http::header::AUTHORIZATION, authorization));
+        }
+
+        let res = test::call_service(&app, req.to_request()).await;
+        let status = res.status();
+        let challenge = res
+            .headers()
+            .get(WWW_AUTHENTICATE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        // the protected route answers with an empty body
+        let body: serde_json::Value =
+            serde_json::from_slice(&test::read_body(res).await).unwrap_or_default();
+
+        (
+            status,
+            body["data"]["code"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            challenge,
+        )
+    }
+
+    #[actix_web::test]
+    async fn missing_token() {
+        let (status, code, challenge) = call(None, None).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(code, "auth_token_missing");
+        assert_eq!(challenge, "Bearer realm=\"nucleus\"");
+    }
+
+    #[actix_web::test]
+    async fn malformed_header() {
+        for header in ["Basic dXNlcjpwYXNz", "Bearer", "token-without-scheme"] {
+            let (status, code, challenge) = call(Some(header.to_string()), None).await;
+
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", header);
+            assert_eq!(code, "auth_header_malformed");
+            assert!(challenge.contains("error=\"invalid_request\""));
+        }
+    }
+
+    #[actix_web::test]
+    async fn non_ascii_header() {
+        let header = HeaderValue::from_bytes(b"Bearer \xc3\xa9t\xc3\xa9").unwrap();
+
+        let (status, code, challenge) = call_with_header(Some(header), None).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(code, "auth_header_malformed");
+        assert!(challenge.contains("error=\"invalid_request\""));
+    }
+
+    #[actix_web::test]
+    async fn bad_signature() {
+        let token = make_token(Uuid::new_v4(), Duration::hours(1));
+        let (header, _) = token.rsplit_once('.').unwrap();
+        let forged = format!("Bearer {}.{}", header, "c2lnbmF0dXJl");
+
+        let (status, code, challenge) = call(Some(forged), None).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(code, "auth_token_invalid");
+        assert!(challenge.contains("error=\"invalid_token\""));
+    }
+
+    #[actix_web::test]
+    async fn expired_token() {
+        let token = make_token(Uuid::new_v4(), Duration::hours(-1));
+
+        let (status, code, challenge) = call(Some(format!("Bearer {}", token)), None).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(code, "auth_token_expired");
+        assert!(challenge.contains("error=\"invalid_token\""));
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs the migrated database configured in .env"]
+    async fn unknown_user() {
+        let token = make_token(Uuid::new_v4(), Duration::hours(1));
+
+        let (status, code, challenge) =
+            call(Some(format!("Bearer {}", token)), Some(test_pool())).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(code, "auth_token_revoked");
+        assert!(challenge.contains("error=\"invalid_token\""));
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs the migrated database configured in .env"]
+    async fn any_scheme_casing_authenticates() {
+        let pool = test_pool();
+        let user = create_user(&pool);
+        let token = make_token(user.user_id, Duration::hours(1));
+
+        for scheme in ["bearer", "Token"] {
+            let header = format!("{} {}", scheme, token);
+            let (status, code, _) = call(Some(header), Some(pool.clone())).await;
+
+            assert_eq!(status, StatusCode::OK, "{}", scheme);
+            assert_eq!(code, "");
+        }
     }
 }
@@ -1,6 +1,7 @@
 // use crate::http::middlewares::test_middleware::TestMiddleware;
 
 pub mod auth_middleware;
+pub mod request_id_middleware;
 
 // pub fn middlewares() {
 // let mut middlewares = Vec::new();
@@ -1,5 +1,6 @@
 mod controllers;
 
+pub mod extractors;
 pub mod middlewares;
 
 pub(crate) mod kernel;
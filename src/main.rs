@@ -1,17 +1,22 @@
 use std::env;
 
 use actix_files::Files;
-use actix_web::http::StatusCode;
 use actix_web::middleware::Logger;
 use actix_web::web::Data;
-use actix_web::{web, App, HttpResponse, HttpServer};
+use actix_web::{web, App, HttpServer, ResponseError};
 use diesel::r2d2::ConnectionManager;
 use diesel::PgConnection;
 use env_logger::Env;
 use tera::Tera;
 
-use crate::http::kernel::{register_middlewares, register_routes, setup_cors};
+use crate::core::enums::app_error::AppError;
+use crate::core::helpers::template::register_template_filters;
+use crate::http::kernel::{
+    register_extractor_configs, register_middlewares, register_routes, setup_cors,
+};
+use crate::http::middlewares::request_id_middleware::RequestIdMiddleware;
 use crate::models::DBPool;
+use crate::services::trash_service::run_retention_job;
 
 mod core;
 mod http;
@@ -20,6 +25,9 @@ mod repositories;
 mod schema;
 mod services;
 
+/// Default access log format followed by the request id
+const LOG_FORMAT: &str = "%a \"%r\" %s %b \"%{Referer}i\" \"%{User-Agent}i\" %T %{x-request-id}o";
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv::dotenv().ok();
@@ -43,12 +51,15 @@ async fn main() -> std::io::Result<()> {
         .build(manager)
         .expect("Failed to create pool.");
 
-    let tera = Tera::new(concat!(env!("CARGO_MANIFEST_DIR"), "/templates/**/*")).unwrap();
+    let mut tera = Tera::new(concat!(env!("CARGO_MANIFEST_DIR"), "/templates/**/*")).unwrap();
+    register_template_filters(&mut tera);
 
     env::set_var("RUST_LOG", "debug");
     env::set_var("RUST_BACKTRACE", "1");
     env_logger::init_from_env(Env::default().default_filter_or("info"));
 
+    actix_web::rt::spawn(run_retention_job(pool.clone()));
+
     log::info!("Server started at http://localhost:{}", port);
 
     HttpServer::new(move || {
@@ -56,15 +67,15 @@ async fn main() -> std::io::Result<()> {
             .app_data(Data::new(pool.clone()))
             .app_data(Data::new(tera.clone()))
             .service(Files::new("/static", "./static"))
+            .configure(register_extractor_configs)
             .configure(register_routes)
             .configure(register_middlewares)
             // .wrap(middleware::NormalizePath::new(TrailingSlash::MergeOnly))
-            .wrap(Logger::default())
+            .wrap(RequestIdMiddleware)
+            .wrap(Logger::new(LOG_FORMAT))
             .wrap(setup_cors())
             .default_service(web::to(|| async {
-                HttpResponse::Ok()
-                    .status(StatusCode::NOT_FOUND)
-                    .body("Page Not Found")
+                AppError::RouteNotFound.error_response()
             }))
     })
     .shutdown_timeout(1)
@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+
+use actix_multipart::form::bytes::Bytes;
+use actix_multipart::form::MultipartForm;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::bank_account::BankAccount;
+use crate::models::budget::Budget;
+use crate::models::category::Category;
+use crate::models::expense::Expense;
+use crate::models::expense_rule::ExpenseRule;
+use crate::models::income::Income;
+use crate::models::label::{ExpenseLabel, Label, ProjectLabel};
+use crate::models::project::Project;
+use crate::models::user::User;
+
+/// Layout version of account archives, bumped whenever an entity file changes shape
+pub const ACCOUNT_ARCHIVE_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AccountArchiveManifest {
+    pub version: u32,
+    pub exported_at: NaiveDateTime,
+    /// id of the exported account, kept for reference only
+    pub user_id: Uuid,
+    /// number of records in each entity file
+    pub counts: BTreeMap<String, usize>,
+}
+
+/// The user's profile without credentials
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AccountProfile {
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+    pub timezone: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<&User> for AccountProfile {
+    fn from(user: &User) -> Self {
+        AccountProfile {
+            first_name: user.first_name.clone(),
+            last_name: user.last_name.clone(),
+            email: user.email.clone(),
+            timezone: user.timezone.clone(),
+            created_at: user.created_at,
+        }
+    }
+}
+
+/// Every live record the user owns, each entity is stored as its own JSON file
+pub struct AccountArchive {
+    pub manifest: AccountArchiveManifest,
+    pub profile: AccountProfile,
+    pub projects: Vec<Project>,
+    pub labels: Vec<Label>,
+    pub project_labels: Vec<ProjectLabel>,
+    /// the user's own categories, the default taxonomy is shared by every environment
+    pub categories: Vec<Category>,
+    pub budgets: Vec<Budget>,
+    pub expenses: Vec<Expense>,
+    pub expense_labels: Vec<ExpenseLabel>,
+    pub incomes: Vec<Income>,
+    pub bank_accounts: Vec<BankAccount>,
+    pub expense_rules: Vec<ExpenseRule>,
+}
+
+impl AccountArchive {
+    pub fn counts(&self) -> BTreeMap<String, usize> {
+        BTreeMap::from([
+            (String::from("projects"), self.projects.len()),
+            (String::from("labels"), self.labels.len()),
+            (String::from("project_labels"), self.project_labels.len()),
+            (String::from("categories"), self.categories.len()),
+            (String::from("budgets"), self.budgets.len()),
+            (String::from("expenses"), self.expenses.len()),
+            (String::from("expense_labels"), self.expense_labels.len()),
+            (String::from("incomes"), self.incomes.len()),
+            (String::from("bank_accounts"), self.bank_accounts.len()),
+            (String::from("expense_rules"), self.expense_rules.len()),
+        ])
+    }
+}
+
+#[derive(MultipartForm)]
+pub struct AccountImportForm {
+    #[multipart(limit = "50MiB")]
+    pub file: Bytes,
+}
+
+#[derive(Serialize)]
+pub struct AccountImportResult {
+    pub version: u32,
+    pub imported: BTreeMap<String, usize>,
+}
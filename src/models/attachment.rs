@@ -0,0 +1,39 @@
+#![allow(clippy::extra_unused_lifetimes)]
+
+use actix_multipart::form::bytes::Bytes;
+use actix_multipart::form::MultipartForm;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::super::schema::attachments;
+
+/// Receipt or any other document backing an expense
+#[derive(Debug, Serialize, Deserialize, Insertable, Queryable, Clone)]
+#[diesel(table_name = attachments)]
+pub struct Attachment {
+    pub attachment_id: Uuid,
+    pub user_id: Uuid,
+    pub expense_id: Uuid,
+    pub file_name: String,
+    pub content_type: String,
+    pub size: i64,
+    #[serde(skip_serializing)]
+    pub storage_key: String,
+    /// set when a thumbnail was generated, i.e. for images
+    pub thumbnail_key: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(MultipartForm)]
+pub struct AttachmentUploadForm {
+    pub file: Bytes,
+}
+
+#[derive(Deserialize)]
+pub struct AttachmentDownloadParams {
+    /// serve the generated thumbnail instead of the original file
+    pub thumbnail: Option<bool>,
+}
@@ -0,0 +1,35 @@
+#![allow(clippy::extra_unused_lifetimes)]
+
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::core::helpers::validation::{validate_not_blank, validate_uuid};
+
+use super::super::schema::bank_accounts;
+
+/// Links a bank account (as it appears in imported statements) to the project its transactions belong to
+#[derive(Debug, Serialize, Deserialize, Insertable, Queryable, Clone)]
+#[diesel(table_name = bank_accounts)]
+pub struct BankAccount {
+    pub bank_account_id: Uuid,
+    pub user_id: Uuid,
+    pub project_id: Uuid,
+    pub account_number: String,
+    pub name: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Serialize, Deserialize, Validate)]
+pub struct BankAccountForm {
+    #[validate(custom = "validate_uuid")]
+    pub project_id: String,
+    /// OFX ACCTID or QIF account name
+    #[validate(length(max = 100), custom = "validate_not_blank")]
+    pub account_number: String,
+    #[validate(length(max = 150))]
+    pub name: Option<String>,
+}
@@ -2,7 +2,9 @@
 
 use diesel::{Insertable, Queryable};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use uuid::Uuid;
+use validator::{Validate, ValidationError};
 
 use super::super::schema::budgets;
 
@@ -20,18 +22,183 @@ pub struct Budget {
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
     pub deleted_at: Option<chrono::NaiveDateTime>,
+    pub alert_thresholds: Vec<i16>,
+    pub overspend_policy: String,
+    pub over_limit_at: Option<chrono::NaiveDateTime>,
+}
+
+/// What happens to an expense that exceeds the budget's available amount
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverspendPolicy {
+    Reject,
+    AllowAndFlag,
+    Allow,
+}
+
+impl OverspendPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OverspendPolicy::Reject => "reject",
+            OverspendPolicy::AllowAndFlag => "allow_and_flag",
+            OverspendPolicy::Allow => "allow",
+        }
+    }
+
+    pub fn parse(policy: &str) -> OverspendPolicy {
+        match policy {
+            "allow_and_flag" => OverspendPolicy::AllowAndFlag,
+            "allow" => OverspendPolicy::Allow,
+            _ => OverspendPolicy::Reject,
+        }
+    }
 }
 
 impl Budget {
     pub fn available_amount(&mut self) -> i64 {
         self.amount - self.amount_used
     }
+
+    pub fn overspent_amount(&self) -> i64 {
+        (self.amount_used - self.amount).max(0)
+    }
+
+    pub fn overspend_policy(&self) -> OverspendPolicy {
+        OverspendPolicy::parse(self.overspend_policy.as_str())
+    }
+
+    /// Alert thresholds (in percent of the budget amount) that were not reached in the
+    /// `before` state of the budget but are now, whether through spending or a lowered amount
+    pub fn crossed_thresholds(&self, before: &Budget) -> Vec<i16> {
+        self.alert_thresholds
+            .iter()
+            .filter(|threshold| !before.reaches(**threshold) && self.reaches(**threshold))
+            .copied()
+            .collect()
+    }
+
+    fn reaches(&self, threshold: i16) -> bool {
+        self.amount_used * 100 >= self.amount * threshold as i64
+    }
+}
+
+pub const DEFAULT_ALERT_THRESHOLDS: [i16; 2] = [80, 100];
+
+/// Keeps thresholds between 1% and 1000%, sorted and without duplicates
+pub fn sanitize_alert_thresholds(mut thresholds: Vec<i16>) -> Vec<i16> {
+    thresholds.retain(|threshold| (1..=1000).contains(threshold));
+    thresholds.sort_unstable();
+    thresholds.dedup();
+    thresholds
+}
+
+fn validate_alert_thresholds(thresholds: &[i16]) -> Result<(), ValidationError> {
+    match thresholds
+        .iter()
+        .all(|threshold| (1..=1000).contains(threshold))
+    {
+        true => Ok(()),
+        false => {
+            let mut error = ValidationError::new("range");
+            error.message = Some(Cow::Borrowed(
+                "Thresholds must be between 1 and 1000 percent",
+            ));
+            Err(error)
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Validate)]
 pub struct BudgetForm {
+    #[validate(range(min = 1))]
     pub amount: i64,
+    #[validate(range(min = 1, max = 12))]
     pub month: i16,
+    #[validate(range(min = 1970, max = 9999))]
     pub year: i16,
+    #[validate(length(max = 250))]
     pub comment: Option<String>,
+    #[validate(custom = "validate_alert_thresholds")]
+    pub alert_thresholds: Option<Vec<i16>>,
+    pub overspend_policy: Option<OverspendPolicy>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::helpers::testing::make_budget;
+
+    fn crossed(budget: &Budget, used_before: i64, used_after: i64) -> Vec<i16> {
+        let mut before = budget.clone();
+        before.amount_used = used_before;
+        let mut after = budget.clone();
+        after.amount_used = used_after;
+        after.crossed_thresholds(&before)
+    }
+
+    #[test]
+    fn thresholds_are_crossed_once() {
+        let budget = make_budget(1000, 0, 7, 2026);
+
+        assert_eq!(crossed(&budget, 790, 800), [80]);
+        assert!(crossed(&budget, 800, 990).is_empty());
+        assert_eq!(crossed(&budget, 990, 1000), [100]);
+        assert_eq!(crossed(&budget, 0, 1500), [80, 100]);
+        assert!(crossed(&budget, 1500, 1600).is_empty());
+        // releasing spending never alerts
+        assert!(crossed(&budget, 1000, 0).is_empty());
+    }
+
+    #[test]
+    fn thresholds_round_in_favour_of_the_budget() {
+        let mut budget = make_budget(333, 0, 7, 2026);
+        budget.alert_thresholds = vec![50];
+
+        // 50% of 333 is 166.5, which 166 doesn't reach yet
+        assert!(crossed(&budget, 0, 166).is_empty());
+        assert_eq!(crossed(&budget, 166, 167), [50]);
+    }
+
+    #[test]
+    fn lowering_the_amount_crosses_thresholds() {
+        let before = make_budget(1000, 700, 7, 2026);
+        let mut after = before.clone();
+        after.amount = 800;
+
+        assert_eq!(after.crossed_thresholds(&before), [80]);
+        after.amount = 600;
+        assert_eq!(after.crossed_thresholds(&before), [80, 100]);
+        // raising it never alerts
+        assert!(before.crossed_thresholds(&after).is_empty());
+    }
+
+    #[test]
+    fn alert_thresholds_are_sanitized() {
+        assert_eq!(
+            sanitize_alert_thresholds(vec![100, 0, 80, 1001, 80, 150]),
+            [80, 100, 150]
+        );
+    }
+
+    #[test]
+    fn overspend_policies_default_to_reject() {
+        for policy in [
+            OverspendPolicy::Reject,
+            OverspendPolicy::AllowAndFlag,
+            OverspendPolicy::Allow,
+        ] {
+            assert_eq!(OverspendPolicy::parse(policy.as_str()), policy);
+        }
+
+        assert_eq!(OverspendPolicy::parse("anything"), OverspendPolicy::Reject);
+    }
+
+    #[test]
+    fn available_amount_goes_negative_once_overspent() {
+        let mut budget = make_budget(100, 130, 7, 2026);
+
+        assert_eq!(budget.available_amount(), -30);
+        assert_eq!(budget.overspent_amount(), 30);
+        assert_eq!(make_budget(100, 60, 7, 2026).overspent_amount(), 0);
+    }
 }
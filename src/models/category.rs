@@ -0,0 +1,88 @@
+#![allow(clippy::extra_unused_lifetimes)]
+
+use diesel::sql_types::{Nullable, Uuid as SqlUuid, VarChar};
+use diesel::{Insertable, Queryable, QueryableByName};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::core::helpers::validation::{validate_not_blank, validate_uuid};
+
+use super::super::schema::categories;
+
+/// Expense category, categories without an owner belong to the default taxonomy shared by everyone
+#[derive(Debug, Serialize, Deserialize, Insertable, Queryable, Clone)]
+#[diesel(table_name = categories)]
+pub struct Category {
+    pub category_id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub parent_id: Option<Uuid>,
+    pub name: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+}
+
+impl Category {
+    pub fn is_default(&self) -> bool {
+        self.user_id.is_none()
+    }
+}
+
+#[derive(Serialize, Deserialize, Validate)]
+pub struct CategoryForm {
+    #[validate(length(max = 150), custom = "validate_not_blank")]
+    pub name: String,
+    #[validate(custom = "validate_uuid")]
+    pub parent_id: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct CategoryNode {
+    #[serde(flatten)]
+    pub category: Category,
+    pub is_default: bool,
+    pub children: Vec<CategoryNode>,
+}
+
+impl CategoryNode {
+    /// Arranges a flat list of categories into trees rooted at top-level categories
+    pub fn build_tree(categories: Vec<Category>) -> Vec<CategoryNode> {
+        Self::children_of(None, &categories)
+    }
+
+    fn children_of(parent_id: Option<Uuid>, categories: &[Category]) -> Vec<CategoryNode> {
+        categories
+            .iter()
+            .filter(|category| category.parent_id == parent_id)
+            .map(|category| CategoryNode {
+                is_default: category.is_default(),
+                children: Self::children_of(Some(category.category_id), categories),
+                category: category.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Expense totals of a top-level category, sub-categories are rolled up into it
+#[derive(QueryableByName, Serialize)]
+pub struct CategoryAggregate {
+    #[diesel(sql_type = Nullable<SqlUuid>)]
+    pub category_id: Option<Uuid>,
+    #[diesel(sql_type = VarChar)]
+    pub name: String,
+    #[diesel(sql_type = Nullable<VarChar>)]
+    pub year_expenses: Option<String>,
+    #[diesel(sql_type = Nullable<VarChar>)]
+    pub month_expenses: Option<String>,
+    #[diesel(sql_type = Nullable<VarChar>)]
+    pub week_expenses: Option<String>,
+    #[diesel(sql_type = Nullable<VarChar>)]
+    pub today_expenses: Option<String>,
+}
+
+#[derive(QueryableByName)]
+pub struct CategoryId {
+    #[diesel(sql_type = SqlUuid)]
+    pub category_id: Uuid,
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::account_archive::AccountProfile;
+use crate::models::expense::{Expense, ExpenseAggregateSummary};
+use crate::models::project::Project;
+use crate::models::report::BudgetReport;
+
+#[derive(Deserialize)]
+pub struct DashboardLoginForm {
+    pub email: String,
+    pub password: String,
+    pub csrf_token: String,
+}
+
+#[derive(Deserialize)]
+pub struct DashboardLogoutForm {
+    pub csrf_token: String,
+}
+
+/// Everything shown on the dashboard page
+#[derive(Serialize)]
+pub struct Dashboard {
+    pub profile: AccountProfile,
+    /// report of the current month's budget, when one was created
+    pub budget: Option<BudgetReport>,
+    pub budget_title: Option<String>,
+    pub recent_expenses: Vec<(Expense, Project)>,
+    pub aggregate: ExpenseAggregateSummary,
+}
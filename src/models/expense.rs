@@ -1,11 +1,16 @@
 #![allow(clippy::extra_unused_lifetimes)]
 
+use chrono::NaiveDate;
 use diesel::sql_types::{Nullable, VarChar};
 use diesel::{Associations, Insertable, Queryable, QueryableByName};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use validator::Validate;
 
 use super::super::schema::expenses;
+use crate::core::helpers::http::deserialize_uuid_list;
+use crate::core::helpers::validation::{validate_date_time, validate_not_blank, validate_uuid};
+use crate::models::category::CategoryAggregate;
 use crate::models::project::Project;
 use crate::models::user::User;
 
@@ -24,14 +29,113 @@ pub struct Expense {
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
     pub deleted_at: Option<chrono::NaiveDateTime>,
+    pub category_id: Option<Uuid>,
+    pub import_id: Option<Uuid>,
+    pub external_id: Option<String>,
+    /// bank account the transaction was imported from (OFX ACCTID)
+    #[serde(default)]
+    pub external_account: Option<String>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Validate)]
 pub struct ExpenseForm {
+    #[validate(custom = "validate_uuid")]
     pub project_id: String,
+    #[validate(range(min = 1))]
     pub amount: i64,
+    #[validate(length(max = 1000), custom = "validate_not_blank")]
     pub narration: String,
+    #[validate(custom = "validate_date_time")]
     pub spent_at: Option<String>,
+    #[validate(custom = "validate_uuid")]
+    pub category_id: Option<String>,
+}
+
+/// Filters of the expense listings, read from the query string next to `QueryParams`
+#[derive(Deserialize, Clone, Default)]
+pub struct ExpenseFilter {
+    /// first day (inclusive) the expenses were spent on, as YYYY-MM-DD
+    pub spent_from: Option<NaiveDate>,
+    /// last day (inclusive) the expenses were spent on, as YYYY-MM-DD
+    pub spent_to: Option<NaiveDate>,
+    pub min_amount: Option<i64>,
+    pub max_amount: Option<i64>,
+    /// comma separated project ids, expenses of any of them are matched
+    #[serde(default, deserialize_with = "deserialize_uuid_list")]
+    pub project_id: Vec<Uuid>,
+    pub budget_id: Option<Uuid>,
+    /// lists deleted expenses along with the live ones
+    #[serde(default)]
+    pub include_deleted: bool,
+}
+
+impl ExpenseFilter {
+    pub fn check_ranges(&self) -> Result<(), String> {
+        if let (Some(from), Some(to)) = (self.spent_from, self.spent_to) {
+            if from > to {
+                return Err(String::from("spent_from must not be after spent_to"));
+            }
+        }
+
+        if let (Some(min), Some(max)) = (self.min_amount, self.max_amount) {
+            if min > max {
+                return Err(String::from(
+                    "min_amount must not be greater than max_amount",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// What deleting a project or budget does with its live expenses, read from the query string
+#[derive(Deserialize)]
+pub struct ExpenseCascadeParams {
+    /// refuse (the default), cascade or move
+    pub expenses: Option<String>,
+    /// project or budget the expenses are moved to
+    pub move_to: Option<Uuid>,
+}
+
+impl ExpenseCascadeParams {
+    pub fn get_cascade(&self) -> Result<ExpenseCascade, String> {
+        match (self.expenses.as_deref(), self.move_to) {
+            (None | Some("refuse"), _) => Ok(ExpenseCascade::Refuse),
+            (Some("cascade"), _) => Ok(ExpenseCascade::Cascade),
+            (Some("move"), Some(id)) => Ok(ExpenseCascade::Move(id)),
+            (Some("move"), None) => Err(String::from("move_to is required when moving expenses")),
+            (Some(other), _) => Err(format!(
+                "Invalid expenses \"{}\", expected one of refuse, cascade or move",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExpenseCascade {
+    /// the delete fails while live expenses remain
+    Refuse,
+    /// the expenses are deleted along, releasing their amounts from their budgets
+    Cascade,
+    /// the expenses are moved to another project or budget of the user
+    Move(Uuid),
+}
+
+/// Figures about every expense matching a listing's filters, not only the current page
+#[derive(Serialize)]
+pub struct ExpenseListSummary {
+    pub total_amount: i64,
+}
+
+/// Freshly recorded expense along with how far it pushed its budget over the limit
+#[derive(Serialize)]
+pub struct CreatedExpense {
+    #[serde(flatten)]
+    pub expense: Expense,
+    pub overspent_amount: i64,
+    pub budget_over_limit: bool,
 }
 
 #[derive(QueryableByName, Serialize)]
@@ -45,3 +149,11 @@ pub struct ExpenseAggregate {
     #[diesel(sql_type = Nullable<VarChar>)]
     pub today_expenses: Option<String>,
 }
+
+/// Expense totals along with their per category breakdown
+#[derive(Serialize)]
+pub struct ExpenseAggregateSummary {
+    #[serde(flatten)]
+    pub totals: ExpenseAggregate,
+    pub categories: Vec<CategoryAggregate>,
+}
@@ -0,0 +1,77 @@
+#![allow(clippy::extra_unused_lifetimes)]
+
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::core::helpers::validation::{validate_not_blank, validate_uuid, validate_uuids};
+
+use super::super::schema::expense_rules;
+
+/// Categorization rule, every condition that is set must hold for an expense to match
+#[derive(Debug, Serialize, Deserialize, Insertable, Queryable, Clone)]
+#[diesel(table_name = expense_rules)]
+pub struct ExpenseRule {
+    pub expense_rule_id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub priority: i32,
+    pub narration_contains: Option<String>,
+    pub narration_pattern: Option<String>,
+    pub min_amount: Option<i64>,
+    pub max_amount: Option<i64>,
+    pub category_id: Option<Uuid>,
+    pub label_ids: Vec<Uuid>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Serialize, Deserialize, Validate)]
+pub struct ExpenseRuleForm {
+    #[validate(length(max = 150), custom = "validate_not_blank")]
+    pub name: String,
+    pub priority: Option<i32>,
+    #[validate(length(min = 1, max = 250))]
+    pub narration_contains: Option<String>,
+    #[validate(length(min = 1, max = 250))]
+    pub narration_pattern: Option<String>,
+    #[validate(range(min = 0))]
+    pub min_amount: Option<i64>,
+    #[validate(range(min = 0))]
+    pub max_amount: Option<i64>,
+    #[validate(custom = "validate_uuid")]
+    pub category_id: Option<String>,
+    #[validate(custom = "validate_uuids")]
+    pub label_ids: Option<Vec<String>>,
+}
+
+/// Expense-like sample used to preview which rules would match
+#[derive(Deserialize, Validate)]
+pub struct ExpenseRuleSample {
+    #[validate(length(max = 1000))]
+    pub narration: String,
+    pub amount: i64,
+}
+
+#[derive(Serialize)]
+pub struct ExpenseRuleDryRun {
+    /// every matching rule, in the order they are evaluated
+    pub matched_rules: Vec<ExpenseRule>,
+    /// the rule that would actually be applied (the first match)
+    pub applied_rule: Option<ExpenseRule>,
+}
+
+#[derive(Deserialize)]
+pub struct ExpenseRuleApplyForm {
+    /// also re-categorize expenses that already have a category
+    pub overwrite: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct ExpenseRuleApplyResult {
+    pub scanned: usize,
+    pub matched: usize,
+    pub categorized: usize,
+}
@@ -0,0 +1,77 @@
+use serde::Deserialize;
+
+/// Records that can be exported through `/exports/{entity}`
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExportEntity {
+    Expenses,
+    Budgets,
+    Projects,
+}
+
+impl ExportEntity {
+    pub fn parse(entity: &str) -> Option<ExportEntity> {
+        match entity {
+            "expenses" => Some(ExportEntity::Expenses),
+            "budgets" => Some(ExportEntity::Budgets),
+            "projects" => Some(ExportEntity::Projects),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportEntity::Expenses => "expenses",
+            ExportEntity::Budgets => "budgets",
+            ExportEntity::Projects => "projects",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Xlsx,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> Option<ExportFormat> {
+        match format {
+            "csv" => Some(ExportFormat::Csv),
+            "xlsx" => Some(ExportFormat::Xlsx),
+            "json" => Some(ExportFormat::Json),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Xlsx => "xlsx",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Xlsx => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+            }
+            ExportFormat::Json => "application/json",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ExportPathParam {
+    pub entity: String,
+}
+
+/// Export options, the list filters (search, labels, categories) are read
+/// from the same query string
+#[derive(Deserialize)]
+pub struct ExportForm {
+    /// csv (default), xlsx or json
+    pub format: Option<String>,
+}
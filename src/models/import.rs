@@ -0,0 +1,186 @@
+#![allow(clippy::extra_unused_lifetimes)]
+
+use actix_multipart::form::bytes::Bytes;
+use actix_multipart::form::MultipartForm;
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::{Validate, ValidationErrors};
+
+use super::super::schema::imports;
+use crate::core::helpers::validation::validate_uuid;
+
+pub const IMPORT_STATUS_PENDING: &str = "pending";
+pub const IMPORT_STATUS_COMMITTED: &str = "committed";
+
+pub const IMPORT_SOURCE_CSV: &str = "csv";
+pub const IMPORT_SOURCE_OFX: &str = "ofx";
+pub const IMPORT_SOURCE_QIF: &str = "qif";
+
+/// Uploaded bank statement waiting to be previewed and committed as expenses and incomes
+#[derive(Debug, Serialize, Deserialize, Insertable, Queryable, Clone)]
+#[diesel(table_name = imports)]
+pub struct Import {
+    pub import_id: Uuid,
+    pub user_id: Uuid,
+    pub source: String,
+    pub file_name: String,
+    #[serde(skip_serializing)]
+    pub content: String,
+    pub status: String,
+    pub committed_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub deleted_at: Option<NaiveDateTime>,
+}
+
+impl Import {
+    pub fn is_pending(&self) -> bool {
+        self.status == IMPORT_STATUS_PENDING
+    }
+}
+
+#[derive(MultipartForm)]
+pub struct ImportUploadForm {
+    #[multipart(limit = "2MiB")]
+    pub file: Bytes,
+}
+
+#[derive(Serialize)]
+pub struct UploadedImport {
+    #[serde(flatten)]
+    pub import: Import,
+    /// header row of CSV files, to help picking the column mapping
+    pub headers: Vec<String>,
+    /// bank accounts found in OFX/QIF statements, to help mapping them to projects
+    pub accounts: Vec<String>,
+}
+
+/// Tells how to read the statement, CSV columns are referenced by header
+/// name or by their zero-based position
+#[derive(Serialize, Deserialize, Clone, Default, Validate)]
+pub struct ImportMapping {
+    /// project receiving every transaction, OFX/QIF statements can rely on
+    /// their bank account mapping instead
+    #[validate(custom = "validate_uuid")]
+    pub project_id: Option<String>,
+    pub amount_column: Option<String>,
+    pub narration_column: Option<String>,
+    pub date_column: Option<String>,
+    /// chrono format of the dates, defaults to "%Y-%m-%d" for CSV and US style dates for QIF
+    pub date_format: Option<String>,
+    pub delimiter: Option<char>,
+    pub has_headers: Option<bool>,
+    /// separator of the amounts' fractions, "." unless told otherwise
+    pub decimal_separator: Option<char>,
+    /// separator of the amounts' digit groups, "," or "." when the decimal separator is ","
+    pub thousands_separator: Option<char>,
+}
+
+#[derive(Deserialize)]
+pub struct ImportCommitForm {
+    #[serde(flatten)]
+    pub mapping: ImportMapping,
+    /// commit the valid rows even when some rows failed validation
+    pub skip_invalid: Option<bool>,
+    /// also commit rows that look like already recorded expenses
+    pub include_duplicates: Option<bool>,
+}
+
+/// The mapping is flattened into the form, so are its errors
+impl Validate for ImportCommitForm {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        self.mapping.validate()
+    }
+}
+
+/// Debits are recorded as expenses, credits as incomes
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionKind {
+    Expense,
+    Income,
+}
+
+/// Parsed statement row along with everything the preview found about it
+#[derive(Serialize, Clone)]
+pub struct ImportRow {
+    pub line: usize,
+    pub kind: TransactionKind,
+    pub narration: Option<String>,
+    pub amount: Option<i64>,
+    pub spent_at: Option<NaiveDateTime>,
+    /// bank's own transaction id (OFX FITID)
+    pub external_id: Option<String>,
+    pub account: Option<String>,
+    pub project_id: Option<Uuid>,
+    pub budget_id: Option<Uuid>,
+    pub category_id: Option<Uuid>,
+    pub expense_rule_id: Option<Uuid>,
+    pub duplicate: bool,
+    /// the bank transaction was recorded by an earlier import, it is never imported twice
+    pub already_imported: bool,
+    pub errors: Vec<String>,
+}
+
+impl ImportRow {
+    pub fn new(line: usize) -> ImportRow {
+        ImportRow {
+            line,
+            kind: TransactionKind::Expense,
+            narration: None,
+            amount: None,
+            spent_at: None,
+            external_id: None,
+            account: None,
+            project_id: None,
+            budget_id: None,
+            category_id: None,
+            expense_rule_id: None,
+            duplicate: false,
+            already_imported: false,
+            errors: vec![],
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+#[derive(Serialize)]
+pub struct ImportPreview {
+    pub total_rows: usize,
+    pub valid_rows: usize,
+    pub invalid_rows: usize,
+    pub duplicate_rows: usize,
+    pub already_imported_rows: usize,
+    pub rows: Vec<ImportRow>,
+}
+
+impl ImportPreview {
+    pub fn new(rows: Vec<ImportRow>) -> ImportPreview {
+        ImportPreview {
+            total_rows: rows.len(),
+            valid_rows: rows.iter().filter(|row| row.is_valid()).count(),
+            invalid_rows: rows.iter().filter(|row| !row.is_valid()).count(),
+            duplicate_rows: rows
+                .iter()
+                .filter(|row| row.duplicate && !row.already_imported)
+                .count(),
+            already_imported_rows: rows.iter().filter(|row| row.already_imported).count(),
+            rows,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ImportResult {
+    pub import: Import,
+    pub imported_expenses: usize,
+    pub imported_incomes: usize,
+    pub skipped_invalid: usize,
+    pub skipped_duplicates: usize,
+    pub skipped_already_imported: usize,
+}
@@ -0,0 +1,42 @@
+#![allow(clippy::extra_unused_lifetimes)]
+
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::core::helpers::validation::{validate_date_time, validate_not_blank, validate_uuid};
+
+use super::super::schema::incomes;
+
+/// Money received into a project, e.g. credits of an imported bank statement
+#[derive(Debug, Serialize, Deserialize, Insertable, Queryable, Clone)]
+#[diesel(table_name = incomes)]
+pub struct Income {
+    pub income_id: Uuid,
+    pub user_id: Uuid,
+    pub project_id: Uuid,
+    pub amount: i64,
+    pub narration: String,
+    pub received_at: chrono::NaiveDateTime,
+    pub import_id: Option<Uuid>,
+    pub external_id: Option<String>,
+    /// bank account the transaction was imported from (OFX ACCTID)
+    #[serde(default)]
+    pub external_account: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Serialize, Deserialize, Validate)]
+pub struct IncomeForm {
+    #[validate(custom = "validate_uuid")]
+    pub project_id: String,
+    #[validate(range(min = 1))]
+    pub amount: i64,
+    #[validate(length(max = 250), custom = "validate_not_blank")]
+    pub narration: String,
+    #[validate(custom = "validate_date_time")]
+    pub received_at: Option<String>,
+}
@@ -3,8 +3,11 @@
 use diesel::{Insertable, Queryable};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use validator::Validate;
 
-use super::super::schema::labels;
+use crate::core::helpers::validation::{validate_label_module, validate_not_blank};
+
+use super::super::schema::{expense_labels, labels, project_labels};
 
 #[derive(Debug, Serialize, Deserialize, Insertable, Queryable, Clone)]
 #[diesel(table_name = labels)]
@@ -18,8 +21,66 @@ pub struct Label {
     pub deleted_at: Option<chrono::NaiveDateTime>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Insertable, Queryable, Clone)]
+#[diesel(table_name = project_labels)]
+pub struct ProjectLabel {
+    pub project_label_id: Uuid,
+    pub user_id: Uuid,
+    pub project_id: Uuid,
+    pub label_id: Uuid,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Insertable, Queryable, Clone)]
+#[diesel(table_name = expense_labels)]
+pub struct ExpenseLabel {
+    pub expense_label_id: Uuid,
+    pub user_id: Uuid,
+    pub expense_id: Uuid,
+    pub label_id: Uuid,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Serialize, Deserialize, Validate)]
 pub struct LabelForm {
+    #[validate(length(max = 150), custom = "validate_not_blank")]
     pub name: String,
+    #[validate(custom = "validate_label_module")]
     pub module: String,
 }
+
+#[derive(Deserialize)]
+pub struct LabelFilter {
+    pub module: Option<String>,
+}
+
+/// Entity kind a label can be attached to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LabelModule {
+    Projects,
+    Expenses,
+}
+
+impl LabelModule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LabelModule::Projects => "projects",
+            LabelModule::Expenses => "expenses",
+        }
+    }
+
+    pub fn parse(module: &str) -> Result<LabelModule, String> {
+        match module {
+            "projects" => Ok(LabelModule::Projects),
+            "expenses" => Ok(LabelModule::Expenses),
+            _ => Err(format!(
+                "Invalid label module \"{}\", expected either projects or expenses",
+                module
+            )),
+        }
+    }
+}
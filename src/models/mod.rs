@@ -1,9 +1,23 @@
 #![allow(clippy::extra_unused_lifetimes)]
 
+pub mod account_archive;
+pub mod attachment;
+pub mod bank_account;
 pub mod budget;
+pub mod category;
+pub mod dashboard;
 pub mod expense;
+pub mod expense_rule;
+pub mod export;
+pub mod import;
+pub mod income;
 pub mod label;
+pub mod notification;
 pub mod project;
+pub mod report;
+pub mod search;
+pub mod statement;
+pub mod trash;
 pub mod user;
 
 use diesel::{r2d2::ConnectionManager, PgConnection};
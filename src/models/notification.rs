@@ -0,0 +1,69 @@
+#![allow(clippy::extra_unused_lifetimes)]
+
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::super::schema::notifications;
+use crate::models::budget::Budget;
+
+#[derive(Debug, Serialize, Deserialize, Insertable, Queryable, Clone)]
+#[diesel(table_name = notifications)]
+pub struct Notification {
+    pub notification_id: Uuid,
+    pub user_id: Uuid,
+    pub budget_id: Option<Uuid>,
+    pub kind: String,
+    pub title: String,
+    pub message: String,
+    pub read_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Deserialize)]
+pub struct NotificationFilter {
+    pub unread: Option<bool>,
+}
+
+pub enum NotificationEvent {
+    BudgetThresholdCrossed { budget: Budget, threshold: i16 },
+}
+
+impl NotificationEvent {
+    pub fn user_id(&self) -> Uuid {
+        match self {
+            NotificationEvent::BudgetThresholdCrossed { budget, .. } => budget.user_id,
+        }
+    }
+
+    pub fn budget_id(&self) -> Option<Uuid> {
+        match self {
+            NotificationEvent::BudgetThresholdCrossed { budget, .. } => Some(budget.budget_id),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NotificationEvent::BudgetThresholdCrossed { .. } => "budget_threshold_crossed",
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match self {
+            NotificationEvent::BudgetThresholdCrossed { budget, threshold } => {
+                format!("{} reached {}%", budget.title, threshold)
+            }
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            NotificationEvent::BudgetThresholdCrossed { budget, threshold } => format!(
+                "You have used {} of {} in \"{}\", crossing your {}% alert threshold",
+                budget.amount_used, budget.amount, budget.title, threshold
+            ),
+        }
+    }
+}
@@ -3,6 +3,9 @@
 use diesel::{Insertable, Queryable};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use validator::Validate;
+
+use crate::core::helpers::validation::validate_not_blank;
 
 use super::super::schema::projects;
 
@@ -18,8 +21,10 @@ pub struct Project {
     pub deleted_at: Option<chrono::NaiveDateTime>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Validate)]
 pub struct ProjectForm {
+    #[validate(length(max = 150), custom = "validate_not_blank")]
     pub name: String,
+    #[validate(length(max = 150))]
     pub description: String,
 }
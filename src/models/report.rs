@@ -0,0 +1,150 @@
+use diesel::sql_types::{BigInt, VarChar};
+use diesel::QueryableByName;
+use serde::{Deserialize, Serialize};
+
+use crate::models::budget::Budget;
+
+#[derive(Deserialize)]
+pub struct SpendingReportParams {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub group_by: Option<String>,
+    pub project_id: Option<String>,
+    pub label_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpendingGroup {
+    Day,
+    Week,
+    Month,
+    Project,
+    Label,
+}
+
+impl SpendingGroup {
+    pub fn parse(group_by: &str) -> Result<SpendingGroup, String> {
+        match group_by {
+            "day" => Ok(SpendingGroup::Day),
+            "week" => Ok(SpendingGroup::Week),
+            "month" => Ok(SpendingGroup::Month),
+            "project" => Ok(SpendingGroup::Project),
+            "label" => Ok(SpendingGroup::Label),
+            _ => Err(format!(
+                "Invalid group_by \"{}\", expected one of day, week, month, project or label",
+                group_by
+            )),
+        }
+    }
+
+    pub fn is_period(&self) -> bool {
+        matches!(
+            self,
+            SpendingGroup::Day | SpendingGroup::Week | SpendingGroup::Month
+        )
+    }
+}
+
+#[derive(QueryableByName)]
+pub struct SpendingRow {
+    #[diesel(sql_type = VarChar)]
+    pub bucket_key: String,
+    #[diesel(sql_type = VarChar)]
+    pub bucket_label: String,
+    #[diesel(sql_type = BigInt)]
+    pub amount: i64,
+    #[diesel(sql_type = BigInt)]
+    pub expenses_count: i64,
+}
+
+#[derive(QueryableByName)]
+pub struct SpendingTotal {
+    #[diesel(sql_type = BigInt)]
+    pub amount: i64,
+    #[diesel(sql_type = BigInt)]
+    pub expenses_count: i64,
+}
+
+#[derive(QueryableByName)]
+pub struct BudgetSpending {
+    #[diesel(sql_type = diesel::sql_types::Uuid)]
+    pub budget_id: uuid::Uuid,
+    #[diesel(sql_type = BigInt)]
+    pub amount: i64,
+}
+
+#[derive(Serialize)]
+pub struct SpendingBucket {
+    pub key: String,
+    pub label: String,
+    pub amount: i64,
+    pub expenses_count: i64,
+    pub budget_amount: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct SpendingReport {
+    pub from: chrono::NaiveDate,
+    pub to: chrono::NaiveDate,
+    pub group_by: SpendingGroup,
+    pub total_amount: i64,
+    pub total_expenses: i64,
+    pub budget_amount: i64,
+    pub budget_balance: i64,
+    pub buckets: Vec<SpendingBucket>,
+}
+
+#[derive(Deserialize)]
+pub struct BudgetYearReportParams {
+    pub year: Option<i16>,
+}
+
+/// Expense seen in each of the two months preceding a budget's month but not yet in it
+#[derive(QueryableByName, Serialize)]
+pub struct RecurringItem {
+    #[diesel(sql_type = diesel::sql_types::Uuid)]
+    pub project_id: uuid::Uuid,
+    #[diesel(sql_type = VarChar)]
+    pub narration: String,
+    #[diesel(sql_type = BigInt)]
+    pub amount: i64,
+}
+
+#[derive(Serialize)]
+pub struct BudgetReport {
+    pub budget: Budget,
+    pub planned_amount: i64,
+    pub actual_amount: i64,
+    pub variance: i64,
+    pub overspent_amount: i64,
+    pub days_in_month: i64,
+    pub days_elapsed: i64,
+    pub burn_rate_per_day: i64,
+    pub recurring_amount: i64,
+    pub recurring_items: Vec<RecurringItem>,
+    pub projected_amount: i64,
+    pub projected_variance: i64,
+    pub projected_overspent_amount: i64,
+}
+
+#[derive(Serialize)]
+pub struct BudgetMonthSummary {
+    pub month: i16,
+    pub name: String,
+    pub budget_id: Option<uuid::Uuid>,
+    pub planned_amount: i64,
+    pub actual_amount: i64,
+    pub variance: i64,
+    pub overspent_amount: i64,
+}
+
+#[derive(Serialize)]
+pub struct BudgetYearReport {
+    pub year: i16,
+    pub planned_amount: i64,
+    pub actual_amount: i64,
+    pub variance: i64,
+    pub overspent_amount: i64,
+    pub months: Vec<BudgetMonthSummary>,
+}
@@ -0,0 +1,69 @@
+use chrono::NaiveDateTime;
+use diesel::sql_types::{Float, Timestamp, Uuid as SqlUuid, VarChar};
+use diesel::QueryableByName;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub struct SearchParams {
+    pub q: Option<String>,
+    /// comma separated kinds of records to search, all of them when omitted
+    pub types: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchKind {
+    Project,
+    Budget,
+    Expense,
+}
+
+impl SearchKind {
+    pub const ALL: [SearchKind; 3] = [SearchKind::Project, SearchKind::Budget, SearchKind::Expense];
+
+    pub fn parse(kind: &str) -> Result<SearchKind, String> {
+        match kind {
+            "projects" => Ok(SearchKind::Project),
+            "budgets" => Ok(SearchKind::Budget),
+            "expenses" => Ok(SearchKind::Expense),
+            _ => Err(format!(
+                "Invalid type \"{}\", expected one of projects, budgets or expenses",
+                kind
+            )),
+        }
+    }
+
+    /// Name the search query tags its hits with
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchKind::Project => "project",
+            SearchKind::Budget => "budget",
+            SearchKind::Expense => "expense",
+        }
+    }
+}
+
+#[derive(QueryableByName, Serialize)]
+pub struct SearchHit {
+    #[serde(rename = "type")]
+    #[diesel(sql_type = VarChar)]
+    pub kind: String,
+    #[diesel(sql_type = SqlUuid)]
+    pub id: Uuid,
+    #[diesel(sql_type = VarChar)]
+    pub title: String,
+    /// matching excerpt, HTML escaped with the matched words wrapped in `<mark>`
+    #[diesel(sql_type = VarChar)]
+    pub snippet: String,
+    #[diesel(sql_type = Float)]
+    pub rank: f32,
+    #[diesel(sql_type = Timestamp)]
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub struct SearchResults {
+    pub query: String,
+    pub hits: Vec<SearchHit>,
+}
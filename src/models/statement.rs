@@ -0,0 +1,42 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::models::budget::Budget;
+
+/// One row of a statement breakdown
+#[derive(Serialize)]
+pub struct StatementLine {
+    pub name: String,
+    pub expenses: usize,
+    pub amount: i64,
+    /// percentage of the statement's total spending
+    pub share: f64,
+}
+
+#[derive(Serialize)]
+pub struct StatementExpense {
+    pub spent_at: NaiveDateTime,
+    pub narration: String,
+    pub project: String,
+    /// labels of the expense and of its project
+    pub labels: Vec<String>,
+    pub amount: i64,
+}
+
+/// Printable month-end summary of a budget
+#[derive(Serialize)]
+pub struct BudgetStatement {
+    pub title: String,
+    pub account_name: String,
+    /// in the user's timezone
+    pub generated_at: NaiveDateTime,
+    pub budget: Budget,
+    pub total_budgeted: i64,
+    pub total_spent: i64,
+    pub total_available: i64,
+    pub total_overspent: i64,
+    pub projects: Vec<StatementLine>,
+    /// expenses carrying several labels count towards each of them
+    pub labels: Vec<StatementLine>,
+    pub expenses: Vec<StatementExpense>,
+}
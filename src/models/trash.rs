@@ -0,0 +1,92 @@
+use chrono::NaiveDateTime;
+use diesel::sql_types::{BigInt, Nullable, Timestamp, Uuid as SqlUuid, VarChar};
+use diesel::QueryableByName;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::{Error, Uuid};
+
+#[derive(Deserialize)]
+pub struct TrashParams {
+    /// comma separated kinds of records to list, all of them when omitted
+    pub types: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct TrashPathParam {
+    pub kind: String,
+    pub id: String,
+}
+
+impl TrashPathParam {
+    pub fn get_kind(&self) -> Result<TrashKind, String> {
+        TrashKind::parse(self.kind.as_str())
+    }
+
+    pub fn get_uuid(&self) -> Result<Uuid, Error> {
+        Uuid::from_str(self.id.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrashKind {
+    Project,
+    Budget,
+    Expense,
+}
+
+impl TrashKind {
+    /// Expenses go first when emptying the trash, their projects and budgets
+    /// can't be purged while they still exist
+    pub const ALL: [TrashKind; 3] = [TrashKind::Expense, TrashKind::Budget, TrashKind::Project];
+
+    pub fn parse(kind: &str) -> Result<TrashKind, String> {
+        match kind {
+            "projects" => Ok(TrashKind::Project),
+            "budgets" => Ok(TrashKind::Budget),
+            "expenses" => Ok(TrashKind::Expense),
+            _ => Err(format!(
+                "Invalid type \"{}\", expected one of projects, budgets or expenses",
+                kind
+            )),
+        }
+    }
+
+    /// Name the trash query tags its items with
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrashKind::Project => "project",
+            TrashKind::Budget => "budget",
+            TrashKind::Expense => "expense",
+        }
+    }
+}
+
+#[derive(QueryableByName, Serialize)]
+pub struct TrashItem {
+    #[serde(rename = "type")]
+    #[diesel(sql_type = VarChar)]
+    pub kind: String,
+    #[diesel(sql_type = SqlUuid)]
+    pub id: Uuid,
+    #[diesel(sql_type = VarChar)]
+    pub title: String,
+    #[diesel(sql_type = Nullable<BigInt>)]
+    pub amount: Option<i64>,
+    #[diesel(sql_type = Timestamp)]
+    pub deleted_at: NaiveDateTime,
+    #[diesel(sql_type = Nullable<Timestamp>)]
+    pub purge_at: Option<NaiveDateTime>,
+    #[serde(skip_serializing)]
+    #[diesel(sql_type = BigInt)]
+    pub total_records: i64,
+}
+
+/// Outcome of emptying the trash
+#[derive(Serialize, Default)]
+pub struct TrashPurgeSummary {
+    pub purged: usize,
+    /// items other records still depend on, e.g. a project with expenses outside the trash
+    pub skipped: usize,
+    /// items that could not be purged for any other reason, the errors are logged
+    pub failed: usize,
+}
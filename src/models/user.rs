@@ -3,6 +3,9 @@
 use diesel::{Insertable, Queryable};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use validator::Validate;
+
+use crate::core::helpers::validation::{validate_not_blank, validate_password, validate_timezone};
 
 use super::super::schema::users;
 
@@ -13,11 +16,14 @@ pub struct User {
     pub first_name: String,
     pub last_name: String,
     pub email: String,
+    /// password hash, never sent back to clients
+    #[serde(skip_serializing)]
     pub password: String,
     pub status: String,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
     pub deleted_at: Option<chrono::NaiveDateTime>,
+    pub timezone: String,
 }
 
 pub enum UserStatus {
@@ -26,16 +32,27 @@ pub enum UserStatus {
     Pending,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Validate)]
 pub struct LoginForm {
+    #[validate(length(min = 1, max = 100))]
     pub email: String,
+    #[validate(length(min = 1))]
     pub password: String,
+    /// keep the token in an HttpOnly cookie instead of returning it
+    #[serde(default)]
+    pub use_cookie: bool,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Validate)]
 pub struct RegisterForm {
+    #[validate(length(max = 150), custom = "validate_not_blank")]
     pub first_name: String,
+    #[validate(length(max = 150), custom = "validate_not_blank")]
     pub last_name: String,
+    #[validate(email, length(max = 100))]
     pub email: String,
+    #[validate(length(min = 8, max = 128), custom = "validate_password")]
     pub password: String,
+    #[validate(custom = "validate_timezone")]
+    pub timezone: Option<String>,
 }
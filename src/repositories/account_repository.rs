@@ -0,0 +1,174 @@
+use std::ops::DerefMut;
+
+use diesel::{Connection, ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::core::helpers::db::{current_timestamp, OwnedBy, INSERT_CHUNK_SIZE};
+use crate::core::helpers::get_db_conn;
+use crate::models::account_archive::{
+    AccountArchive, AccountArchiveManifest, AccountProfile, ACCOUNT_ARCHIVE_VERSION,
+};
+use crate::models::bank_account::BankAccount;
+use crate::models::budget::Budget;
+use crate::models::category::Category;
+use crate::models::expense::Expense;
+use crate::models::expense_rule::ExpenseRule;
+use crate::models::income::Income;
+use crate::models::label::{ExpenseLabel, Label, ProjectLabel};
+use crate::models::project::Project;
+use crate::models::user::User;
+use crate::models::DBPool;
+use crate::schema::{
+    bank_accounts, budgets, categories, expense_labels, expense_rules, expenses, incomes, labels,
+    project_labels, projects,
+};
+
+pub struct AccountRepository;
+
+impl AccountRepository {
+    /// Collects every live record of the user, oldest first
+    pub fn load_archive(&mut self, pool: &DBPool, user: &User) -> QueryResult<AccountArchive> {
+        let conn = &mut get_db_conn(pool);
+        let user_id = user.user_id;
+
+        let mut archive = AccountArchive {
+            manifest: AccountArchiveManifest {
+                version: ACCOUNT_ARCHIVE_VERSION,
+                exported_at: current_timestamp(),
+                user_id,
+                counts: Default::default(),
+            },
+            profile: AccountProfile::from(user),
+            projects: projects::table
+                .owned_by(user_id)
+                .order_by(projects::created_at.asc())
+                .load::<Project>(conn.deref_mut())?,
+            labels: labels::table
+                .owned_by(user_id)
+                .order_by(labels::created_at.asc())
+                .load::<Label>(conn.deref_mut())?,
+            project_labels: project_labels::table
+                .owned_by(user_id)
+                .order_by(project_labels::created_at.asc())
+                .load::<ProjectLabel>(conn.deref_mut())?,
+            categories: categories::table
+                .owned_by(user_id)
+                .order_by(categories::created_at.asc())
+                .load::<Category>(conn.deref_mut())?,
+            budgets: budgets::table
+                .owned_by(user_id)
+                .order_by(budgets::created_at.asc())
+                .load::<Budget>(conn.deref_mut())?,
+            expenses: expenses::table
+                .owned_by(user_id)
+                .order_by(expenses::created_at.asc())
+                .load::<Expense>(conn.deref_mut())?,
+            expense_labels: expense_labels::table
+                .owned_by(user_id)
+                .order_by(expense_labels::created_at.asc())
+                .load::<ExpenseLabel>(conn.deref_mut())?,
+            incomes: incomes::table
+                .owned_by(user_id)
+                .order_by(incomes::created_at.asc())
+                .load::<Income>(conn.deref_mut())?,
+            bank_accounts: bank_accounts::table
+                .owned_by(user_id)
+                .order_by(bank_accounts::created_at.asc())
+                .load::<BankAccount>(conn.deref_mut())?,
+            expense_rules: expense_rules::table
+                .owned_by(user_id)
+                .order_by(expense_rules::created_at.asc())
+                .load::<ExpenseRule>(conn.deref_mut())?,
+        };
+
+        archive.manifest.counts = archive.counts();
+        Ok(archive)
+    }
+
+    /// Inserts the (already remapped) records of the archive, all or nothing
+    pub fn restore(&mut self, pool: &DBPool, archive: AccountArchive) -> QueryResult<()> {
+        get_db_conn(pool).transaction(|conn| {
+            for chunk in archive.projects.chunks(INSERT_CHUNK_SIZE) {
+                diesel::insert_into(projects::table)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+
+            for chunk in archive.labels.chunks(INSERT_CHUNK_SIZE) {
+                diesel::insert_into(labels::table)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+
+            for chunk in archive.project_labels.chunks(INSERT_CHUNK_SIZE) {
+                diesel::insert_into(project_labels::table)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+
+            // parents come first so the self reference always resolves
+            for category in archive.categories {
+                diesel::insert_into(categories::table)
+                    .values(category)
+                    .execute(conn)?;
+            }
+
+            for chunk in archive.budgets.chunks(INSERT_CHUNK_SIZE) {
+                diesel::insert_into(budgets::table)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+
+            for chunk in archive.expenses.chunks(INSERT_CHUNK_SIZE) {
+                diesel::insert_into(expenses::table)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+
+            for chunk in archive.expense_labels.chunks(INSERT_CHUNK_SIZE) {
+                diesel::insert_into(expense_labels::table)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+
+            for chunk in archive.incomes.chunks(INSERT_CHUNK_SIZE) {
+                diesel::insert_into(incomes::table)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+
+            for chunk in archive.bank_accounts.chunks(INSERT_CHUNK_SIZE) {
+                diesel::insert_into(bank_accounts::table)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+
+            for chunk in archive.expense_rules.chunks(INSERT_CHUNK_SIZE) {
+                diesel::insert_into(expense_rules::table)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    pub fn list_default_category_ids(&mut self, pool: &DBPool) -> QueryResult<Vec<Uuid>> {
+        categories::table
+            .filter(categories::user_id.is_null())
+            .select(categories::category_id)
+            .load::<Uuid>(get_db_conn(pool).deref_mut())
+    }
+
+    /// (year, month) of the user's live budgets
+    pub fn list_budget_periods(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+    ) -> QueryResult<Vec<(i16, i16)>> {
+        budgets::table
+            .owned_by(user_id)
+            .select((budgets::year, budgets::month))
+            .load::<(i16, i16)>(get_db_conn(pool).deref_mut())
+    }
+}
@@ -0,0 +1,61 @@
+use std::ops::DerefMut;
+
+use diesel::{ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::core::enums::app_error::{AppResult, OrNotFound};
+use crate::core::helpers::db::OwnedBy;
+use crate::core::helpers::get_db_conn;
+use crate::models::attachment::Attachment;
+use crate::models::DBPool;
+use crate::schema::attachments;
+
+pub struct AttachmentRepository;
+
+impl AttachmentRepository {
+    pub fn list_by_expense_id(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> QueryResult<Vec<Attachment>> {
+        attachments::table
+            .owned_by(user_id)
+            .filter(attachments::expense_id.eq(id))
+            .order_by(attachments::created_at.asc())
+            .load::<Attachment>(get_db_conn(pool).deref_mut())
+    }
+
+    pub fn create(&mut self, pool: &DBPool, model: Attachment) -> AppResult<Attachment> {
+        diesel::insert_into(attachments::table)
+            .values(model.clone())
+            .execute(get_db_conn(pool).deref_mut())?;
+
+        Ok(model)
+    }
+
+    /// Deletes the attachment for good, attachments are not kept in the trash
+    /// since their stored files go along with them
+    pub fn delete(&mut self, pool: &DBPool, id: Uuid, user_id: Uuid) -> AppResult<Attachment> {
+        diesel::delete(
+            attachments::table
+                .filter(attachments::table.owned_condition(user_id))
+                .filter(attachments::attachment_id.eq(id)),
+        )
+        .get_result::<Attachment>(get_db_conn(pool).deref_mut())
+        .or_not_found("attachment")
+    }
+
+    pub fn find_owned_by_id(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> AppResult<Attachment> {
+        attachments::table
+            .owned_by(user_id)
+            .filter(attachments::attachment_id.eq(id))
+            .first::<Attachment>(get_db_conn(pool).deref_mut())
+            .or_not_found("attachment")
+    }
+}
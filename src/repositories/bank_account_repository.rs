@@ -0,0 +1,125 @@
+use std::ops::DerefMut;
+
+use diesel::{Connection, ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::core::enums::app_error::{AppError, AppResult, OrNotFound};
+use crate::core::helpers::db::{current_timestamp, OwnedBy};
+use crate::core::helpers::form::get_uuid_from_string;
+use crate::core::helpers::get_db_conn;
+use crate::models::bank_account::{BankAccount, BankAccountForm};
+use crate::models::DBPool;
+use crate::repositories::project_repository::lock_owned_project;
+use crate::schema::bank_accounts;
+
+pub struct BankAccountRepository;
+
+impl BankAccountRepository {
+    pub fn list_by_user_id(&mut self, pool: &DBPool, id: Uuid) -> QueryResult<Vec<BankAccount>> {
+        bank_accounts::table
+            .owned_by(id)
+            .order_by(bank_accounts::name.asc())
+            .load::<BankAccount>(get_db_conn(pool).deref_mut())
+    }
+
+    pub fn create(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        form: BankAccountForm,
+    ) -> AppResult<BankAccount> {
+        let model = BankAccount {
+            bank_account_id: Uuid::new_v4(),
+            user_id,
+            project_id: get_uuid_from_string(form.project_id)?,
+            name: form.name.unwrap_or(form.account_number.clone()),
+            account_number: form.account_number,
+            created_at: current_timestamp(),
+            updated_at: current_timestamp(),
+            deleted_at: None,
+        };
+
+        get_db_conn(pool).transaction(|conn| {
+            lock_owned_project(conn, user_id, model.project_id)?
+                .ok_or_else(|| AppError::from("Such project does not exist"))?;
+
+            diesel::insert_into(bank_accounts::table)
+                .values(model.clone())
+                .execute(conn)?;
+
+            Ok(model)
+        })
+    }
+
+    pub fn update(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+        form: BankAccountForm,
+    ) -> AppResult<BankAccount> {
+        let bank_account = self.find_owned_by_id(pool, id, user_id)?;
+        let project_id = get_uuid_from_string(form.project_id)?;
+
+        get_db_conn(pool).transaction(|conn| {
+            lock_owned_project(conn, user_id, project_id)?
+                .ok_or_else(|| AppError::from("Such project does not exist"))?;
+
+            diesel::update(
+                bank_accounts::table
+                    .filter(bank_accounts::table.owned_condition(user_id))
+                    .filter(bank_accounts::bank_account_id.eq(id)),
+            )
+            .set((
+                bank_accounts::dsl::project_id.eq(project_id),
+                bank_accounts::dsl::name.eq(form.name.unwrap_or(form.account_number.clone())),
+                bank_accounts::dsl::account_number.eq(form.account_number),
+                bank_accounts::dsl::updated_at.eq(current_timestamp()),
+            ))
+            .execute(conn)?;
+
+            Ok(bank_account)
+        })
+    }
+
+    pub fn delete(&mut self, pool: &DBPool, id: Uuid, user_id: Uuid) -> AppResult<BankAccount> {
+        let bank_account = self.find_owned_by_id(pool, id, user_id)?;
+
+        diesel::update(
+            bank_accounts::table
+                .filter(bank_accounts::table.owned_condition(user_id))
+                .filter(bank_accounts::bank_account_id.eq(id)),
+        )
+        .set(bank_accounts::dsl::deleted_at.eq(current_timestamp()))
+        .execute(get_db_conn(pool).deref_mut())?;
+
+        Ok(bank_account)
+    }
+
+    pub fn find_owned_by_id(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> AppResult<BankAccount> {
+        bank_accounts::table
+            .owned_by(user_id)
+            .filter(bank_accounts::bank_account_id.eq(id))
+            .first::<BankAccount>(get_db_conn(pool).deref_mut())
+            .or_not_found("bank account")
+    }
+
+    pub fn find_by_account_number(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        account_number: &str,
+    ) -> QueryResult<Option<BankAccount>> {
+        diesel::OptionalExtension::optional(
+            bank_accounts::table
+                .owned_by(user_id)
+                .filter(bank_accounts::account_number.eq(account_number))
+                .first::<BankAccount>(get_db_conn(pool).deref_mut()),
+        )
+    }
+}
@@ -1,17 +1,26 @@
-use crate::core::enums::http_error::{DBResult, ErroneousOption, OptionalResult};
-use crate::core::helpers::date_time::Month;
-use crate::core::helpers::db::current_timestamp;
-use crate::core::helpers::db_pagination::{Paginate, PaginationResult};
+use crate::core::enums::app_error::{AppError, AppResult, OrNotFound};
+use crate::core::helpers::date_time::{today_in_timezone, Month};
+use crate::core::helpers::db::{current_timestamp, OwnedBy};
+use crate::core::helpers::db_pagination::{sort_query, PageRequest, PaginationResult};
 use crate::core::helpers::get_db_conn;
 use crate::core::helpers::http::QueryParams;
-use crate::models::budget::{Budget, BudgetForm};
+use crate::models::budget::{
+    sanitize_alert_thresholds, Budget, BudgetForm, OverspendPolicy, DEFAULT_ALERT_THRESHOLDS,
+};
+use crate::models::expense::{Expense, ExpenseCascade};
 use crate::models::DBPool;
-use crate::schema::budgets;
-use chrono::{Datelike, Utc};
-use diesel::{ExpressionMethods, PgTextExpressionMethods, QueryDsl, QueryResult, RunQueryDsl};
+use crate::repositories::expense_repository::{trash_expenses, untrash_expenses};
+use crate::schema::{budgets, expenses, notifications, projects};
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use diesel::{
+    BoolExpressionMethods, Connection, ExpressionMethods, OptionalExtension, PgConnection,
+    PgTextExpressionMethods, QueryDsl, QueryResult, RunQueryDsl,
+};
 use std::ops::DerefMut;
 use uuid::Uuid;
 
+const BUDGET_SORTS: [&str; 4] = ["created_at", "amount", "year", "month"];
+
 pub struct BudgetRepository;
 
 impl BudgetRepository {
@@ -20,23 +29,62 @@ impl BudgetRepository {
         pool: &DBPool,
         id: Uuid,
         mut query_params: QueryParams,
-    ) -> QueryResult<PaginationResult<Budget>> {
-        let builder = budgets::table
-            .filter(budgets::user_id.eq(id))
-            .filter(budgets::deleted_at.is_null())
-            .order_by(budgets::created_at.desc());
+    ) -> AppResult<PaginationResult<Budget>> {
+        let page = PageRequest::new(&mut query_params, &BUDGET_SORTS, "-created_at")?;
+        let search_format = format!("%{}%", query_params.get_search_query());
+        let builder = budgets::table.owned_by(id).filter(
+            budgets::title
+                .ilike(search_format.clone())
+                .or(budgets::comment.ilike(search_format)),
+        );
+
+        let builder = sort_query!(builder, &page, budgets::budget_id, {
+            "created_at" => budgets::created_at => NaiveDateTime,
+            "amount" => budgets::amount => i64,
+            "year" => budgets::year => i16,
+            "month" => budgets::month => i16,
+        });
+
+        page.load(builder, get_db_conn(pool).deref_mut(), |budget: &Budget| {
+            (
+                budget.budget_id,
+                serde_json::to_value(budget).unwrap_or_default(),
+            )
+        })
+    }
 
+    /// Next batch of the user's budgets matching the search, newest first and
+    /// resuming after the given (created_at, budget_id) cursor
+    pub fn list_batch_by_user_id(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        mut query_params: QueryParams,
+        after: Option<(NaiveDateTime, Uuid)>,
+        limit: i64,
+    ) -> QueryResult<Vec<Budget>> {
         let search_format = format!("%{}%", query_params.get_search_query());
+        let mut builder = budgets::table.owned_by(id).filter(
+            budgets::title
+                .ilike(search_format.clone())
+                .or(budgets::comment.ilike(search_format)),
+        );
+
+        if let Some((created_at, budget_id)) = after {
+            builder = builder.filter(
+                budgets::created_at.lt(created_at).or(budgets::created_at
+                    .eq(created_at)
+                    .and(budgets::budget_id.lt(budget_id))),
+            );
+        }
 
         builder
-            .filter(budgets::title.ilike(search_format.clone()))
-            .or_filter(budgets::comment.ilike(search_format))
-            .paginate(query_params.get_page())
-            .per_page(query_params.get_per_page())
-            .load_and_count_pages::<Budget>(get_db_conn(pool).deref_mut())
+            .order_by((budgets::created_at.desc(), budgets::budget_id.desc()))
+            .limit(limit)
+            .load::<Budget>(get_db_conn(pool).deref_mut())
     }
 
-    pub fn create(&mut self, pool: &DBPool, user_id: Uuid, form: BudgetForm) -> Budget {
+    pub fn create(&mut self, pool: &DBPool, user_id: Uuid, form: BudgetForm) -> AppResult<Budget> {
         let model = Budget {
             user_id,
             amount: form.amount,
@@ -49,64 +97,278 @@ impl BudgetRepository {
             created_at: current_timestamp(),
             updated_at: current_timestamp(),
             deleted_at: None,
+            alert_thresholds: sanitize_alert_thresholds(
+                form.alert_thresholds
+                    .unwrap_or_else(|| DEFAULT_ALERT_THRESHOLDS.to_vec()),
+            ),
+            overspend_policy: form
+                .overspend_policy
+                .unwrap_or(OverspendPolicy::Reject)
+                .as_str()
+                .to_string(),
+            over_limit_at: None,
         };
 
         diesel::insert_into(budgets::table)
             .values(model.clone())
-            .execute(get_db_conn(pool).deref_mut())
-            .unwrap();
+            .execute(get_db_conn(pool).deref_mut())?;
 
-        model
+        Ok(model)
     }
 
+    /// Updates the budget on its locked row, an amount lowered below what was spent
+    /// flags a budget asking for it as over the limit and one raised above it lifts
+    /// the flag. Returns the budget before and after the update
     pub fn update(
         &mut self,
         pool: &DBPool,
         id: Uuid,
         user_id: Uuid,
         form: BudgetForm,
-    ) -> DBResult<Budget> {
-        let result = self.find_owned_by_id(pool, id, user_id);
+    ) -> AppResult<(Budget, Budget)> {
+        get_db_conn(pool).transaction(|conn| {
+            let budget = lock_owned_budget(conn, user_id, id)?
+                .ok_or_else(|| AppError::EntityNotFound(String::from("budget")))?;
 
-        if result.is_error_or_empty() {
-            return result.get_error_result();
-        }
+            let policy = form
+                .overspend_policy
+                .unwrap_or_else(|| budget.overspend_policy());
+            let alert_thresholds = match form.alert_thresholds {
+                Some(thresholds) => sanitize_alert_thresholds(thresholds),
+                None => budget.alert_thresholds.clone(),
+            };
+            let over_limit_at = match form.amount >= budget.amount_used {
+                true => None,
+                false if policy == OverspendPolicy::AllowAndFlag => {
+                    Some(budget.over_limit_at.unwrap_or_else(current_timestamp))
+                }
+                false => budget.over_limit_at,
+            };
 
-        diesel::update(budgets::dsl::budgets.filter(budgets::budget_id.eq(id)))
+            let updated = diesel::update(
+                budgets::table
+                    .filter(budgets::table.owned_condition(user_id))
+                    .filter(budgets::budget_id.eq(id)),
+            )
             .set((
                 budgets::dsl::amount.eq(form.amount),
                 budgets::dsl::comment.eq(form.comment),
                 budgets::dsl::title.eq(make_budget_title(form.month, form.year)),
                 budgets::dsl::month.eq(form.month),
                 budgets::dsl::year.eq(form.year),
+                budgets::dsl::overspend_policy.eq(policy.as_str()),
+                budgets::dsl::alert_thresholds.eq(alert_thresholds),
+                budgets::dsl::over_limit_at.eq(over_limit_at),
+                budgets::dsl::updated_at.eq(current_timestamp()),
             ))
-            .execute(get_db_conn(pool).deref_mut())
-            .expect("Failed to update budget");
+            .get_result::<Budget>(conn)?;
 
-        Ok(result.unwrap_entity())
+            Ok((budget, updated))
+        })
     }
 
-    pub fn delete(&mut self, pool: &DBPool, id: Uuid, user_id: Uuid) -> DBResult<Budget> {
-        let result = self.find_owned_by_id(pool, id, user_id);
+    /// Moves the budget to the trash, its live expenses have to be trashed along or
+    /// moved to another budget (which then carries their amounts), all or nothing.
+    /// Returns the deleted budget and, on a move, the target before and after taking the expenses
+    pub fn delete(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+        cascade: ExpenseCascade,
+    ) -> AppResult<(Budget, Option<(Budget, Budget)>)> {
+        get_db_conn(pool).transaction(|conn| {
+            let target_id = match cascade {
+                ExpenseCascade::Move(target_id) => Some(target_id),
+                _ => None,
+            };
 
-        if result.is_error_or_empty() {
-            return result.get_error_result();
-        }
+            // spending locks the budget too, so no expense can be added while it's deleted.
+            // Both budgets of a move are locked lowest id first, so that two opposite moves
+            // can't deadlock, the target may have been deleted meanwhile
+            let mut lock_ids: Vec<Uuid> = [Some(id), target_id].into_iter().flatten().collect();
+            lock_ids.sort();
+            lock_ids.dedup();
 
-        diesel::update(budgets::dsl::budgets.filter(budgets::budget_id.eq(id)))
-            .set(budgets::dsl::deleted_at.eq(current_timestamp()))
-            .execute(get_db_conn(pool).deref_mut())
-            .expect("Failed to delete budget");
+            let mut budget = None;
+            let mut target = None;
+            for lock_id in lock_ids {
+                let locked = lock_owned_budget(conn, user_id, lock_id)?;
+                match lock_id == id {
+                    true => budget = locked,
+                    false => target = locked,
+                }
+            }
 
-        Ok(result.unwrap_entity())
+            let budget = budget.ok_or_else(|| AppError::EntityNotFound(String::from("budget")))?;
+
+            let live_expenses = expenses::table
+                .owned_by(user_id)
+                .filter(expenses::budget_id.eq(id))
+                .load::<Expense>(conn)?;
+            let ids: Vec<Uuid> = live_expenses.iter().map(|expense| expense.expense_id).collect();
+
+            let deleted_at = current_timestamp();
+            let mut moved = None;
+            match cascade {
+                ExpenseCascade::Refuse if !live_expenses.is_empty() => {
+                    return Err(AppError::Conflict(format!(
+                        "This budget has {} expenses, delete it with expenses=cascade or expenses=move",
+                        live_expenses.len()
+                    )));
+                }
+                ExpenseCascade::Refuse => {}
+                ExpenseCascade::Cascade => {
+                    // trashed along with the budget, restoring it brings them back
+                    trash_expenses(conn, user_id, ids, deleted_at)?;
+                }
+                ExpenseCascade::Move(_) => {
+                    let amount: i64 = live_expenses.iter().map(|expense| expense.amount).sum();
+
+                    let mut target = target.ok_or_else(|| {
+                        AppError::from("The budget to move the expenses to does not exist")
+                    })?;
+
+                    if (target.year, target.month) != (budget.year, budget.month) {
+                        return Err(AppError::from(
+                            "Expenses can only be moved to a budget of the same month",
+                        ));
+                    }
+
+                    if amount > target.available_amount()
+                        && target.overspend_policy() == OverspendPolicy::Reject
+                    {
+                        return Err(AppError::from(
+                            "The expenses exceed the budget they are moved to",
+                        ));
+                    }
+
+                    diesel::update(
+                        expenses::table
+                            .filter(expenses::table.owned_condition(user_id))
+                            .filter(expenses::expense_id.eq_any(ids)),
+                    )
+                    .set((
+                        expenses::budget_id.eq(target.budget_id),
+                        expenses::updated_at.eq(current_timestamp()),
+                    ))
+                    .execute(conn)?;
+
+                    let updated_target = charge_budget(conn, target.budget_id, amount)?;
+                    charge_budget(conn, id, -amount)?;
+
+                    moved = Some((target, updated_target));
+                }
+            }
+
+            let budget = diesel::update(budgets::table.filter(budgets::budget_id.eq(id)))
+                .set(budgets::deleted_at.eq(deleted_at))
+                .get_result::<Budget>(conn)?;
+
+            Ok((budget, moved))
+        })
     }
 
-    #[allow(dead_code)]
-    pub fn find_by_id(&mut self, pool: &DBPool, id: Uuid) -> QueryResult<Budget> {
+    /// Restores the budget along with the expenses deleted together with it, the ones
+    /// of a project that is still deleted stay in the trash. Returns the restored
+    /// budget and the budget before and after charging the expenses, when there were any
+    pub fn restore(
+        &mut self,
+        pool: &DBPool,
+        budget: &Budget,
+    ) -> AppResult<(Budget, Option<(Budget, Budget)>)> {
+        let user_id = budget.user_id;
+
+        get_db_conn(pool).transaction(|conn| {
+            let cascaded = expenses::table
+                .filter(expenses::table.trashed_condition(user_id))
+                .filter(expenses::budget_id.eq(budget.budget_id))
+                .filter(expenses::deleted_at.eq(budget.deleted_at))
+                .select((expenses::expense_id, expenses::project_id))
+                .load::<(Uuid, Uuid)>(conn)?;
+
+            // projects are locked before budgets, like spending does
+            let mut project_ids: Vec<Uuid> = cascaded.iter().map(|(_, id)| *id).collect();
+            project_ids.sort();
+            project_ids.dedup();
+            let live_project_ids = projects::table
+                .filter(projects::table.owned_condition(user_id))
+                .filter(projects::project_id.eq_any(project_ids))
+                .order_by(projects::project_id)
+                .select(projects::project_id)
+                .for_update()
+                .load::<Uuid>(conn)?;
+
+            let restored = diesel::update(
+                budgets::table
+                    .filter(budgets::table.trashed_condition(user_id))
+                    .filter(budgets::budget_id.eq(budget.budget_id)),
+            )
+            .set((
+                budgets::deleted_at.eq(None::<NaiveDateTime>),
+                budgets::updated_at.eq(current_timestamp()),
+            ))
+            .get_result::<Budget>(conn)
+            .or_not_found("budget")?;
+
+            let ids = cascaded
+                .into_iter()
+                .filter(|(_, project_id)| live_project_ids.contains(project_id))
+                .map(|(id, _)| id)
+                .collect();
+            let charged = untrash_expenses(conn, user_id, ids)?.pop();
+
+            Ok((restored, charged))
+        })
+    }
+
+    pub fn find_trashed_by_id(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> AppResult<Budget> {
         budgets::table
+            .trashed_by(user_id)
             .filter(budgets::budget_id.eq(id))
-            .filter(budgets::deleted_at.is_null())
             .first::<Budget>(get_db_conn(pool).deref_mut())
+            .or_not_found("budget")
+    }
+
+    /// Ids of every budget (of any user) that was deleted before the given time
+    pub fn list_ids_deleted_before(
+        &mut self,
+        pool: &DBPool,
+        time: NaiveDateTime,
+    ) -> QueryResult<Vec<Uuid>> {
+        budgets::table
+            .filter(budgets::deleted_at.lt(time))
+            .select(budgets::budget_id)
+            .load::<Uuid>(get_db_conn(pool).deref_mut())
+    }
+
+    /// Permanently deletes the budget, refused while expenses (trashed ones
+    /// included) still belong to it
+    pub fn purge(&mut self, pool: &DBPool, id: Uuid) -> AppResult<()> {
+        get_db_conn(pool).transaction(|conn| {
+            let expenses_count = expenses::table
+                .filter(expenses::budget_id.eq(id))
+                .count()
+                .get_result::<i64>(conn)?;
+
+            if expenses_count > 0 {
+                return Err(AppError::Conflict(String::from(
+                    "This budget still has expenses, purge or move them first",
+                )));
+            }
+
+            diesel::update(notifications::table.filter(notifications::budget_id.eq(id)))
+                .set(notifications::budget_id.eq(None::<Uuid>))
+                .execute(conn)?;
+
+            diesel::delete(budgets::table.filter(budgets::budget_id.eq(id))).execute(conn)?;
+            Ok(())
+        })
     }
 
     pub fn find_owned_by_id(
@@ -114,30 +376,85 @@ impl BudgetRepository {
         pool: &DBPool,
         id: Uuid,
         user_id: Uuid,
-    ) -> DBResult<Option<Budget>> {
+    ) -> AppResult<Budget> {
         budgets::table
+            .owned_by(user_id)
             .filter(budgets::budget_id.eq(id))
-            .filter(budgets::user_id.eq(user_id))
-            .filter(budgets::deleted_at.is_null())
             .first::<Budget>(get_db_conn(pool).deref_mut())
-            .optional("budget")
+            .or_not_found("budget")
+    }
+
+    /// Live budgets of the user whose month falls between the given dates' months
+    pub fn list_by_period(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> QueryResult<Vec<Budget>> {
+        let (from_year, from_month) = (from.year() as i16, from.month() as i16);
+        let (to_year, to_month) = (to.year() as i16, to.month() as i16);
+
+        budgets::table
+            .owned_by(user_id)
+            .filter(
+                budgets::year.gt(from_year).or(budgets::year
+                    .eq(from_year)
+                    .and(budgets::month.ge(from_month))),
+            )
+            .filter(
+                budgets::year
+                    .lt(to_year)
+                    .or(budgets::year.eq(to_year).and(budgets::month.le(to_month))),
+            )
+            .order_by((budgets::year.asc(), budgets::month.asc()))
+            .load::<Budget>(get_db_conn(pool).deref_mut())
     }
 
+    /// Budget of the month the user is in, going by the user's timezone
     pub fn find_owned_current_month_budget(
         &mut self,
         pool: &DBPool,
         user_id: Uuid,
-    ) -> DBResult<Option<Budget>> {
-        let budget = budgets::table
-            .filter(budgets::month.eq(Utc::now().month() as i16))
-            .filter(budgets::user_id.eq(user_id))
-            .filter(budgets::deleted_at.is_null())
-            .first::<Budget>(get_db_conn(pool).deref_mut());
-
-        budget.optional("budget")
+        timezone: &str,
+    ) -> QueryResult<Option<Budget>> {
+        let today = today_in_timezone(timezone);
+        budgets::table
+            .owned_by(user_id)
+            .filter(budgets::month.eq(today.month() as i16))
+            .filter(budgets::year.eq(today.year() as i16))
+            .first::<Budget>(get_db_conn(pool).deref_mut())
+            .optional()
     }
 }
 
-fn make_budget_title(month: i16, year: i16) -> String {
-    format!("{}, {} Budget", Month::new(month).name().unwrap(), year)
+/// Locks the user's live budget until the transaction ends, so that checking whether it
+/// can take an amount and charging it can't interleave with other spending
+pub fn lock_owned_budget(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    id: Uuid,
+) -> QueryResult<Option<Budget>> {
+    budgets::table
+        .filter(budgets::table.owned_condition(user_id))
+        .filter(budgets::budget_id.eq(id))
+        .for_update()
+        .first::<Budget>(conn)
+        .optional()
+}
+
+/// Adds the amount (negative to release it) to what was used of the budget, relative
+/// to the stored value so that concurrent charges add up instead of overwriting each other
+pub fn charge_budget(conn: &mut PgConnection, id: Uuid, amount: i64) -> QueryResult<Budget> {
+    diesel::update(budgets::table.filter(budgets::budget_id.eq(id)))
+        .set(budgets::amount_used.eq(budgets::amount_used + amount))
+        .get_result::<Budget>(conn)
+}
+
+pub fn make_budget_title(month: i16, year: i16) -> String {
+    format!(
+        "{}, {} Budget",
+        Month::new(month).name().unwrap_or_default(),
+        year
+    )
 }
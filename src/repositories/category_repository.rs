@@ -0,0 +1,187 @@
+use std::ops::DerefMut;
+
+use diesel::sql_types::{Array, Uuid as SqlUuid};
+use diesel::{
+    BoolExpressionMethods, Connection, ExpressionMethods, PgConnection, QueryDsl, QueryResult,
+    RunQueryDsl,
+};
+use uuid::Uuid;
+
+use crate::core::enums::app_error::{AppError, AppResult, OrNotFound};
+use crate::core::helpers::db::{current_timestamp, tenant_sql_query, visible_categories, OwnedBy};
+use crate::core::helpers::get_db_conn;
+use crate::models::category::{Category, CategoryId};
+use crate::models::DBPool;
+use crate::schema::{categories, expenses};
+
+pub struct CategoryRepository;
+
+impl CategoryRepository {
+    /// Default categories along with the user's own ones
+    pub fn list_by_user_id(&mut self, pool: &DBPool, id: Uuid) -> QueryResult<Vec<Category>> {
+        visible_categories(id)
+            .order_by(categories::name.asc())
+            .load::<Category>(get_db_conn(pool).deref_mut())
+    }
+
+    pub fn create(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        parent_id: Option<Uuid>,
+        name: String,
+    ) -> AppResult<Category> {
+        let model = Category {
+            category_id: Uuid::new_v4(),
+            user_id: Some(user_id),
+            parent_id,
+            name,
+            created_at: current_timestamp(),
+            updated_at: current_timestamp(),
+            deleted_at: None,
+        };
+
+        diesel::insert_into(categories::table)
+            .values(model.clone())
+            .execute(get_db_conn(pool).deref_mut())?;
+
+        Ok(model)
+    }
+
+    /// Updates the category, refusing to move it beneath itself or one of its
+    /// sub-categories. The user's categories stay locked until the update is
+    /// done, so two concurrent moves cannot build a cycle together
+    pub fn update(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+        parent_id: Option<Uuid>,
+        name: String,
+    ) -> AppResult<Category> {
+        get_db_conn(pool).transaction(|conn| {
+            categories::table
+                .filter(categories::user_id.eq(user_id))
+                .select(categories::category_id)
+                .for_update()
+                .load::<Uuid>(conn)?;
+
+            categories::table
+                .owned_by(user_id)
+                .filter(categories::category_id.eq(id))
+                .first::<Category>(conn)
+                .or_not_found("category")?;
+
+            if let Some(parent_id) = parent_id {
+                if descendants_of(conn, user_id, vec![id])?.contains(&parent_id) {
+                    return Err(AppError::BadRequest(String::from(
+                        "A category cannot be moved beneath itself or one of its sub-categories",
+                    )));
+                }
+            }
+
+            diesel::update(
+                categories::table
+                    .filter(categories::table.owned_condition(user_id))
+                    .filter(categories::category_id.eq(id)),
+            )
+            .set((
+                categories::dsl::name.eq(name),
+                categories::dsl::parent_id.eq(parent_id),
+                categories::dsl::updated_at.eq(current_timestamp()),
+            ))
+            .get_result::<Category>(conn)
+            .map_err(AppError::from)
+        })
+    }
+
+    /// Deletes the category, its sub-categories and expenses are handed over to its parent
+    pub fn delete(&mut self, pool: &DBPool, id: Uuid, user_id: Uuid) -> AppResult<Category> {
+        let category = self.find_owned_by_id(pool, id, user_id)?;
+
+        get_db_conn(pool).transaction(|conn| {
+            diesel::update(
+                categories::table
+                    .filter(categories::table.owned_condition(user_id))
+                    .filter(categories::category_id.eq(id)),
+            )
+            .set(categories::deleted_at.eq(current_timestamp()))
+            .execute(conn)?;
+
+            diesel::update(
+                categories::table
+                    .filter(categories::table.owned_condition(user_id))
+                    .filter(categories::parent_id.eq(id)),
+            )
+            .set(categories::parent_id.eq(category.parent_id))
+            .execute(conn)?;
+
+            // trashed expenses are handed over too, so they restore into a live category
+            diesel::update(
+                expenses::table
+                    .filter(
+                        expenses::table
+                            .owned_condition(user_id)
+                            .or(expenses::table.trashed_condition(user_id)),
+                    )
+                    .filter(expenses::category_id.eq(id)),
+            )
+            .set(expenses::category_id.eq(category.parent_id))
+            .execute(conn)
+        })?;
+
+        Ok(category)
+    }
+
+    /// Finds a default category or one owned by the user
+    pub fn find_visible_by_id(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> AppResult<Category> {
+        visible_categories(user_id)
+            .filter(categories::category_id.eq(id))
+            .first::<Category>(get_db_conn(pool).deref_mut())
+            .or_not_found("category")
+    }
+
+    pub fn find_owned_by_id(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> AppResult<Category> {
+        categories::table
+            .owned_by(user_id)
+            .filter(categories::category_id.eq(id))
+            .first::<Category>(get_db_conn(pool).deref_mut())
+            .or_not_found("category")
+    }
+
+    /// Ids of the given categories and everything nested beneath them, among
+    /// the ones visible to the user
+    pub fn descendant_ids(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        ids: Vec<Uuid>,
+    ) -> QueryResult<Vec<Uuid>> {
+        descendants_of(get_db_conn(pool).deref_mut(), user_id, ids)
+    }
+}
+
+fn descendants_of(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    ids: Vec<Uuid>,
+) -> QueryResult<Vec<Uuid>> {
+    let rows = tenant_sql_query(
+        include_str!("../../sql/categories/descendants.sql"),
+        user_id,
+    )
+    .bind::<Array<SqlUuid>, _>(ids)
+    .load::<CategoryId>(conn)?;
+
+    Ok(rows.into_iter().map(|row| row.category_id).collect())
+}
@@ -1,153 +1,376 @@
-use crate::core::enums::http_error::{DBResult, ErroneousOption, OptionalResult};
-use crate::core::helpers::db::current_timestamp;
-use crate::core::helpers::db_pagination::{Paginate, PaginationResult};
+use crate::core::enums::app_error::{AppError, AppResult, OrNotFound};
+use crate::core::helpers::date_time::{AggregatePeriods, DateRange};
+use crate::core::helpers::db::{current_timestamp, tenant_sql_query, OwnedBy};
+use crate::core::helpers::db_pagination::{sort_query, PageRequest, PaginationResult};
 use crate::core::helpers::form::{get_nullable_time, get_uuid_from_string};
 use crate::core::helpers::get_db_conn;
 use crate::core::helpers::http::QueryParams;
-use crate::models::expense::{Expense, ExpenseAggregate, ExpenseForm};
+use crate::models::attachment::Attachment;
+use crate::models::budget::{Budget, OverspendPolicy};
+use crate::models::category::CategoryAggregate;
+use crate::models::expense::{
+    Expense, ExpenseAggregate, ExpenseAggregateSummary, ExpenseFilter, ExpenseForm,
+    ExpenseListSummary,
+};
 use crate::models::project::Project;
 use crate::models::DBPool;
-use crate::schema::expenses;
-use crate::schema::projects;
-use chrono::{Datelike, Utc};
+use crate::repositories::budget_repository::{charge_budget, lock_owned_budget};
+use crate::repositories::category_repository::CategoryRepository;
+use crate::repositories::project_repository::lock_owned_project;
+use crate::schema::{attachments, budgets, expense_labels, expenses, project_labels, projects};
+use chrono::{NaiveDateTime, NaiveTime};
+use diesel::dsl::sql;
+use diesel::dsl::{InnerJoin, IntoBoxed};
+use diesel::pg::Pg;
+use diesel::query_builder::{BoxedSqlQuery, SqlQuery};
+use diesel::sql_types::{BigInt, Nullable, Timestamp, Uuid as SqlUuid};
 use diesel::{
-    sql_query, ExpressionMethods, PgTextExpressionMethods, QueryDsl, QueryResult, RunQueryDsl,
+    BoolExpressionMethods, Connection, ExpressionMethods, NullableExpressionMethods,
+    OptionalExtension, PgConnection, PgTextExpressionMethods, QueryDsl, QueryResult, RunQueryDsl,
 };
+use std::collections::BTreeMap;
 use std::ops::DerefMut;
 use uuid::Uuid;
 
 pub struct ExpenseRepository;
 
 impl ExpenseRepository {
-    pub fn list_by_user_id(
+    /// Page of the user's expenses matching the filter, along with the sum of
+    /// every matching expense
+    pub fn list(
         &mut self,
         pool: &DBPool,
-        id: Uuid,
+        user_id: Uuid,
+        filter: &ExpenseFilter,
         mut query_params: QueryParams,
-    ) -> QueryResult<PaginationResult<(Expense, Project)>> {
-        let search_format = format!("%{}%", query_params.get_search_query());
-        expenses::table
-            .inner_join(projects::table)
-            .filter(expenses::user_id.eq(id))
-            .filter(expenses::deleted_at.is_null())
-            .order_by(expenses::created_at.desc())
-            .filter(expenses::narration.ilike(search_format))
-            .paginate(query_params.get_page())
-            .per_page(query_params.get_per_page())
-            .load_and_count_pages::<(Expense, Project)>(get_db_conn(pool).deref_mut())
-    }
+    ) -> AppResult<(PaginationResult<(Expense, Project)>, ExpenseListSummary)> {
+        filter.check_ranges()?;
+        let page = PageRequest::new(&mut query_params, &EXPENSE_SORTS, "-created_at")?;
 
-    pub fn list_by_project_id(
-        &mut self,
-        pool: &DBPool,
-        id: Uuid,
-        mut query_params: QueryParams,
-    ) -> QueryResult<PaginationResult<(Expense, Project)>> {
-        let search_format = format!("%{}%", query_params.get_search_query());
-        expenses::table
-            .inner_join(projects::table)
-            .filter(expenses::project_id.eq(id))
-            .filter(expenses::deleted_at.is_null())
-            .order_by(expenses::created_at.desc())
-            .filter(expenses::narration.ilike(search_format))
-            .paginate(query_params.get_page())
-            .per_page(query_params.get_per_page())
-            .load_and_count_pages::<(Expense, Project)>(get_db_conn(pool).deref_mut())
+        let total_amount = filtered_query(pool, user_id, filter, &mut query_params)?
+            .select(sql::<BigInt>("COALESCE(SUM(expenses.amount), 0)::BIGINT"))
+            .first::<i64>(get_db_conn(pool).deref_mut())?;
+
+        let builder = filtered_query(pool, user_id, filter, &mut query_params)?;
+        let builder = sort_query!(builder, &page, expenses::expense_id, {
+            "created_at" => expenses::created_at => NaiveDateTime,
+            "spent_at" => expenses::spent_at => NaiveDateTime,
+            "amount" => expenses::amount => i64,
+            "narration" => expenses::narration => String,
+        });
+
+        let records = page.load(
+            builder,
+            get_db_conn(pool).deref_mut(),
+            |(expense, _): &(Expense, Project)| {
+                (
+                    expense.expense_id,
+                    serde_json::to_value(expense).unwrap_or_default(),
+                )
+            },
+        )?;
+
+        Ok((records, ExpenseListSummary { total_amount }))
     }
 
-    pub fn list_by_budget_id(
+    /// Next batch of the user's expenses matching the list filters, newest
+    /// first and resuming after the given (created_at, expense_id) cursor
+    pub fn list_batch_by_user_id(
         &mut self,
         pool: &DBPool,
         id: Uuid,
+        filter: &ExpenseFilter,
         mut query_params: QueryParams,
-    ) -> QueryResult<PaginationResult<(Expense, Project)>> {
-        let search_format = format!("%{}%", query_params.get_search_query());
-        expenses::table
-            .inner_join(projects::table)
-            .filter(expenses::budget_id.eq(id))
-            .filter(expenses::deleted_at.is_null())
-            .order_by(expenses::created_at.desc())
-            .filter(expenses::narration.ilike(search_format))
-            .paginate(query_params.get_page())
-            .per_page(query_params.get_per_page())
-            .load_and_count_pages::<(Expense, Project)>(get_db_conn(pool).deref_mut())
+        after: Option<(NaiveDateTime, Uuid)>,
+        limit: i64,
+    ) -> QueryResult<Vec<(Expense, Project)>> {
+        let mut builder = filtered_query(pool, id, filter, &mut query_params)?;
+
+        if let Some((created_at, expense_id)) = after {
+            builder = builder.filter(
+                expenses::created_at.lt(created_at).or(expenses::created_at
+                    .eq(created_at)
+                    .and(expenses::expense_id.lt(expense_id))),
+            );
+        }
+
+        builder
+            .order_by((expenses::created_at.desc(), expenses::expense_id.desc()))
+            .limit(limit)
+            .load::<(Expense, Project)>(get_db_conn(pool).deref_mut())
     }
 
+    /// Records the expense and charges its budget in one transaction, the budget is locked
+    /// while checking it can take the amount under its overspend policy. Returns the
+    /// expense along with the budget before and after the charge
     pub fn create(
         &mut self,
         pool: &DBPool,
         user_id: Uuid,
+        timezone: &str,
         budget_id: Uuid,
         form: ExpenseForm,
-    ) -> Expense {
+    ) -> AppResult<(Expense, Budget, Budget)> {
         let model = Expense {
             expense_id: Uuid::new_v4(),
             user_id,
-            project_id: get_uuid_from_string(form.project_id),
+            project_id: get_uuid_from_string(form.project_id)?,
             budget_id,
             amount: form.amount,
             narration: form.narration,
-            spent_at: get_nullable_time(form.spent_at),
+            spent_at: get_nullable_time(form.spent_at, timezone)?,
             created_at: current_timestamp(),
             updated_at: current_timestamp(),
             deleted_at: None,
+            category_id: form.category_id.map(get_uuid_from_string).transpose()?,
+            import_id: None,
+            external_id: None,
+            external_account: None,
         };
 
-        diesel::insert_into(expenses::table)
-            .values(model.clone())
-            .execute(get_db_conn(pool).deref_mut())
-            .unwrap();
+        get_db_conn(pool).transaction(|conn| {
+            // the project before the budget, in the order deleting a project takes them
+            lock_owned_project(conn, user_id, model.project_id)?
+                .ok_or_else(|| AppError::from("Such project does not exist"))?;
+            let mut budget = lock_owned_budget(conn, user_id, budget_id)?
+                .ok_or_else(|| AppError::from("No budget for current month found"))?;
 
-        model
+            if model.amount > budget.available_amount()
+                && budget.overspend_policy() == OverspendPolicy::Reject
+            {
+                return Err(AppError::from("This expense exceeds current budget"));
+            }
+
+            diesel::insert_into(expenses::table)
+                .values(model.clone())
+                .execute(conn)?;
+
+            let updated = charge_budget(conn, budget_id, model.amount)?;
+            Ok((model, budget, updated))
+        })
     }
 
+    /// Updates the expense and charges its budget the difference between the new and the
+    /// old amount in one transaction, with its project, budget and the expense locked so that
+    /// concurrent edits and deletes see the amount that is actually charged. A raised
+    /// amount has to fit the budget's overspend policy like a new expense. Returns the
+    /// expense along with the budget before and after the charge
     pub fn update(
         &mut self,
         pool: &DBPool,
         id: Uuid,
         user_id: Uuid,
+        timezone: &str,
         form: ExpenseForm,
-    ) -> DBResult<Expense> {
-        let result = self.find_owned_by_id(pool, id, user_id);
+    ) -> AppResult<(Expense, Budget, Budget)> {
+        let project_id = get_uuid_from_string(form.project_id)?;
+        let spent_at = get_nullable_time(form.spent_at, timezone)?;
+        let category_id = form.category_id.map(get_uuid_from_string).transpose()?;
 
-        if result.is_error_or_empty() {
-            return result.get_error_result();
-        }
+        get_db_conn(pool).transaction(|conn| {
+            lock_owned_project(conn, user_id, project_id)?
+                .ok_or_else(|| AppError::from("Such project does not exist"))?;
+
+            // the budget before the expense, in the order deleting a budget takes them
+            let budget_id = expenses::table
+                .filter(expenses::table.owned_condition(user_id))
+                .filter(expenses::expense_id.eq(id))
+                .select(expenses::budget_id)
+                .first::<Uuid>(conn)
+                .or_not_found("expense")?;
 
-        diesel::update(expenses::dsl::expenses.filter(expenses::expense_id.eq(id)))
+            let mut budget = lock_owned_budget(conn, user_id, budget_id)?
+                .ok_or_else(|| AppError::from("The budget of this expense is deleted"))?;
+
+            let expense = expenses::table
+                .filter(expenses::table.owned_condition(user_id))
+                .filter(expenses::expense_id.eq(id))
+                .for_update()
+                .first::<Expense>(conn)
+                .or_not_found("expense")?;
+
+            if expense.budget_id != budget_id {
+                return Err(AppError::Conflict(String::from(
+                    "This expense was moved to another budget meanwhile, please try again",
+                )));
+            }
+
+            let raised_by = form.amount - expense.amount;
+            if raised_by > 0
+                && raised_by > budget.available_amount()
+                && budget.overspend_policy() == OverspendPolicy::Reject
+            {
+                return Err(AppError::from("This expense exceeds current budget"));
+            }
+
+            let updated = diesel::update(
+                expenses::table
+                    .filter(expenses::table.owned_condition(user_id))
+                    .filter(expenses::expense_id.eq(id)),
+            )
             .set((
                 expenses::dsl::amount.eq(form.amount),
                 expenses::dsl::narration.eq(form.narration),
-                expenses::dsl::project_id.eq(get_uuid_from_string(form.project_id)),
-                expenses::dsl::spent_at.eq(get_nullable_time(form.spent_at)),
+                expenses::dsl::project_id.eq(project_id),
+                expenses::dsl::spent_at.eq(spent_at),
+                expenses::dsl::category_id.eq(category_id),
+                expenses::dsl::updated_at.eq(current_timestamp()),
             ))
-            .execute(get_db_conn(pool).deref_mut())
-            .expect("Failed to update expense");
+            .get_result::<Expense>(conn)?;
 
-        Ok(result.unwrap().unwrap())
+            let updated_budget = charge_budget(conn, expense.budget_id, raised_by)?;
+            Ok((updated, budget, updated_budget))
+        })
     }
 
-    pub fn delete(&mut self, pool: &DBPool, id: Uuid, user_id: Uuid) -> DBResult<Expense> {
-        let result = self.find_owned_by_id(pool, id, user_id);
+    /// Moves the expense to the trash and releases its amount from the budget
+    pub fn delete(&mut self, pool: &DBPool, id: Uuid, user_id: Uuid) -> AppResult<Expense> {
+        get_db_conn(pool).transaction(|conn| {
+            trash_expenses(conn, user_id, vec![id], current_timestamp())?
+                .pop()
+                .ok_or_else(|| AppError::EntityNotFound(String::from("expense")))
+        })
+    }
 
-        if result.is_error_or_empty() {
-            return result.get_error_result();
-        }
+    /// Takes the expense out of the trash and charges its budget again, the budget is
+    /// locked while checking it can take the amount so that concurrent spending can't
+    /// push it past a rejecting policy, returns the budget before and after the charge
+    pub fn restore(&mut self, pool: &DBPool, expense: &Expense) -> AppResult<(Budget, Budget)> {
+        get_db_conn(pool).transaction(|conn| {
+            lock_owned_project(conn, expense.user_id, expense.project_id)?.ok_or_else(|| {
+                AppError::from("The project of this expense is deleted, restore it first")
+            })?;
+            let mut budget = lock_owned_budget(conn, expense.user_id, expense.budget_id)?
+                .ok_or_else(|| {
+                    AppError::from("The budget of this expense is deleted, restore it first")
+                })?;
+
+            if expense.amount > budget.available_amount()
+                && budget.overspend_policy() == OverspendPolicy::Reject
+            {
+                return Err(AppError::from("Restoring this expense exceeds its budget"));
+            }
+
+            // a concurrent restore may have taken it out of the trash already
+            let restored =
+                diesel::update(expenses::table.filter(expenses::expense_id.eq(expense.expense_id)))
+                    .filter(expenses::table.trashed_condition(expense.user_id))
+                    .set((
+                        expenses::deleted_at.eq(None::<NaiveDateTime>),
+                        expenses::updated_at.eq(current_timestamp()),
+                    ))
+                    .get_result::<Expense>(conn)
+                    .optional()?;
 
-        diesel::update(expenses::dsl::expenses.filter(expenses::expense_id.eq(id)))
-            .set(expenses::dsl::deleted_at.eq(current_timestamp()))
-            .execute(get_db_conn(pool).deref_mut())
-            .expect("Failed to delete expense");
+            let restored = match restored {
+                Some(restored) => restored,
+                None => return Ok((budget.clone(), budget)),
+            };
 
-        Ok(result.unwrap_entity())
+            let updated = charge_budget(conn, restored.budget_id, restored.amount)?;
+            Ok((budget, updated))
+        })
     }
 
-    #[allow(dead_code)]
-    pub fn find_by_id(&mut self, pool: &DBPool, id: Uuid) -> QueryResult<Expense> {
+    /// Permanently deletes the expense along with its labels and attachments,
+    /// the attachments are returned so that their stored files can be removed
+    pub fn purge(&mut self, pool: &DBPool, id: Uuid) -> QueryResult<Vec<Attachment>> {
+        get_db_conn(pool).transaction(|conn| {
+            diesel::delete(expense_labels::table.filter(expense_labels::expense_id.eq(id)))
+                .execute(conn)?;
+
+            let attachments =
+                diesel::delete(attachments::table.filter(attachments::expense_id.eq(id)))
+                    .get_results::<Attachment>(conn)?;
+
+            diesel::delete(expenses::table.filter(expenses::expense_id.eq(id))).execute(conn)?;
+
+            Ok(attachments)
+        })
+    }
+
+    pub fn find_trashed_by_id(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> AppResult<Expense> {
         expenses::table
+            .trashed_by(user_id)
             .filter(expenses::expense_id.eq(id))
-            .filter(expenses::deleted_at.is_null())
             .first::<Expense>(get_db_conn(pool).deref_mut())
+            .or_not_found("expense")
+    }
+
+    /// Ids of every expense (of any user) that was deleted before the given time
+    pub fn list_ids_deleted_before(
+        &mut self,
+        pool: &DBPool,
+        time: NaiveDateTime,
+    ) -> QueryResult<Vec<Uuid>> {
+        expenses::table
+            .filter(expenses::deleted_at.lt(time))
+            .select(expenses::expense_id)
+            .load::<Uuid>(get_db_conn(pool).deref_mut())
+    }
+
+    /// Every live expense of the user, oldest first
+    pub fn list_all_by_user_id(&mut self, pool: &DBPool, id: Uuid) -> QueryResult<Vec<Expense>> {
+        expenses::table
+            .owned_by(id)
+            .order_by(expenses::created_at.asc())
+            .load::<Expense>(get_db_conn(pool).deref_mut())
+    }
+
+    /// Every live expense of the user's budget with its project, in the order they were spent
+    pub fn list_all_by_budget_id(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> QueryResult<Vec<(Expense, Project)>> {
+        expenses::table
+            .inner_join(projects::table)
+            .filter(expenses::table.owned_condition(user_id))
+            .filter(expenses::budget_id.eq(id))
+            .order_by((expenses::spent_at.asc(), expenses::created_at.asc()))
+            .load::<(Expense, Project)>(get_db_conn(pool).deref_mut())
+    }
+
+    /// Live expenses of the user spent within the given (half-open) range
+    pub fn list_by_spent_period(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        range: DateRange,
+    ) -> QueryResult<Vec<Expense>> {
+        expenses::table
+            .owned_by(user_id)
+            .filter(expenses::spent_at.ge(range.start))
+            .filter(expenses::spent_at.lt(range.end))
+            .load::<Expense>(get_db_conn(pool).deref_mut())
+    }
+
+    /// Bank accounts and transaction ids (OFX ACCTID and FITID) of the user's expenses
+    /// whose ids are among the given ones, trashed expenses included as they still hold their ids
+    pub fn list_external_ids(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        ids: Vec<String>,
+    ) -> QueryResult<Vec<(Option<String>, String)>> {
+        expenses::table
+            .filter(
+                expenses::table
+                    .owned_condition(user_id)
+                    .or(expenses::table.trashed_condition(user_id)),
+            )
+            .filter(expenses::external_id.eq_any(ids))
+            .select((
+                expenses::external_account,
+                expenses::external_id.assume_not_null(),
+            ))
+            .load::<(Option<String>, String)>(get_db_conn(pool).deref_mut())
     }
 
     pub fn find_owned_by_id(
@@ -155,29 +378,264 @@ impl ExpenseRepository {
         pool: &DBPool,
         id: Uuid,
         user_id: Uuid,
-    ) -> DBResult<Option<Expense>> {
+    ) -> AppResult<Expense> {
         expenses::table
+            .owned_by(user_id)
             .filter(expenses::expense_id.eq(id))
-            .filter(expenses::user_id.eq(user_id))
-            .filter(expenses::deleted_at.is_null())
             .first::<Expense>(get_db_conn(pool).deref_mut())
-            .optional("expense")
+            .or_not_found("expense")
     }
 
     pub fn fetch_aggregate_by_user_id(
         &mut self,
         pool: &DBPool,
         user_id: Uuid,
-    ) -> QueryResult<Vec<ExpenseAggregate>> {
-        let mut sql = format!("SELECT (SELECT SUM(amount) FROM expenses WHERE EXTRACT(YEAR FROM expenses.spent_at) = {} AND expenses.user_id = '{}')::VARCHAR AS year_expenses", Utc::now().year(), user_id.clone());
-        sql += &*format!(", (SELECT SUM(amount) FROM expenses WHERE EXTRACT(MONTH FROM expenses.spent_at) = {} AND expenses.user_id = '{}')::VARCHAR AS month_expenses", Utc::now().month(), user_id.clone());
-        sql += &*format!(", (SELECT SUM(amount) FROM expenses WHERE EXTRACT(WEEK FROM expenses.spent_at) = EXTRACT(WEEK FROM NOW()) AND expenses.user_id = '{}')::VARCHAR AS week_expenses", user_id.clone());
-        sql += &*format!(", (SELECT SUM(amount) FROM expenses WHERE EXTRACT(DAY FROM expenses.spent_at) = {} AND expenses.user_id = '{}')::VARCHAR AS today_expenses", Utc::now().day(), user_id.clone());
-
-        sql_query(sql)
-            // .bind::<Integer, _>(Utc::now().year())
-            // .bind::<Integer, _>(Utc::now().month() as i32)
-            // .bind::<Integer, _>(Utc::now().day() as i32)
-            .load::<ExpenseAggregate>(get_db_conn(pool).deref_mut())
+        timezone: &str,
+    ) -> QueryResult<ExpenseAggregateSummary> {
+        fetch_expense_aggregate(pool, user_id, None, AggregatePeriods::in_timezone(timezone))
+    }
+}
+
+type ExpenseListQuery<'a> = IntoBoxed<'a, InnerJoin<expenses::table, projects::table>, Pg>;
+
+const EXPENSE_SORTS: [&str; 4] = ["created_at", "spent_at", "amount", "narration"];
+
+/// The user's expenses narrowed down by the filter and the search, label and
+/// category query params, shared by the expense lists and exports
+fn filtered_query<'a>(
+    pool: &DBPool,
+    user_id: Uuid,
+    filter: &ExpenseFilter,
+    query_params: &mut QueryParams,
+) -> QueryResult<ExpenseListQuery<'a>> {
+    let search_format = format!("%{}%", query_params.get_search_query());
+    let mut builder = match filter.include_deleted {
+        true => expenses::table
+            .inner_join(projects::table)
+            .filter(
+                expenses::table
+                    .owned_condition(user_id)
+                    .or(expenses::table.trashed_condition(user_id)),
+            )
+            .into_boxed(),
+        false => expenses::table
+            .inner_join(projects::table)
+            .filter(expenses::table.owned_condition(user_id))
+            .into_boxed(),
+    };
+
+    builder = builder.filter(expenses::narration.ilike(search_format));
+
+    if let Some(from) = filter.spent_from {
+        builder = builder.filter(expenses::spent_at.ge(from.and_time(NaiveTime::MIN)));
+    }
+
+    if let Some(to) = filter.spent_to.and_then(|to| to.succ_opt()) {
+        builder = builder.filter(expenses::spent_at.lt(to.and_time(NaiveTime::MIN)));
+    }
+
+    if let Some(min_amount) = filter.min_amount {
+        builder = builder.filter(expenses::amount.ge(min_amount));
+    }
+
+    if let Some(max_amount) = filter.max_amount {
+        builder = builder.filter(expenses::amount.le(max_amount));
+    }
+
+    if !filter.project_id.is_empty() {
+        builder = builder.filter(expenses::project_id.eq_any(filter.project_id.clone()));
+    }
+
+    if let Some(budget_id) = filter.budget_id {
+        builder = builder.filter(expenses::budget_id.eq(budget_id));
+    }
+
+    if !query_params.labels.is_empty() {
+        builder = builder.filter(
+            expenses::expense_id
+                .eq_any(
+                    expense_labels::table
+                        .select(expense_labels::expense_id)
+                        .filter(expense_labels::table.owned_condition(user_id))
+                        .filter(expense_labels::label_id.eq_any(query_params.labels.clone())),
+                )
+                .or(expenses::project_id.eq_any(
+                    project_labels::table
+                        .select(project_labels::project_id)
+                        .filter(project_labels::table.owned_condition(user_id))
+                        .filter(project_labels::label_id.eq_any(query_params.labels.clone())),
+                )),
+        );
+    }
+
+    if !query_params.categories.is_empty() {
+        let category_ids =
+            CategoryRepository.descendant_ids(pool, user_id, query_params.categories.clone())?;
+        builder = builder.filter(expenses::category_id.eq_any(category_ids));
+    }
+
+    Ok(builder)
+}
+
+/// Moves the user's live expenses among the given ones to the trash and releases their
+/// amounts from their budgets, on the caller's connection so that it can be part of a
+/// larger transaction. Only the rows actually trashed here are released and returned,
+/// expenses a concurrent delete got to first are skipped
+pub fn trash_expenses(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    ids: Vec<Uuid>,
+    deleted_at: NaiveDateTime,
+) -> QueryResult<Vec<Expense>> {
+    if ids.is_empty() {
+        return Ok(vec![]);
     }
+
+    // the budgets are locked before the expenses, like deleting a budget does, in a
+    // stable order so that concurrent trashing can't deadlock
+    let budget_ids = expenses::table
+        .filter(expenses::expense_id.eq_any(&ids))
+        .select(expenses::budget_id)
+        .distinct()
+        .load::<Uuid>(conn)?;
+    budgets::table
+        .filter(budgets::budget_id.eq_any(budget_ids))
+        .order_by(budgets::budget_id)
+        .select(budgets::budget_id)
+        .for_update()
+        .load::<Uuid>(conn)?;
+
+    let trashed = diesel::update(
+        expenses::table
+            .filter(expenses::table.owned_condition(user_id))
+            .filter(expenses::expense_id.eq_any(ids)),
+    )
+    .set(expenses::deleted_at.eq(deleted_at))
+    .get_results::<Expense>(conn)?;
+
+    // one update per budget, in the order they were locked
+    let mut released: BTreeMap<Uuid, i64> = BTreeMap::new();
+    for expense in trashed.iter() {
+        *released.entry(expense.budget_id).or_default() += expense.amount;
+    }
+
+    for (budget_id, amount) in released {
+        diesel::update(budgets::table.filter(budgets::budget_id.eq(budget_id)))
+            .set(budgets::amount_used.eq(budgets::amount_used - amount))
+            .execute(conn)?;
+    }
+
+    Ok(trashed)
+}
+
+/// Takes the user's given expenses out of the trash and charges their budgets again,
+/// the ones of a deleted budget stay in the trash. The budgets are locked lowest id
+/// first and have to take the amounts under their overspend policy, returns every
+/// charged budget before and after the charge
+pub fn untrash_expenses(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    ids: Vec<Uuid>,
+) -> AppResult<Vec<(Budget, Budget)>> {
+    if ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let budget_ids = expenses::table
+        .filter(expenses::expense_id.eq_any(&ids))
+        .select(expenses::budget_id)
+        .distinct()
+        .load::<Uuid>(conn)?;
+    let budgets = budgets::table
+        .filter(budgets::table.owned_condition(user_id))
+        .filter(budgets::budget_id.eq_any(budget_ids))
+        .order_by(budgets::budget_id)
+        .for_update()
+        .load::<Budget>(conn)?;
+    let live_budget_ids: Vec<Uuid> = budgets.iter().map(|budget| budget.budget_id).collect();
+
+    let restored = diesel::update(
+        expenses::table
+            .filter(expenses::table.trashed_condition(user_id))
+            .filter(expenses::expense_id.eq_any(ids))
+            .filter(expenses::budget_id.eq_any(live_budget_ids)),
+    )
+    .set((
+        expenses::deleted_at.eq(None::<NaiveDateTime>),
+        expenses::updated_at.eq(current_timestamp()),
+    ))
+    .get_results::<Expense>(conn)?;
+
+    let mut charged = vec![];
+    for mut budget in budgets {
+        let amount: i64 = restored
+            .iter()
+            .filter(|expense| expense.budget_id == budget.budget_id)
+            .map(|expense| expense.amount)
+            .sum();
+
+        if amount == 0 {
+            continue;
+        }
+
+        if amount > budget.available_amount()
+            && budget.overspend_policy() == OverspendPolicy::Reject
+        {
+            return Err(AppError::BadRequest(format!(
+                "Restoring the expenses exceeds the {}",
+                budget.title
+            )));
+        }
+
+        let updated = charge_budget(conn, budget.budget_id, amount)?;
+        charged.push((budget, updated));
+    }
+
+    Ok(charged)
+}
+
+/// Sums the user's live expenses (optionally narrowed down to a project) over
+/// the year, month, week and day ranges of the given periods, both overall and per top-level category
+pub fn fetch_expense_aggregate(
+    pool: &DBPool,
+    user_id: Uuid,
+    project_id: Option<Uuid>,
+    periods: AggregatePeriods,
+) -> QueryResult<ExpenseAggregateSummary> {
+    let mut conn = get_db_conn(pool);
+
+    let totals = aggregate_query(
+        include_str!("../../sql/aggregates/expenses.sql"),
+        user_id,
+        project_id,
+        &periods,
+    )
+    .get_result::<ExpenseAggregate>(conn.deref_mut())?;
+
+    let categories = aggregate_query(
+        include_str!("../../sql/aggregates/expense_categories.sql"),
+        user_id,
+        project_id,
+        &periods,
+    )
+    .load::<CategoryAggregate>(conn.deref_mut())?;
+
+    Ok(ExpenseAggregateSummary { totals, categories })
+}
+
+fn aggregate_query<'a>(
+    sql: &str,
+    user_id: Uuid,
+    project_id: Option<Uuid>,
+    periods: &AggregatePeriods,
+) -> BoxedSqlQuery<'a, Pg, SqlQuery> {
+    let mut query = tenant_sql_query(sql, user_id).bind::<Nullable<SqlUuid>, _>(project_id);
+
+    for range in [&periods.year, &periods.month, &periods.week, &periods.today] {
+        query = query
+            .bind::<Timestamp, _>(range.start)
+            .bind::<Timestamp, _>(range.end);
+    }
+
+    query
 }
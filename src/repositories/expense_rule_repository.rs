@@ -0,0 +1,194 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::DerefMut;
+
+use diesel::{Connection, ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::core::enums::app_error::{AppResult, OrNotFound};
+use crate::core::helpers::db::{current_timestamp, OwnedBy, INSERT_CHUNK_SIZE};
+use crate::core::helpers::form::get_uuid_from_string;
+use crate::core::helpers::get_db_conn;
+use crate::models::expense_rule::{ExpenseRule, ExpenseRuleForm};
+use crate::models::label::{ExpenseLabel, LabelModule};
+use crate::models::DBPool;
+use crate::schema::{expense_labels, expense_rules, expenses, labels};
+
+pub struct ExpenseRuleRepository;
+
+impl ExpenseRuleRepository {
+    /// User's rules in the order they are evaluated
+    pub fn list_by_user_id(&mut self, pool: &DBPool, id: Uuid) -> QueryResult<Vec<ExpenseRule>> {
+        expense_rules::table
+            .owned_by(id)
+            .order_by((
+                expense_rules::priority.asc(),
+                expense_rules::created_at.asc(),
+            ))
+            .load::<ExpenseRule>(get_db_conn(pool).deref_mut())
+    }
+
+    pub fn create(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        form: ExpenseRuleForm,
+    ) -> AppResult<ExpenseRule> {
+        let model = ExpenseRule {
+            expense_rule_id: Uuid::new_v4(),
+            user_id,
+            name: form.name,
+            priority: form.priority.unwrap_or(0),
+            narration_contains: form.narration_contains,
+            narration_pattern: form.narration_pattern,
+            min_amount: form.min_amount,
+            max_amount: form.max_amount,
+            category_id: form.category_id.map(get_uuid_from_string).transpose()?,
+            label_ids: form
+                .label_ids
+                .unwrap_or_default()
+                .into_iter()
+                .map(get_uuid_from_string)
+                .collect::<AppResult<_>>()?,
+            created_at: current_timestamp(),
+            updated_at: current_timestamp(),
+            deleted_at: None,
+        };
+
+        diesel::insert_into(expense_rules::table)
+            .values(model.clone())
+            .execute(get_db_conn(pool).deref_mut())?;
+
+        Ok(model)
+    }
+
+    pub fn update(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+        form: ExpenseRuleForm,
+    ) -> AppResult<ExpenseRule> {
+        let rule = self.find_owned_by_id(pool, id, user_id)?;
+
+        let label_ids: Vec<Uuid> = form
+            .label_ids
+            .unwrap_or_default()
+            .into_iter()
+            .map(get_uuid_from_string)
+            .collect::<AppResult<_>>()?;
+
+        diesel::update(
+            expense_rules::table
+                .filter(expense_rules::table.owned_condition(user_id))
+                .filter(expense_rules::expense_rule_id.eq(id)),
+        )
+        .set((
+            expense_rules::dsl::name.eq(form.name),
+            expense_rules::dsl::priority.eq(form.priority.unwrap_or(0)),
+            expense_rules::dsl::narration_contains.eq(form.narration_contains),
+            expense_rules::dsl::narration_pattern.eq(form.narration_pattern),
+            expense_rules::dsl::min_amount.eq(form.min_amount),
+            expense_rules::dsl::max_amount.eq(form.max_amount),
+            expense_rules::dsl::category_id
+                .eq(form.category_id.map(get_uuid_from_string).transpose()?),
+            expense_rules::dsl::label_ids.eq(label_ids),
+            expense_rules::dsl::updated_at.eq(current_timestamp()),
+        ))
+        .execute(get_db_conn(pool).deref_mut())?;
+
+        Ok(rule)
+    }
+
+    pub fn delete(&mut self, pool: &DBPool, id: Uuid, user_id: Uuid) -> AppResult<ExpenseRule> {
+        let rule = self.find_owned_by_id(pool, id, user_id)?;
+
+        diesel::update(
+            expense_rules::table
+                .filter(expense_rules::table.owned_condition(user_id))
+                .filter(expense_rules::expense_rule_id.eq(id)),
+        )
+        .set(expense_rules::dsl::deleted_at.eq(current_timestamp()))
+        .execute(get_db_conn(pool).deref_mut())?;
+
+        Ok(rule)
+    }
+
+    pub fn find_owned_by_id(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> AppResult<ExpenseRule> {
+        expense_rules::table
+            .owned_by(user_id)
+            .filter(expense_rules::expense_rule_id.eq(id))
+            .first::<ExpenseRule>(get_db_conn(pool).deref_mut())
+            .or_not_found("expense rule")
+    }
+
+    /// Writes the outcome of running the rules over existing expenses in one transaction:
+    /// a single update per category and a bulk insert of the labels not attached yet,
+    /// skipping labels deleted since the rules were saved
+    pub fn apply_matches(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        categories: HashMap<Uuid, Vec<Uuid>>,
+        label_pairs: HashSet<(Uuid, Uuid)>,
+    ) -> QueryResult<()> {
+        get_db_conn(pool).transaction(|conn| {
+            for (category_id, expense_ids) in categories {
+                diesel::update(expenses::table.filter(expenses::expense_id.eq_any(expense_ids)))
+                    .filter(expenses::table.owned_condition(user_id))
+                    .set((
+                        expenses::category_id.eq(category_id),
+                        expenses::updated_at.eq(current_timestamp()),
+                    ))
+                    .execute(conn)?;
+            }
+
+            if label_pairs.is_empty() {
+                return Ok(());
+            }
+
+            let live_labels: HashSet<Uuid> = labels::table
+                .owned_by(user_id)
+                .filter(labels::module.eq(LabelModule::Expenses.as_str()))
+                .select(labels::label_id)
+                .load::<Uuid>(conn)?
+                .into_iter()
+                .collect();
+
+            let expense_ids: HashSet<Uuid> = label_pairs.iter().map(|(id, _)| *id).collect();
+            let attached: HashSet<(Uuid, Uuid)> = expense_labels::table
+                .owned_by(user_id)
+                .filter(expense_labels::expense_id.eq_any(expense_ids))
+                .select((expense_labels::expense_id, expense_labels::label_id))
+                .load::<(Uuid, Uuid)>(conn)?
+                .into_iter()
+                .collect();
+
+            let records: Vec<ExpenseLabel> = label_pairs
+                .into_iter()
+                .filter(|pair| live_labels.contains(&pair.1) && !attached.contains(pair))
+                .map(|(expense_id, label_id)| ExpenseLabel {
+                    expense_label_id: Uuid::new_v4(),
+                    user_id,
+                    expense_id,
+                    label_id,
+                    created_at: current_timestamp(),
+                    updated_at: current_timestamp(),
+                    deleted_at: None,
+                })
+                .collect();
+
+            for chunk in records.chunks(INSERT_CHUNK_SIZE) {
+                diesel::insert_into(expense_labels::table)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+use std::ops::DerefMut;
+
+use chrono::NaiveDateTime;
+use diesel::{Connection, ExpressionMethods, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::core::enums::app_error::{AppError, AppResult, OrNotFound};
+use crate::core::helpers::db::{current_timestamp, OwnedBy, INSERT_CHUNK_SIZE};
+use crate::core::helpers::db_pagination::{sort_query, PageRequest, PaginationResult};
+use crate::core::helpers::get_db_conn;
+use crate::core::helpers::http::QueryParams;
+use crate::models::budget::{Budget, OverspendPolicy};
+use crate::models::expense::Expense;
+use crate::models::import::{Import, IMPORT_STATUS_COMMITTED, IMPORT_STATUS_PENDING};
+use crate::models::income::Income;
+use crate::models::DBPool;
+use crate::repositories::project_repository::lock_owned_project;
+use crate::schema::{budgets, expenses, imports, incomes};
+
+const IMPORT_SORTS: [&str; 2] = ["created_at", "file_name"];
+
+pub struct ImportRepository;
+
+impl ImportRepository {
+    pub fn list_by_user_id(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        mut query_params: QueryParams,
+    ) -> AppResult<PaginationResult<Import>> {
+        let page = PageRequest::new(&mut query_params, &IMPORT_SORTS, "-created_at")?;
+        let builder = imports::table.owned_by(id);
+
+        let builder = sort_query!(builder, &page, imports::import_id, {
+            "created_at" => imports::created_at => NaiveDateTime,
+            "file_name" => imports::file_name => String,
+        });
+
+        page.load(builder, get_db_conn(pool).deref_mut(), |import: &Import| {
+            (
+                import.import_id,
+                serde_json::to_value(import).unwrap_or_default(),
+            )
+        })
+    }
+
+    pub fn create(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        source: &str,
+        file_name: String,
+        content: String,
+    ) -> AppResult<Import> {
+        let model = Import {
+            import_id: Uuid::new_v4(),
+            user_id,
+            source: source.to_string(),
+            file_name,
+            content,
+            status: IMPORT_STATUS_PENDING.to_string(),
+            committed_at: None,
+            created_at: current_timestamp(),
+            updated_at: current_timestamp(),
+            deleted_at: None,
+        };
+
+        diesel::insert_into(imports::table)
+            .values(model.clone())
+            .execute(get_db_conn(pool).deref_mut())?;
+
+        Ok(model)
+    }
+
+    /// Records the expenses and incomes, charges the budgets and marks the
+    /// import as committed, all or nothing. The import and the budgets are locked
+    /// so that concurrent commits or spending can't record it twice or push a
+    /// budget that rejects overspending over its limit. Budgets are locked in
+    /// the order of their ids so that two commits can't deadlock each other
+    pub fn commit(
+        &mut self,
+        pool: &DBPool,
+        import: &Import,
+        expenses: Vec<Expense>,
+        incomes: Vec<Income>,
+        budget_usage: &BTreeMap<Uuid, i64>,
+    ) -> AppResult<Import> {
+        get_db_conn(pool).transaction(|conn| {
+            let locked = imports::table
+                .filter(imports::table.owned_condition(import.user_id))
+                .filter(imports::import_id.eq(import.import_id))
+                .for_update()
+                .first::<Import>(conn)?;
+
+            if !locked.is_pending() {
+                return Err(AppError::Conflict(String::from(
+                    "This import has already been committed",
+                )));
+            }
+
+            // the projects before the budgets, in the order deleting a project takes them
+            let mut project_ids: Vec<Uuid> = expenses
+                .iter()
+                .map(|expense| expense.project_id)
+                .chain(incomes.iter().map(|income| income.project_id))
+                .collect();
+            project_ids.sort();
+            project_ids.dedup();
+            for project_id in project_ids {
+                lock_owned_project(conn, import.user_id, project_id)?.ok_or_else(|| {
+                    AppError::from("A project of the import was deleted, preview it again")
+                })?;
+            }
+
+            for (budget_id, amount) in budget_usage {
+                let mut budget = budgets::table
+                    .filter(budgets::table.owned_condition(import.user_id))
+                    .filter(budgets::budget_id.eq(budget_id))
+                    .for_update()
+                    .first::<Budget>(conn)
+                    .or_not_found("budget")?;
+
+                if *amount > budget.available_amount()
+                    && budget.overspend_policy() == OverspendPolicy::Reject
+                {
+                    return Err(AppError::BadRequest(format!(
+                        "The import exceeds the {}, preview it again",
+                        budget.title
+                    )));
+                }
+
+                diesel::update(budgets::table.filter(budgets::budget_id.eq(budget_id)))
+                    .filter(budgets::table.owned_condition(import.user_id))
+                    .set(budgets::amount_used.eq(budgets::amount_used + amount))
+                    .execute(conn)?;
+            }
+
+            for chunk in expenses.chunks(INSERT_CHUNK_SIZE) {
+                diesel::insert_into(expenses::table)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+
+            for chunk in incomes.chunks(INSERT_CHUNK_SIZE) {
+                diesel::insert_into(incomes::table)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+
+            Ok(
+                diesel::update(imports::table.filter(imports::import_id.eq(import.import_id)))
+                    .filter(imports::table.owned_condition(import.user_id))
+                    .set((
+                        imports::status.eq(IMPORT_STATUS_COMMITTED),
+                        imports::committed_at.eq(current_timestamp()),
+                        imports::updated_at.eq(current_timestamp()),
+                    ))
+                    .get_result::<Import>(conn)?,
+            )
+        })
+    }
+
+    pub fn delete(&mut self, pool: &DBPool, id: Uuid, user_id: Uuid) -> AppResult<Import> {
+        let import = self.find_owned_by_id(pool, id, user_id)?;
+
+        diesel::update(
+            imports::table
+                .filter(imports::table.owned_condition(user_id))
+                .filter(imports::import_id.eq(id)),
+        )
+        .set(imports::dsl::deleted_at.eq(current_timestamp()))
+        .execute(get_db_conn(pool).deref_mut())?;
+
+        Ok(import)
+    }
+
+    pub fn find_owned_by_id(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> AppResult<Import> {
+        imports::table
+            .owned_by(user_id)
+            .filter(imports::import_id.eq(id))
+            .first::<Import>(get_db_conn(pool).deref_mut())
+            .or_not_found("import")
+    }
+}
@@ -0,0 +1,183 @@
+use std::ops::DerefMut;
+
+use chrono::NaiveDateTime;
+use diesel::{
+    BoolExpressionMethods, Connection, ExpressionMethods, NullableExpressionMethods,
+    PgTextExpressionMethods, QueryDsl, QueryResult, RunQueryDsl,
+};
+use uuid::Uuid;
+
+use crate::core::enums::app_error::{AppError, AppResult, OrNotFound};
+use crate::core::helpers::date_time::DateRange;
+use crate::core::helpers::db::{current_timestamp, OwnedBy};
+use crate::core::helpers::db_pagination::{sort_query, PageRequest, PaginationResult};
+use crate::core::helpers::form::{get_nullable_time, get_uuid_from_string};
+use crate::core::helpers::get_db_conn;
+use crate::core::helpers::http::QueryParams;
+use crate::models::income::{Income, IncomeForm};
+use crate::models::DBPool;
+use crate::repositories::project_repository::lock_owned_project;
+use crate::schema::incomes;
+
+const INCOME_SORTS: [&str; 3] = ["received_at", "amount", "created_at"];
+
+pub struct IncomeRepository;
+
+impl IncomeRepository {
+    pub fn list_by_user_id(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        mut query_params: QueryParams,
+    ) -> AppResult<PaginationResult<Income>> {
+        let page = PageRequest::new(&mut query_params, &INCOME_SORTS, "-received_at")?;
+        let search_format = format!("%{}%", query_params.get_search_query());
+        let builder = incomes::table
+            .owned_by(id)
+            .filter(incomes::narration.ilike(search_format));
+
+        let builder = sort_query!(builder, &page, incomes::income_id, {
+            "received_at" => incomes::received_at => NaiveDateTime,
+            "amount" => incomes::amount => i64,
+            "created_at" => incomes::created_at => NaiveDateTime,
+        });
+
+        page.load(builder, get_db_conn(pool).deref_mut(), |income: &Income| {
+            (
+                income.income_id,
+                serde_json::to_value(income).unwrap_or_default(),
+            )
+        })
+    }
+
+    pub fn create(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        timezone: &str,
+        form: IncomeForm,
+    ) -> AppResult<Income> {
+        let model = Income {
+            income_id: Uuid::new_v4(),
+            user_id,
+            project_id: get_uuid_from_string(form.project_id)?,
+            amount: form.amount,
+            narration: form.narration,
+            received_at: get_nullable_time(form.received_at, timezone)?,
+            import_id: None,
+            external_id: None,
+            external_account: None,
+            created_at: current_timestamp(),
+            updated_at: current_timestamp(),
+            deleted_at: None,
+        };
+
+        get_db_conn(pool).transaction(|conn| {
+            lock_owned_project(conn, user_id, model.project_id)?
+                .ok_or_else(|| AppError::from("Such project does not exist"))?;
+
+            diesel::insert_into(incomes::table)
+                .values(model.clone())
+                .execute(conn)?;
+
+            Ok(model)
+        })
+    }
+
+    pub fn update(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+        timezone: &str,
+        form: IncomeForm,
+    ) -> AppResult<Income> {
+        let income = self.find_owned_by_id(pool, id, user_id)?;
+        let project_id = get_uuid_from_string(form.project_id)?;
+        let received_at = get_nullable_time(form.received_at, timezone)?;
+
+        get_db_conn(pool).transaction(|conn| {
+            lock_owned_project(conn, user_id, project_id)?
+                .ok_or_else(|| AppError::from("Such project does not exist"))?;
+
+            diesel::update(
+                incomes::table
+                    .filter(incomes::table.owned_condition(user_id))
+                    .filter(incomes::income_id.eq(id)),
+            )
+            .set((
+                incomes::dsl::amount.eq(form.amount),
+                incomes::dsl::narration.eq(form.narration),
+                incomes::dsl::project_id.eq(project_id),
+                incomes::dsl::received_at.eq(received_at),
+                incomes::dsl::updated_at.eq(current_timestamp()),
+            ))
+            .execute(conn)?;
+
+            Ok(income)
+        })
+    }
+
+    pub fn delete(&mut self, pool: &DBPool, id: Uuid, user_id: Uuid) -> AppResult<Income> {
+        let income = self.find_owned_by_id(pool, id, user_id)?;
+
+        diesel::update(
+            incomes::table
+                .filter(incomes::table.owned_condition(user_id))
+                .filter(incomes::income_id.eq(id)),
+        )
+        .set(incomes::dsl::deleted_at.eq(current_timestamp()))
+        .execute(get_db_conn(pool).deref_mut())?;
+
+        Ok(income)
+    }
+
+    pub fn find_owned_by_id(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> AppResult<Income> {
+        incomes::table
+            .owned_by(user_id)
+            .filter(incomes::income_id.eq(id))
+            .first::<Income>(get_db_conn(pool).deref_mut())
+            .or_not_found("income")
+    }
+
+    /// Live incomes of the user received within the given (half-open) range
+    pub fn list_by_received_period(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        range: DateRange,
+    ) -> QueryResult<Vec<Income>> {
+        incomes::table
+            .owned_by(user_id)
+            .filter(incomes::received_at.ge(range.start))
+            .filter(incomes::received_at.lt(range.end))
+            .load::<Income>(get_db_conn(pool).deref_mut())
+    }
+
+    /// Bank accounts and transaction ids (OFX ACCTID and FITID) of the user's incomes
+    /// whose ids are among the given ones, trashed incomes included as they still hold their ids
+    pub fn list_external_ids(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        ids: Vec<String>,
+    ) -> QueryResult<Vec<(Option<String>, String)>> {
+        incomes::table
+            .filter(
+                incomes::table
+                    .owned_condition(user_id)
+                    .or(incomes::table.trashed_condition(user_id)),
+            )
+            .filter(incomes::external_id.eq_any(ids))
+            .select((
+                incomes::external_account,
+                incomes::external_id.assume_not_null(),
+            ))
+            .load::<(Option<String>, String)>(get_db_conn(pool).deref_mut())
+    }
+}
@@ -0,0 +1,306 @@
+use std::ops::DerefMut;
+
+use chrono::NaiveDateTime;
+use diesel::{
+    Connection, ExpressionMethods, PgTextExpressionMethods, QueryDsl, QueryResult, RunQueryDsl,
+};
+use uuid::Uuid;
+
+use crate::core::enums::app_error::{AppError, AppResult, OrNotFound};
+use crate::core::helpers::db::{current_timestamp, OwnedBy};
+use crate::core::helpers::db_pagination::{sort_query, PageRequest, PaginationResult};
+use crate::core::helpers::get_db_conn;
+use crate::core::helpers::http::QueryParams;
+use crate::models::label::{ExpenseLabel, Label, LabelForm, ProjectLabel};
+use crate::models::DBPool;
+use crate::schema::{expense_labels, labels, project_labels};
+
+const LABEL_SORTS: [&str; 2] = ["created_at", "name"];
+
+pub struct LabelRepository;
+
+impl LabelRepository {
+    pub fn list_by_user_id(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        module: Option<String>,
+        mut query_params: QueryParams,
+    ) -> AppResult<PaginationResult<Label>> {
+        let page = PageRequest::new(&mut query_params, &LABEL_SORTS, "-created_at")?;
+        let mut builder = labels::table.owned_by(id);
+
+        if let Some(module) = module {
+            builder = builder.filter(labels::module.eq(module));
+        }
+
+        let search_format = format!("%{}%", query_params.get_search_query());
+        let builder = builder.filter(labels::name.ilike(search_format));
+        let builder = sort_query!(builder, &page, labels::label_id, {
+            "created_at" => labels::created_at => NaiveDateTime,
+            "name" => labels::name => String,
+        });
+
+        page.load(builder, get_db_conn(pool).deref_mut(), |label: &Label| {
+            (
+                label.label_id,
+                serde_json::to_value(label).unwrap_or_default(),
+            )
+        })
+    }
+
+    pub fn list_by_project_id(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> QueryResult<Vec<Label>> {
+        labels::table
+            .inner_join(project_labels::table)
+            .filter(labels::table.owned_condition(user_id))
+            .filter(project_labels::table.owned_condition(user_id))
+            .filter(project_labels::project_id.eq(id))
+            .order_by(labels::name.asc())
+            .select(labels::all_columns)
+            .load::<Label>(get_db_conn(pool).deref_mut())
+    }
+
+    pub fn list_by_expense_id(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> QueryResult<Vec<Label>> {
+        labels::table
+            .inner_join(expense_labels::table)
+            .filter(labels::table.owned_condition(user_id))
+            .filter(expense_labels::table.owned_condition(user_id))
+            .filter(expense_labels::expense_id.eq(id))
+            .order_by(labels::name.asc())
+            .select(labels::all_columns)
+            .load::<Label>(get_db_conn(pool).deref_mut())
+    }
+
+    /// Labels of the given projects, paired with the project they are attached to
+    pub fn list_by_project_ids(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        ids: Vec<Uuid>,
+    ) -> QueryResult<Vec<(Uuid, Label)>> {
+        labels::table
+            .inner_join(project_labels::table)
+            .filter(labels::table.owned_condition(user_id))
+            .filter(project_labels::table.owned_condition(user_id))
+            .filter(project_labels::project_id.eq_any(ids))
+            .order_by(labels::name.asc())
+            .select((project_labels::project_id, labels::all_columns))
+            .load::<(Uuid, Label)>(get_db_conn(pool).deref_mut())
+    }
+
+    /// Labels of the given expenses, paired with the expense they are attached to
+    pub fn list_by_expense_ids(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        ids: Vec<Uuid>,
+    ) -> QueryResult<Vec<(Uuid, Label)>> {
+        labels::table
+            .inner_join(expense_labels::table)
+            .filter(labels::table.owned_condition(user_id))
+            .filter(expense_labels::table.owned_condition(user_id))
+            .filter(expense_labels::expense_id.eq_any(ids))
+            .order_by(labels::name.asc())
+            .select((expense_labels::expense_id, labels::all_columns))
+            .load::<(Uuid, Label)>(get_db_conn(pool).deref_mut())
+    }
+
+    pub fn create(&mut self, pool: &DBPool, user_id: Uuid, form: LabelForm) -> AppResult<Label> {
+        let model = Label {
+            label_id: Uuid::new_v4(),
+            user_id,
+            name: form.name,
+            module: form.module,
+            created_at: current_timestamp(),
+            updated_at: current_timestamp(),
+            deleted_at: None,
+        };
+
+        diesel::insert_into(labels::table)
+            .values(model.clone())
+            .execute(get_db_conn(pool).deref_mut())?;
+
+        Ok(model)
+    }
+
+    pub fn update(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+        form: LabelForm,
+    ) -> AppResult<Label> {
+        let label = self.find_owned_by_id(pool, id, user_id)?;
+
+        get_db_conn(pool).transaction(|conn| {
+            // a label only ever tags records of its own module
+            if label.module != form.module {
+                let projects_count = project_labels::table
+                    .owned_by(user_id)
+                    .filter(project_labels::label_id.eq(id))
+                    .count()
+                    .get_result::<i64>(conn)?;
+
+                let expenses_count = expense_labels::table
+                    .owned_by(user_id)
+                    .filter(expense_labels::label_id.eq(id))
+                    .count()
+                    .get_result::<i64>(conn)?;
+
+                if projects_count + expenses_count > 0 {
+                    return Err(AppError::Conflict(String::from(
+                        "This label is still attached, detach it before changing its module",
+                    )));
+                }
+            }
+
+            Ok(
+                diesel::update(labels::table.filter(labels::label_id.eq(id)))
+                    .filter(labels::table.owned_condition(user_id))
+                    .set((
+                        labels::name.eq(form.name),
+                        labels::module.eq(form.module),
+                        labels::updated_at.eq(current_timestamp()),
+                    ))
+                    .get_result::<Label>(conn)?,
+            )
+        })
+    }
+
+    pub fn delete(&mut self, pool: &DBPool, id: Uuid, user_id: Uuid) -> AppResult<Label> {
+        let label = self.find_owned_by_id(pool, id, user_id)?;
+
+        get_db_conn(pool).transaction(|conn| {
+            diesel::update(labels::table.filter(labels::label_id.eq(id)))
+                .filter(labels::table.owned_condition(user_id))
+                .set(labels::deleted_at.eq(current_timestamp()))
+                .execute(conn)?;
+
+            diesel::update(project_labels::table.filter(project_labels::label_id.eq(id)))
+                .filter(project_labels::table.owned_condition(user_id))
+                .set(project_labels::deleted_at.eq(current_timestamp()))
+                .execute(conn)?;
+
+            diesel::update(expense_labels::table.filter(expense_labels::label_id.eq(id)))
+                .filter(expense_labels::table.owned_condition(user_id))
+                .set(expense_labels::deleted_at.eq(current_timestamp()))
+                .execute(conn)
+        })?;
+
+        Ok(label)
+    }
+
+    pub fn find_owned_by_id(&mut self, pool: &DBPool, id: Uuid, user_id: Uuid) -> AppResult<Label> {
+        labels::table
+            .owned_by(user_id)
+            .filter(labels::label_id.eq(id))
+            .first::<Label>(get_db_conn(pool).deref_mut())
+            .or_not_found("label")
+    }
+
+    pub fn attach_to_project(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        project_id: Uuid,
+        label_id: Uuid,
+    ) -> QueryResult<ProjectLabel> {
+        let mut conn = get_db_conn(pool);
+        let existing = diesel::OptionalExtension::optional(
+            project_labels::table
+                .owned_by(user_id)
+                .filter(project_labels::project_id.eq(project_id))
+                .filter(project_labels::label_id.eq(label_id))
+                .first::<ProjectLabel>(conn.deref_mut()),
+        )?;
+
+        if let Some(project_label) = existing {
+            return Ok(project_label);
+        }
+
+        diesel::insert_into(project_labels::table)
+            .values(ProjectLabel {
+                project_label_id: Uuid::new_v4(),
+                user_id,
+                project_id,
+                label_id,
+                created_at: current_timestamp(),
+                updated_at: current_timestamp(),
+                deleted_at: None,
+            })
+            .get_result::<ProjectLabel>(conn.deref_mut())
+    }
+
+    pub fn detach_from_project(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        project_id: Uuid,
+        label_id: Uuid,
+    ) -> QueryResult<usize> {
+        diesel::update(project_labels::table)
+            .filter(project_labels::table.owned_condition(user_id))
+            .filter(project_labels::project_id.eq(project_id))
+            .filter(project_labels::label_id.eq(label_id))
+            .set(project_labels::deleted_at.eq(current_timestamp()))
+            .execute(get_db_conn(pool).deref_mut())
+    }
+
+    pub fn attach_to_expense(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        expense_id: Uuid,
+        label_id: Uuid,
+    ) -> QueryResult<ExpenseLabel> {
+        let mut conn = get_db_conn(pool);
+        let existing = diesel::OptionalExtension::optional(
+            expense_labels::table
+                .owned_by(user_id)
+                .filter(expense_labels::expense_id.eq(expense_id))
+                .filter(expense_labels::label_id.eq(label_id))
+                .first::<ExpenseLabel>(conn.deref_mut()),
+        )?;
+
+        if let Some(expense_label) = existing {
+            return Ok(expense_label);
+        }
+
+        diesel::insert_into(expense_labels::table)
+            .values(ExpenseLabel {
+                expense_label_id: Uuid::new_v4(),
+                user_id,
+                expense_id,
+                label_id,
+                created_at: current_timestamp(),
+                updated_at: current_timestamp(),
+                deleted_at: None,
+            })
+            .get_result::<ExpenseLabel>(conn.deref_mut())
+    }
+
+    pub fn detach_from_expense(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        expense_id: Uuid,
+        label_id: Uuid,
+    ) -> QueryResult<usize> {
+        diesel::update(expense_labels::table)
+            .filter(expense_labels::table.owned_condition(user_id))
+            .filter(expense_labels::expense_id.eq(expense_id))
+            .filter(expense_labels::label_id.eq(label_id))
+            .set(expense_labels::deleted_at.eq(current_timestamp()))
+            .execute(get_db_conn(pool).deref_mut())
+    }
+}
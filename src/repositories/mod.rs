@@ -1,4 +1,264 @@
+pub mod account_repository;
+pub mod attachment_repository;
+pub mod bank_account_repository;
 pub mod budget_repository;
+pub mod category_repository;
 pub mod expense_repository;
+pub mod expense_rule_repository;
+pub mod import_repository;
+pub mod income_repository;
+pub mod label_repository;
+pub mod notification_repository;
 pub mod project_repository;
+pub mod report_repository;
+pub mod search_repository;
+pub mod trash_repository;
 pub mod user_repository;
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::budget_repository::BudgetRepository;
+    use super::expense_repository::ExpenseRepository;
+    use super::label_repository::LabelRepository;
+    use super::project_repository::ProjectRepository;
+    use super::search_repository::SearchRepository;
+    use crate::core::helpers::http::QueryParams;
+    use crate::core::helpers::testing::{create_user, test_pool};
+    use crate::models::budget::BudgetForm;
+    use crate::models::expense::{ExpenseCascade, ExpenseFilter, ExpenseForm};
+    use crate::models::label::LabelForm;
+    use crate::models::project::ProjectForm;
+    use crate::models::search::SearchKind;
+    use crate::models::DBPool;
+
+    /// Comment of the owner's budget, only the owner may find it by searching
+    const BUDGET_COMMENT: &str = "quokka allowance";
+
+    struct Owned {
+        project_id: Uuid,
+        budget_id: Uuid,
+        expense_id: Uuid,
+        label_id: Uuid,
+    }
+
+    fn project_form() -> ProjectForm {
+        ProjectForm {
+            name: String::from("Household"),
+            description: String::from("Shared costs"),
+        }
+    }
+
+    fn budget_form() -> BudgetForm {
+        BudgetForm {
+            amount: 1000,
+            month: 1,
+            year: 2031,
+            comment: Some(String::from(BUDGET_COMMENT)),
+            alert_thresholds: None,
+            overspend_policy: None,
+        }
+    }
+
+    fn expense_form(project_id: Uuid) -> ExpenseForm {
+        ExpenseForm {
+            project_id: project_id.to_string(),
+            amount: 10,
+            narration: String::from("Groceries"),
+            spent_at: None,
+            category_id: None,
+        }
+    }
+
+    fn label_form() -> LabelForm {
+        LabelForm {
+            name: String::from("Urgent"),
+            module: String::from("expenses"),
+        }
+    }
+
+    fn search(term: &str) -> QueryParams {
+        QueryParams {
+            search: Some(String::from(term)),
+            ..QueryParams::default()
+        }
+    }
+
+    fn create_owned(pool: &DBPool, user_id: Uuid) -> Owned {
+        let project = ProjectRepository
+            .create(pool, user_id, project_form())
+            .unwrap();
+        let budget = BudgetRepository
+            .create(pool, user_id, budget_form())
+            .unwrap();
+        let (expense, _, _) = ExpenseRepository
+            .create(
+                pool,
+                user_id,
+                "UTC",
+                budget.budget_id,
+                expense_form(project.project_id),
+            )
+            .unwrap();
+        let label = LabelRepository.create(pool, user_id, label_form()).unwrap();
+
+        Owned {
+            project_id: project.project_id,
+            budget_id: budget.budget_id,
+            expense_id: expense.expense_id,
+            label_id: label.label_id,
+        }
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn other_users_cannot_list_or_read() {
+        let pool = test_pool();
+        let owner = create_user(&pool);
+        let other = create_user(&pool);
+        let owned = create_owned(&pool, owner.user_id);
+
+        let projects = ProjectRepository
+            .list_by_user_id(&pool, other.user_id, QueryParams::default())
+            .unwrap();
+        assert!(projects.records.is_empty());
+
+        let budgets = BudgetRepository
+            .list_by_user_id(&pool, other.user_id, QueryParams::default())
+            .unwrap();
+        assert!(budgets.records.is_empty());
+
+        let (expenses, summary) = ExpenseRepository
+            .list(
+                &pool,
+                other.user_id,
+                &ExpenseFilter {
+                    include_deleted: true,
+                    ..ExpenseFilter::default()
+                },
+                QueryParams::default(),
+            )
+            .unwrap();
+        assert!(expenses.records.is_empty());
+        assert_eq!(summary.total_amount, 0);
+
+        let labels = LabelRepository
+            .list_by_user_id(&pool, other.user_id, None, QueryParams::default())
+            .unwrap();
+        assert!(labels.records.is_empty());
+
+        assert!(ProjectRepository
+            .find_owned_by_id(&pool, owned.project_id, other.user_id)
+            .is_err());
+        assert!(BudgetRepository
+            .find_owned_by_id(&pool, owned.budget_id, other.user_id)
+            .is_err());
+        assert!(ExpenseRepository
+            .find_owned_by_id(&pool, owned.expense_id, other.user_id)
+            .is_err());
+        assert!(LabelRepository
+            .find_owned_by_id(&pool, owned.label_id, other.user_id)
+            .is_err());
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn other_users_cannot_search() {
+        let pool = test_pool();
+        let owner = create_user(&pool);
+        let other = create_user(&pool);
+        let owned = create_owned(&pool, owner.user_id);
+
+        // the comment is matched next to the title, it must not widen the search to other users
+        let budgets = BudgetRepository
+            .list_by_user_id(&pool, other.user_id, search(BUDGET_COMMENT))
+            .unwrap();
+        assert!(budgets.records.is_empty());
+
+        let budgets = BudgetRepository
+            .list_by_user_id(&pool, owner.user_id, search(BUDGET_COMMENT))
+            .unwrap();
+        assert_eq!(budgets.records.len(), 1);
+        assert_eq!(budgets.records[0].budget_id, owned.budget_id);
+
+        for term in ["Household", "quokka", "Groceries"] {
+            let hits = SearchRepository
+                .search(&pool, other.user_id, term, &SearchKind::ALL, 20)
+                .unwrap();
+            assert!(hits.is_empty(), "{} leaked to another user", term);
+        }
+
+        let hits = SearchRepository
+            .search(&pool, owner.user_id, "quokka", &SearchKind::ALL, 20)
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn other_users_cannot_update_or_delete() {
+        let pool = test_pool();
+        let owner = create_user(&pool);
+        let other = create_user(&pool);
+        let owned = create_owned(&pool, owner.user_id);
+
+        assert!(ProjectRepository
+            .update(&pool, owned.project_id, other.user_id, project_form())
+            .is_err());
+        assert!(BudgetRepository
+            .update(&pool, owned.budget_id, other.user_id, budget_form())
+            .is_err());
+        assert!(ExpenseRepository
+            .update(
+                &pool,
+                owned.expense_id,
+                other.user_id,
+                "UTC",
+                expense_form(owned.project_id)
+            )
+            .is_err());
+        assert!(LabelRepository
+            .update(&pool, owned.label_id, other.user_id, label_form())
+            .is_err());
+
+        assert!(ExpenseRepository
+            .delete(&pool, owned.expense_id, other.user_id)
+            .is_err());
+        assert!(BudgetRepository
+            .delete(
+                &pool,
+                owned.budget_id,
+                other.user_id,
+                ExpenseCascade::Cascade
+            )
+            .is_err());
+        assert!(ProjectRepository
+            .delete(
+                &pool,
+                owned.project_id,
+                other.user_id,
+                ExpenseCascade::Cascade
+            )
+            .is_err());
+        assert!(LabelRepository
+            .delete(&pool, owned.label_id, other.user_id)
+            .is_err());
+
+        // everything is still live for its owner
+        ProjectRepository
+            .find_owned_by_id(&pool, owned.project_id, owner.user_id)
+            .unwrap();
+        ExpenseRepository
+            .find_owned_by_id(&pool, owned.expense_id, owner.user_id)
+            .unwrap();
+        LabelRepository
+            .find_owned_by_id(&pool, owned.label_id, owner.user_id)
+            .unwrap();
+        let budget = BudgetRepository
+            .find_owned_by_id(&pool, owned.budget_id, owner.user_id)
+            .unwrap();
+        // a leaked cascade would have released the expense from the budget
+        assert_eq!(budget.amount_used, 10);
+    }
+}
@@ -0,0 +1,123 @@
+use std::ops::DerefMut;
+
+use chrono::NaiveDateTime;
+use diesel::{ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::core::enums::app_error::{AppResult, OrNotFound};
+use crate::core::helpers::db::{current_timestamp, OwnedBy};
+use crate::core::helpers::db_pagination::{sort_query, PageRequest, PaginationResult};
+use crate::core::helpers::get_db_conn;
+use crate::core::helpers::http::QueryParams;
+use crate::models::notification::{Notification, NotificationEvent};
+use crate::models::DBPool;
+use crate::schema::notifications;
+
+const NOTIFICATION_SORTS: [&str; 1] = ["created_at"];
+
+pub struct NotificationRepository;
+
+impl NotificationRepository {
+    pub fn list_by_user_id(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        unread_only: bool,
+        mut query_params: QueryParams,
+    ) -> AppResult<PaginationResult<Notification>> {
+        let page = PageRequest::new(&mut query_params, &NOTIFICATION_SORTS, "-created_at")?;
+        let mut builder = notifications::table.owned_by(id);
+
+        if unread_only {
+            builder = builder.filter(notifications::read_at.is_null());
+        }
+
+        let builder = sort_query!(builder, &page, notifications::notification_id, {
+            "created_at" => notifications::created_at => NaiveDateTime,
+        });
+
+        page.load(
+            builder,
+            get_db_conn(pool).deref_mut(),
+            |notification: &Notification| {
+                (
+                    notification.notification_id,
+                    serde_json::to_value(notification).unwrap_or_default(),
+                )
+            },
+        )
+    }
+
+    pub fn create(
+        &mut self,
+        pool: &DBPool,
+        event: &NotificationEvent,
+    ) -> QueryResult<Notification> {
+        let model = Notification {
+            notification_id: Uuid::new_v4(),
+            user_id: event.user_id(),
+            budget_id: event.budget_id(),
+            kind: event.kind().to_string(),
+            title: event.title(),
+            message: event.message(),
+            read_at: None,
+            created_at: current_timestamp(),
+            updated_at: current_timestamp(),
+            deleted_at: None,
+        };
+
+        diesel::insert_into(notifications::table)
+            .values(model)
+            .get_result::<Notification>(get_db_conn(pool).deref_mut())
+    }
+
+    pub fn mark_as_read(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> AppResult<Notification> {
+        let notification = self.find_owned_by_id(pool, id, user_id)?;
+        if notification.read_at.is_some() {
+            return Ok(notification);
+        }
+
+        diesel::update(
+            notifications::table
+                .filter(notifications::table.owned_condition(user_id))
+                .filter(notifications::notification_id.eq(id)),
+        )
+        .set((
+            notifications::read_at.eq(current_timestamp()),
+            notifications::updated_at.eq(current_timestamp()),
+        ))
+        .get_result::<Notification>(get_db_conn(pool).deref_mut())
+        .or_not_found("notification")
+    }
+
+    pub fn mark_all_as_read(&mut self, pool: &DBPool, user_id: Uuid) -> QueryResult<usize> {
+        diesel::update(
+            notifications::table
+                .filter(notifications::table.owned_condition(user_id))
+                .filter(notifications::read_at.is_null()),
+        )
+        .set((
+            notifications::read_at.eq(current_timestamp()),
+            notifications::updated_at.eq(current_timestamp()),
+        ))
+        .execute(get_db_conn(pool).deref_mut())
+    }
+
+    pub fn find_owned_by_id(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> AppResult<Notification> {
+        notifications::table
+            .owned_by(user_id)
+            .filter(notifications::notification_id.eq(id))
+            .first::<Notification>(get_db_conn(pool).deref_mut())
+            .or_not_found("notification")
+    }
+}
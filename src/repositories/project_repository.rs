@@ -1,20 +1,28 @@
 use std::ops::DerefMut;
 
-use chrono::{Datelike, Utc};
+use chrono::NaiveDateTime;
 use diesel::{
-    sql_query, ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl, TextExpressionMethods,
+    BoolExpressionMethods, Connection, ExpressionMethods, OptionalExtension, PgConnection,
+    QueryDsl, QueryResult, RunQueryDsl, TextExpressionMethods,
 };
 use uuid::Uuid;
 
-use crate::core::enums::http_error::{DBResult, ErroneousOption, OptionalResult};
-use crate::core::helpers::db::current_timestamp;
-use crate::core::helpers::db_pagination::{Paginate, PaginationResult};
+use crate::core::enums::app_error::{AppError, AppResult, OrNotFound};
+use crate::core::helpers::date_time::AggregatePeriods;
+use crate::core::helpers::db::{current_timestamp, OwnedBy};
+use crate::core::helpers::db_pagination::{sort_query, PageRequest, PaginationResult};
 use crate::core::helpers::get_db_conn;
 use crate::core::helpers::http::QueryParams;
-use crate::models::expense::ExpenseAggregate;
+use crate::models::budget::Budget;
+use crate::models::expense::{Expense, ExpenseAggregateSummary, ExpenseCascade};
 use crate::models::project::{Project, ProjectForm};
 use crate::models::DBPool;
-use crate::schema::projects;
+use crate::repositories::expense_repository::{
+    fetch_expense_aggregate, trash_expenses, untrash_expenses,
+};
+use crate::schema::{bank_accounts, expenses, incomes, project_labels, projects};
+
+const PROJECT_SORTS: [&str; 3] = ["created_at", "updated_at", "name"];
 
 pub struct ProjectRepository;
 
@@ -24,21 +32,87 @@ impl ProjectRepository {
         pool: &DBPool,
         id: Uuid,
         mut query_params: QueryParams,
-    ) -> QueryResult<PaginationResult<Project>> {
-        let builder = projects::table
-            .filter(projects::user_id.eq(id))
-            .filter(projects::deleted_at.is_null())
-            .order_by(projects::created_at.desc());
+    ) -> AppResult<PaginationResult<Project>> {
+        let page = PageRequest::new(&mut query_params, &PROJECT_SORTS, "-created_at")?;
+        let mut builder = projects::table.owned_by(id);
+
+        if !query_params.labels.is_empty() {
+            builder = builder.filter(
+                projects::project_id.eq_any(
+                    project_labels::table
+                        .select(project_labels::project_id)
+                        .filter(project_labels::table.owned_condition(id))
+                        .filter(project_labels::label_id.eq_any(query_params.labels.clone())),
+                ),
+            );
+        }
 
         let search_format = format!("%{}%", query_params.get_search_query());
+        let builder = builder.filter(projects::name.like(search_format));
+        let builder = sort_query!(builder, &page, projects::project_id, {
+            "created_at" => projects::created_at => NaiveDateTime,
+            "updated_at" => projects::updated_at => NaiveDateTime,
+            "name" => projects::name => String,
+        });
+
+        page.load(
+            builder,
+            get_db_conn(pool).deref_mut(),
+            |project: &Project| {
+                (
+                    project.project_id,
+                    serde_json::to_value(project).unwrap_or_default(),
+                )
+            },
+        )
+    }
+
+    /// Next batch of the user's projects matching the list filters, newest
+    /// first and resuming after the given (created_at, project_id) cursor
+    pub fn list_batch_by_user_id(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        mut query_params: QueryParams,
+        after: Option<(NaiveDateTime, Uuid)>,
+        limit: i64,
+    ) -> QueryResult<Vec<Project>> {
+        let search_format = format!("%{}%", query_params.get_search_query());
+        let mut builder = projects::table
+            .owned_by(id)
+            .filter(projects::name.like(search_format));
+
+        if !query_params.labels.is_empty() {
+            builder = builder.filter(
+                projects::project_id.eq_any(
+                    project_labels::table
+                        .select(project_labels::project_id)
+                        .filter(project_labels::table.owned_condition(id))
+                        .filter(project_labels::label_id.eq_any(query_params.labels.clone())),
+                ),
+            );
+        }
+
+        if let Some((created_at, project_id)) = after {
+            builder = builder.filter(
+                projects::created_at.lt(created_at).or(projects::created_at
+                    .eq(created_at)
+                    .and(projects::project_id.lt(project_id))),
+            );
+        }
+
         builder
-            .filter(projects::name.like(search_format))
-            .paginate(query_params.get_page())
-            .per_page(query_params.get_per_page())
-            .load_and_count_pages::<Project>(get_db_conn(pool).deref_mut())
+            .order_by((projects::created_at.desc(), projects::project_id.desc()))
+            .limit(limit)
+            .load::<Project>(get_db_conn(pool).deref_mut())
     }
 
-    pub fn create(&mut self, pool: &DBPool, user_id: Uuid, form: ProjectForm) -> Project {
+    pub fn create(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        form: ProjectForm,
+    ) -> AppResult<Project> {
         let model = Project {
             project_id: Uuid::new_v4(),
             user_id,
@@ -51,10 +125,9 @@ impl ProjectRepository {
 
         diesel::insert_into(projects::table)
             .values(model.clone())
-            .execute(get_db_conn(pool).deref_mut())
-            .unwrap();
+            .execute(get_db_conn(pool).deref_mut())?;
 
-        model
+        Ok(model)
     }
 
     pub fn update(
@@ -63,45 +136,249 @@ impl ProjectRepository {
         id: Uuid,
         user_id: Uuid,
         form: ProjectForm,
-    ) -> DBResult<Project> {
-        let result = self.find_owned_by_id(pool, id, user_id);
+    ) -> AppResult<Project> {
+        let project = self.find_owned_by_id(pool, id, user_id)?;
 
-        if result.is_error_or_empty() {
-            return result.get_error_result();
-        }
+        diesel::update(
+            projects::table
+                .filter(projects::table.owned_condition(user_id))
+                .filter(projects::project_id.eq(id)),
+        )
+        .set((
+            projects::dsl::name.eq(form.name),
+            projects::dsl::description.eq(form.description),
+        ))
+        .execute(get_db_conn(pool).deref_mut())?;
 
-        diesel::update(projects::dsl::projects.filter(projects::project_id.eq(id)))
-            .set((
-                projects::dsl::name.eq(form.name),
-                projects::dsl::description.eq(form.description),
-            ))
-            .execute(get_db_conn(pool).deref_mut())
-            .expect("Failed to update project");
+        Ok(project)
+    }
+
+    /// Moves the project to the trash, its live expenses, incomes and bank accounts are
+    /// refused, trashed along or moved to another project as the cascade says, all or nothing
+    pub fn delete(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+        cascade: ExpenseCascade,
+    ) -> AppResult<Project> {
+        get_db_conn(pool).transaction(|conn| {
+            let target_id = match cascade {
+                ExpenseCascade::Move(target_id) => Some(target_id),
+                _ => None,
+            };
+
+            // spending locks the project too, so nothing can be added while it's deleted.
+            // Both projects of a move are locked lowest id first, so that two opposite moves
+            // can't deadlock, the target may have been deleted meanwhile
+            let mut lock_ids: Vec<Uuid> = [Some(id), target_id].into_iter().flatten().collect();
+            lock_ids.sort();
+            lock_ids.dedup();
+
+            let mut project = None;
+            let mut target = None;
+            for lock_id in lock_ids {
+                let locked = lock_owned_project(conn, user_id, lock_id)?;
+                match lock_id == id {
+                    true => project = locked,
+                    false => target = locked,
+                }
+            }
+
+            let project =
+                project.ok_or_else(|| AppError::EntityNotFound(String::from("project")))?;
 
-        Ok(result.unwrap_entity())
+            let live_expenses = expenses::table
+                .owned_by(user_id)
+                .filter(expenses::project_id.eq(id))
+                .load::<Expense>(conn)?;
+            let ids: Vec<Uuid> = live_expenses.iter().map(|expense| expense.expense_id).collect();
+
+            let live_incomes = incomes::table
+                .filter(incomes::table.owned_condition(user_id))
+                .filter(incomes::project_id.eq(id));
+            let live_bank_accounts = bank_accounts::table
+                .filter(bank_accounts::table.owned_condition(user_id))
+                .filter(bank_accounts::project_id.eq(id));
+
+            let deleted_at = current_timestamp();
+            match cascade {
+                ExpenseCascade::Refuse => {
+                    let incomes_count = live_incomes.count().get_result::<i64>(conn)?;
+                    let bank_accounts_count = live_bank_accounts.count().get_result::<i64>(conn)?;
+
+                    if !ids.is_empty() || incomes_count + bank_accounts_count > 0 {
+                        return Err(AppError::Conflict(format!(
+                            "This project has {} expenses, {} incomes and {} bank accounts, delete it with expenses=cascade or expenses=move",
+                            ids.len(),
+                            incomes_count,
+                            bank_accounts_count
+                        )));
+                    }
+                }
+                ExpenseCascade::Cascade => {
+                    trash_expenses(conn, user_id, ids, deleted_at)?;
+
+                    // trashed along with the project, restoring it brings them back
+                    diesel::update(live_incomes)
+                        .set(incomes::deleted_at.eq(deleted_at))
+                        .execute(conn)?;
+                    diesel::update(live_bank_accounts)
+                        .set(bank_accounts::deleted_at.eq(deleted_at))
+                        .execute(conn)?;
+                }
+                ExpenseCascade::Move(target_id) => {
+                    target.ok_or_else(|| {
+                        AppError::from("The project to move the expenses to does not exist")
+                    })?;
+
+                    diesel::update(
+                        expenses::table
+                            .filter(expenses::table.owned_condition(user_id))
+                            .filter(expenses::expense_id.eq_any(ids)),
+                    )
+                    .set((
+                        expenses::project_id.eq(target_id),
+                        expenses::updated_at.eq(deleted_at),
+                    ))
+                    .execute(conn)?;
+
+                    diesel::update(live_incomes)
+                        .set((
+                            incomes::project_id.eq(target_id),
+                            incomes::updated_at.eq(deleted_at),
+                        ))
+                        .execute(conn)?;
+                    diesel::update(live_bank_accounts)
+                        .set((
+                            bank_accounts::project_id.eq(target_id),
+                            bank_accounts::updated_at.eq(deleted_at),
+                        ))
+                        .execute(conn)?;
+                }
+            }
+
+            diesel::update(projects::table.filter(projects::project_id.eq(project.project_id)))
+                .set(projects::deleted_at.eq(deleted_at))
+                .get_result::<Project>(conn)
+                .map_err(AppError::from)
+        })
     }
 
-    pub fn delete(&mut self, pool: &DBPool, id: Uuid, user_id: Uuid) -> DBResult<Project> {
-        let result = self.find_owned_by_id(pool, id, user_id);
+    /// Brings the project back along with the incomes and bank accounts trashed with it
+    /// Restores the project along with the expenses, incomes and bank accounts deleted
+    /// together with it, expenses of a budget that is still deleted stay in the trash.
+    /// Returns the restored project and every charged budget before and after the charge
+    pub fn restore(
+        &mut self,
+        pool: &DBPool,
+        project: &Project,
+    ) -> AppResult<(Project, Vec<(Budget, Budget)>)> {
+        get_db_conn(pool).transaction(|conn| {
+            let restored = diesel::update(
+                projects::table
+                    .filter(projects::table.trashed_condition(project.user_id))
+                    .filter(projects::project_id.eq(project.project_id)),
+            )
+            .set((
+                projects::deleted_at.eq(None::<NaiveDateTime>),
+                projects::updated_at.eq(current_timestamp()),
+            ))
+            .get_result::<Project>(conn)
+            .or_not_found("project")?;
 
-        if result.is_error_or_empty() {
-            return result.get_error_result();
-        }
+            diesel::update(
+                incomes::table
+                    .filter(incomes::table.trashed_condition(project.user_id))
+                    .filter(incomes::project_id.eq(project.project_id))
+                    .filter(incomes::deleted_at.eq(project.deleted_at)),
+            )
+            .set(incomes::deleted_at.eq(None::<NaiveDateTime>))
+            .execute(conn)?;
+
+            diesel::update(
+                bank_accounts::table
+                    .filter(bank_accounts::table.trashed_condition(project.user_id))
+                    .filter(bank_accounts::project_id.eq(project.project_id))
+                    .filter(bank_accounts::deleted_at.eq(project.deleted_at)),
+            )
+            .set(bank_accounts::deleted_at.eq(None::<NaiveDateTime>))
+            .execute(conn)?;
 
-        diesel::update(projects::dsl::projects.filter(projects::project_id.eq(id)))
-            .set(projects::dsl::deleted_at.eq(current_timestamp()))
-            .execute(get_db_conn(pool).deref_mut())
-            .expect("Failed to delete project");
+            let ids = expenses::table
+                .filter(expenses::table.trashed_condition(project.user_id))
+                .filter(expenses::project_id.eq(project.project_id))
+                .filter(expenses::deleted_at.eq(project.deleted_at))
+                .select(expenses::expense_id)
+                .load::<Uuid>(conn)?;
+            let charged = untrash_expenses(conn, project.user_id, ids)?;
 
-        Ok(result.unwrap_entity())
+            Ok((restored, charged))
+        })
     }
 
-    #[allow(dead_code)]
-    pub fn find_by_id(&mut self, pool: &DBPool, id: Uuid) -> QueryResult<Project> {
+    pub fn find_trashed_by_id(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> AppResult<Project> {
         projects::table
+            .trashed_by(user_id)
             .filter(projects::project_id.eq(id))
-            .filter(projects::deleted_at.is_null())
             .first::<Project>(get_db_conn(pool).deref_mut())
+            .or_not_found("project")
+    }
+
+    /// Ids of every project (of any user) that was deleted before the given time
+    pub fn list_ids_deleted_before(
+        &mut self,
+        pool: &DBPool,
+        time: NaiveDateTime,
+    ) -> QueryResult<Vec<Uuid>> {
+        projects::table
+            .filter(projects::deleted_at.lt(time))
+            .select(projects::project_id)
+            .load::<Uuid>(get_db_conn(pool).deref_mut())
+    }
+
+    /// Permanently deletes the project along with its trashed incomes and bank accounts,
+    /// refused while expenses (trashed ones included) or live incomes and bank accounts
+    /// still belong to it
+    pub fn purge(&mut self, pool: &DBPool, id: Uuid) -> AppResult<()> {
+        get_db_conn(pool).transaction(|conn| {
+            let expenses_count = expenses::table
+                .filter(expenses::project_id.eq(id))
+                .count()
+                .get_result::<i64>(conn)?;
+
+            let incomes_count = incomes::table
+                .filter(incomes::project_id.eq(id))
+                .filter(incomes::deleted_at.is_null())
+                .count()
+                .get_result::<i64>(conn)?;
+
+            let bank_accounts_count = bank_accounts::table
+                .filter(bank_accounts::project_id.eq(id))
+                .filter(bank_accounts::deleted_at.is_null())
+                .count()
+                .get_result::<i64>(conn)?;
+
+            if expenses_count + incomes_count + bank_accounts_count > 0 {
+                return Err(AppError::Conflict(String::from(
+                    "This project still has expenses, incomes or bank accounts, purge or move them first",
+                )));
+            }
+
+            diesel::delete(incomes::table.filter(incomes::project_id.eq(id))).execute(conn)?;
+            diesel::delete(bank_accounts::table.filter(bank_accounts::project_id.eq(id)))
+                .execute(conn)?;
+            diesel::delete(project_labels::table.filter(project_labels::project_id.eq(id)))
+                .execute(conn)?;
+
+            diesel::delete(projects::table.filter(projects::project_id.eq(id))).execute(conn)?;
+            Ok(())
+        })
     }
 
     pub fn find_owned_by_id(
@@ -109,29 +386,41 @@ impl ProjectRepository {
         pool: &DBPool,
         id: Uuid,
         user_id: Uuid,
-    ) -> DBResult<Option<Project>> {
+    ) -> AppResult<Project> {
         projects::table
+            .owned_by(user_id)
             .filter(projects::project_id.eq(id))
-            .filter(projects::user_id.eq(user_id))
-            .filter(projects::deleted_at.is_null())
             .first::<Project>(get_db_conn(pool).deref_mut())
-            .optional("project")
+            .or_not_found("project")
     }
 
     pub fn fetch_aggregate_by_project_id(
         &mut self,
         pool: &DBPool,
         project_id: Uuid,
-    ) -> QueryResult<Vec<ExpenseAggregate>> {
-        let mut sql = format!("SELECT (SELECT SUM(amount) FROM expenses WHERE EXTRACT(YEAR FROM expenses.spent_at) = {} AND expenses.project_id = '{}')::VARCHAR AS year_expenses", Utc::now().year(), project_id.clone());
-        sql += &*format!(", (SELECT SUM(amount) FROM expenses WHERE EXTRACT(MONTH FROM expenses.spent_at) = {} AND expenses.project_id = '{}')::VARCHAR AS month_expenses", Utc::now().month(), project_id.clone());
-        sql += &*format!(", (SELECT SUM(amount) FROM expenses WHERE EXTRACT(WEEK FROM expenses.spent_at) = EXTRACT(WEEK FROM NOW()) AND expenses.project_id = '{}')::VARCHAR AS week_expenses", project_id.clone());
-        sql += &*format!(", (SELECT SUM(amount) FROM expenses WHERE EXTRACT(DAY FROM expenses.spent_at) = {} AND expenses.project_id = '{}')::VARCHAR AS today_expenses", Utc::now().day(), project_id.clone());
-
-        sql_query(sql)
-            // .bind::<Integer, _>(Utc::now().year())
-            // .bind::<Integer, _>(Utc::now().month() as i32)
-            // .bind::<Integer, _>(Utc::now().day() as i32)
-            .load::<ExpenseAggregate>(get_db_conn(pool).deref_mut())
+        user_id: Uuid,
+        timezone: &str,
+    ) -> QueryResult<ExpenseAggregateSummary> {
+        fetch_expense_aggregate(
+            pool,
+            user_id,
+            Some(project_id),
+            AggregatePeriods::in_timezone(timezone),
+        )
     }
 }
+
+/// Locks the user's live project until the transaction ends, so that deleting it
+/// can't interleave with spending on it
+pub fn lock_owned_project(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    id: Uuid,
+) -> QueryResult<Option<Project>> {
+    projects::table
+        .filter(projects::table.owned_condition(user_id))
+        .filter(projects::project_id.eq(id))
+        .for_update()
+        .first::<Project>(conn)
+        .optional()
+}
@@ -0,0 +1,203 @@
+use std::ops::DerefMut;
+
+use chrono::Months;
+use diesel::pg::Pg;
+use diesel::query_builder::{BoxedSqlQuery, SqlQuery};
+use diesel::sql_types::{Array, Timestamp, Uuid as SqlUuid};
+use diesel::{QueryResult, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::core::helpers::date_time::DateRange;
+use crate::core::helpers::db::tenant_sql_query;
+use crate::core::helpers::get_db_conn;
+use crate::models::report::{
+    BudgetSpending, RecurringItem, SpendingGroup, SpendingRow, SpendingTotal,
+};
+use crate::models::DBPool;
+
+/// Composable filter over a user's live expenses (joined with their projects),
+/// shared by reports so they all scope and narrow down expenses the same way
+pub struct SpendingQuery {
+    user_id: Uuid,
+    range: Option<DateRange>,
+    project_id: Option<Uuid>,
+    label_id: Option<Uuid>,
+    budget_ids: Option<Vec<Uuid>>,
+}
+
+impl SpendingQuery {
+    pub fn new(user_id: Uuid) -> SpendingQuery {
+        SpendingQuery {
+            user_id,
+            range: None,
+            project_id: None,
+            label_id: None,
+            budget_ids: None,
+        }
+    }
+
+    pub fn between(self, range: DateRange) -> SpendingQuery {
+        SpendingQuery {
+            range: Some(range),
+            ..self
+        }
+    }
+
+    pub fn project(self, project_id: Option<Uuid>) -> SpendingQuery {
+        SpendingQuery { project_id, ..self }
+    }
+
+    pub fn label(self, label_id: Option<Uuid>) -> SpendingQuery {
+        SpendingQuery { label_id, ..self }
+    }
+
+    pub fn budget(self, budget_id: Option<Uuid>) -> SpendingQuery {
+        SpendingQuery {
+            budget_ids: budget_id.map(|id| vec![id]),
+            ..self
+        }
+    }
+
+    pub fn budgets(self, budget_ids: Vec<Uuid>) -> SpendingQuery {
+        SpendingQuery {
+            budget_ids: Some(budget_ids),
+            ..self
+        }
+    }
+
+    pub fn total(&self, pool: &DBPool) -> QueryResult<SpendingTotal> {
+        self.build(
+            "SELECT CAST(COALESCE(SUM(e.amount), 0) AS BIGINT) AS amount, COUNT(*) AS expenses_count",
+            "",
+        )
+        .get_result::<SpendingTotal>(get_db_conn(pool).deref_mut())
+    }
+
+    /// Spending of every budget the query covers, in a single grouped query
+    pub fn by_budget(&self, pool: &DBPool) -> QueryResult<Vec<BudgetSpending>> {
+        self.build(
+            "SELECT e.budget_id, CAST(SUM(e.amount) AS BIGINT) AS amount",
+            "",
+        )
+        .sql(" GROUP BY e.budget_id")
+        .load::<BudgetSpending>(get_db_conn(pool).deref_mut())
+    }
+
+    pub fn grouped(&self, pool: &DBPool, group: SpendingGroup) -> QueryResult<Vec<SpendingRow>> {
+        let (key, label, joins) = match group {
+            SpendingGroup::Day => (
+                "to_char(e.spent_at, 'YYYY-MM-DD')",
+                "to_char(e.spent_at, 'YYYY-MM-DD')",
+                "",
+            ),
+            SpendingGroup::Week => (
+                "to_char(date_trunc('week', e.spent_at), 'YYYY-MM-DD')",
+                "to_char(date_trunc('week', e.spent_at), 'YYYY-MM-DD')",
+                "",
+            ),
+            SpendingGroup::Month => (
+                "to_char(e.spent_at, 'YYYY-MM')",
+                "to_char(e.spent_at, 'YYYY-MM')",
+                "",
+            ),
+            SpendingGroup::Project => ("CAST(p.project_id AS VARCHAR)", "p.name", ""),
+            // every expense counts towards a single label so that the buckets add up to
+            // the total: its own labels come before its project's, ties go to the
+            // alphabetically first, and expenses without any end up in "unlabeled"
+            SpendingGroup::Label => (
+                "COALESCE(CAST(l.label_id AS VARCHAR), 'unlabeled')",
+                "COALESCE(l.name, 'Unlabeled')",
+                " LEFT JOIN (SELECT DISTINCT ON (lbl.expense_id) lbl.expense_id, lb.label_id, lb.name \
+                 FROM (SELECT el.expense_id, el.label_id, 0 AS source FROM expense_labels el \
+                 WHERE el.user_id = $1 AND el.deleted_at IS NULL \
+                 UNION SELECT ex.expense_id, pl.label_id, 1 AS source FROM expenses ex \
+                 INNER JOIN project_labels pl ON pl.project_id = ex.project_id \
+                 AND pl.user_id = $1 AND pl.deleted_at IS NULL \
+                 WHERE ex.user_id = $1) lbl \
+                 INNER JOIN labels lb ON lb.label_id = lbl.label_id \
+                 AND lb.user_id = $1 AND lb.deleted_at IS NULL \
+                 ORDER BY lbl.expense_id, lbl.source, lb.name, lb.label_id) l \
+                 ON l.expense_id = e.expense_id",
+            ),
+        };
+
+        let select = format!(
+            "SELECT {} AS bucket_key, {} AS bucket_label, \
+             CAST(SUM(e.amount) AS BIGINT) AS amount, COUNT(*) AS expenses_count",
+            key, label
+        );
+
+        self.build(select.as_str(), joins)
+            .sql(" GROUP BY bucket_key, bucket_label ORDER BY bucket_key")
+            .load::<SpendingRow>(get_db_conn(pool).deref_mut())
+    }
+
+    fn build(&self, select: &str, joins: &str) -> BoxedSqlQuery<'static, Pg, SqlQuery> {
+        let mut query = tenant_sql_query(select, self.user_id)
+            .sql(" FROM expenses e INNER JOIN projects p ON p.project_id = e.project_id AND p.user_id = $1")
+            .sql(joins)
+            .sql(" WHERE e.user_id = $1 AND e.deleted_at IS NULL");
+
+        let mut binds = 1;
+
+        if let Some(range) = self.range {
+            query = query
+                .sql(format!(
+                    " AND e.spent_at >= ${} AND e.spent_at < ${}",
+                    binds + 1,
+                    binds + 2
+                ))
+                .bind::<Timestamp, _>(range.start)
+                .bind::<Timestamp, _>(range.end);
+            binds += 2;
+        }
+
+        if let Some(project_id) = self.project_id {
+            binds += 1;
+            query = query
+                .sql(format!(" AND e.project_id = ${}", binds))
+                .bind::<SqlUuid, _>(project_id);
+        }
+
+        if let Some(label_id) = self.label_id {
+            binds += 1;
+            query = query
+                .sql(format!(
+                    " AND (EXISTS (SELECT 1 FROM expense_labels fel WHERE fel.expense_id = e.expense_id \
+                     AND fel.label_id = ${0} AND fel.user_id = $1 AND fel.deleted_at IS NULL) \
+                     OR EXISTS (SELECT 1 FROM project_labels fpl WHERE fpl.project_id = e.project_id \
+                     AND fpl.label_id = ${0} AND fpl.user_id = $1 AND fpl.deleted_at IS NULL))",
+                    binds
+                ))
+                .bind::<SqlUuid, _>(label_id);
+        }
+
+        if let Some(budget_ids) = &self.budget_ids {
+            binds += 1;
+            query = query
+                .sql(format!(" AND e.budget_id = ANY(${})", binds))
+                .bind::<Array<SqlUuid>, _>(budget_ids.clone());
+        }
+
+        query
+    }
+}
+
+/// Expenses (matched by project and case-insensitive narration) recorded in each
+/// of the two months preceding `month` that haven't shown up within `month` yet
+pub fn fetch_recurring_items(
+    pool: &DBPool,
+    user_id: Uuid,
+    month: DateRange,
+) -> QueryResult<Vec<RecurringItem>> {
+    let lookback_start = month.start.date() - Months::new(2);
+
+    tenant_sql_query(
+        include_str!("../../sql/reports/recurring_items.sql"),
+        user_id,
+    )
+    .bind::<Timestamp, _>(lookback_start.and_hms_opt(0, 0, 0).unwrap())
+    .bind::<Timestamp, _>(month.start)
+    .bind::<Timestamp, _>(month.end)
+    .load::<RecurringItem>(get_db_conn(pool).deref_mut())
+}
@@ -0,0 +1,33 @@
+use std::ops::DerefMut;
+
+use diesel::sql_types::{Array, BigInt, Text};
+use diesel::{QueryResult, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::core::helpers::db::tenant_sql_query;
+use crate::core::helpers::get_db_conn;
+use crate::models::search::{SearchHit, SearchKind};
+use crate::models::DBPool;
+
+pub struct SearchRepository;
+
+impl SearchRepository {
+    /// The user's projects, budgets and expenses of the given kinds matching
+    /// the (web search syntax) query, best matches first
+    pub fn search(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        query: &str,
+        kinds: &[SearchKind],
+        limit: i64,
+    ) -> QueryResult<Vec<SearchHit>> {
+        let kinds: Vec<&str> = kinds.iter().map(|kind| kind.as_str()).collect();
+
+        tenant_sql_query(include_str!("../../sql/search/search.sql"), user_id)
+            .bind::<Text, _>(query)
+            .bind::<Array<Text>, _>(kinds)
+            .bind::<BigInt, _>(limit)
+            .load::<SearchHit>(get_db_conn(pool).deref_mut())
+    }
+}
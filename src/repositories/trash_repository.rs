@@ -0,0 +1,75 @@
+use std::ops::DerefMut;
+
+use diesel::sql_types::{Array, BigInt, Integer, Nullable, Text};
+use diesel::{QueryDsl, QueryResult, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::core::enums::app_error::AppResult;
+use crate::core::helpers::db::{tenant_sql_query, OwnedBy};
+use crate::core::helpers::db_pagination::{page_offset, PaginationResult};
+use crate::core::helpers::get_db_conn;
+use crate::models::trash::{TrashItem, TrashKind};
+use crate::models::DBPool;
+use crate::schema::{budgets, expenses, projects};
+
+pub struct TrashRepository;
+
+impl TrashRepository {
+    /// The user's deleted projects, budgets and expenses of the given kinds,
+    /// most recently deleted first
+    pub fn list(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        kinds: &[TrashKind],
+        page: i64,
+        per_page: i64,
+        retention_days: Option<i32>,
+    ) -> AppResult<PaginationResult<TrashItem>> {
+        let offset = page_offset(page, per_page)?;
+        let kinds: Vec<&str> = kinds.iter().map(|kind| kind.as_str()).collect();
+
+        let records = tenant_sql_query(include_str!("../../sql/trash/list.sql"), user_id)
+            .bind::<Array<Text>, _>(kinds)
+            .bind::<BigInt, _>(per_page)
+            .bind::<BigInt, _>(offset)
+            .bind::<Nullable<Integer>, _>(retention_days)
+            .load::<TrashItem>(get_db_conn(pool).deref_mut())?;
+
+        let total_records = records.first().map(|item| item.total_records).unwrap_or(0);
+
+        Ok(PaginationResult {
+            records,
+            total_pages: Some((total_records as f64 / per_page as f64).ceil() as i64),
+            total_records: Some(total_records),
+            page: Some(page),
+            per_page,
+            next_cursor: None,
+        })
+    }
+
+    /// Ids of everything of the given kind in the user's trash
+    pub fn list_ids(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        kind: TrashKind,
+    ) -> QueryResult<Vec<Uuid>> {
+        let mut conn = get_db_conn(pool);
+        let conn = conn.deref_mut();
+        match kind {
+            TrashKind::Project => projects::table
+                .trashed_by(user_id)
+                .select(projects::project_id)
+                .load::<Uuid>(conn),
+            TrashKind::Budget => budgets::table
+                .trashed_by(user_id)
+                .select(budgets::budget_id)
+                .load::<Uuid>(conn),
+            TrashKind::Expense => expenses::table
+                .trashed_by(user_id)
+                .select(expenses::expense_id)
+                .load::<Uuid>(conn),
+        }
+    }
+}
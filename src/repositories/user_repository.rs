@@ -1,3 +1,5 @@
+use crate::core::enums::app_error::{AppError, AppResult};
+use crate::core::helpers::date_time::parse_timezone;
 use crate::core::helpers::db::current_timestamp;
 use crate::core::helpers::get_db_conn;
 use crate::core::helpers::string::password_hash;
@@ -13,12 +15,22 @@ use uuid::Uuid;
 pub struct UserRepository;
 
 impl UserRepository {
-    pub fn create(&mut self, pool: &DBPool, data: RegisterForm) -> Result<User, String> {
+    pub fn create(&mut self, pool: &DBPool, data: RegisterForm) -> AppResult<User> {
         let existing = self.find_by_email(pool, data.email.clone());
         if existing.is_ok() {
-            return Err(String::from("User with such email address already exists"));
+            return Err(AppError::Conflict(String::from(
+                "User with such email address already exists",
+            )));
         }
 
+        let timezone = match data.timezone {
+            None => String::from("UTC"),
+            Some(timezone) => match parse_timezone(timezone.as_str()) {
+                Ok(_) => timezone,
+                Err(message) => return Err(AppError::BadRequest(message)),
+            },
+        };
+
         let model = User {
             user_id: Uuid::new_v4(),
             first_name: data.first_name,
@@ -29,12 +41,12 @@ impl UserRepository {
             created_at: current_timestamp(),
             updated_at: current_timestamp(),
             deleted_at: None,
+            timezone,
         };
 
         let user = diesel::insert_into(users::dsl::users)
             .values(model)
-            .get_result::<User>(get_db_conn(pool).deref_mut())
-            .unwrap();
+            .get_result::<User>(get_db_conn(pool).deref_mut())?;
 
         Ok(user)
     }
@@ -62,5 +74,5 @@ pub fn user_stringy_status(status: UserStatus) -> &'static str {
 }
 
 pub fn user_status_is(str: String, status: UserStatus) -> bool {
-    return str.eq(user_stringy_status(status));
+    str.eq(user_stringy_status(status))
 }
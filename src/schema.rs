@@ -1,5 +1,34 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    attachments (attachment_id) {
+        attachment_id -> Uuid,
+        user_id -> Uuid,
+        expense_id -> Uuid,
+        file_name -> Varchar,
+        content_type -> Varchar,
+        size -> Int8,
+        storage_key -> Varchar,
+        thumbnail_key -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    bank_accounts (bank_account_id) {
+        bank_account_id -> Uuid,
+        user_id -> Uuid,
+        project_id -> Uuid,
+        account_number -> Varchar,
+        name -> Varchar,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     budgets (budget_id) {
         budget_id -> Uuid,
@@ -13,6 +42,51 @@ diesel::table! {
         created_at -> Timestamp,
         updated_at -> Timestamp,
         deleted_at -> Nullable<Timestamp>,
+        alert_thresholds -> Array<Int2>,
+        overspend_policy -> Varchar,
+        over_limit_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    categories (category_id) {
+        category_id -> Uuid,
+        user_id -> Nullable<Uuid>,
+        parent_id -> Nullable<Uuid>,
+        name -> Varchar,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    expense_labels (expense_label_id) {
+        expense_label_id -> Uuid,
+        user_id -> Uuid,
+        expense_id -> Uuid,
+        label_id -> Uuid,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    expense_rules (expense_rule_id) {
+        expense_rule_id -> Uuid,
+        user_id -> Uuid,
+        name -> Varchar,
+        priority -> Int4,
+        narration_contains -> Nullable<Varchar>,
+        narration_pattern -> Nullable<Varchar>,
+        min_amount -> Nullable<Int8>,
+        max_amount -> Nullable<Int8>,
+        category_id -> Nullable<Uuid>,
+        label_ids -> Array<Uuid>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
     }
 }
 
@@ -28,6 +102,42 @@ diesel::table! {
         created_at -> Timestamp,
         updated_at -> Timestamp,
         deleted_at -> Nullable<Timestamp>,
+        category_id -> Nullable<Uuid>,
+        import_id -> Nullable<Uuid>,
+        external_id -> Nullable<Varchar>,
+        external_account -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    imports (import_id) {
+        import_id -> Uuid,
+        user_id -> Uuid,
+        source -> Varchar,
+        file_name -> Varchar,
+        content -> Text,
+        status -> Varchar,
+        committed_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    incomes (income_id) {
+        income_id -> Uuid,
+        user_id -> Uuid,
+        project_id -> Uuid,
+        amount -> Int8,
+        narration -> Varchar,
+        received_at -> Timestamp,
+        import_id -> Nullable<Uuid>,
+        external_id -> Nullable<Varchar>,
+        external_account -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
     }
 }
 
@@ -43,6 +153,21 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    notifications (notification_id) {
+        notification_id -> Uuid,
+        user_id -> Uuid,
+        budget_id -> Nullable<Uuid>,
+        kind -> Varchar,
+        title -> Varchar,
+        message -> Varchar,
+        read_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     project_labels (project_label_id) {
         project_label_id -> Uuid,
@@ -78,23 +203,50 @@ diesel::table! {
         created_at -> Timestamp,
         updated_at -> Timestamp,
         deleted_at -> Nullable<Timestamp>,
+        timezone -> Varchar,
     }
 }
 
+diesel::joinable!(attachments -> expenses (expense_id));
+diesel::joinable!(attachments -> users (user_id));
+diesel::joinable!(bank_accounts -> projects (project_id));
+diesel::joinable!(bank_accounts -> users (user_id));
 diesel::joinable!(budgets -> users (user_id));
+diesel::joinable!(categories -> users (user_id));
+diesel::joinable!(expense_labels -> expenses (expense_id));
+diesel::joinable!(expense_labels -> labels (label_id));
+diesel::joinable!(expense_labels -> users (user_id));
+diesel::joinable!(expense_rules -> categories (category_id));
+diesel::joinable!(expense_rules -> users (user_id));
 diesel::joinable!(expenses -> budgets (budget_id));
+diesel::joinable!(expenses -> categories (category_id));
+diesel::joinable!(expenses -> imports (import_id));
 diesel::joinable!(expenses -> projects (project_id));
 diesel::joinable!(expenses -> users (user_id));
+diesel::joinable!(imports -> users (user_id));
+diesel::joinable!(incomes -> imports (import_id));
+diesel::joinable!(incomes -> projects (project_id));
+diesel::joinable!(incomes -> users (user_id));
 diesel::joinable!(labels -> users (user_id));
+diesel::joinable!(notifications -> budgets (budget_id));
+diesel::joinable!(notifications -> users (user_id));
 diesel::joinable!(project_labels -> labels (label_id));
 diesel::joinable!(project_labels -> projects (project_id));
 diesel::joinable!(project_labels -> users (user_id));
 diesel::joinable!(projects -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    attachments,
+    bank_accounts,
     budgets,
+    categories,
+    expense_labels,
+    expense_rules,
     expenses,
+    imports,
+    incomes,
     labels,
+    notifications,
     project_labels,
     projects,
     users,
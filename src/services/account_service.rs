@@ -0,0 +1,695 @@
+use std::collections::{HashMap, HashSet};
+
+use uuid::Uuid;
+
+use crate::core::enums::app_error::{AppError, AppResult, OrInternal};
+use crate::core::export::archive::{read_archive, write_archive};
+use crate::models::account_archive::{
+    AccountArchive, AccountImportForm, AccountImportResult, ACCOUNT_ARCHIVE_VERSION,
+};
+use crate::models::category::Category;
+use crate::models::label::LabelModule;
+use crate::models::user::User;
+use crate::models::DBPool;
+use crate::repositories::account_repository::AccountRepository;
+use crate::repositories::bank_account_repository::BankAccountRepository;
+use crate::repositories::expense_repository::ExpenseRepository;
+use crate::repositories::income_repository::IncomeRepository;
+use crate::services::expense_rule_service::compile_pattern;
+
+/// Problems reported at once when an archive fails validation
+const MAX_REPORTED_PROBLEMS: usize = 10;
+
+pub struct AccountService;
+
+impl AccountService {
+    pub fn export(&mut self, pool: &DBPool, user: &User) -> AppResult<Vec<u8>> {
+        let archive = AccountRepository.load_archive(pool, user)?;
+
+        write_archive(&archive).or_internal("Failed to write the account archive")
+    }
+
+    /// Recreates the archived records for the given user under new ids, the
+    /// profile in the archive is informational and leaves the user untouched
+    pub fn import(
+        &mut self,
+        pool: &DBPool,
+        user: &User,
+        form: AccountImportForm,
+    ) -> AppResult<AccountImportResult> {
+        let archive = read_archive(&form.file.data)?;
+        let version = archive.manifest.version;
+
+        if version == 0 || version > ACCOUNT_ARCHIVE_VERSION {
+            return Err(AppError::from(format!(
+                "Account archives of version {} are not supported, the latest supported version is {}",
+                version, ACCOUNT_ARCHIVE_VERSION
+            )));
+        }
+
+        let default_categories: HashSet<Uuid> = AccountRepository
+            .list_default_category_ids(pool)?
+            .into_iter()
+            .collect();
+
+        self.validate(pool, user.user_id, &archive, &default_categories)?;
+
+        let imported = archive.counts();
+        let archive = remap(archive, user.user_id, default_categories);
+
+        AccountRepository.restore(pool, archive)?;
+
+        Ok(AccountImportResult { version, imported })
+    }
+
+    /// Checks that every reference in the archive points to a record of the
+    /// archive (or to a default category) and that nothing clashes with the
+    /// data the user already has
+    fn validate(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        archive: &AccountArchive,
+        default_categories: &HashSet<Uuid>,
+    ) -> AppResult<()> {
+        let mut problems: Vec<String> = vec![];
+
+        let projects = unique_ids(
+            "projects",
+            archive.projects.iter().map(|project| project.project_id),
+            &mut problems,
+        );
+        let budgets = unique_ids(
+            "budgets",
+            archive.budgets.iter().map(|budget| budget.budget_id),
+            &mut problems,
+        );
+        let expenses = unique_ids(
+            "expenses",
+            archive.expenses.iter().map(|expense| expense.expense_id),
+            &mut problems,
+        );
+        let categories = unique_ids(
+            "categories",
+            archive
+                .categories
+                .iter()
+                .map(|category| category.category_id),
+            &mut problems,
+        );
+        unique_ids(
+            "labels",
+            archive.labels.iter().map(|label| label.label_id),
+            &mut problems,
+        );
+        unique_ids(
+            "incomes",
+            archive.incomes.iter().map(|income| income.income_id),
+            &mut problems,
+        );
+        unique_ids(
+            "bank_accounts",
+            archive
+                .bank_accounts
+                .iter()
+                .map(|bank_account| bank_account.bank_account_id),
+            &mut problems,
+        );
+        unique_ids(
+            "expense_rules",
+            archive
+                .expense_rules
+                .iter()
+                .map(|rule| rule.expense_rule_id),
+            &mut problems,
+        );
+
+        let label_modules: HashMap<Uuid, &str> = archive
+            .labels
+            .iter()
+            .map(|label| (label.label_id, label.module.as_str()))
+            .collect();
+
+        for label in &archive.labels {
+            if let Err(err) = LabelModule::parse(label.module.as_str()) {
+                problems.push(format!("labels: label {}: {}", label.label_id, err));
+            }
+        }
+
+        for project_label in &archive.project_labels {
+            if !projects.contains(&project_label.project_id) {
+                problems.push(missing_reference(
+                    "project_labels",
+                    project_label.project_label_id,
+                    "project",
+                    project_label.project_id,
+                ));
+            }
+
+            if label_modules.get(&project_label.label_id) != Some(&LabelModule::Projects.as_str()) {
+                problems.push(missing_reference(
+                    "project_labels",
+                    project_label.project_label_id,
+                    "project label",
+                    project_label.label_id,
+                ));
+            }
+        }
+
+        for category in &archive.categories {
+            if let Some(parent_id) = category.parent_id {
+                if !categories.contains(&parent_id) && !default_categories.contains(&parent_id) {
+                    problems.push(missing_reference(
+                        "categories",
+                        category.category_id,
+                        "parent category",
+                        parent_id,
+                    ));
+                }
+            }
+        }
+
+        if order_categories(archive.categories.clone()).is_none() {
+            problems.push(String::from(
+                "categories: the category tree contains a cycle",
+            ));
+        }
+
+        let existing_periods: HashSet<(i16, i16)> = AccountRepository
+            .list_budget_periods(pool, user_id)?
+            .into_iter()
+            .collect();
+
+        let mut periods: HashSet<(i16, i16)> = HashSet::new();
+        for budget in &archive.budgets {
+            if !(1..=12).contains(&budget.month) {
+                problems.push(format!(
+                    "budgets: budget {} has an invalid month {}",
+                    budget.budget_id, budget.month
+                ));
+            }
+
+            if !periods.insert((budget.year, budget.month)) {
+                problems.push(format!("budgets: {} appears more than once", budget.title));
+            }
+
+            if existing_periods.contains(&(budget.year, budget.month)) {
+                problems.push(format!(
+                    "budgets: your account already has the {}",
+                    budget.title
+                ));
+            }
+        }
+
+        for expense in &archive.expenses {
+            if expense.amount <= 0 {
+                problems.push(format!(
+                    "expenses: expense {} has an invalid amount",
+                    expense.expense_id
+                ));
+            }
+
+            if !projects.contains(&expense.project_id) {
+                problems.push(missing_reference(
+                    "expenses",
+                    expense.expense_id,
+                    "project",
+                    expense.project_id,
+                ));
+            }
+
+            if !budgets.contains(&expense.budget_id) {
+                problems.push(missing_reference(
+                    "expenses",
+                    expense.expense_id,
+                    "budget",
+                    expense.budget_id,
+                ));
+            }
+
+            if let Some(category_id) = expense.category_id {
+                if !categories.contains(&category_id) && !default_categories.contains(&category_id)
+                {
+                    problems.push(missing_reference(
+                        "expenses",
+                        expense.expense_id,
+                        "category",
+                        category_id,
+                    ));
+                }
+            }
+        }
+
+        for expense_label in &archive.expense_labels {
+            if !expenses.contains(&expense_label.expense_id) {
+                problems.push(missing_reference(
+                    "expense_labels",
+                    expense_label.expense_label_id,
+                    "expense",
+                    expense_label.expense_id,
+                ));
+            }
+
+            if label_modules.get(&expense_label.label_id) != Some(&LabelModule::Expenses.as_str()) {
+                problems.push(missing_reference(
+                    "expense_labels",
+                    expense_label.expense_label_id,
+                    "expense label",
+                    expense_label.label_id,
+                ));
+            }
+        }
+
+        let external_ids: Vec<String> = archive
+            .expenses
+            .iter()
+            .filter_map(|expense| expense.external_id.clone())
+            .collect();
+
+        if !external_ids.is_empty() {
+            let imported: HashSet<(Option<String>, String)> = ExpenseRepository
+                .list_external_ids(pool, user_id, external_ids)?
+                .into_iter()
+                .collect();
+            let imported = archive
+                .expenses
+                .iter()
+                .filter_map(|expense| {
+                    let id = expense.external_id.clone()?;
+                    imported.get(&(expense.external_account.clone(), id))
+                })
+                .count();
+
+            if imported > 0 {
+                problems.push(format!(
+                    "expenses: {} bank transactions of the archive are already recorded in your account",
+                    imported
+                ));
+            }
+        }
+
+        for income in &archive.incomes {
+            if income.amount <= 0 {
+                problems.push(format!(
+                    "incomes: income {} has an invalid amount",
+                    income.income_id
+                ));
+            }
+
+            if !projects.contains(&income.project_id) {
+                problems.push(missing_reference(
+                    "incomes",
+                    income.income_id,
+                    "project",
+                    income.project_id,
+                ));
+            }
+        }
+
+        let income_external_ids: Vec<String> = archive
+            .incomes
+            .iter()
+            .filter_map(|income| income.external_id.clone())
+            .collect();
+
+        if !income_external_ids.is_empty() {
+            let imported: HashSet<(Option<String>, String)> = IncomeRepository
+                .list_external_ids(pool, user_id, income_external_ids)?
+                .into_iter()
+                .collect();
+            let imported = archive
+                .incomes
+                .iter()
+                .filter_map(|income| {
+                    let id = income.external_id.clone()?;
+                    imported.get(&(income.external_account.clone(), id))
+                })
+                .count();
+
+            if imported > 0 {
+                problems.push(format!(
+                    "incomes: {} bank transactions of the archive are already recorded in your account",
+                    imported
+                ));
+            }
+        }
+
+        let mut account_numbers: HashSet<&str> = HashSet::new();
+        for bank_account in &archive.bank_accounts {
+            if !projects.contains(&bank_account.project_id) {
+                problems.push(missing_reference(
+                    "bank_accounts",
+                    bank_account.bank_account_id,
+                    "project",
+                    bank_account.project_id,
+                ));
+            }
+
+            let account_number = bank_account.account_number.as_str();
+            if !account_numbers.insert(account_number) {
+                problems.push(format!(
+                    "bank_accounts: {} appears more than once",
+                    account_number
+                ));
+            }
+
+            let existing =
+                BankAccountRepository.find_by_account_number(pool, user_id, account_number)?;
+
+            if existing.is_some() {
+                problems.push(format!(
+                    "bank_accounts: your account already links the bank account {}",
+                    account_number
+                ));
+            }
+        }
+
+        for rule in &archive.expense_rules {
+            if let Some(pattern) = &rule.narration_pattern {
+                if let Err(err) = compile_pattern(pattern) {
+                    problems.push(format!(
+                        "expense_rules: rule {} has an invalid narration pattern: {}",
+                        rule.expense_rule_id, err
+                    ));
+                }
+            }
+
+            if let Some(category_id) = rule.category_id {
+                if !categories.contains(&category_id) && !default_categories.contains(&category_id)
+                {
+                    problems.push(missing_reference(
+                        "expense_rules",
+                        rule.expense_rule_id,
+                        "category",
+                        category_id,
+                    ));
+                }
+            }
+
+            for label_id in &rule.label_ids {
+                if label_modules.get(label_id) != Some(&LabelModule::Expenses.as_str()) {
+                    problems.push(missing_reference(
+                        "expense_rules",
+                        rule.expense_rule_id,
+                        "expense label",
+                        *label_id,
+                    ));
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+
+        let total = problems.len();
+        problems.truncate(MAX_REPORTED_PROBLEMS);
+
+        Err(AppError::from(format!(
+            "The account archive failed validation ({} problems): {}",
+            total,
+            problems.join("; ")
+        )))
+    }
+}
+
+/// New ids handed out to archived records, default categories keep theirs
+struct IdMap {
+    ids: HashMap<Uuid, Uuid>,
+    kept: HashSet<Uuid>,
+}
+
+impl IdMap {
+    fn get(&mut self, id: Uuid) -> Uuid {
+        if self.kept.contains(&id) {
+            return id;
+        }
+
+        *self.ids.entry(id).or_insert_with(Uuid::new_v4)
+    }
+}
+
+/// Gives every archived record a new id owned by the user, keeping the links
+/// between them. What the budgets have used is recomputed from their live
+/// expenses, the archived figure may not add up
+fn remap(
+    archive: AccountArchive,
+    user_id: Uuid,
+    default_categories: HashSet<Uuid>,
+) -> AccountArchive {
+    let mut ids = IdMap {
+        ids: HashMap::new(),
+        kept: default_categories,
+    };
+
+    let projects = archive
+        .projects
+        .into_iter()
+        .map(|mut project| {
+            project.project_id = ids.get(project.project_id);
+            project.user_id = user_id;
+            project
+        })
+        .collect();
+
+    let labels = archive
+        .labels
+        .into_iter()
+        .map(|mut label| {
+            label.label_id = ids.get(label.label_id);
+            label.user_id = user_id;
+            label
+        })
+        .collect();
+
+    let project_labels = archive
+        .project_labels
+        .into_iter()
+        .map(|mut project_label| {
+            project_label.project_label_id = ids.get(project_label.project_label_id);
+            project_label.project_id = ids.get(project_label.project_id);
+            project_label.label_id = ids.get(project_label.label_id);
+            project_label.user_id = user_id;
+            project_label
+        })
+        .collect();
+
+    let categories = order_categories(archive.categories)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|mut category| {
+            category.category_id = ids.get(category.category_id);
+            category.parent_id = category.parent_id.map(|id| ids.get(id));
+            category.user_id = Some(user_id);
+            category
+        })
+        .collect();
+
+    let mut spent: HashMap<Uuid, i64> = HashMap::new();
+    for expense in archive.expenses.iter() {
+        if expense.deleted_at.is_none() {
+            *spent.entry(expense.budget_id).or_insert(0) += expense.amount;
+        }
+    }
+
+    let budgets = archive
+        .budgets
+        .into_iter()
+        .map(|mut budget| {
+            budget.amount_used = spent.get(&budget.budget_id).copied().unwrap_or(0);
+            budget.budget_id = ids.get(budget.budget_id);
+            budget.user_id = user_id;
+            budget
+        })
+        .collect();
+
+    let expenses = archive
+        .expenses
+        .into_iter()
+        .map(|mut expense| {
+            expense.expense_id = ids.get(expense.expense_id);
+            expense.project_id = ids.get(expense.project_id);
+            expense.budget_id = ids.get(expense.budget_id);
+            expense.category_id = expense.category_id.map(|id| ids.get(id));
+            expense.user_id = user_id;
+            expense.import_id = None;
+            expense
+        })
+        .collect();
+
+    let expense_labels = archive
+        .expense_labels
+        .into_iter()
+        .map(|mut expense_label| {
+            expense_label.expense_label_id = ids.get(expense_label.expense_label_id);
+            expense_label.expense_id = ids.get(expense_label.expense_id);
+            expense_label.label_id = ids.get(expense_label.label_id);
+            expense_label.user_id = user_id;
+            expense_label
+        })
+        .collect();
+
+    let incomes = archive
+        .incomes
+        .into_iter()
+        .map(|mut income| {
+            income.income_id = ids.get(income.income_id);
+            income.project_id = ids.get(income.project_id);
+            income.user_id = user_id;
+            income.import_id = None;
+            income
+        })
+        .collect();
+
+    let bank_accounts = archive
+        .bank_accounts
+        .into_iter()
+        .map(|mut bank_account| {
+            bank_account.bank_account_id = ids.get(bank_account.bank_account_id);
+            bank_account.project_id = ids.get(bank_account.project_id);
+            bank_account.user_id = user_id;
+            bank_account
+        })
+        .collect();
+
+    let expense_rules = archive
+        .expense_rules
+        .into_iter()
+        .map(|mut rule| {
+            rule.expense_rule_id = ids.get(rule.expense_rule_id);
+            rule.category_id = rule.category_id.map(|id| ids.get(id));
+            rule.label_ids = rule.label_ids.into_iter().map(|id| ids.get(id)).collect();
+            rule.user_id = user_id;
+            rule
+        })
+        .collect();
+
+    AccountArchive {
+        manifest: archive.manifest,
+        profile: archive.profile,
+        projects,
+        labels,
+        project_labels,
+        categories,
+        budgets,
+        expenses,
+        expense_labels,
+        incomes,
+        bank_accounts,
+        expense_rules,
+    }
+}
+
+/// Sorts categories so parents always come before their children, `None`
+/// when the parent links form a cycle
+fn order_categories(mut pending: Vec<Category>) -> Option<Vec<Category>> {
+    let archived: HashSet<Uuid> = pending
+        .iter()
+        .map(|category| category.category_id)
+        .collect();
+    let mut placed: HashSet<Uuid> = HashSet::new();
+    let mut ordered: Vec<Category> = vec![];
+
+    while !pending.is_empty() {
+        let (ready, waiting): (Vec<Category>, Vec<Category>) =
+            pending.into_iter().partition(|category| {
+                category.parent_id.is_none_or(|parent_id| {
+                    !archived.contains(&parent_id) || placed.contains(&parent_id)
+                })
+            });
+
+        if ready.is_empty() {
+            return None;
+        }
+
+        placed.extend(ready.iter().map(|category| category.category_id));
+        ordered.extend(ready);
+        pending = waiting;
+    }
+
+    Some(ordered)
+}
+
+fn unique_ids(
+    entity: &str,
+    ids: impl Iterator<Item = Uuid>,
+    problems: &mut Vec<String>,
+) -> HashSet<Uuid> {
+    let mut seen = HashSet::new();
+    for id in ids {
+        if !seen.insert(id) {
+            problems.push(format!("{}: {} appears more than once", entity, id));
+        }
+    }
+
+    seen
+}
+
+fn missing_reference(entity: &str, id: Uuid, reference: &str, reference_id: Uuid) -> String {
+    format!(
+        "{}: record {} references {} {} that is not in the archive",
+        entity, id, reference, reference_id
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::core::helpers::testing::{make_budget, make_expense};
+    use crate::models::account_archive::{AccountArchiveManifest, AccountProfile};
+
+    fn archive() -> AccountArchive {
+        let now = Utc::now().naive_utc();
+        AccountArchive {
+            manifest: AccountArchiveManifest {
+                version: ACCOUNT_ARCHIVE_VERSION,
+                exported_at: now,
+                user_id: Uuid::new_v4(),
+                counts: Default::default(),
+            },
+            profile: AccountProfile {
+                first_name: String::from("Ada"),
+                last_name: String::from("Lovelace"),
+                email: String::from("ada@example.com"),
+                timezone: String::from("UTC"),
+                created_at: now,
+            },
+            projects: vec![],
+            labels: vec![],
+            project_labels: vec![],
+            categories: vec![],
+            budgets: vec![],
+            expenses: vec![],
+            expense_labels: vec![],
+            incomes: vec![],
+            bank_accounts: vec![],
+            expense_rules: vec![],
+        }
+    }
+
+    #[test]
+    fn budget_usage_is_recomputed_from_live_expenses() {
+        let spent = make_budget(1000, 999, 10, 2026);
+        let untouched = make_budget(1000, 500, 11, 2026);
+        let mut trashed = make_expense(&spent, 50);
+        trashed.deleted_at = Some(Utc::now().naive_utc());
+
+        let mut archive = archive();
+        archive.expenses = vec![make_expense(&spent, 120), make_expense(&spent, 30), trashed];
+        archive.budgets = vec![spent.clone(), untouched.clone()];
+
+        let user_id = Uuid::new_v4();
+        let remapped = remap(archive, user_id, HashSet::new());
+
+        assert_eq!(remapped.budgets[0].amount_used, 150);
+        assert_eq!(remapped.budgets[1].amount_used, 0);
+        assert_ne!(remapped.budgets[0].budget_id, spent.budget_id);
+        assert_eq!(remapped.budgets[0].user_id, user_id);
+        assert!(remapped
+            .expenses
+            .iter()
+            .all(|expense| expense.budget_id == remapped.budgets[0].budget_id));
+    }
+}
@@ -0,0 +1,373 @@
+use std::env;
+
+use uuid::Uuid;
+
+use crate::core::enums::app_error::{AppError, AppResult};
+use crate::core::helpers::db::current_timestamp;
+use crate::core::helpers::image::{make_thumbnail, sniff_content_type};
+use crate::core::storage::{make_storage, Storage};
+use crate::models::attachment::{Attachment, AttachmentUploadForm};
+use crate::models::expense::Expense;
+use crate::models::DBPool;
+use crate::repositories::attachment_repository::AttachmentRepository;
+
+pub const ALLOWED_CONTENT_TYPES: [&str; 5] = [
+    "image/jpeg",
+    "image/png",
+    "image/gif",
+    "image/webp",
+    "application/pdf",
+];
+
+/// Largest accepted upload, set in megabytes through `ATTACHMENT_MAX_SIZE_MB` (10 by default)
+pub fn max_attachment_size() -> usize {
+    let megabytes = env::var("ATTACHMENT_MAX_SIZE_MB")
+        .ok()
+        .and_then(|size| size.parse::<usize>().ok())
+        .unwrap_or(10);
+
+    megabytes * 1024 * 1024
+}
+
+pub struct AttachmentService;
+
+impl AttachmentService {
+    /// Stores the uploaded file (and a thumbnail of images) and records it
+    /// against the expense, the stored files are removed again when recording
+    /// fails. Storing and resizing block, so this runs off the request thread
+    pub fn upload(
+        &mut self,
+        pool: &DBPool,
+        expense: &Expense,
+        form: AttachmentUploadForm,
+    ) -> AppResult<Attachment> {
+        let data = form.file.data;
+        if data.is_empty() {
+            return Err(AppError::from("The uploaded file is empty"));
+        }
+
+        if data.len() > max_attachment_size() {
+            return Err(AppError::BadRequest(format!(
+                "Attachments may not be larger than {}MB",
+                max_attachment_size() / 1024 / 1024
+            )));
+        }
+
+        // the declared type is only trusted when the content agrees with it
+        let content_type = match sniff_content_type(&data) {
+            Some(content_type) => content_type,
+            None => {
+                return Err(AppError::BadRequest(format!(
+                    "Unsupported file type, attachments must be one of {}",
+                    ALLOWED_CONTENT_TYPES.join(", ")
+                )))
+            }
+        };
+
+        if let Some(declared) = &form.file.content_type {
+            let declared = declared.essence_str();
+            if declared != "application/octet-stream" && declared != content_type {
+                return Err(AppError::BadRequest(format!(
+                    "The file was sent as {} but its content is {}",
+                    declared, content_type
+                )));
+            }
+        }
+
+        let attachment_id = Uuid::new_v4();
+        let storage_key = format!("attachments/{}/{}", expense.user_id, attachment_id);
+        let storage = make_storage();
+
+        storage
+            .put(storage_key.as_str(), content_type, &data)
+            .map_err(|err| {
+                AppError::Internal(format!(
+                    "Failed to store {} in {} storage: {}",
+                    storage_key,
+                    storage.name(),
+                    err
+                ))
+            })?;
+
+        let thumbnail_key = match content_type.starts_with("image/") {
+            true => {
+                let key = format!("{}-thumbnail.png", storage_key);
+                match make_thumbnail(&data)
+                    .and_then(|thumbnail| storage.put(key.as_str(), "image/png", &thumbnail))
+                {
+                    Ok(_) => Some(key),
+                    Err(err) => {
+                        log::warn!("Failed to create thumbnail of {}: {}", attachment_id, err);
+                        None
+                    }
+                }
+            }
+            false => None,
+        };
+
+        let file_name = form
+            .file
+            .file_name
+            .clone()
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or(attachment_id.to_string());
+
+        let created = AttachmentRepository.create(
+            pool,
+            Attachment {
+                attachment_id,
+                user_id: expense.user_id,
+                expense_id: expense.expense_id,
+                file_name,
+                content_type: content_type.to_string(),
+                size: data.len() as i64,
+                storage_key: storage_key.clone(),
+                thumbnail_key: thumbnail_key.clone(),
+                created_at: current_timestamp(),
+                updated_at: current_timestamp(),
+                deleted_at: None,
+            },
+        );
+
+        if created.is_err() {
+            remove_files(
+                storage.as_ref(),
+                [Some(&storage_key), thumbnail_key.as_ref()],
+            );
+        }
+
+        created
+    }
+
+    /// Content and content type of the file or of its thumbnail
+    pub fn download(
+        &mut self,
+        attachment: &Attachment,
+        thumbnail: bool,
+    ) -> AppResult<(Vec<u8>, String)> {
+        let storage = make_storage();
+        let read = |key: &str| {
+            storage.get(key).map_err(|err| {
+                AppError::Internal(format!(
+                    "Failed to read {} from {} storage: {}",
+                    key,
+                    storage.name(),
+                    err
+                ))
+            })
+        };
+
+        if !thumbnail {
+            let data = read(attachment.storage_key.as_str())?;
+            return Ok((data, attachment.content_type.clone()));
+        }
+
+        match &attachment.thumbnail_key {
+            Some(key) => Ok((read(key.as_str())?, String::from("image/png"))),
+            None => Err(AppError::from("This attachment has no thumbnail")),
+        }
+    }
+
+    /// Deletes the attachment along with its stored file and thumbnail
+    pub fn delete(&mut self, pool: &DBPool, attachment: &Attachment) -> AppResult<()> {
+        let attachment =
+            AttachmentRepository.delete(pool, attachment.attachment_id, attachment.user_id)?;
+
+        remove_files(
+            make_storage().as_ref(),
+            [
+                Some(&attachment.storage_key),
+                attachment.thumbnail_key.as_ref(),
+            ],
+        );
+
+        Ok(())
+    }
+}
+
+/// Removing stored files is best effort, leftovers are only logged
+fn remove_files<'a>(storage: &dyn Storage, keys: impl IntoIterator<Item = Option<&'a String>>) {
+    for key in keys.into_iter().flatten() {
+        if let Err(err) = storage.delete(key.as_str()) {
+            log::warn!("Failed to remove stored file {}: {}", key, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use actix_multipart::form::bytes::Bytes;
+    use actix_web::http::header::ContentType;
+    use image::{ImageOutputFormat, RgbImage};
+
+    use super::*;
+    use crate::core::helpers::testing::{create_budget, create_user, test_pool, use_test_storage};
+    use crate::models::budget::OverspendPolicy;
+    use crate::models::expense::ExpenseForm;
+    use crate::models::project::ProjectForm;
+    use crate::models::trash::TrashKind;
+    use crate::repositories::expense_repository::ExpenseRepository;
+    use crate::repositories::project_repository::ProjectRepository;
+    use crate::services::trash_service::TrashService;
+
+    fn create_expense(pool: &DBPool) -> Expense {
+        let user = create_user(pool);
+        let budget = create_budget(pool, user.user_id, 1000, OverspendPolicy::Allow);
+        let project = ProjectRepository
+            .create(
+                pool,
+                user.user_id,
+                ProjectForm {
+                    name: String::from("Household"),
+                    description: String::new(),
+                },
+            )
+            .unwrap();
+        let form = ExpenseForm {
+            project_id: project.project_id.to_string(),
+            amount: 10,
+            narration: String::from("Groceries"),
+            spent_at: None,
+            category_id: None,
+        };
+        let (expense, _, _) = ExpenseRepository
+            .create(pool, user.user_id, "UTC", budget.budget_id, form)
+            .unwrap();
+
+        expense
+    }
+
+    fn png() -> Vec<u8> {
+        let mut data = Cursor::new(vec![]);
+        RgbImage::new(400, 300)
+            .write_to(&mut data, ImageOutputFormat::Png)
+            .unwrap();
+        data.into_inner()
+    }
+
+    fn upload_form(data: Vec<u8>, content_type: Option<ContentType>) -> AttachmentUploadForm {
+        AttachmentUploadForm {
+            file: Bytes {
+                data: data.into(),
+                content_type: content_type.map(|content_type| content_type.0),
+                file_name: Some(String::from("receipt")),
+            },
+        }
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn uploads_are_checked_before_anything_is_stored() {
+        let pool = test_pool();
+        let storage = use_test_storage();
+        let expense = create_expense(&pool);
+
+        let mut too_large = png();
+        too_large.resize(max_attachment_size() + 1, 0);
+        let pdf = b"%PDF-1.7 receipt".to_vec();
+
+        for (data, content_type) in [
+            (too_large, None),
+            (vec![], None),
+            (b"plain text receipt".to_vec(), None),
+            (pdf.clone(), Some(ContentType::png())),
+        ] {
+            let uploaded =
+                AttachmentService.upload(&pool, &expense, upload_form(data, content_type));
+            assert!(matches!(uploaded, Err(AppError::BadRequest(_))));
+        }
+
+        let listed = AttachmentRepository
+            .list_by_expense_id(&pool, expense.expense_id, expense.user_id)
+            .unwrap();
+        assert!(listed.is_empty());
+        assert!(!storage
+            .join(format!("attachments/{}", expense.user_id))
+            .exists());
+
+        // a generic declared type is left to the sniffing
+        let attachment = AttachmentService
+            .upload(
+                &pool,
+                &expense,
+                upload_form(pdf, Some(ContentType::octet_stream())),
+            )
+            .unwrap();
+        assert_eq!(attachment.content_type, "application/pdf");
+        assert_eq!(attachment.thumbnail_key, None);
+        assert!(storage.join(&attachment.storage_key).exists());
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn stored_files_are_removed_when_recording_fails() {
+        let pool = test_pool();
+        let storage = use_test_storage();
+        let expense = Expense {
+            // no such expense, so the attachment cannot be recorded
+            expense_id: Uuid::new_v4(),
+            ..create_expense(&pool)
+        };
+
+        let uploaded = AttachmentService.upload(&pool, &expense, upload_form(png(), None));
+        assert!(uploaded.is_err());
+
+        let directory = storage.join(format!("attachments/{}", expense.user_id));
+        let stored = std::fs::read_dir(directory)
+            .map(|entries| entries.count())
+            .unwrap_or(0);
+        assert_eq!(stored, 0);
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn images_get_a_thumbnail_removed_along_with_them() {
+        let pool = test_pool();
+        let storage = use_test_storage();
+        let expense = create_expense(&pool);
+
+        let attachment = AttachmentService
+            .upload(
+                &pool,
+                &expense,
+                upload_form(png(), Some(ContentType::png())),
+            )
+            .unwrap();
+        let thumbnail_key = attachment.thumbnail_key.clone().unwrap();
+
+        let (original, content_type) = AttachmentService.download(&attachment, false).unwrap();
+        assert_eq!((original, content_type.as_str()), (png(), "image/png"));
+
+        let (thumbnail, content_type) = AttachmentService.download(&attachment, true).unwrap();
+        assert_eq!(content_type, "image/png");
+        let thumbnail = image::load_from_memory(&thumbnail).unwrap();
+        assert!(thumbnail.width() < 400 && thumbnail.width() * 3 == thumbnail.height() * 4);
+
+        AttachmentService.delete(&pool, &attachment).unwrap();
+        assert!(AttachmentRepository
+            .find_owned_by_id(&pool, attachment.attachment_id, expense.user_id)
+            .is_err());
+        assert!(!storage.join(&attachment.storage_key).exists());
+        assert!(!storage.join(&thumbnail_key).exists());
+
+        // purging the expense takes its remaining attachments along
+        let attachment = AttachmentService
+            .upload(&pool, &expense, upload_form(png(), None))
+            .unwrap();
+        ExpenseRepository
+            .delete(&pool, expense.expense_id, expense.user_id)
+            .unwrap();
+        TrashService
+            .purge(
+                &pool,
+                expense.user_id,
+                TrashKind::Expense,
+                expense.expense_id,
+            )
+            .unwrap();
+        assert!(!storage.join(&attachment.storage_key).exists());
+        assert!(!storage.join(attachment.thumbnail_key.unwrap()).exists());
+    }
+}
@@ -1,8 +1,10 @@
+use crate::core::enums::app_error::{AppError, AppResult};
 use crate::core::helpers::string::password_verify;
 use crate::models::user::UserStatus;
 use crate::models::DBPool;
 use crate::repositories::user_repository::{user_status_is, UserRepository};
 use chrono::{Duration, Utc};
+use diesel::result::Error as DieselError;
 use jsonwebtoken::{encode, EncodingKey, Header};
 use serde::{Deserialize, Serialize};
 use std::env;
@@ -16,6 +18,15 @@ pub struct AuthAccessData {
     pub expires_in: i64,
 }
 
+/// Login result of cookie sessions, the token itself only travels in the cookie
+#[derive(Serialize)]
+pub struct CookieAuthData {
+    pub token_type: String,
+    pub expires_in: i64,
+    /// to be echoed back in the X-CSRF-Token header of state-changing requests
+    pub csrf_token: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TokenClaims {
     pub sub: String,
@@ -29,30 +40,40 @@ impl AuthService {
         pool: &DBPool,
         email: String,
         password: String,
-    ) -> Result<AuthAccessData, String> {
-        let user_lookup = UserRepository.find_by_email(pool, email);
-        let context_less_error_message = Err(String::from("Invalid email address or password"));
-
-        if user_lookup.is_err() {
-            return context_less_error_message;
-        }
+    ) -> AppResult<AuthAccessData> {
+        let context_less_error =
+            || AppError::Unauthorized(String::from("Invalid email address or password"));
 
-        let user = user_lookup.unwrap();
+        let user = match UserRepository.find_by_email(pool, email) {
+            Ok(user) => user,
+            Err(DieselError::NotFound) => return Err(context_less_error()),
+            Err(err) => return Err(AppError::from(err)),
+        };
 
         if !password_verify(user.password.as_str(), password.as_str()) {
-            return context_less_error_message;
+            return Err(context_less_error());
         }
 
         if user_status_is(user.status.to_owned(), UserStatus::Pending) {
-            return Err(String::from("Your account is not activated yet"));
+            return Err(AppError::Unauthorized(String::from(
+                "Your account is not activated yet",
+            )));
         }
 
         if user_status_is(user.status, UserStatus::Inactive) {
-            return Err(String::from("Your account is not active"));
+            return Err(AppError::Unauthorized(String::from(
+                "Your account is not active",
+            )));
         }
 
-        let token_lifetime_in_minutes: i64 =
-            env::var("AUTH_TOKEN_LIFETIME").unwrap().parse().unwrap();
+        let token_lifetime_in_minutes: i64 = env::var("AUTH_TOKEN_LIFETIME")
+            .ok()
+            .and_then(|lifetime| lifetime.parse().ok())
+            .ok_or_else(|| {
+                AppError::Internal(String::from("AUTH_TOKEN_LIFETIME is not a number"))
+            })?;
+        let key = env::var("APP_KEY")
+            .map_err(|_| AppError::Internal(String::from("APP_KEY is not set")))?;
 
         let now = Utc::now();
         let iat = now.timestamp() as usize;
@@ -66,9 +87,9 @@ impl AuthService {
         let token = encode(
             &Header::default(),
             &claims,
-            &EncodingKey::from_secret(env::var("APP_KEY").unwrap().as_ref()),
+            &EncodingKey::from_secret(key.as_ref()),
         )
-        .unwrap();
+        .map_err(|e| AppError::Internal(format!("Failed to sign the auth token: {}", e)))?;
 
         Ok(AuthAccessData {
             access_token: token,
@@ -0,0 +1,56 @@
+use uuid::Uuid;
+
+use crate::core::enums::app_error::{AppError, AppResult};
+use crate::models::bank_account::{BankAccount, BankAccountForm};
+use crate::models::DBPool;
+use crate::repositories::bank_account_repository::BankAccountRepository;
+use crate::services::project_service::ProjectService;
+
+pub struct BankAccountService;
+
+impl BankAccountService {
+    pub fn create(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        form: BankAccountForm,
+    ) -> AppResult<BankAccount> {
+        self.validate(pool, user_id, None, &form)?;
+        BankAccountRepository.create(pool, user_id, form)
+    }
+
+    pub fn update(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+        form: BankAccountForm,
+    ) -> AppResult<BankAccount> {
+        self.validate(pool, user_id, Some(id), &form)?;
+        BankAccountRepository.update(pool, id, user_id, form)
+    }
+
+    fn validate(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        id: Option<Uuid>,
+        form: &BankAccountForm,
+    ) -> AppResult<()> {
+        ProjectService.find_owned_project_id(pool, user_id, form.project_id.as_str())?;
+
+        let existing = BankAccountRepository.find_by_account_number(
+            pool,
+            user_id,
+            form.account_number.as_str(),
+        )?;
+
+        match existing {
+            Some(account) if Some(account.bank_account_id) != id => Err(AppError::from(format!(
+                "Bank account \"{}\" is already mapped to a project",
+                form.account_number
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
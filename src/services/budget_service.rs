@@ -1,9 +1,13 @@
-use crate::core::enums::http_error::DBResult;
+use crate::core::enums::app_error::{AppError, AppResult};
+use crate::core::helpers::db::current_timestamp;
 use crate::core::helpers::get_db_conn;
-use crate::models::budget::{Budget, BudgetForm};
+use crate::models::budget::{Budget, BudgetForm, OverspendPolicy};
+use crate::models::expense::ExpenseCascade;
+use crate::models::notification::NotificationEvent;
 use crate::models::DBPool;
 use crate::repositories::budget_repository::BudgetRepository;
 use crate::schema::budgets;
+use crate::services::notification_service::NotificationService;
 use diesel::{ExpressionMethods, QueryResult, RunQueryDsl};
 use std::ops::DerefMut;
 use uuid::Uuid;
@@ -11,7 +15,7 @@ use uuid::Uuid;
 pub struct BudgetService;
 
 impl BudgetService {
-    pub fn create(&mut self, pool: &DBPool, user_id: Uuid, form: BudgetForm) -> Budget {
+    pub fn create(&mut self, pool: &DBPool, user_id: Uuid, form: BudgetForm) -> AppResult<Budget> {
         BudgetRepository.create(pool, user_id, form)
     }
 
@@ -21,24 +25,275 @@ impl BudgetService {
         id: Uuid,
         user_id: Uuid,
         form: BudgetForm,
-    ) -> DBResult<Budget> {
-        BudgetRepository.update(pool, id, user_id, form)
+    ) -> AppResult<Budget> {
+        let (budget, updated) = BudgetRepository.update(pool, id, user_id, form)?;
+        Ok(self.after_spending(pool, &budget, updated))
     }
 
-    pub fn decrement(
-        &mut self,
-        pool: &DBPool,
-        budget: &Budget,
-        amount: i64,
-    ) -> QueryResult<Budget> {
-        let used_amount = budget.amount_used + amount;
+    pub fn flag_over_limit(&mut self, pool: &DBPool, budget: &Budget) -> QueryResult<Budget> {
         diesel::update(budgets::table)
             .filter(budgets::budget_id.eq(budget.budget_id))
-            .set(budgets::amount_used.eq(used_amount))
+            .set(budgets::over_limit_at.eq(current_timestamp()))
             .get_result::<Budget>(get_db_conn(pool).deref_mut())
     }
 
-    pub fn delete(&mut self, pool: &DBPool, id: Uuid, user_id: Uuid) -> DBResult<Budget> {
-        BudgetRepository.delete(pool, id, user_id)
+    /// Flags the budget once spending or a lowered amount pushed it over the limit (when
+    /// its policy asks for it) and dispatches alerts for every threshold crossed between
+    /// both states
+    pub fn after_spending(&mut self, pool: &DBPool, before: &Budget, after: Budget) -> Budget {
+        let mut after = after;
+        if after.overspent_amount() > 0
+            && after.overspend_policy() == OverspendPolicy::AllowAndFlag
+            && after.over_limit_at.is_none()
+        {
+            match self.flag_over_limit(pool, &after) {
+                Ok(flagged) => after = flagged,
+                Err(err) => log::error!("Failed to flag budget as over limit: {}", err),
+            }
+        }
+
+        for threshold in after.crossed_thresholds(before) {
+            let event = NotificationEvent::BudgetThresholdCrossed {
+                budget: after.clone(),
+                threshold,
+            };
+
+            if let Err(err) = NotificationService.dispatch(pool, event) {
+                log::error!("Failed to record budget threshold notification: {}", err);
+            }
+        }
+
+        after
+    }
+
+    /// Deletes the budget, the budget its expenses are moved to gets the usual
+    /// over limit flag and threshold alerts
+    pub fn delete(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+        cascade: ExpenseCascade,
+    ) -> AppResult<Budget> {
+        if cascade == ExpenseCascade::Move(id) {
+            return Err(AppError::from(
+                "Expenses can't be moved to the budget being deleted",
+            ));
+        }
+
+        let (budget, moved) = BudgetRepository.delete(pool, id, user_id, cascade)?;
+        if let Some((target, updated_target)) = moved {
+            self.after_spending(pool, &target, updated_target);
+        }
+
+        Ok(budget)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::core::helpers::http::QueryParams;
+    use crate::core::helpers::testing::{create_budget, create_user, test_pool};
+    use crate::models::expense::ExpenseForm;
+    use crate::models::project::ProjectForm;
+    use crate::repositories::budget_repository::charge_budget;
+    use crate::repositories::expense_repository::ExpenseRepository;
+    use crate::repositories::notification_repository::NotificationRepository;
+    use crate::repositories::project_repository::ProjectRepository;
+
+    fn spend(pool: &DBPool, budget: &Budget, amount: i64) -> Budget {
+        let after = charge_budget(get_db_conn(pool).deref_mut(), budget.budget_id, amount).unwrap();
+        BudgetService.after_spending(pool, budget, after)
+    }
+
+    fn create_project(pool: &DBPool, user_id: Uuid) -> Uuid {
+        ProjectRepository
+            .create(
+                pool,
+                user_id,
+                ProjectForm {
+                    name: String::from("Household"),
+                    description: String::new(),
+                },
+            )
+            .unwrap()
+            .project_id
+    }
+
+    fn add_expense(
+        pool: &DBPool,
+        user_id: Uuid,
+        budget_id: Uuid,
+        project_id: Uuid,
+        amount: i64,
+    ) -> AppResult<Budget> {
+        let form = ExpenseForm {
+            project_id: project_id.to_string(),
+            amount,
+            narration: String::from("Groceries"),
+            spent_at: None,
+            category_id: None,
+        };
+
+        ExpenseRepository
+            .create(pool, user_id, "UTC", budget_id, form)
+            .map(|(_, _, budget)| budget)
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn crossing_thresholds_records_notifications() {
+        let pool = test_pool();
+        let user = create_user(&pool);
+        let budget = create_budget(&pool, user.user_id, 1000, OverspendPolicy::Allow);
+
+        let budget = spend(&pool, &budget, 500);
+        let budget = spend(&pool, &budget, 350);
+        let budget = spend(&pool, &budget, 100);
+        let budget = spend(&pool, &budget, 200);
+        assert!(budget.over_limit_at.is_none());
+
+        let notifications = NotificationRepository
+            .list_by_user_id(&pool, user.user_id, true, QueryParams::default())
+            .unwrap()
+            .records;
+
+        let mut titles: Vec<&str> = notifications
+            .iter()
+            .map(|notification| notification.title.as_str())
+            .collect();
+        titles.sort_unstable();
+        assert_eq!(
+            titles,
+            [
+                format!("{} reached 100%", budget.title),
+                format!("{} reached 80%", budget.title)
+            ]
+        );
+        assert!(notifications
+            .iter()
+            .all(|notification| notification.budget_id == Some(budget.budget_id)));
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn overspending_flags_budgets_asking_for_it() {
+        let pool = test_pool();
+        let user = create_user(&pool);
+
+        let flagged = create_budget(&pool, user.user_id, 100, OverspendPolicy::AllowAndFlag);
+        let flagged = spend(&pool, &flagged, 100);
+        assert!(flagged.over_limit_at.is_none());
+        let flagged = spend(&pool, &flagged, 1);
+        assert!(flagged.over_limit_at.is_some());
+
+        let allowed = create_budget(&pool, user.user_id, 100, OverspendPolicy::Allow);
+        let allowed = spend(&pool, &allowed, 150);
+        assert!(allowed.over_limit_at.is_none());
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn lowering_the_amount_flags_and_alerts() {
+        let pool = test_pool();
+        let user = create_user(&pool);
+        let budget = create_budget(&pool, user.user_id, 1000, OverspendPolicy::AllowAndFlag);
+        let budget = spend(&pool, &budget, 500);
+
+        let form = |amount| BudgetForm {
+            amount,
+            month: budget.month,
+            year: budget.year,
+            comment: None,
+            alert_thresholds: None,
+            overspend_policy: None,
+        };
+
+        let lowered = BudgetService
+            .update(&pool, budget.budget_id, user.user_id, form(400))
+            .unwrap();
+        assert_eq!(lowered.amount, 400);
+        assert_eq!(lowered.overspend_policy(), OverspendPolicy::AllowAndFlag);
+        assert!(lowered.over_limit_at.is_some());
+
+        let notifications = NotificationRepository
+            .list_by_user_id(&pool, user.user_id, true, QueryParams::default())
+            .unwrap()
+            .records;
+        assert_eq!(notifications.len(), 2);
+
+        let raised = BudgetService
+            .update(&pool, budget.budget_id, user.user_id, form(600))
+            .unwrap();
+        assert!(raised.over_limit_at.is_none());
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn moving_expenses_charges_and_flags_the_target() {
+        let pool = test_pool();
+        let user = create_user(&pool);
+        let project_id = create_project(&pool, user.user_id);
+        let source = create_budget(&pool, user.user_id, 1000, OverspendPolicy::Allow);
+        let target = create_budget(&pool, user.user_id, 50, OverspendPolicy::AllowAndFlag);
+        add_expense(&pool, user.user_id, source.budget_id, project_id, 80).unwrap();
+
+        let deleted = BudgetService
+            .delete(
+                &pool,
+                source.budget_id,
+                user.user_id,
+                ExpenseCascade::Move(target.budget_id),
+            )
+            .unwrap();
+        assert!(deleted.deleted_at.is_some());
+        assert_eq!(deleted.amount_used, 0);
+
+        let target = BudgetRepository
+            .find_owned_by_id(&pool, target.budget_id, user.user_id)
+            .unwrap();
+        assert_eq!(target.amount_used, 80);
+        assert!(target.over_limit_at.is_some());
+
+        let notifications = NotificationRepository
+            .list_by_user_id(&pool, user.user_id, true, QueryParams::default())
+            .unwrap()
+            .records;
+        assert_eq!(notifications.len(), 2);
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn spending_cannot_slip_into_a_deleted_budget() {
+        let pool = test_pool();
+        let user = create_user(&pool);
+        let project_id = create_project(&pool, user.user_id);
+
+        for _ in 0..10 {
+            let budget = create_budget(&pool, user.user_id, 1000, OverspendPolicy::Allow);
+
+            let spending = {
+                let (pool, user_id, budget_id) = (pool.clone(), user.user_id, budget.budget_id);
+                thread::spawn(move || add_expense(&pool, user_id, budget_id, project_id, 10))
+            };
+            let deleted = BudgetService.delete(
+                &pool,
+                budget.budget_id,
+                user.user_id,
+                ExpenseCascade::Refuse,
+            );
+            let spent = spending.join().unwrap();
+
+            // either the expense got in first and the delete is refused, or it finds no budget
+            assert_ne!(deleted.is_ok(), spent.is_ok());
+
+            let live = ExpenseRepository
+                .list_all_by_budget_id(&pool, budget.budget_id, user.user_id)
+                .unwrap();
+            assert_eq!(live.len(), spent.is_ok() as usize);
+        }
     }
 }
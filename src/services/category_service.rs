@@ -0,0 +1,137 @@
+use uuid::Uuid;
+
+use crate::core::enums::app_error::{AppResult, MissingAs};
+use crate::core::helpers::form::parse_optional_uuid;
+use crate::models::category::{Category, CategoryForm};
+use crate::models::DBPool;
+use crate::repositories::category_repository::CategoryRepository;
+
+pub struct CategoryService;
+
+impl CategoryService {
+    pub fn create(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        form: CategoryForm,
+    ) -> AppResult<Category> {
+        let parent_id = self.find_category_id(pool, user_id, form.parent_id, "parent category")?;
+        CategoryRepository.create(pool, user_id, parent_id, form.name)
+    }
+
+    pub fn update(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+        form: CategoryForm,
+    ) -> AppResult<Category> {
+        let parent_id = self.find_category_id(pool, user_id, form.parent_id, "parent category")?;
+
+        CategoryRepository.update(pool, id, user_id, parent_id, form.name)
+    }
+
+    /// Makes sure the given category id (if any) is visible to the user
+    pub fn find_category_id(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        category_id: Option<String>,
+        entity: &str,
+    ) -> AppResult<Option<Uuid>> {
+        match parse_optional_uuid(category_id, entity)? {
+            None => Ok(None),
+            Some(id) => {
+                CategoryRepository
+                    .find_visible_by_id(pool, id, user_id)
+                    .missing_as(format!("Such {} does not exist", entity).as_str())?;
+
+                Ok(Some(id))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::core::helpers::testing::{create_user, test_pool};
+
+    fn create_category(pool: &DBPool, user_id: Uuid, name: &str, parent: Option<Uuid>) -> Uuid {
+        CategoryService
+            .create(
+                pool,
+                user_id,
+                CategoryForm {
+                    name: String::from(name),
+                    parent_id: parent.map(|id| id.to_string()),
+                },
+            )
+            .unwrap()
+            .category_id
+    }
+
+    fn move_beneath(pool: &DBPool, user_id: Uuid, id: Uuid, parent: Uuid) -> AppResult<Category> {
+        CategoryService.update(
+            pool,
+            id,
+            user_id,
+            CategoryForm {
+                name: String::from("Moved"),
+                parent_id: Some(parent.to_string()),
+            },
+        )
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn update_returns_the_stored_category() {
+        let pool = test_pool();
+        let user = create_user(&pool);
+        let food = create_category(&pool, user.user_id, "Food", None);
+        let snacks = create_category(&pool, user.user_id, "Snacks", None);
+
+        let category = move_beneath(&pool, user.user_id, snacks, food).unwrap();
+
+        assert_eq!(category.name, "Moved");
+        assert_eq!(category.parent_id, Some(food));
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn categories_cannot_be_moved_beneath_themselves() {
+        let pool = test_pool();
+        let user = create_user(&pool);
+        let food = create_category(&pool, user.user_id, "Food", None);
+        let snacks = create_category(&pool, user.user_id, "Snacks", Some(food));
+        let chips = create_category(&pool, user.user_id, "Chips", Some(snacks));
+
+        assert!(move_beneath(&pool, user.user_id, food, food).is_err());
+        assert!(move_beneath(&pool, user.user_id, food, chips).is_err());
+        assert!(move_beneath(&pool, user.user_id, chips, food).is_ok());
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn crossed_moves_cannot_build_a_cycle() {
+        let pool = test_pool();
+        let user = create_user(&pool);
+
+        for _ in 0..10 {
+            let food = create_category(&pool, user.user_id, "Food", None);
+            let drinks = create_category(&pool, user.user_id, "Drinks", None);
+
+            let moving = {
+                let (pool, user_id) = (pool.clone(), user.user_id);
+                thread::spawn(move || move_beneath(&pool, user_id, food, drinks))
+            };
+            let moved = move_beneath(&pool, user.user_id, drinks, food);
+            let other = moving.join().unwrap();
+
+            // the second move sees the first one and finds itself beneath its own child
+            assert_ne!(moved.is_ok(), other.is_ok());
+        }
+    }
+}
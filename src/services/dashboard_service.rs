@@ -0,0 +1,60 @@
+use crate::core::enums::app_error::AppResult;
+use crate::core::helpers::http::QueryParams;
+use crate::models::account_archive::AccountProfile;
+use crate::models::dashboard::Dashboard;
+use crate::models::expense::ExpenseFilter;
+use crate::models::user::User;
+use crate::models::DBPool;
+use crate::repositories::budget_repository::{make_budget_title, BudgetRepository};
+use crate::repositories::expense_repository::ExpenseRepository;
+use crate::services::report_service::ReportService;
+
+pub struct DashboardService;
+
+const RECENT_EXPENSES: i64 = 10;
+
+impl DashboardService {
+    pub fn build(&mut self, pool: &DBPool, user: &User) -> AppResult<Dashboard> {
+        let budget = BudgetRepository.find_owned_current_month_budget(
+            pool,
+            user.user_id,
+            user.timezone.as_str(),
+        )?;
+
+        let budget_title = budget
+            .as_ref()
+            .map(|budget| make_budget_title(budget.month, budget.year));
+
+        let budget = match budget {
+            None => None,
+            Some(budget) => Some(ReportService.budget(pool, user, budget)?),
+        };
+
+        let recent_expenses = ExpenseRepository
+            .list(
+                pool,
+                user.user_id,
+                &ExpenseFilter::default(),
+                QueryParams {
+                    per_page: Some(RECENT_EXPENSES),
+                    ..QueryParams::default()
+                },
+            )?
+            .0
+            .records;
+
+        let aggregate = ExpenseRepository.fetch_aggregate_by_user_id(
+            pool,
+            user.user_id,
+            user.timezone.as_str(),
+        )?;
+
+        Ok(Dashboard {
+            profile: AccountProfile::from(user),
+            budget,
+            budget_title,
+            recent_expenses,
+            aggregate,
+        })
+    }
+}
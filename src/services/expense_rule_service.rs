@@ -0,0 +1,361 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use regex::{Regex, RegexBuilder};
+use uuid::Uuid;
+
+use crate::core::enums::app_error::{AppError, AppResult, MissingAs};
+use crate::models::expense::Expense;
+use crate::models::expense_rule::{
+    ExpenseRule, ExpenseRuleApplyResult, ExpenseRuleDryRun, ExpenseRuleForm, ExpenseRuleSample,
+};
+use crate::models::label::LabelModule;
+use crate::models::DBPool;
+use crate::repositories::expense_repository::ExpenseRepository;
+use crate::repositories::expense_rule_repository::ExpenseRuleRepository;
+use crate::repositories::label_repository::LabelRepository;
+use crate::services::category_service::CategoryService;
+
+pub struct ExpenseRuleService;
+
+impl ExpenseRuleService {
+    pub fn create(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        form: ExpenseRuleForm,
+    ) -> AppResult<ExpenseRule> {
+        self.validate(pool, user_id, &form)?;
+        ExpenseRuleRepository.create(pool, user_id, form)
+    }
+
+    pub fn update(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+        form: ExpenseRuleForm,
+    ) -> AppResult<ExpenseRule> {
+        self.validate(pool, user_id, &form)?;
+        ExpenseRuleRepository.update(pool, id, user_id, form)
+    }
+
+    pub fn dry_run(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        sample: ExpenseRuleSample,
+    ) -> AppResult<ExpenseRuleDryRun> {
+        let matcher = RuleMatcher::load(pool, user_id)?;
+        let matched_rules: Vec<ExpenseRule> = matcher
+            .matching(sample.narration.as_str(), sample.amount)
+            .into_iter()
+            .cloned()
+            .collect();
+
+        Ok(ExpenseRuleDryRun {
+            applied_rule: matched_rules.first().cloned(),
+            matched_rules,
+        })
+    }
+
+    /// Runs the user's rules over all of their expenses, existing categories
+    /// are only replaced when "overwrite" is set
+    pub fn apply(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        overwrite: bool,
+    ) -> AppResult<ExpenseRuleApplyResult> {
+        let matcher = RuleMatcher::load(pool, user_id)?;
+        let expenses = ExpenseRepository.list_all_by_user_id(pool, user_id)?;
+
+        let mut result = ExpenseRuleApplyResult {
+            scanned: expenses.len(),
+            matched: 0,
+            categorized: 0,
+        };
+
+        let mut categories: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        let mut label_pairs = HashSet::new();
+
+        for expense in expenses {
+            let rule = match matcher.first_match(expense.narration.as_str(), expense.amount) {
+                None => continue,
+                Some(rule) => rule,
+            };
+
+            result.matched += 1;
+
+            if let Some(category_id) = rule.category_id {
+                if expense.category_id != Some(category_id)
+                    && (overwrite || expense.category_id.is_none())
+                {
+                    categories
+                        .entry(category_id)
+                        .or_default()
+                        .push(expense.expense_id);
+                    result.categorized += 1;
+                }
+            }
+
+            for label_id in &rule.label_ids {
+                label_pairs.insert((expense.expense_id, *label_id));
+            }
+        }
+
+        ExpenseRuleRepository.apply_matches(pool, user_id, categories, label_pairs)?;
+
+        Ok(result)
+    }
+
+    /// Attaches the rule's labels to the expense, labels deleted or moved to another module
+    /// since the rule was saved are skipped
+    pub fn attach_labels(&mut self, pool: &DBPool, expense: &Expense, rule: &ExpenseRule) {
+        for label_id in &rule.label_ids {
+            match LabelRepository.find_owned_by_id(pool, *label_id, expense.user_id) {
+                Ok(label) if label.module == LabelModule::Expenses.as_str() => {}
+                _ => continue,
+            }
+
+            if let Err(err) = LabelRepository.attach_to_expense(
+                pool,
+                expense.user_id,
+                expense.expense_id,
+                *label_id,
+            ) {
+                log::error!("Failed to attach rule label to expense: {}", err);
+            }
+        }
+    }
+
+    fn validate(&mut self, pool: &DBPool, user_id: Uuid, form: &ExpenseRuleForm) -> AppResult<()> {
+        if form.narration_contains.is_none()
+            && form.narration_pattern.is_none()
+            && form.min_amount.is_none()
+            && form.max_amount.is_none()
+        {
+            return Err(AppError::from(
+                "A rule needs at least one of narration_contains, narration_pattern, min_amount or max_amount",
+            ));
+        }
+
+        if form.category_id.is_none() && form.label_ids.clone().unwrap_or_default().is_empty() {
+            return Err(AppError::from(
+                "A rule must assign either a category or some labels",
+            ));
+        }
+
+        if let Some(pattern) = &form.narration_pattern {
+            compile_pattern(pattern).map_err(|e| format!("Invalid narration pattern: {}", e))?;
+        }
+
+        if let (Some(min), Some(max)) = (form.min_amount, form.max_amount) {
+            if min > max {
+                return Err(AppError::from(
+                    "min_amount cannot be greater than max_amount",
+                ));
+            }
+        }
+
+        CategoryService.find_category_id(pool, user_id, form.category_id.clone(), "category")?;
+
+        for label_id in form.label_ids.clone().unwrap_or_default() {
+            let label_id = Uuid::from_str(label_id.as_str()).map_err(|_| {
+                String::from("Your provided label ID is invalid, please inspect it")
+            })?;
+            let label = LabelRepository
+                .find_owned_by_id(pool, label_id, user_id)
+                .missing_as("Such label does not exist")?;
+            if label.module != LabelModule::Expenses.as_str() {
+                return Err(AppError::from(format!(
+                    "Label \"{}\" belongs to {} and cannot be assigned to expenses",
+                    label.name, label.module
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// User's rules with their narration patterns compiled, ready to be evaluated against expenses
+pub struct RuleMatcher {
+    rules: Vec<(ExpenseRule, Option<Regex>)>,
+}
+
+impl RuleMatcher {
+    pub fn load(pool: &DBPool, user_id: Uuid) -> AppResult<RuleMatcher> {
+        let rules = ExpenseRuleRepository.list_by_user_id(pool, user_id)?;
+
+        Ok(RuleMatcher::new(rules))
+    }
+
+    pub fn new(rules: Vec<ExpenseRule>) -> RuleMatcher {
+        let rules = rules
+            .into_iter()
+            .filter_map(|rule| match &rule.narration_pattern {
+                None => Some((rule, None)),
+                Some(pattern) => match compile_pattern(pattern) {
+                    Ok(regex) => Some((rule, Some(regex))),
+                    Err(err) => {
+                        log::warn!("Skipping rule {}: {}", rule.expense_rule_id, err);
+                        None
+                    }
+                },
+            })
+            .collect();
+
+        RuleMatcher { rules }
+    }
+
+    pub fn find(&self, id: Uuid) -> Option<&ExpenseRule> {
+        self.rules
+            .iter()
+            .map(|(rule, _)| rule)
+            .find(|rule| rule.expense_rule_id == id)
+    }
+
+    /// The rule to apply, rules are ordered by priority so the first match wins
+    pub fn first_match(&self, narration: &str, amount: i64) -> Option<&ExpenseRule> {
+        self.matching(narration, amount).into_iter().next()
+    }
+
+    pub fn matching(&self, narration: &str, amount: i64) -> Vec<&ExpenseRule> {
+        let narration_lower = narration.to_lowercase();
+
+        self.rules
+            .iter()
+            .filter(|(rule, pattern)| {
+                let contains = rule
+                    .narration_contains
+                    .as_ref()
+                    .is_none_or(|needle| narration_lower.contains(&needle.to_lowercase()));
+
+                contains
+                    && pattern
+                        .as_ref()
+                        .is_none_or(|regex| regex.is_match(narration))
+                    && rule.min_amount.is_none_or(|min| amount >= min)
+                    && rule.max_amount.is_none_or(|max| amount <= max)
+            })
+            .map(|(rule, _)| rule)
+            .collect()
+    }
+}
+
+pub fn compile_pattern(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern).case_insensitive(true).build()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn rule(name: &str, priority: i32) -> ExpenseRule {
+        let now = Utc::now().naive_utc();
+        ExpenseRule {
+            expense_rule_id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            name: String::from(name),
+            priority,
+            narration_contains: None,
+            narration_pattern: None,
+            min_amount: None,
+            max_amount: None,
+            category_id: None,
+            label_ids: vec![],
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+        }
+    }
+
+    fn names(rules: Vec<&ExpenseRule>) -> Vec<&str> {
+        rules.iter().map(|rule| rule.name.as_str()).collect()
+    }
+
+    #[test]
+    fn narration_matching_ignores_case() {
+        let mut contains = rule("contains", 1);
+        contains.narration_contains = Some(String::from("Coffee"));
+        let mut pattern = rule("pattern", 2);
+        pattern.narration_pattern = Some(String::from("^uber\\s+trip"));
+
+        let matcher = RuleMatcher::new(vec![contains, pattern]);
+
+        assert_eq!(
+            names(matcher.matching("COFFEE shop", 300)),
+            vec!["contains"]
+        );
+        assert_eq!(
+            names(matcher.matching("Uber  Trip 42", 1500)),
+            vec!["pattern"]
+        );
+        assert!(matcher.matching("my uber trip", 1500).is_empty());
+    }
+
+    #[test]
+    fn amount_bounds_are_inclusive() {
+        let mut bounded = rule("bounded", 1);
+        bounded.min_amount = Some(100);
+        bounded.max_amount = Some(200);
+
+        let matcher = RuleMatcher::new(vec![bounded]);
+
+        assert!(matcher.first_match("anything", 99).is_none());
+        assert!(matcher.first_match("anything", 100).is_some());
+        assert!(matcher.first_match("anything", 200).is_some());
+        assert!(matcher.first_match("anything", 201).is_none());
+    }
+
+    #[test]
+    fn every_condition_must_hold() {
+        let mut both = rule("both", 1);
+        both.narration_contains = Some(String::from("rent"));
+        both.min_amount = Some(1000);
+
+        let matcher = RuleMatcher::new(vec![both]);
+
+        assert!(matcher.first_match("rent", 999).is_none());
+        assert!(matcher.first_match("groceries", 5000).is_none());
+        assert!(matcher.first_match("Rent for May", 5000).is_some());
+    }
+
+    #[test]
+    fn first_rule_in_priority_order_wins() {
+        let mut first = rule("first", 1);
+        first.narration_contains = Some(String::from("market"));
+        let mut second = rule("second", 2);
+        second.narration_contains = Some(String::from("super"));
+
+        let matcher = RuleMatcher::new(vec![first, second]);
+
+        assert_eq!(
+            names(matcher.matching("supermarket", 10)),
+            vec!["first", "second"]
+        );
+        assert_eq!(
+            matcher.first_match("supermarket", 10).unwrap().name,
+            "first"
+        );
+        assert_eq!(matcher.first_match("super", 10).unwrap().name, "second");
+    }
+
+    #[test]
+    fn invalid_patterns_are_skipped() {
+        let mut broken = rule("broken", 1);
+        broken.narration_pattern = Some(String::from("(unclosed"));
+        let fallback = rule("fallback", 2);
+        let fallback_id = fallback.expense_rule_id;
+        let broken_id = broken.expense_rule_id;
+
+        let matcher = RuleMatcher::new(vec![broken, fallback]);
+
+        assert!(matcher.find(broken_id).is_none());
+        assert!(matcher.find(fallback_id).is_some());
+        assert_eq!(names(matcher.matching("(unclosed", 1)), vec!["fallback"]);
+    }
+}
@@ -1,53 +1,451 @@
-use crate::core::enums::http_error::DBResult;
-use crate::models::expense::{Expense, ExpenseForm};
+use crate::core::enums::app_error::{AppError, AppResult};
+use crate::models::expense::{CreatedExpense, Expense, ExpenseForm};
+use crate::models::user::User;
 use crate::models::DBPool;
 use crate::repositories::budget_repository::BudgetRepository;
 use crate::repositories::expense_repository::ExpenseRepository;
 use crate::services::budget_service::BudgetService;
+use crate::services::category_service::CategoryService;
+use crate::services::expense_rule_service::{ExpenseRuleService, RuleMatcher};
+use crate::services::project_service::ProjectService;
 use uuid::Uuid;
 
 pub struct ExpenseService;
 
 impl ExpenseService {
-    pub fn create<'a>(
+    pub fn create(
         &mut self,
         pool: &DBPool,
-        user_id: Uuid,
-        form: ExpenseForm,
-    ) -> Result<Expense, &'a str> {
-        let result = BudgetRepository
-            .find_owned_current_month_budget(pool, user_id)
-            .unwrap();
-        if result.is_none() {
-            return Err("No budget for current month found");
-        }
+        user: &User,
+        mut form: ExpenseForm,
+    ) -> AppResult<CreatedExpense> {
+        let user_id = user.user_id;
+        ProjectService.find_owned_project_id(pool, user_id, form.project_id.as_str())?;
+        CategoryService.find_category_id(pool, user_id, form.category_id.clone(), "category")?;
+
+        let budget = BudgetRepository
+            .find_owned_current_month_budget(pool, user_id, user.timezone.as_str())?
+            .ok_or_else(|| AppError::from("No budget for current month found"))?;
 
         let amount = form.amount;
-        let mut budget = result.unwrap();
-        if amount > budget.available_amount() {
-            return Err("This expense exceeds current budget");
+
+        let matcher = RuleMatcher::load(pool, user_id).unwrap_or_else(|err| {
+            log::error!("Failed to load expense rules: {}", err);
+            RuleMatcher::new(vec![])
+        });
+
+        let rule = matcher
+            .first_match(form.narration.as_str(), amount)
+            .cloned();
+
+        // an explicitly chosen category always takes precedence over rules
+        if let Some(rule) = &rule {
+            if form.category_id.is_none() {
+                form.category_id = rule.category_id.map(|id| id.to_string());
+            }
         }
 
-        let expense = ExpenseRepository.create(pool, user_id, budget.budget_id, form);
+        let (expense, budget, updated_budget) = ExpenseRepository.create(
+            pool,
+            user_id,
+            user.timezone.as_str(),
+            budget.budget_id,
+            form,
+        )?;
 
-        BudgetService
-            .decrement(pool, &budget, amount)
-            .expect("Failed to decrement budget");
+        if let Some(rule) = &rule {
+            ExpenseRuleService.attach_labels(pool, &expense, rule);
+        }
 
-        Ok(expense)
+        let updated_budget = BudgetService.after_spending(pool, &budget, updated_budget);
+        let overspent_amount = updated_budget.overspent_amount();
+
+        Ok(CreatedExpense {
+            expense,
+            // the part of the overspend caused by this very expense
+            overspent_amount: overspent_amount.min(amount),
+            budget_over_limit: overspent_amount > 0,
+        })
     }
 
     pub fn update(
         &mut self,
         pool: &DBPool,
         id: Uuid,
-        user_id: Uuid,
+        user: &User,
         form: ExpenseForm,
-    ) -> DBResult<Expense> {
-        ExpenseRepository.update(pool, id, user_id, form)
+    ) -> AppResult<Expense> {
+        let user_id = user.user_id;
+        ProjectService.find_owned_project_id(pool, user_id, form.project_id.as_str())?;
+        CategoryService.find_category_id(pool, user_id, form.category_id.clone(), "category")?;
+
+        let (expense, budget, updated_budget) =
+            ExpenseRepository.update(pool, id, user_id, user.timezone.as_str(), form)?;
+
+        BudgetService.after_spending(pool, &budget, updated_budget);
+        Ok(expense)
     }
 
-    pub fn delete(&mut self, pool: &DBPool, id: Uuid, user_id: Uuid) -> DBResult<Expense> {
+    pub fn delete(&mut self, pool: &DBPool, id: Uuid, user_id: Uuid) -> AppResult<Expense> {
         ExpenseRepository.delete(pool, id, user_id)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::core::helpers::date_time::{today_in_timezone, AggregatePeriods};
+    use crate::core::helpers::http::QueryParams;
+    use crate::core::helpers::testing::{create_budget, create_user, test_pool};
+    use crate::models::budget::{Budget, OverspendPolicy};
+    use crate::models::expense::ExpenseFilter;
+    use crate::models::project::ProjectForm;
+    use crate::models::user::User;
+    use crate::repositories::expense_repository::fetch_expense_aggregate;
+    use crate::repositories::notification_repository::NotificationRepository;
+    use crate::repositories::project_repository::ProjectRepository;
+
+    fn setup(policy: OverspendPolicy) -> (DBPool, User, Budget, String) {
+        let pool = test_pool();
+        let user = create_user(&pool);
+        let budget = create_budget(&pool, user.user_id, 100, policy);
+        let project = ProjectRepository
+            .create(
+                &pool,
+                user.user_id,
+                ProjectForm {
+                    name: String::from("Household"),
+                    description: String::new(),
+                },
+            )
+            .unwrap();
+
+        (pool, user, budget, project.project_id.to_string())
+    }
+
+    fn form(project_id: &str, amount: i64) -> ExpenseForm {
+        ExpenseForm {
+            project_id: project_id.to_string(),
+            amount,
+            narration: String::from("Groceries"),
+            spent_at: None,
+            category_id: None,
+        }
+    }
+
+    fn amount_used(pool: &DBPool, user: &User, budget: &Budget) -> i64 {
+        BudgetRepository
+            .find_owned_by_id(pool, budget.budget_id, user.user_id)
+            .unwrap()
+            .amount_used
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn rejecting_budgets_refuse_overspending() {
+        let (pool, user, budget, project_id) = setup(OverspendPolicy::Reject);
+
+        let created = ExpenseService
+            .create(&pool, &user, form(&project_id, 100))
+            .unwrap();
+        assert!(!created.budget_over_limit);
+
+        let refused = ExpenseService.create(&pool, &user, form(&project_id, 1));
+        assert!(matches!(refused, Err(AppError::BadRequest(_))));
+        assert_eq!(amount_used(&pool, &user, &budget), 100);
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn rejecting_budgets_refuse_raising_expenses() {
+        let (pool, user, budget, project_id) = setup(OverspendPolicy::Reject);
+
+        let created = ExpenseService
+            .create(&pool, &user, form(&project_id, 60))
+            .unwrap();
+        let expense_id = created.expense.expense_id;
+
+        let refused = ExpenseService.update(&pool, expense_id, &user, form(&project_id, 101));
+        assert!(matches!(refused, Err(AppError::BadRequest(_))));
+        assert_eq!(amount_used(&pool, &user, &budget), 60);
+
+        let updated = ExpenseService
+            .update(&pool, expense_id, &user, form(&project_id, 100))
+            .unwrap();
+        assert_eq!(updated.amount, 100);
+        assert_eq!(amount_used(&pool, &user, &budget), 100);
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn raising_expenses_flags_and_alerts() {
+        let (pool, user, budget, project_id) = setup(OverspendPolicy::AllowAndFlag);
+
+        let created = ExpenseService
+            .create(&pool, &user, form(&project_id, 10))
+            .unwrap();
+        ExpenseService
+            .update(
+                &pool,
+                created.expense.expense_id,
+                &user,
+                form(&project_id, 120),
+            )
+            .unwrap();
+
+        let budget = BudgetRepository
+            .find_owned_by_id(&pool, budget.budget_id, user.user_id)
+            .unwrap();
+        assert_eq!(budget.amount_used, 120);
+        assert!(budget.over_limit_at.is_some());
+
+        let notifications = NotificationRepository
+            .list_by_user_id(&pool, user.user_id, true, QueryParams::default())
+            .unwrap()
+            .records;
+        assert_eq!(notifications.len(), 2);
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn flagging_budgets_record_the_overspend() {
+        let (pool, user, budget, project_id) = setup(OverspendPolicy::AllowAndFlag);
+
+        ExpenseService
+            .create(&pool, &user, form(&project_id, 80))
+            .unwrap();
+        let created = ExpenseService
+            .create(&pool, &user, form(&project_id, 50))
+            .unwrap();
+
+        assert!(created.budget_over_limit);
+        assert_eq!(created.overspent_amount, 30);
+
+        let budget = BudgetRepository
+            .find_owned_by_id(&pool, budget.budget_id, user.user_id)
+            .unwrap();
+        assert_eq!(budget.amount_used, 130);
+        assert!(budget.over_limit_at.is_some());
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn concurrent_expenses_cannot_both_pass_the_reject_check() {
+        let (pool, user, budget, project_id) = setup(OverspendPolicy::Reject);
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let (pool, user, project_id) = (pool.clone(), user.clone(), project_id.clone());
+                thread::spawn(move || ExpenseService.create(&pool, &user, form(&project_id, 40)))
+            })
+            .collect();
+
+        let created = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .filter(|result| result.is_ok())
+            .count();
+
+        assert_eq!(created, 2);
+        assert_eq!(amount_used(&pool, &user, &budget), 80);
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn updating_and_deleting_keep_the_budget_in_step() {
+        let (pool, user, budget, project_id) = setup(OverspendPolicy::Allow);
+
+        let created = ExpenseService
+            .create(&pool, &user, form(&project_id, 10))
+            .unwrap();
+        assert_eq!(amount_used(&pool, &user, &budget), 10);
+
+        let expense_id = created.expense.expense_id;
+        let updated = ExpenseService
+            .update(&pool, expense_id, &user, form(&project_id, 900))
+            .unwrap();
+        assert_eq!(updated.amount, 900);
+        assert_eq!(amount_used(&pool, &user, &budget), 900);
+
+        ExpenseService
+            .delete(&pool, expense_id, user.user_id)
+            .unwrap();
+        assert_eq!(amount_used(&pool, &user, &budget), 0);
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn trashing_and_restoring_twice_charge_the_budget_once() {
+        let (pool, user, budget, project_id) = setup(OverspendPolicy::Allow);
+
+        let created = ExpenseService
+            .create(&pool, &user, form(&project_id, 40))
+            .unwrap();
+        let expense_id = created.expense.expense_id;
+
+        ExpenseService
+            .delete(&pool, expense_id, user.user_id)
+            .unwrap();
+        let deleted_again = ExpenseService.delete(&pool, expense_id, user.user_id);
+        assert!(matches!(deleted_again, Err(AppError::EntityNotFound(_))));
+        assert_eq!(amount_used(&pool, &user, &budget), 0);
+
+        // both restores read the expense while it was still in the trash
+        let trashed = ExpenseRepository
+            .find_trashed_by_id(&pool, expense_id, user.user_id)
+            .unwrap();
+        ExpenseRepository.restore(&pool, &trashed).unwrap();
+        ExpenseRepository.restore(&pool, &trashed).unwrap();
+        assert_eq!(amount_used(&pool, &user, &budget), 40);
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn aggregates_split_at_the_users_new_year() {
+        let (pool, user, budget, project_id) = setup(OverspendPolicy::Allow);
+        let timezone = "Asia/Tokyo";
+
+        // the first hours of the year in Tokyo are still the previous year in UTC
+        for (spent_at, amount) in [
+            ("2026-12-31 23:30:00", 1),
+            ("2027-01-01 00:30:00", 10),
+            ("2027-01-31 23:59:59", 100),
+            ("2027-02-01 00:00:00", 1000),
+        ] {
+            let form = ExpenseForm {
+                spent_at: Some(String::from(spent_at)),
+                ..form(&project_id, amount)
+            };
+            ExpenseRepository
+                .create(&pool, user.user_id, timezone, budget.budget_id, form)
+                .unwrap();
+        }
+
+        let periods = AggregatePeriods::new(NaiveDate::from_ymd_opt(2027, 1, 1).unwrap());
+        let totals = fetch_expense_aggregate(&pool, user.user_id, None, periods)
+            .unwrap()
+            .totals;
+        assert_eq!(totals.year_expenses.as_deref(), Some("1110"));
+        assert_eq!(totals.month_expenses.as_deref(), Some("110"));
+        assert_eq!(totals.week_expenses.as_deref(), Some("11"));
+        assert_eq!(totals.today_expenses.as_deref(), Some("10"));
+
+        // without a time, the expense is spent at the current wall-clock time of Tokyo
+        let (expense, _, _) = ExpenseRepository
+            .create(
+                &pool,
+                user.user_id,
+                timezone,
+                budget.budget_id,
+                form(&project_id, 5),
+            )
+            .unwrap();
+        assert_eq!(expense.spent_at.date(), today_in_timezone(timezone));
+
+        let totals = fetch_expense_aggregate(
+            &pool,
+            user.user_id,
+            None,
+            AggregatePeriods::in_timezone(timezone),
+        )
+        .unwrap()
+        .totals;
+        assert_eq!(totals.today_expenses.as_deref(), Some("5"));
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn filtered_totals_add_up_the_filtered_rows() {
+        let (pool, user, budget, project_id) = setup(OverspendPolicy::Allow);
+        let other_project_id = ProjectRepository
+            .create(
+                &pool,
+                user.user_id,
+                ProjectForm {
+                    name: String::from("Car"),
+                    description: String::new(),
+                },
+            )
+            .unwrap()
+            .project_id
+            .to_string();
+
+        let mut matching = 0;
+        for (project_id, spent_at, amount, matches) in [
+            (&project_id, "2031-03-01 00:00:00", 10, true),
+            (&project_id, "2031-03-15 12:00:00", 20, true),
+            (&project_id, "2031-03-31 23:59:59", 40, true),
+            // outside of the dates
+            (&project_id, "2031-02-28 23:59:59", 20, false),
+            (&project_id, "2031-04-01 00:00:00", 20, false),
+            // outside of the amounts
+            (&project_id, "2031-03-10 00:00:00", 5, false),
+            (&project_id, "2031-03-10 00:00:00", 500, false),
+            // another project
+            (&other_project_id, "2031-03-10 00:00:00", 20, false),
+        ] {
+            let form = ExpenseForm {
+                spent_at: Some(String::from(spent_at)),
+                ..form(project_id, amount)
+            };
+            ExpenseRepository
+                .create(&pool, user.user_id, "UTC", budget.budget_id, form)
+                .unwrap();
+
+            if matches {
+                matching += amount;
+            }
+        }
+
+        // trashed expenses are left out unless asked for
+        let form = ExpenseForm {
+            spent_at: Some(String::from("2031-03-20 00:00:00")),
+            ..form(&project_id, 80)
+        };
+        let (trashed, _, _) = ExpenseRepository
+            .create(&pool, user.user_id, "UTC", budget.budget_id, form)
+            .unwrap();
+        ExpenseRepository
+            .delete(&pool, trashed.expense_id, user.user_id)
+            .unwrap();
+
+        let mut filter = ExpenseFilter {
+            spent_from: NaiveDate::from_ymd_opt(2031, 3, 1),
+            spent_to: NaiveDate::from_ymd_opt(2031, 3, 31),
+            min_amount: Some(10),
+            max_amount: Some(100),
+            project_id: vec![Uuid::parse_str(&project_id).unwrap()],
+            ..ExpenseFilter::default()
+        };
+
+        for per_page in [100, 2] {
+            let params = QueryParams {
+                per_page: Some(per_page),
+                ..QueryParams::default()
+            };
+            let (page, summary) = ExpenseRepository
+                .list(&pool, user.user_id, &filter, params)
+                .unwrap();
+
+            // the total covers every match, not only the page
+            assert_eq!(summary.total_amount, matching, "{}", per_page);
+            assert_eq!(page.total_records, Some(3));
+            if per_page == 100 {
+                let sum: i64 = page.records.iter().map(|(expense, _)| expense.amount).sum();
+                assert_eq!(sum, summary.total_amount);
+            }
+        }
+
+        filter.include_deleted = true;
+        let (page, summary) = ExpenseRepository
+            .list(&pool, user.user_id, &filter, QueryParams::default())
+            .unwrap();
+        let sum: i64 = page.records.iter().map(|(expense, _)| expense.amount).sum();
+        assert_eq!(summary.total_amount, matching + 80);
+        assert_eq!(sum, summary.total_amount);
+    }
+}
@@ -0,0 +1,352 @@
+use std::env;
+
+use actix_web::error::ErrorInternalServerError;
+use actix_web::web::{self, Bytes};
+use futures_util::stream::{self, Stream};
+use uuid::Uuid;
+
+use crate::core::enums::app_error::{AppError, AppResult, OrInternal};
+use crate::core::export::{
+    csv_chunk, json_chunk, xlsx_workbook, ExportCell, ExportColumn, ExportCursor, ExportRow,
+};
+use crate::core::helpers::http::QueryParams;
+use crate::models::expense::ExpenseFilter;
+use crate::models::export::{ExportEntity, ExportFormat};
+use crate::models::DBPool;
+use crate::repositories::budget_repository::BudgetRepository;
+use crate::repositories::expense_repository::ExpenseRepository;
+use crate::repositories::project_repository::ProjectRepository;
+
+/// Records fetched from the database at a time while exporting
+const EXPORT_BATCH_SIZE: i64 = 500;
+
+/// Most rows an XLSX export may hold, set through `EXPORT_XLSX_MAX_ROWS` (50000 by default)
+fn xlsx_max_rows() -> usize {
+    env::var("EXPORT_XLSX_MAX_ROWS")
+        .ok()
+        .and_then(|rows| rows.parse::<usize>().ok())
+        .filter(|rows| *rows > 0)
+        .unwrap_or(50_000)
+}
+
+const EXPENSE_COLUMNS: [ExportColumn; 7] = [
+    ExportColumn {
+        key: "expense_id",
+        title: "ID",
+    },
+    ExportColumn {
+        key: "spent_at",
+        title: "Spent At",
+    },
+    ExportColumn {
+        key: "narration",
+        title: "Narration",
+    },
+    ExportColumn {
+        key: "amount",
+        title: "Amount",
+    },
+    ExportColumn {
+        key: "project",
+        title: "Project",
+    },
+    ExportColumn {
+        key: "budget_id",
+        title: "Budget ID",
+    },
+    ExportColumn {
+        key: "created_at",
+        title: "Created At",
+    },
+];
+
+const BUDGET_COLUMNS: [ExportColumn; 10] = [
+    ExportColumn {
+        key: "budget_id",
+        title: "ID",
+    },
+    ExportColumn {
+        key: "title",
+        title: "Title",
+    },
+    ExportColumn {
+        key: "month",
+        title: "Month",
+    },
+    ExportColumn {
+        key: "year",
+        title: "Year",
+    },
+    ExportColumn {
+        key: "amount",
+        title: "Amount",
+    },
+    ExportColumn {
+        key: "amount_used",
+        title: "Amount Used",
+    },
+    ExportColumn {
+        key: "amount_available",
+        title: "Amount Available",
+    },
+    ExportColumn {
+        key: "overspend_policy",
+        title: "Overspend Policy",
+    },
+    ExportColumn {
+        key: "comment",
+        title: "Comment",
+    },
+    ExportColumn {
+        key: "created_at",
+        title: "Created At",
+    },
+];
+
+const PROJECT_COLUMNS: [ExportColumn; 4] = [
+    ExportColumn {
+        key: "project_id",
+        title: "ID",
+    },
+    ExportColumn {
+        key: "name",
+        title: "Name",
+    },
+    ExportColumn {
+        key: "description",
+        title: "Description",
+    },
+    ExportColumn {
+        key: "created_at",
+        title: "Created At",
+    },
+];
+
+struct ExportState {
+    pool: DBPool,
+    user_id: Uuid,
+    entity: ExportEntity,
+    format: ExportFormat,
+    filter: ExpenseFilter,
+    query_params: QueryParams,
+    cursor: Option<ExportCursor>,
+    written: usize,
+    finished: bool,
+}
+
+pub struct ExportService;
+
+impl ExportService {
+    pub fn columns(&mut self, entity: ExportEntity) -> &'static [ExportColumn] {
+        match entity {
+            ExportEntity::Expenses => &EXPENSE_COLUMNS,
+            ExportEntity::Budgets => &BUDGET_COLUMNS,
+            ExportEntity::Projects => &PROJECT_COLUMNS,
+        }
+    }
+
+    /// Streams the CSV or JSON export batch by batch, so large exports never
+    /// sit in memory as a whole
+    pub fn stream(
+        &mut self,
+        pool: DBPool,
+        user_id: Uuid,
+        entity: ExportEntity,
+        format: ExportFormat,
+        filter: ExpenseFilter,
+        query_params: QueryParams,
+    ) -> impl Stream<Item = Result<Bytes, actix_web::Error>> {
+        let state = ExportState {
+            pool,
+            user_id,
+            entity,
+            format,
+            filter,
+            query_params,
+            cursor: None,
+            written: 0,
+            finished: false,
+        };
+
+        stream::unfold(state, |mut state| async move {
+            if state.finished {
+                return None;
+            }
+
+            // the batch is queried on the blocking pool, off the thread serving the stream
+            let (pool, user_id, entity, cursor) = (
+                state.pool.clone(),
+                state.user_id,
+                state.entity,
+                state.cursor,
+            );
+            let (filter, query_params) = (state.filter.clone(), state.query_params.clone());
+            let fetched = web::block(move || {
+                ExportService.fetch_batch(&pool, user_id, entity, &filter, &query_params, cursor)
+            })
+            .await
+            .map_err(|err| AppError::Internal(err.to_string()))
+            .and_then(|rows| rows);
+
+            let rows = match fetched {
+                Ok(rows) => rows,
+                Err(err) => {
+                    // the response has started, the error only cuts the stream short
+                    log::error!("failed to export {}: {:?}", state.entity.as_str(), err);
+                    state.finished = true;
+                    return Some((
+                        Err(ErrorInternalServerError("Internal Server Error")),
+                        state,
+                    ));
+                }
+            };
+
+            let columns = ExportService.columns(state.entity);
+            let is_first = state.cursor.is_none();
+            state.finished = rows.len() < EXPORT_BATCH_SIZE as usize;
+
+            let chunk = match state.format {
+                ExportFormat::Json => {
+                    let mut chunk = match is_first {
+                        true => vec![b'['],
+                        false => vec![],
+                    };
+
+                    chunk.extend(json_chunk(columns, &rows, state.written));
+                    if state.finished {
+                        chunk.push(b']');
+                    }
+
+                    Ok(chunk)
+                }
+                _ => csv_chunk(is_first.then_some(columns), &rows),
+            };
+
+            state.written += rows.len();
+            state.cursor = rows.last().map(|row| row.cursor);
+
+            match chunk {
+                Ok(chunk) => Some((Ok(Bytes::from(chunk)), state)),
+                Err(err) => {
+                    log::error!("failed to write {} export: {}", state.entity.as_str(), err);
+                    state.finished = true;
+                    Some((
+                        Err(ErrorInternalServerError("Internal Server Error")),
+                        state,
+                    ))
+                }
+            }
+        })
+    }
+
+    /// Workbooks are zip archives that can only be written once complete, so
+    /// XLSX exports are built in memory and refused past `xlsx_max_rows`
+    pub fn xlsx(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        entity: ExportEntity,
+        filter: ExpenseFilter,
+        query_params: QueryParams,
+    ) -> AppResult<Vec<u8>> {
+        let mut rows: Vec<ExportRow> = vec![];
+        let mut cursor: Option<ExportCursor> = None;
+        let max_rows = xlsx_max_rows();
+
+        loop {
+            let batch = self.fetch_batch(pool, user_id, entity, &filter, &query_params, cursor)?;
+            let is_last = batch.len() < EXPORT_BATCH_SIZE as usize;
+            cursor = batch.last().map(|row| row.cursor);
+            rows.extend(batch);
+
+            if rows.len() > max_rows {
+                return Err(AppError::from(format!(
+                    "XLSX exports are limited to {} rows, narrow the filters or export as CSV",
+                    max_rows
+                )));
+            }
+
+            if is_last {
+                break;
+            }
+        }
+
+        xlsx_workbook(entity.as_str(), self.columns(entity), &rows)
+            .or_internal("Failed to write the XLSX export")
+    }
+
+    /// The filter only narrows expense exports down
+    fn fetch_batch(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        entity: ExportEntity,
+        filter: &ExpenseFilter,
+        query_params: &QueryParams,
+        cursor: Option<ExportCursor>,
+    ) -> AppResult<Vec<ExportRow>> {
+        let query_params = query_params.clone();
+        let rows = match entity {
+            ExportEntity::Expenses => ExpenseRepository
+                .list_batch_by_user_id(
+                    pool,
+                    user_id,
+                    filter,
+                    query_params,
+                    cursor,
+                    EXPORT_BATCH_SIZE,
+                )?
+                .into_iter()
+                .map(|(expense, project)| ExportRow {
+                    cursor: (expense.created_at, expense.expense_id),
+                    cells: vec![
+                        ExportCell::Text(expense.expense_id.to_string()),
+                        ExportCell::DateTime(expense.spent_at),
+                        ExportCell::Text(expense.narration),
+                        ExportCell::Money(expense.amount),
+                        ExportCell::Text(project.name),
+                        ExportCell::Text(expense.budget_id.to_string()),
+                        ExportCell::DateTime(expense.created_at),
+                    ],
+                })
+                .collect(),
+            ExportEntity::Budgets => BudgetRepository
+                .list_batch_by_user_id(pool, user_id, query_params, cursor, EXPORT_BATCH_SIZE)?
+                .into_iter()
+                .map(|mut budget| ExportRow {
+                    cursor: (budget.created_at, budget.budget_id),
+                    cells: vec![
+                        ExportCell::Text(budget.budget_id.to_string()),
+                        ExportCell::Text(budget.title.clone()),
+                        ExportCell::Number(budget.month as i64),
+                        ExportCell::Number(budget.year as i64),
+                        ExportCell::Money(budget.amount),
+                        ExportCell::Money(budget.amount_used),
+                        ExportCell::Money(budget.available_amount()),
+                        ExportCell::Text(budget.overspend_policy.clone()),
+                        budget
+                            .comment
+                            .clone()
+                            .map_or(ExportCell::Empty, ExportCell::Text),
+                        ExportCell::DateTime(budget.created_at),
+                    ],
+                })
+                .collect(),
+            ExportEntity::Projects => ProjectRepository
+                .list_batch_by_user_id(pool, user_id, query_params, cursor, EXPORT_BATCH_SIZE)?
+                .into_iter()
+                .map(|project| ExportRow {
+                    cursor: (project.created_at, project.project_id),
+                    cells: vec![
+                        ExportCell::Text(project.project_id.to_string()),
+                        ExportCell::Text(project.name),
+                        ExportCell::Text(project.description),
+                        ExportCell::DateTime(project.created_at),
+                    ],
+                })
+                .collect(),
+        };
+
+        Ok(rows)
+    }
+}
@@ -0,0 +1,406 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::{Datelike, Days, NaiveDate};
+use uuid::Uuid;
+
+use crate::core::enums::app_error::{AppError, AppResult};
+use crate::core::helpers::date_time::DateRange;
+use crate::core::helpers::db::current_timestamp;
+use crate::core::import::{csv_statement, detect_source, parse_statement};
+use crate::models::budget::{Budget, OverspendPolicy};
+use crate::models::expense::Expense;
+use crate::models::import::{
+    Import, ImportCommitForm, ImportMapping, ImportPreview, ImportResult, ImportRow,
+    ImportUploadForm, TransactionKind, UploadedImport, IMPORT_SOURCE_CSV,
+};
+use crate::models::income::Income;
+use crate::models::DBPool;
+use crate::repositories::bank_account_repository::BankAccountRepository;
+use crate::repositories::budget_repository::BudgetRepository;
+use crate::repositories::expense_repository::ExpenseRepository;
+use crate::repositories::import_repository::ImportRepository;
+use crate::repositories::income_repository::IncomeRepository;
+use crate::services::budget_service::BudgetService;
+use crate::services::expense_rule_service::{ExpenseRuleService, RuleMatcher};
+use crate::services::project_service::ProjectService;
+
+pub struct ImportService;
+
+impl ImportService {
+    pub fn upload(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        form: ImportUploadForm,
+    ) -> AppResult<UploadedImport> {
+        let file_name = form
+            .file
+            .file_name
+            .clone()
+            .unwrap_or(String::from("statement.csv"));
+
+        let content = String::from_utf8(form.file.data.to_vec())
+            .map_err(|_| AppError::from("The uploaded file must be UTF-8 encoded text"))?;
+        let content = content.trim_start_matches('\u{feff}').to_string();
+
+        if content.trim().is_empty() {
+            return Err(AppError::from("The uploaded file is empty"));
+        }
+
+        let source = detect_source(file_name.as_str(), content.as_str());
+        let import = ImportRepository.create(pool, user_id, source, file_name, content)?;
+
+        let (headers, accounts) = match source {
+            IMPORT_SOURCE_CSV => (csv_statement::read_headers(&import.content, None)?, vec![]),
+            _ => {
+                let mut accounts: Vec<String> = vec![];
+                for row in parse_statement(&import, &ImportMapping::default())? {
+                    if let Some(account) = row.account {
+                        if !accounts.contains(&account) {
+                            accounts.push(account);
+                        }
+                    }
+                }
+
+                (vec![], accounts)
+            }
+        };
+
+        Ok(UploadedImport {
+            import,
+            headers,
+            accounts,
+        })
+    }
+
+    pub fn preview(
+        &mut self,
+        pool: &DBPool,
+        import: &Import,
+        mapping: &ImportMapping,
+    ) -> AppResult<ImportPreview> {
+        let mut rows = parse_statement(import, mapping)?;
+        self.assign_projects(pool, import, mapping, &mut rows)?;
+        let rows = self.review(pool, import.user_id, rows)?;
+        Ok(ImportPreview::new(rows))
+    }
+
+    /// Re-validates the statement with the given mapping and records debits as
+    /// expenses and credits as incomes of their projects in one go
+    pub fn commit(
+        &mut self,
+        pool: &DBPool,
+        import: &Import,
+        form: ImportCommitForm,
+    ) -> AppResult<ImportResult> {
+        if !import.is_pending() {
+            return Err(AppError::Conflict(String::from(
+                "This import has already been committed",
+            )));
+        }
+
+        let preview = self.preview(pool, import, &form.mapping)?;
+
+        if preview.invalid_rows > 0 && !form.skip_invalid.unwrap_or(false) {
+            return Err(AppError::BadRequest(format!(
+                "{} of the {} rows are invalid, preview the import to see why or commit with skip_invalid",
+                preview.invalid_rows, preview.total_rows
+            )));
+        }
+
+        let include_duplicates = form.include_duplicates.unwrap_or(false);
+        let rows: Vec<ImportRow> = preview
+            .rows
+            .into_iter()
+            .filter(|row| row.is_valid() && !row.already_imported)
+            .filter(|row| include_duplicates || !row.duplicate)
+            .collect();
+
+        let mut budget_usage: BTreeMap<Uuid, i64> = BTreeMap::new();
+        let mut expenses: Vec<(ImportRow, Expense)> = vec![];
+        let mut incomes: Vec<Income> = vec![];
+
+        for row in rows {
+            let amount = row.amount.unwrap();
+            let narration = row.narration.clone().unwrap();
+            let project_id = row.project_id.unwrap();
+
+            if row.kind == TransactionKind::Income {
+                incomes.push(Income {
+                    income_id: Uuid::new_v4(),
+                    user_id: import.user_id,
+                    project_id,
+                    amount,
+                    narration,
+                    received_at: row.spent_at.unwrap(),
+                    import_id: Some(import.import_id),
+                    external_id: row.external_id.clone(),
+                    external_account: row.account.clone(),
+                    created_at: current_timestamp(),
+                    updated_at: current_timestamp(),
+                    deleted_at: None,
+                });
+                continue;
+            }
+
+            let budget_id = row.budget_id.unwrap();
+            *budget_usage.entry(budget_id).or_insert(0) += amount;
+
+            let expense = Expense {
+                expense_id: Uuid::new_v4(),
+                user_id: import.user_id,
+                project_id,
+                budget_id,
+                amount,
+                narration,
+                spent_at: row.spent_at.unwrap(),
+                created_at: current_timestamp(),
+                updated_at: current_timestamp(),
+                deleted_at: None,
+                category_id: row.category_id,
+                import_id: Some(import.import_id),
+                external_id: row.external_id.clone(),
+                external_account: row.account.clone(),
+            };
+
+            expenses.push((row, expense));
+        }
+
+        let budgets_before: Vec<Budget> = budget_usage
+            .keys()
+            .filter_map(|id| {
+                BudgetRepository
+                    .find_owned_by_id(pool, *id, import.user_id)
+                    .ok()
+            })
+            .collect();
+
+        let committed = ImportRepository.commit(
+            pool,
+            import,
+            expenses
+                .iter()
+                .map(|(_, expense)| expense.clone())
+                .collect(),
+            incomes.clone(),
+            &budget_usage,
+        )?;
+
+        for before in budgets_before {
+            if let Ok(after) =
+                BudgetRepository.find_owned_by_id(pool, before.budget_id, import.user_id)
+            {
+                BudgetService.after_spending(pool, &before, after);
+            }
+        }
+
+        let matcher = RuleMatcher::load(pool, import.user_id)?;
+        for (row, expense) in expenses.iter() {
+            if let Some(rule) = row.expense_rule_id.and_then(|id| matcher.find(id)) {
+                ExpenseRuleService.attach_labels(pool, expense, rule);
+            }
+        }
+
+        Ok(ImportResult {
+            import: committed,
+            imported_expenses: expenses.len(),
+            imported_incomes: incomes.len(),
+            skipped_invalid: preview.invalid_rows,
+            skipped_duplicates: match include_duplicates {
+                true => 0,
+                false => preview.duplicate_rows,
+            },
+            skipped_already_imported: preview.already_imported_rows,
+        })
+    }
+
+    /// Assigns each expense row its budget and rule-based category and flags rows
+    /// that are already recorded (same day, amount and narration, or same bank
+    /// account and transaction id) or cannot be recorded
+    pub fn review(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        mut rows: Vec<ImportRow>,
+    ) -> AppResult<Vec<ImportRow>> {
+        let external_ids: Vec<String> = rows
+            .iter()
+            .filter_map(|row| row.external_id.clone())
+            .collect();
+
+        let mut imported_ids: HashSet<(Option<String>, String)> = HashSet::new();
+        if !external_ids.is_empty() {
+            imported_ids.extend(ExpenseRepository.list_external_ids(
+                pool,
+                user_id,
+                external_ids.clone(),
+            )?);
+            imported_ids.extend(IncomeRepository.list_external_ids(pool, user_id, external_ids)?);
+        }
+
+        let dates: Vec<NaiveDate> = rows
+            .iter()
+            .filter_map(|row| row.spent_at.map(|spent_at| spent_at.date()))
+            .collect();
+
+        let (first_date, last_date) = match (dates.iter().min(), dates.iter().max()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return Ok(rows),
+        };
+
+        let mut budgets: HashMap<(i32, u32), Budget> = BudgetRepository
+            .list_by_period(pool, user_id, first_date, last_date)?
+            .into_iter()
+            .map(|budget| ((budget.year as i32, budget.month as u32), budget))
+            .collect();
+
+        let range = DateRange::from_dates(first_date, last_date + Days::new(1));
+        let mut seen: HashSet<DuplicateKey> = ExpenseRepository
+            .list_by_spent_period(pool, user_id, range)?
+            .into_iter()
+            .map(|expense| {
+                duplicate_key(
+                    TransactionKind::Expense,
+                    expense.spent_at.date(),
+                    expense.amount,
+                    expense.narration.as_str(),
+                )
+            })
+            .collect();
+
+        seen.extend(
+            IncomeRepository
+                .list_by_received_period(pool, user_id, range)?
+                .into_iter()
+                .map(|income| {
+                    duplicate_key(
+                        TransactionKind::Income,
+                        income.received_at.date(),
+                        income.amount,
+                        income.narration.as_str(),
+                    )
+                }),
+        );
+
+        let matcher = RuleMatcher::load(pool, user_id)?;
+
+        for row in rows.iter_mut() {
+            if let Some(external_id) = &row.external_id {
+                // also catches a transaction repeated within the same statement
+                if !imported_ids.insert((row.account.clone(), external_id.clone())) {
+                    row.already_imported = true;
+                    row.duplicate = true;
+                    continue;
+                }
+            }
+
+            let (spent_at, amount, narration) = match (row.spent_at, row.amount, &row.narration) {
+                (Some(spent_at), Some(amount), Some(narration)) => {
+                    (spent_at, amount, narration.clone())
+                }
+                _ => continue,
+            };
+
+            row.duplicate = !seen.insert(duplicate_key(
+                row.kind,
+                spent_at.date(),
+                amount,
+                narration.as_str(),
+            ));
+
+            if row.kind == TransactionKind::Income {
+                continue;
+            }
+
+            if let Some(rule) = matcher.first_match(narration.as_str(), amount) {
+                row.category_id = rule.category_id;
+                row.expense_rule_id = Some(rule.expense_rule_id);
+            }
+
+            let budget = match budgets.get_mut(&(spent_at.year(), spent_at.month())) {
+                Some(budget) => budget,
+                None => {
+                    row.errors
+                        .push(format!("No budget for {} found", spent_at.format("%B, %Y")));
+                    continue;
+                }
+            };
+
+            row.budget_id = Some(budget.budget_id);
+
+            // duplicates are skipped by default, so they don't use up the budget
+            if row.duplicate {
+                continue;
+            }
+
+            if amount > budget.available_amount()
+                && budget.overspend_policy() == OverspendPolicy::Reject
+            {
+                row.errors
+                    .push(format!("This expense exceeds the {}", budget.title));
+                continue;
+            }
+
+            budget.amount_used += amount;
+        }
+
+        Ok(rows)
+    }
+
+    /// Puts every row into the mapped project, or into the project its bank
+    /// account is linked to when no project was picked
+    fn assign_projects(
+        &mut self,
+        pool: &DBPool,
+        import: &Import,
+        mapping: &ImportMapping,
+        rows: &mut [ImportRow],
+    ) -> AppResult<()> {
+        if let Some(project_id) = &mapping.project_id {
+            let project_id =
+                ProjectService.find_owned_project_id(pool, import.user_id, project_id.as_str())?;
+
+            rows.iter_mut()
+                .for_each(|row| row.project_id = Some(project_id));
+            return Ok(());
+        }
+
+        if import.source == IMPORT_SOURCE_CSV {
+            return Err(AppError::from("project_id is required for CSV imports"));
+        }
+
+        let accounts: HashMap<String, Uuid> = BankAccountRepository
+            .list_by_user_id(pool, import.user_id)?
+            .into_iter()
+            .map(|account| (account.account_number, account.project_id))
+            .collect();
+
+        for row in rows.iter_mut() {
+            match &row.account {
+                Some(account) => match accounts.get(account) {
+                    Some(project_id) => row.project_id = Some(*project_id),
+                    None => row.errors.push(format!(
+                        "Bank account \"{}\" is not mapped to a project, map it or pick a project for this import",
+                        account
+                    )),
+                },
+                None => row.errors.push(String::from(
+                    "Pick a project for this import, the statement does not name its bank account",
+                )),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+type DuplicateKey = (TransactionKind, NaiveDate, i64, String);
+
+fn duplicate_key(
+    kind: TransactionKind,
+    date: NaiveDate,
+    amount: i64,
+    narration: &str,
+) -> DuplicateKey {
+    (kind, date, amount, narration.trim().to_lowercase())
+}
@@ -0,0 +1,28 @@
+use uuid::Uuid;
+
+use crate::core::enums::app_error::AppResult;
+use crate::models::income::{Income, IncomeForm};
+use crate::models::user::User;
+use crate::models::DBPool;
+use crate::repositories::income_repository::IncomeRepository;
+use crate::services::project_service::ProjectService;
+
+pub struct IncomeService;
+
+impl IncomeService {
+    pub fn create(&mut self, pool: &DBPool, user: &User, form: IncomeForm) -> AppResult<Income> {
+        ProjectService.find_owned_project_id(pool, user.user_id, form.project_id.as_str())?;
+        IncomeRepository.create(pool, user.user_id, user.timezone.as_str(), form)
+    }
+
+    pub fn update(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user: &User,
+        form: IncomeForm,
+    ) -> AppResult<Income> {
+        ProjectService.find_owned_project_id(pool, user.user_id, form.project_id.as_str())?;
+        IncomeRepository.update(pool, id, user.user_id, user.timezone.as_str(), form)
+    }
+}
@@ -0,0 +1,219 @@
+use uuid::Uuid;
+
+use crate::core::enums::app_error::AppResult;
+use crate::models::label::{ExpenseLabel, Label, LabelForm, LabelModule, ProjectLabel};
+use crate::models::DBPool;
+use crate::repositories::label_repository::LabelRepository;
+
+pub struct LabelService;
+
+impl LabelService {
+    pub fn create(&mut self, pool: &DBPool, user_id: Uuid, form: LabelForm) -> AppResult<Label> {
+        LabelModule::parse(form.module.as_str())?;
+        LabelRepository.create(pool, user_id, form)
+    }
+
+    pub fn update(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+        form: LabelForm,
+    ) -> AppResult<Label> {
+        LabelModule::parse(form.module.as_str())?;
+        LabelRepository.update(pool, id, user_id, form)
+    }
+
+    pub fn attach_to_project(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        project_id: Uuid,
+        label: &Label,
+    ) -> AppResult<ProjectLabel> {
+        ensure_module(label, LabelModule::Projects)?;
+        Ok(LabelRepository.attach_to_project(pool, user_id, project_id, label.label_id)?)
+    }
+
+    pub fn attach_to_expense(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        expense_id: Uuid,
+        label: &Label,
+    ) -> AppResult<ExpenseLabel> {
+        ensure_module(label, LabelModule::Expenses)?;
+        Ok(LabelRepository.attach_to_expense(pool, user_id, expense_id, label.label_id)?)
+    }
+}
+
+fn ensure_module(label: &Label, module: LabelModule) -> Result<(), String> {
+    match label.module == module.as_str() {
+        true => Ok(()),
+        false => Err(format!(
+            "Label \"{}\" belongs to {} and cannot be attached to {}",
+            label.name,
+            label.module,
+            module.as_str()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::enums::app_error::AppError;
+    use crate::core::helpers::testing::{create_budget, create_user, test_pool};
+    use crate::models::budget::OverspendPolicy;
+    use crate::models::expense::ExpenseForm;
+    use crate::models::project::ProjectForm;
+    use crate::repositories::expense_repository::ExpenseRepository;
+    use crate::repositories::project_repository::ProjectRepository;
+
+    /// Ids of a user with a project holding one expense
+    fn setup(pool: &DBPool) -> (Uuid, Uuid, Uuid) {
+        let user = create_user(pool);
+        let budget = create_budget(pool, user.user_id, 1000, OverspendPolicy::Allow);
+        let project = ProjectRepository
+            .create(
+                pool,
+                user.user_id,
+                ProjectForm {
+                    name: String::from("Household"),
+                    description: String::new(),
+                },
+            )
+            .unwrap();
+        let form = ExpenseForm {
+            project_id: project.project_id.to_string(),
+            amount: 10,
+            narration: String::from("Groceries"),
+            spent_at: None,
+            category_id: None,
+        };
+        let (expense, _, _) = ExpenseRepository
+            .create(pool, user.user_id, "UTC", budget.budget_id, form)
+            .unwrap();
+
+        (user.user_id, project.project_id, expense.expense_id)
+    }
+
+    fn create_label(pool: &DBPool, user_id: Uuid, module: &str) -> Label {
+        LabelService
+            .create(
+                pool,
+                user_id,
+                LabelForm {
+                    name: format!("Shared {}", module),
+                    module: String::from(module),
+                },
+            )
+            .unwrap()
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn labels_only_tag_records_of_their_module() {
+        let pool = test_pool();
+        let (user_id, project_id, expense_id) = setup(&pool);
+        let project_label = create_label(&pool, user_id, "projects");
+        let expense_label = create_label(&pool, user_id, "expenses");
+
+        let attached = LabelService.attach_to_expense(&pool, user_id, expense_id, &project_label);
+        assert!(matches!(attached, Err(AppError::BadRequest(_))));
+        let attached = LabelService.attach_to_project(&pool, user_id, project_id, &expense_label);
+        assert!(matches!(attached, Err(AppError::BadRequest(_))));
+
+        assert!(LabelRepository
+            .list_by_expense_id(&pool, expense_id, user_id)
+            .unwrap()
+            .is_empty());
+        assert!(LabelRepository
+            .list_by_project_id(&pool, project_id, user_id)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn attaching_twice_keeps_one_attachment() {
+        let pool = test_pool();
+        let (user_id, project_id, expense_id) = setup(&pool);
+        let project_label = create_label(&pool, user_id, "projects");
+        let expense_label = create_label(&pool, user_id, "expenses");
+
+        let first = LabelService
+            .attach_to_expense(&pool, user_id, expense_id, &expense_label)
+            .unwrap();
+        let second = LabelService
+            .attach_to_expense(&pool, user_id, expense_id, &expense_label)
+            .unwrap();
+        assert_eq!(first.expense_label_id, second.expense_label_id);
+
+        let first = LabelService
+            .attach_to_project(&pool, user_id, project_id, &project_label)
+            .unwrap();
+        let second = LabelService
+            .attach_to_project(&pool, user_id, project_id, &project_label)
+            .unwrap();
+        assert_eq!(first.project_label_id, second.project_label_id);
+
+        let labels = LabelRepository
+            .list_by_expense_id(&pool, expense_id, user_id)
+            .unwrap();
+        assert_eq!(labels.len(), 1);
+        let labels = LabelRepository
+            .list_by_project_id(&pool, project_id, user_id)
+            .unwrap();
+        assert_eq!(labels.len(), 1);
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn deleting_an_attached_label_detaches_it() {
+        let pool = test_pool();
+        let (user_id, project_id, expense_id) = setup(&pool);
+        let project_label = create_label(&pool, user_id, "projects");
+        let expense_label = create_label(&pool, user_id, "expenses");
+        LabelService
+            .attach_to_expense(&pool, user_id, expense_id, &expense_label)
+            .unwrap();
+        LabelService
+            .attach_to_project(&pool, user_id, project_id, &project_label)
+            .unwrap();
+
+        // an attached label keeps its module
+        let moved = LabelService.update(
+            &pool,
+            expense_label.label_id,
+            user_id,
+            LabelForm {
+                name: expense_label.name.clone(),
+                module: String::from("projects"),
+            },
+        );
+        assert!(matches!(moved, Err(AppError::Conflict(_))));
+
+        for label in [&expense_label, &project_label] {
+            LabelRepository
+                .delete(&pool, label.label_id, user_id)
+                .unwrap();
+        }
+
+        assert!(LabelRepository
+            .list_by_expense_id(&pool, expense_id, user_id)
+            .unwrap()
+            .is_empty());
+        assert!(LabelRepository
+            .list_by_project_id(&pool, project_id, user_id)
+            .unwrap()
+            .is_empty());
+        // the tagged records stay
+        ExpenseRepository
+            .find_owned_by_id(&pool, expense_id, user_id)
+            .unwrap();
+        ProjectRepository
+            .find_owned_by_id(&pool, project_id, user_id)
+            .unwrap();
+    }
+}
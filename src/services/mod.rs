@@ -1,4 +1,19 @@
+pub mod account_service;
+pub mod attachment_service;
 pub mod auth_service;
+pub mod bank_account_service;
 pub mod budget_service;
+pub mod category_service;
+pub mod dashboard_service;
+pub mod expense_rule_service;
 pub mod expense_service;
+pub mod export_service;
+pub mod import_service;
+pub mod income_service;
+pub mod label_service;
+pub mod notification_service;
 pub mod project_service;
+pub mod report_service;
+pub mod search_service;
+pub mod statement_service;
+pub mod trash_service;
@@ -0,0 +1,33 @@
+use diesel::QueryResult;
+
+use crate::core::notification::{notification_channels, queue_delivery};
+use crate::models::notification::{Notification, NotificationEvent};
+use crate::models::DBPool;
+use crate::repositories::notification_repository::NotificationRepository;
+use crate::repositories::user_repository::UserRepository;
+
+pub struct NotificationService;
+
+impl NotificationService {
+    /// Persists the event as an in-app notification, then queues it for every enabled channel.
+    /// Delivery happens in the background and failures are only logged, so they never
+    /// slow down or fail the action that raised the event
+    pub fn dispatch(
+        &mut self,
+        pool: &DBPool,
+        event: NotificationEvent,
+    ) -> QueryResult<Notification> {
+        let notification = NotificationRepository.create(pool, &event)?;
+
+        let channels = notification_channels();
+        if channels.is_empty() {
+            return Ok(notification);
+        }
+
+        if let Some(user) = UserRepository.find_by_id(pool, notification.user_id)? {
+            queue_delivery(user, notification.clone());
+        }
+
+        Ok(notification)
+    }
+}
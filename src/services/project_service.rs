@@ -1,6 +1,9 @@
+use std::str::FromStr;
+
 use uuid::Uuid;
 
-use crate::core::enums::http_error::DBResult;
+use crate::core::enums::app_error::{AppError, AppResult, MissingAs};
+use crate::models::expense::ExpenseCascade;
 use crate::models::project::{Project, ProjectForm};
 use crate::models::DBPool;
 use crate::repositories::project_repository::ProjectRepository;
@@ -8,7 +11,12 @@ use crate::repositories::project_repository::ProjectRepository;
 pub struct ProjectService;
 
 impl ProjectService {
-    pub fn create(&mut self, pool: &DBPool, user_id: Uuid, form: ProjectForm) -> Project {
+    pub fn create(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        form: ProjectForm,
+    ) -> AppResult<Project> {
         ProjectRepository.create(pool, user_id, form)
     }
 
@@ -18,11 +26,201 @@ impl ProjectService {
         id: Uuid,
         user_id: Uuid,
         form: ProjectForm,
-    ) -> DBResult<Project> {
+    ) -> AppResult<Project> {
         ProjectRepository.update(pool, id, user_id, form)
     }
 
-    pub fn delete(&mut self, pool: &DBPool, id: Uuid, user_id: Uuid) -> DBResult<Project> {
-        ProjectRepository.delete(pool, id, user_id)
+    /// Makes sure the given project id belongs to the user
+    pub fn find_owned_project_id(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        project_id: &str,
+    ) -> AppResult<Uuid> {
+        let id = Uuid::from_str(project_id).map_err(|_| {
+            AppError::from("Your provided project ID is invalid, please inspect it")
+        })?;
+
+        ProjectRepository
+            .find_owned_by_id(pool, id, user_id)
+            .missing_as("Such project does not exist")?;
+
+        Ok(id)
+    }
+
+    pub fn delete(
+        &mut self,
+        pool: &DBPool,
+        id: Uuid,
+        user_id: Uuid,
+        cascade: ExpenseCascade,
+    ) -> AppResult<Project> {
+        if let ExpenseCascade::Move(target_id) = cascade {
+            if target_id == id {
+                return Err(AppError::from(
+                    "Expenses can't be moved to the project being deleted",
+                ));
+            }
+
+            ProjectRepository
+                .find_owned_by_id(pool, target_id, user_id)
+                .missing_as("The project to move the expenses to does not exist")?;
+        }
+
+        ProjectRepository.delete(pool, id, user_id, cascade)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::core::helpers::testing::{create_budget, create_user, test_pool};
+    use crate::models::bank_account::BankAccountForm;
+    use crate::models::budget::OverspendPolicy;
+    use crate::models::expense::ExpenseForm;
+    use crate::models::income::IncomeForm;
+    use crate::models::trash::TrashKind;
+    use crate::repositories::bank_account_repository::BankAccountRepository;
+    use crate::repositories::expense_repository::ExpenseRepository;
+    use crate::repositories::income_repository::IncomeRepository;
+    use crate::services::trash_service::TrashService;
+
+    fn create_project(pool: &DBPool, user_id: Uuid) -> Uuid {
+        ProjectService
+            .create(
+                pool,
+                user_id,
+                ProjectForm {
+                    name: String::from("Household"),
+                    description: String::new(),
+                },
+            )
+            .unwrap()
+            .project_id
+    }
+
+    /// Project with an income and a bank account, returns their ids
+    fn create_funded_project(pool: &DBPool, user_id: Uuid) -> (Uuid, Uuid, Uuid) {
+        let project_id = create_project(pool, user_id);
+        let income = IncomeRepository
+            .create(
+                pool,
+                user_id,
+                "UTC",
+                IncomeForm {
+                    project_id: project_id.to_string(),
+                    amount: 500,
+                    narration: String::from("Salary"),
+                    received_at: None,
+                },
+            )
+            .unwrap();
+        let bank_account = BankAccountRepository
+            .create(
+                pool,
+                user_id,
+                BankAccountForm {
+                    project_id: project_id.to_string(),
+                    account_number: Uuid::new_v4().to_string(),
+                    name: None,
+                },
+            )
+            .unwrap();
+
+        (project_id, income.income_id, bank_account.bank_account_id)
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn incomes_and_bank_accounts_follow_the_cascade() {
+        let pool = test_pool();
+        let user = create_user(&pool);
+        let user_id = user.user_id;
+        let (project_id, income_id, bank_account_id) = create_funded_project(&pool, user_id);
+
+        let refused = ProjectService.delete(&pool, project_id, user_id, ExpenseCascade::Refuse);
+        assert!(matches!(refused, Err(AppError::Conflict(_))));
+
+        ProjectService
+            .delete(&pool, project_id, user_id, ExpenseCascade::Cascade)
+            .unwrap();
+        assert!(IncomeRepository
+            .find_owned_by_id(&pool, income_id, user_id)
+            .is_err());
+        assert!(BankAccountRepository
+            .find_owned_by_id(&pool, bank_account_id, user_id)
+            .is_err());
+
+        // restoring the project brings back what was trashed with it
+        TrashService
+            .restore(&pool, user_id, TrashKind::Project, project_id)
+            .unwrap();
+        IncomeRepository
+            .find_owned_by_id(&pool, income_id, user_id)
+            .unwrap();
+        BankAccountRepository
+            .find_owned_by_id(&pool, bank_account_id, user_id)
+            .unwrap();
+
+        // what is trashed with the project doesn't keep it from being purged
+        ProjectService
+            .delete(&pool, project_id, user_id, ExpenseCascade::Cascade)
+            .unwrap();
+        TrashService
+            .purge(&pool, user_id, TrashKind::Project, project_id)
+            .unwrap();
+
+        let target_id = create_project(&pool, user_id);
+        let (project_id, income_id, bank_account_id) = create_funded_project(&pool, user_id);
+        ProjectService
+            .delete(&pool, project_id, user_id, ExpenseCascade::Move(target_id))
+            .unwrap();
+
+        let income = IncomeRepository
+            .find_owned_by_id(&pool, income_id, user_id)
+            .unwrap();
+        assert_eq!(income.project_id, target_id);
+        let bank_account = BankAccountRepository
+            .find_owned_by_id(&pool, bank_account_id, user_id)
+            .unwrap();
+        assert_eq!(bank_account.project_id, target_id);
+
+        TrashService
+            .purge(&pool, user_id, TrashKind::Project, project_id)
+            .unwrap();
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn spending_cannot_slip_into_a_deleted_project() {
+        let pool = test_pool();
+        let user = create_user(&pool);
+        let budget = create_budget(&pool, user.user_id, 1000, OverspendPolicy::Allow);
+
+        for _ in 0..10 {
+            let project_id = create_project(&pool, user.user_id);
+
+            let spending = {
+                let (pool, user_id, budget_id) = (pool.clone(), user.user_id, budget.budget_id);
+                thread::spawn(move || {
+                    let form = ExpenseForm {
+                        project_id: project_id.to_string(),
+                        amount: 10,
+                        narration: String::from("Groceries"),
+                        spent_at: None,
+                        category_id: None,
+                    };
+                    ExpenseRepository.create(&pool, user_id, "UTC", budget_id, form)
+                })
+            };
+            let deleted =
+                ProjectService.delete(&pool, project_id, user.user_id, ExpenseCascade::Refuse);
+            let spent = spending.join().unwrap();
+
+            // either the expense got in first and the delete is refused, or it finds no project
+            assert_ne!(deleted.is_ok(), spent.is_ok());
+        }
     }
 }
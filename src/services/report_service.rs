@@ -0,0 +1,514 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, Duration, Months, NaiveDate};
+use uuid::Uuid;
+
+use crate::core::enums::app_error::{AppError, AppResult, OrInternal};
+use crate::core::helpers::date_time::{parse_date, today_in_timezone, DateRange, Month};
+use crate::core::helpers::form::parse_optional_uuid;
+use crate::models::budget::Budget;
+use crate::models::report::{
+    BudgetMonthSummary, BudgetReport, BudgetYearReport, RecurringItem, SpendingBucket,
+    SpendingGroup, SpendingReport, SpendingReportParams, SpendingRow,
+};
+use crate::models::user::User;
+use crate::models::DBPool;
+use crate::repositories::budget_repository::BudgetRepository;
+use crate::repositories::report_repository::{fetch_recurring_items, SpendingQuery};
+
+pub struct ReportService;
+
+// guards against zero-filling years worth of daily buckets
+const MAX_SPENDING_BUCKETS: usize = 1000;
+
+impl ReportService {
+    pub fn spending(
+        &mut self,
+        pool: &DBPool,
+        user: &User,
+        params: SpendingReportParams,
+    ) -> AppResult<SpendingReport> {
+        let today = today_in_timezone(user.timezone.as_str());
+        let from = match params.from {
+            None => today.with_day(1).unwrap(),
+            Some(from) => parse_date(from.as_str())?,
+        };
+        let to = match params.to {
+            None => today,
+            Some(to) => parse_date(to.as_str())?,
+        };
+
+        if from > to {
+            return Err(AppError::from(
+                "The \"from\" date must not be after the \"to\" date",
+            ));
+        }
+
+        let end = to
+            .succ_opt()
+            .ok_or_else(|| String::from("The \"to\" date is out of range"))?;
+
+        let group = SpendingGroup::parse(params.group_by.as_deref().unwrap_or("day"))?;
+        let query = SpendingQuery::new(user.user_id)
+            .between(DateRange::from_dates(from, end))
+            .project(parse_optional_uuid(params.project_id, "project")?)
+            .label(parse_optional_uuid(params.label_id, "label")?);
+
+        let total = query.total(pool)?;
+        let rows = query.grouped(pool, group)?;
+        let budgets = BudgetRepository.list_by_period(pool, user.user_id, from, to)?;
+
+        let buckets = match group.is_period() {
+            true => zero_filled_buckets(group, from, to, rows, &budgets)?,
+            false => rows
+                .into_iter()
+                .map(|row| SpendingBucket {
+                    key: row.bucket_key,
+                    label: row.bucket_label,
+                    amount: row.amount,
+                    expenses_count: row.expenses_count,
+                    budget_amount: None,
+                })
+                .collect(),
+        };
+
+        let budget_amount = budgets.iter().map(|budget| budget.amount).sum();
+
+        Ok(SpendingReport {
+            from,
+            to,
+            group_by: group,
+            total_amount: total.amount,
+            total_expenses: total.expenses_count,
+            budget_amount,
+            budget_balance: budget_amount - total.amount,
+            buckets,
+        })
+    }
+
+    /// Planned vs actual spending of a budget, projecting the month's end total from
+    /// the daily burn rate so far plus recurring items that haven't been recorded yet
+    pub fn budget(
+        &mut self,
+        pool: &DBPool,
+        user: &User,
+        budget: Budget,
+    ) -> AppResult<BudgetReport> {
+        let month = budget_month(&budget).or_internal("Failed to build the budget report")?;
+        let actual_amount = SpendingQuery::new(user.user_id)
+            .budget(Some(budget.budget_id))
+            .total(pool)?
+            .amount;
+
+        let today = today_in_timezone(user.timezone.as_str());
+        let recurring_items = match days_elapsed(month, today) < month.days() {
+            true => fetch_recurring_items(pool, user.user_id, month)?,
+            false => vec![],
+        };
+
+        Ok(budget_report(
+            budget,
+            month,
+            today,
+            actual_amount,
+            recurring_items,
+        ))
+    }
+
+    /// Planned vs actual spending of every month within the given (or current) year
+    pub fn budget_year(
+        &mut self,
+        pool: &DBPool,
+        user: &User,
+        year: Option<i16>,
+    ) -> AppResult<BudgetYearReport> {
+        let year = year.unwrap_or(today_in_timezone(user.timezone.as_str()).year() as i16);
+        let (from, to) = match (
+            NaiveDate::from_ymd_opt(year as i32, 1, 1),
+            NaiveDate::from_ymd_opt(year as i32, 12, 31),
+        ) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return Err(AppError::from(format!("Invalid year \"{}\"", year))),
+        };
+
+        let budgets = BudgetRepository.list_by_period(pool, user.user_id, from, to)?;
+
+        let spending: HashMap<Uuid, i64> = SpendingQuery::new(user.user_id)
+            .budgets(budgets.iter().map(|b| b.budget_id).collect())
+            .by_budget(pool)?
+            .into_iter()
+            .map(|row| (row.budget_id, row.amount))
+            .collect();
+
+        year_report(year, &budgets, &spending).or_internal("Failed to build the year report")
+    }
+}
+
+/// Planned vs actual spending of each month of the year given its budgets and what was spent on them
+fn year_report(
+    year: i16,
+    budgets: &[Budget],
+    spending: &HashMap<Uuid, i64>,
+) -> Result<BudgetYearReport, String> {
+    let mut months = vec![];
+    for month in 1..=12 {
+        let month_budgets: Vec<&Budget> = budgets.iter().filter(|b| b.month == month).collect();
+
+        let actual_amount = month_budgets
+            .iter()
+            .filter_map(|b| spending.get(&b.budget_id))
+            .sum();
+
+        let planned_amount = month_budgets.iter().map(|b| b.amount).sum();
+
+        months.push(BudgetMonthSummary {
+            month,
+            name: Month::new(month).name()?.to_string(),
+            budget_id: month_budgets.first().map(|b| b.budget_id),
+            planned_amount,
+            actual_amount,
+            variance: planned_amount - actual_amount,
+            overspent_amount: (actual_amount - planned_amount).max(0),
+        });
+    }
+
+    let planned_amount = months.iter().map(|m| m.planned_amount).sum();
+    let actual_amount = months.iter().map(|m| m.actual_amount).sum();
+
+    Ok(BudgetYearReport {
+        year,
+        planned_amount,
+        actual_amount,
+        variance: planned_amount - actual_amount,
+        overspent_amount: months.iter().map(|m| m.overspent_amount).sum(),
+        months,
+    })
+}
+
+fn budget_month(budget: &Budget) -> Result<DateRange, String> {
+    DateRange::month(budget.year as i32, budget.month as u32)
+        .ok_or_else(|| String::from("Budget has an invalid month or year"))
+}
+
+/// Days of the month gone by at the given date, the whole month once it's over
+fn days_elapsed(month: DateRange, today: NaiveDate) -> i64 {
+    let now = today.and_hms_opt(0, 0, 0).unwrap();
+    match now {
+        _ if now >= month.end => month.days(),
+        _ if now < month.start => 0,
+        _ => today.day() as i64,
+    }
+}
+
+/// Planned vs actual figures of the budget along with the month's end projection
+fn budget_report(
+    budget: Budget,
+    month: DateRange,
+    today: NaiveDate,
+    actual_amount: i64,
+    recurring_items: Vec<RecurringItem>,
+) -> BudgetReport {
+    let days_in_month = month.days();
+    let days_elapsed = days_elapsed(month, today);
+
+    let burn_rate_per_day = match days_elapsed {
+        0 => 0,
+        _ => actual_amount / days_elapsed,
+    };
+
+    let recurring_amount = recurring_items.iter().map(|item| item.amount).sum();
+    let projected_amount =
+        actual_amount + burn_rate_per_day * (days_in_month - days_elapsed) + recurring_amount;
+
+    BudgetReport {
+        planned_amount: budget.amount,
+        actual_amount,
+        variance: budget.amount - actual_amount,
+        overspent_amount: (actual_amount - budget.amount).max(0),
+        days_in_month,
+        days_elapsed,
+        burn_rate_per_day,
+        recurring_amount,
+        recurring_items,
+        projected_amount,
+        projected_variance: budget.amount - projected_amount,
+        projected_overspent_amount: (projected_amount - budget.amount).max(0),
+        budget,
+    }
+}
+
+/// Lays out every period between the two dates, filling periods without expenses with zeros
+fn zero_filled_buckets(
+    group: SpendingGroup,
+    from: NaiveDate,
+    to: NaiveDate,
+    rows: Vec<SpendingRow>,
+    budgets: &[Budget],
+) -> Result<Vec<SpendingBucket>, String> {
+    let mut rows: HashMap<String, SpendingRow> = rows
+        .into_iter()
+        .map(|row| (row.bucket_key.clone(), row))
+        .collect();
+
+    let mut buckets = vec![];
+    let mut period = period_start(group, from)
+        .ok_or_else(|| String::from("The \"from\" date is out of range"))?;
+
+    while period <= to {
+        if buckets.len() == MAX_SPENDING_BUCKETS {
+            return Err(format!(
+                "Too many buckets, please narrow down the date range or group by a longer period (max {})",
+                MAX_SPENDING_BUCKETS
+            ));
+        }
+
+        let (key, label) = period_key(group, period);
+        let row = rows.remove(&key);
+        let budget_amount = match group {
+            SpendingGroup::Month => budgets
+                .iter()
+                .filter(|b| b.year as i32 == period.year() && b.month as u32 == period.month())
+                .map(|b| b.amount)
+                .reduce(|a, b| a + b),
+            _ => None,
+        };
+
+        buckets.push(SpendingBucket {
+            key,
+            label,
+            amount: row.as_ref().map(|r| r.amount).unwrap_or(0),
+            expenses_count: row.as_ref().map(|r| r.expenses_count).unwrap_or(0),
+            budget_amount,
+        });
+
+        period = match next_period(group, period) {
+            Some(next) => next,
+            None => break,
+        };
+    }
+
+    Ok(buckets)
+}
+
+/// First day of the period containing the date, `None` past the supported dates
+fn period_start(group: SpendingGroup, date: NaiveDate) -> Option<NaiveDate> {
+    match group {
+        SpendingGroup::Week => {
+            date.checked_sub_signed(Duration::days(date.weekday().num_days_from_monday() as i64))
+        }
+        SpendingGroup::Month => date.with_day(1),
+        _ => Some(date),
+    }
+}
+
+/// First day of the following period, `None` past the supported dates
+fn next_period(group: SpendingGroup, date: NaiveDate) -> Option<NaiveDate> {
+    match group {
+        SpendingGroup::Week => date.checked_add_signed(Duration::days(7)),
+        SpendingGroup::Month => date.checked_add_months(Months::new(1)),
+        _ => date.succ_opt(),
+    }
+}
+
+/// Bucket key (matching the one produced by `SpendingQuery::grouped`) and display label
+fn period_key(group: SpendingGroup, period: NaiveDate) -> (String, String) {
+    match group {
+        SpendingGroup::Week => (
+            period.format("%Y-%m-%d").to_string(),
+            format!(
+                "{}-W{:02}",
+                period.iso_week().year(),
+                period.iso_week().week()
+            ),
+        ),
+        SpendingGroup::Month => (
+            period.format("%Y-%m").to_string(),
+            period.format("%B %Y").to_string(),
+        ),
+        _ => {
+            let key = period.format("%Y-%m-%d").to_string();
+            (key.clone(), key)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::helpers::testing::make_budget;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn row(key: &str, amount: i64) -> SpendingRow {
+        SpendingRow {
+            bucket_key: String::from(key),
+            bucket_label: String::from(key),
+            amount,
+            expenses_count: 1,
+        }
+    }
+
+    fn amounts(buckets: &[SpendingBucket]) -> Vec<i64> {
+        buckets.iter().map(|bucket| bucket.amount).collect()
+    }
+
+    fn july_budget(amount: i64) -> (Budget, DateRange) {
+        let budget = make_budget(amount, 0, 7, 2026);
+        let month = budget_month(&budget).unwrap();
+        (budget, month)
+    }
+
+    #[test]
+    fn days_without_expenses_are_zero_filled() {
+        let buckets = zero_filled_buckets(
+            SpendingGroup::Day,
+            date(2026, 7, 1),
+            date(2026, 7, 3),
+            vec![row("2026-07-02", 250)],
+            &[],
+        )
+        .unwrap();
+
+        let keys: Vec<&str> = buckets.iter().map(|bucket| bucket.key.as_str()).collect();
+        assert_eq!(keys, ["2026-07-01", "2026-07-02", "2026-07-03"]);
+        assert_eq!(amounts(&buckets), [0, 250, 0]);
+        assert_eq!(buckets[0].expenses_count, 0);
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        let buckets = zero_filled_buckets(
+            SpendingGroup::Week,
+            date(2026, 10, 14),
+            date(2026, 10, 20),
+            vec![row("2026-10-19", 80)],
+            &[],
+        )
+        .unwrap();
+
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(buckets[0].key, "2026-10-12");
+        assert_eq!(buckets[0].label, "2026-W42");
+        assert_eq!(amounts(&buckets), [0, 80]);
+    }
+
+    #[test]
+    fn months_carry_their_budget_amounts() {
+        let buckets = zero_filled_buckets(
+            SpendingGroup::Month,
+            date(2026, 6, 15),
+            date(2026, 8, 10),
+            vec![row("2026-07", 400)],
+            &[make_budget(1000, 0, 7, 2026), make_budget(500, 0, 7, 2026)],
+        )
+        .unwrap();
+
+        assert_eq!(amounts(&buckets), [0, 400, 0]);
+        let budget_amounts: Vec<Option<i64>> =
+            buckets.iter().map(|bucket| bucket.budget_amount).collect();
+        assert_eq!(budget_amounts, [None, Some(1500), None]);
+        assert_eq!(buckets[1].label, "July 2026");
+    }
+
+    #[test]
+    fn zero_filling_stops_at_the_last_supported_date() {
+        let buckets = zero_filled_buckets(
+            SpendingGroup::Week,
+            NaiveDate::MAX - Duration::days(10),
+            NaiveDate::MAX,
+            vec![],
+            &[],
+        )
+        .unwrap();
+
+        assert!(buckets.len() >= 2);
+        assert!(next_period(SpendingGroup::Day, NaiveDate::MAX).is_none());
+    }
+
+    #[test]
+    fn too_many_buckets_are_refused() {
+        let result = zero_filled_buckets(
+            SpendingGroup::Day,
+            date(2020, 1, 1),
+            date(2026, 1, 1),
+            vec![],
+            &[],
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn month_end_is_projected_from_the_burn_rate_and_recurring_items() {
+        let (budget, month) = july_budget(3100);
+        let recurring = vec![RecurringItem {
+            project_id: budget.budget_id,
+            narration: String::from("Rent"),
+            amount: 200,
+        }];
+
+        let report = budget_report(budget, month, date(2026, 7, 10), 1000, recurring);
+
+        assert_eq!(report.days_in_month, 31);
+        assert_eq!(report.days_elapsed, 10);
+        assert_eq!(report.burn_rate_per_day, 100);
+        assert_eq!(report.variance, 2100);
+        assert_eq!(report.overspent_amount, 0);
+        assert_eq!(report.recurring_amount, 200);
+        assert_eq!(report.projected_amount, 1000 + 100 * 21 + 200);
+        assert_eq!(report.projected_variance, -200);
+        assert_eq!(report.projected_overspent_amount, 200);
+    }
+
+    #[test]
+    fn past_months_report_their_actual_spending() {
+        let (budget, month) = july_budget(3100);
+
+        let report = budget_report(budget, month, date(2026, 8, 5), 3500, vec![]);
+
+        assert_eq!(report.days_elapsed, 31);
+        assert_eq!(report.projected_amount, 3500);
+        assert_eq!(report.variance, -400);
+        assert_eq!(report.overspent_amount, 400);
+        assert_eq!(report.projected_overspent_amount, 400);
+    }
+
+    #[test]
+    fn future_months_project_recurring_items_only() {
+        let (budget, month) = july_budget(3100);
+        assert_eq!(days_elapsed(month, date(2026, 6, 20)), 0);
+
+        let report = budget_report(budget, month, date(2026, 6, 20), 0, vec![]);
+        assert_eq!(report.burn_rate_per_day, 0);
+        assert_eq!(report.projected_amount, 0);
+        assert_eq!(report.projected_variance, 3100);
+    }
+
+    #[test]
+    fn year_report_compares_every_month() {
+        let march = make_budget(1000, 0, 3, 2026);
+        let july = make_budget(2000, 0, 7, 2026);
+        let spending = HashMap::from([(march.budget_id, 1200), (july.budget_id, 500)]);
+
+        let report = year_report(2026, &[march.clone(), july], &spending).unwrap();
+
+        assert_eq!(report.months.len(), 12);
+        assert_eq!(report.months[0].planned_amount, 0);
+        assert_eq!(report.months[0].budget_id, None);
+
+        let march_summary = &report.months[2];
+        assert_eq!(march_summary.name, "March");
+        assert_eq!(march_summary.budget_id, Some(march.budget_id));
+        assert_eq!(march_summary.variance, -200);
+        assert_eq!(march_summary.overspent_amount, 200);
+
+        assert_eq!(report.months[6].variance, 1500);
+        assert_eq!(report.planned_amount, 3000);
+        assert_eq!(report.actual_amount, 1700);
+        assert_eq!(report.variance, 1300);
+        // an underspent month doesn't make up for an overspent one
+        assert_eq!(report.overspent_amount, 200);
+    }
+}
@@ -0,0 +1,188 @@
+use uuid::Uuid;
+
+use crate::core::enums::app_error::AppResult;
+use crate::core::helpers::db_pagination::max_per_page;
+use crate::models::search::{SearchKind, SearchParams, SearchResults};
+use crate::models::DBPool;
+use crate::repositories::search_repository::SearchRepository;
+
+const DEFAULT_SEARCH_LIMIT: i64 = 20;
+
+// longer queries are almost certainly not typed by a person
+const MAX_QUERY_LENGTH: usize = 200;
+
+// the search query wraps matched words in these, the snippets are escaped
+// before they are turned into markup
+const MATCH_START: char = '\u{2}';
+const MATCH_END: char = '\u{3}';
+
+pub struct SearchService;
+
+impl SearchService {
+    pub fn search(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        params: SearchParams,
+    ) -> AppResult<SearchResults> {
+        let query = params.q.unwrap_or_default().trim().to_string();
+        if query.is_empty() {
+            return Err("Search query (q) is required".into());
+        }
+
+        if query.chars().count() > MAX_QUERY_LENGTH {
+            return Err(format!(
+                "Search query must not be longer than {} characters",
+                MAX_QUERY_LENGTH
+            )
+            .into());
+        }
+
+        let kinds = match params.types.as_deref().map(str::trim) {
+            None | Some("") => SearchKind::ALL.to_vec(),
+            Some(types) => types
+                .split(',')
+                .map(|kind| SearchKind::parse(kind.trim()))
+                .collect::<Result<Vec<SearchKind>, String>>()?,
+        };
+
+        let limit = params
+            .limit
+            .unwrap_or(DEFAULT_SEARCH_LIMIT)
+            .clamp(1, max_per_page());
+
+        let mut hits = SearchRepository.search(pool, user_id, query.as_str(), &kinds, limit)?;
+        for hit in hits.iter_mut() {
+            hit.snippet = highlight(hit.snippet.as_str());
+        }
+
+        Ok(SearchResults { query, hits })
+    }
+}
+
+/// Escapes the snippet and marks up the matched words
+fn highlight(snippet: &str) -> String {
+    tera::escape_html(snippet)
+        .replace(MATCH_START, "<mark>")
+        .replace(MATCH_END, "</mark>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::enums::app_error::AppError;
+    use crate::core::helpers::testing::{create_budget, create_user, test_pool};
+    use crate::models::budget::OverspendPolicy;
+    use crate::models::expense::ExpenseForm;
+    use crate::models::project::ProjectForm;
+    use crate::repositories::expense_repository::ExpenseRepository;
+    use crate::repositories::project_repository::ProjectRepository;
+
+    /// User whose only expenses carry the given narrations
+    fn user_with_expenses(pool: &DBPool, narrations: &[&str]) -> Uuid {
+        let user = create_user(pool);
+        let budget = create_budget(pool, user.user_id, 10_000, OverspendPolicy::Allow);
+        let project = ProjectRepository
+            .create(
+                pool,
+                user.user_id,
+                ProjectForm {
+                    name: String::from("Household"),
+                    description: String::new(),
+                },
+            )
+            .unwrap();
+
+        for narration in narrations {
+            let form = ExpenseForm {
+                project_id: project.project_id.to_string(),
+                amount: 10,
+                narration: narration.to_string(),
+                spent_at: None,
+                category_id: None,
+            };
+            ExpenseRepository
+                .create(pool, user.user_id, "UTC", budget.budget_id, form)
+                .unwrap();
+        }
+
+        user.user_id
+    }
+
+    fn search(pool: &DBPool, user_id: Uuid, q: &str) -> AppResult<SearchResults> {
+        SearchService.search(
+            pool,
+            user_id,
+            SearchParams {
+                q: Some(String::from(q)),
+                types: Some(String::from("expenses")),
+                limit: None,
+            },
+        )
+    }
+
+    #[test]
+    fn highlight_escapes_before_marking_up() {
+        let snippet = format!("<b>Tom</b> & {}coffee{}", MATCH_START, MATCH_END);
+
+        assert_eq!(
+            highlight(snippet.as_str()),
+            "&lt;b&gt;Tom&lt;&#x2F;b&gt; &amp; <mark>coffee</mark>"
+        );
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn best_matches_come_first_with_the_words_marked() {
+        let pool = test_pool();
+        let user_id = user_with_expenses(
+            &pool,
+            &[
+                "Coffee beans",
+                "Coffee for the office, coffee filters and a coffee grinder",
+                "Train tickets",
+            ],
+        );
+
+        let results = search(&pool, user_id, "coffee").unwrap();
+        let titles: Vec<&str> = results.hits.iter().map(|hit| hit.title.as_str()).collect();
+
+        assert_eq!(
+            titles,
+            [
+                "Coffee for the office, coffee filters and a coffee grinder",
+                "Coffee beans"
+            ]
+        );
+        assert!(results.hits[0].rank > results.hits[1].rank);
+        assert_eq!(results.hits[1].snippet, "<mark>Coffee</mark> beans");
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn tsquery_operators_are_taken_as_text() {
+        let pool = test_pool();
+        let user_id = user_with_expenses(&pool, &["Rent & water", "Tom's <b>bakery</b>"]);
+
+        let results = search(&pool, user_id, "rent & water").unwrap();
+        assert_eq!(results.hits.len(), 1);
+        assert!(results.hits[0]
+            .snippet
+            .contains("<mark>Rent</mark> &amp; <mark>water</mark>"));
+
+        let results = search(&pool, user_id, "bakery").unwrap();
+        assert!(!results.hits[0].snippet.contains("<b>"));
+
+        for q in ["'", "a & b", "\"unclosed", "!(", "water:*", "or -", "\\"] {
+            let results = search(&pool, user_id, q);
+            assert!(results.is_ok(), "{}", q);
+        }
+
+        for q in ["", "   "] {
+            assert!(matches!(
+                search(&pool, user_id, q),
+                Err(AppError::BadRequest(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::{BTreeMap, HashMap};
+
+use tera::{Context, Tera};
+use uuid::Uuid;
+
+use crate::core::enums::app_error::AppResult;
+use crate::core::export::pdf;
+use crate::core::helpers::date_time::now_in_timezone;
+use crate::models::budget::Budget;
+use crate::models::label::Label;
+use crate::models::statement::{BudgetStatement, StatementExpense, StatementLine};
+use crate::models::user::User;
+use crate::models::DBPool;
+use crate::repositories::budget_repository::make_budget_title;
+use crate::repositories::expense_repository::ExpenseRepository;
+use crate::repositories::label_repository::LabelRepository;
+
+pub struct StatementService;
+
+const UNLABELLED: &str = "Unlabelled";
+
+impl StatementService {
+    pub fn build(
+        &mut self,
+        pool: &DBPool,
+        user: &User,
+        mut budget: Budget,
+    ) -> AppResult<BudgetStatement> {
+        let rows = ExpenseRepository.list_all_by_budget_id(pool, budget.budget_id, user.user_id)?;
+
+        let expense_ids = rows.iter().map(|(e, _)| e.expense_id).collect();
+        let mut project_ids: Vec<Uuid> = rows.iter().map(|(_, p)| p.project_id).collect();
+        project_ids.sort();
+        project_ids.dedup();
+
+        let expense_labels = group_label_names(LabelRepository.list_by_expense_ids(
+            pool,
+            user.user_id,
+            expense_ids,
+        )?);
+        let project_labels = group_label_names(LabelRepository.list_by_project_ids(
+            pool,
+            user.user_id,
+            project_ids,
+        )?);
+
+        let total_spent: i64 = rows.iter().map(|(e, _)| e.amount).sum();
+        let mut projects: BTreeMap<String, (usize, i64)> = BTreeMap::new();
+        let mut labels: BTreeMap<String, (usize, i64)> = BTreeMap::new();
+        let mut expenses = vec![];
+
+        for (expense, project) in rows {
+            let mut names = expense_labels
+                .get(&expense.expense_id)
+                .cloned()
+                .unwrap_or_default();
+            if let Some(inherited) = project_labels.get(&project.project_id) {
+                names.extend(inherited.iter().cloned());
+            }
+            names.sort();
+            names.dedup();
+
+            add_to_line(&mut projects, project.name.clone(), expense.amount);
+            match names.is_empty() {
+                true => add_to_line(&mut labels, String::from(UNLABELLED), expense.amount),
+                false => {
+                    for name in &names {
+                        add_to_line(&mut labels, name.clone(), expense.amount);
+                    }
+                }
+            }
+
+            expenses.push(StatementExpense {
+                spent_at: expense.spent_at,
+                narration: expense.narration,
+                project: project.name,
+                labels: names,
+                amount: expense.amount,
+            });
+        }
+
+        Ok(BudgetStatement {
+            title: make_budget_title(budget.month, budget.year),
+            account_name: format!("{} {}", user.first_name, user.last_name),
+            generated_at: now_in_timezone(user.timezone.as_str()),
+            total_budgeted: budget.amount,
+            total_spent,
+            total_available: budget.available_amount().max(0),
+            total_overspent: budget.overspent_amount(),
+            projects: to_lines(projects, total_spent),
+            labels: to_lines(labels, total_spent),
+            expenses,
+            budget,
+        })
+    }
+
+    pub fn render_html(
+        &mut self,
+        tera: &Tera,
+        statement: &BudgetStatement,
+    ) -> Result<String, String> {
+        let mut context = Context::new();
+        context.insert("statement", statement);
+
+        tera.render("statements/budget.tera.html", &context)
+            .map_err(|e| {
+                log::error!("failed to render budget statement: {:?}", e);
+                String::from("Failed to render the statement")
+            })
+    }
+
+    pub fn render_pdf(&mut self, statement: &BudgetStatement) -> Result<Vec<u8>, String> {
+        pdf::budget_statement(statement)
+    }
+}
+
+fn group_label_names(labels: Vec<(Uuid, Label)>) -> HashMap<Uuid, Vec<String>> {
+    let mut grouped: HashMap<Uuid, Vec<String>> = HashMap::new();
+    for (owner_id, label) in labels {
+        grouped.entry(owner_id).or_default().push(label.name);
+    }
+
+    grouped
+}
+
+fn add_to_line(lines: &mut BTreeMap<String, (usize, i64)>, name: String, amount: i64) {
+    let line = lines.entry(name).or_insert((0, 0));
+    line.0 += 1;
+    line.1 += amount;
+}
+
+/// Breakdown lines ordered by amount, largest first
+fn to_lines(lines: BTreeMap<String, (usize, i64)>, total: i64) -> Vec<StatementLine> {
+    let mut lines: Vec<StatementLine> = lines
+        .into_iter()
+        .map(|(name, (expenses, amount))| StatementLine {
+            name,
+            expenses,
+            amount,
+            share: match total {
+                0 => 0.0,
+                _ => (amount as f64 * 1000.0 / total as f64).round() / 10.0,
+            },
+        })
+        .collect();
+
+    lines.sort_by_key(|line| std::cmp::Reverse(line.amount));
+    lines
+}
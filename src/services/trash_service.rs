@@ -0,0 +1,430 @@
+use std::env;
+use std::time::Duration;
+
+use actix_web::rt::time::interval;
+use actix_web::web;
+use chrono::Duration as ChronoDuration;
+use uuid::Uuid;
+
+use crate::core::enums::app_error::{AppError, AppResult, MissingAs};
+use crate::core::helpers::db::current_timestamp;
+use crate::core::helpers::db_pagination::PaginationResult;
+use crate::core::helpers::http::QueryParams;
+use crate::core::storage::make_storage;
+use crate::models::attachment::Attachment;
+use crate::models::trash::{TrashItem, TrashKind, TrashParams, TrashPurgeSummary};
+use crate::models::DBPool;
+use crate::repositories::budget_repository::BudgetRepository;
+use crate::repositories::expense_repository::ExpenseRepository;
+use crate::repositories::project_repository::ProjectRepository;
+use crate::repositories::trash_repository::TrashRepository;
+use crate::services::budget_service::BudgetService;
+
+/// How often the retention job looks for expired trash
+const RETENTION_JOB_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Days deleted records stay in the trash, set through `TRASH_RETENTION_DAYS`
+/// (30 by default, 0 keeps them until purged by hand)
+pub fn trash_retention_days() -> Option<i32> {
+    let days = env::var("TRASH_RETENTION_DAYS")
+        .ok()
+        .and_then(|days| days.parse::<i32>().ok())
+        .unwrap_or(30);
+
+    match days > 0 {
+        true => Some(days),
+        false => None,
+    }
+}
+
+/// Purges expired trash every hour for as long as the server runs
+pub async fn run_retention_job(pool: DBPool) {
+    let days = match trash_retention_days() {
+        Some(days) => days,
+        None => return,
+    };
+
+    let mut ticks = interval(RETENTION_JOB_INTERVAL);
+    loop {
+        ticks.tick().await;
+
+        let pool = pool.clone();
+        match web::block(move || TrashService.purge_expired(&pool, days)).await {
+            Ok(Ok(summary)) => {
+                if summary.purged > 0 || summary.skipped > 0 || summary.failed > 0 {
+                    log::info!(
+                        "Purged {} expired trash items, skipped {}, failed {}",
+                        summary.purged,
+                        summary.skipped,
+                        summary.failed
+                    );
+                }
+            }
+            Ok(Err(err)) => log::error!("Failed to purge expired trash: {}", err),
+            Err(err) => log::error!("Failed to run trash retention job: {}", err),
+        }
+    }
+}
+
+pub struct TrashService;
+
+impl TrashService {
+    pub fn list(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        params: TrashParams,
+        mut query_params: QueryParams,
+    ) -> AppResult<PaginationResult<TrashItem>> {
+        let kinds = match params.types.as_deref().map(str::trim) {
+            None | Some("") => TrashKind::ALL.to_vec(),
+            Some(types) => types
+                .split(',')
+                .map(|kind| TrashKind::parse(kind.trim()))
+                .collect::<Result<Vec<TrashKind>, String>>()?,
+        };
+
+        TrashRepository.list(
+            pool,
+            user_id,
+            &kinds,
+            query_params.get_page(),
+            query_params.get_per_page(),
+            trash_retention_days(),
+        )
+    }
+
+    pub fn restore(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        kind: TrashKind,
+        id: Uuid,
+    ) -> AppResult<()> {
+        let charged = match kind {
+            TrashKind::Project => {
+                let project = ProjectRepository.find_trashed_by_id(pool, id, user_id)?;
+                ProjectRepository.restore(pool, &project)?.1
+            }
+            TrashKind::Budget => {
+                let budget = BudgetRepository.find_trashed_by_id(pool, id, user_id)?;
+                BudgetRepository
+                    .restore(pool, &budget)?
+                    .1
+                    .into_iter()
+                    .collect()
+            }
+            TrashKind::Expense => return self.restore_expense(pool, user_id, id),
+        };
+
+        for (budget, updated) in charged {
+            BudgetService.after_spending(pool, &budget, updated);
+        }
+
+        Ok(())
+    }
+
+    /// Charges the expense to its budget again, which has to be live and able
+    /// to take the amount under its overspend policy, its project has to be live too
+    fn restore_expense(&mut self, pool: &DBPool, user_id: Uuid, id: Uuid) -> AppResult<()> {
+        let expense = ExpenseRepository.find_trashed_by_id(pool, id, user_id)?;
+
+        ProjectRepository
+            .find_owned_by_id(pool, expense.project_id, user_id)
+            .missing_as("The project of this expense is deleted, restore it first")?;
+
+        let (budget, updated) = ExpenseRepository.restore(pool, &expense)?;
+        BudgetService.after_spending(pool, &budget, updated);
+
+        Ok(())
+    }
+
+    pub fn purge(
+        &mut self,
+        pool: &DBPool,
+        user_id: Uuid,
+        kind: TrashKind,
+        id: Uuid,
+    ) -> AppResult<()> {
+        // only the user's own trash is theirs to purge
+        let id = match kind {
+            TrashKind::Project => {
+                ProjectRepository
+                    .find_trashed_by_id(pool, id, user_id)?
+                    .project_id
+            }
+            TrashKind::Budget => {
+                BudgetRepository
+                    .find_trashed_by_id(pool, id, user_id)?
+                    .budget_id
+            }
+            TrashKind::Expense => {
+                ExpenseRepository
+                    .find_trashed_by_id(pool, id, user_id)?
+                    .expense_id
+            }
+        };
+
+        self.purge_by_id(pool, kind, id)
+    }
+
+    /// Purges everything in the user's trash, projects and budgets still
+    /// referenced by other records are skipped
+    pub fn empty(&mut self, pool: &DBPool, user_id: Uuid) -> AppResult<TrashPurgeSummary> {
+        let mut summary = TrashPurgeSummary::default();
+        for kind in TrashKind::ALL {
+            let ids = TrashRepository.list_ids(pool, user_id, kind)?;
+            self.purge_all(pool, kind, ids, &mut summary);
+        }
+
+        Ok(summary)
+    }
+
+    /// Purges the records of every user that were deleted more than `days` ago
+    pub fn purge_expired(&mut self, pool: &DBPool, days: i32) -> AppResult<TrashPurgeSummary> {
+        let cutoff = current_timestamp() - ChronoDuration::days(days as i64);
+
+        let mut summary = TrashPurgeSummary::default();
+        for kind in TrashKind::ALL {
+            let ids = match kind {
+                TrashKind::Project => ProjectRepository.list_ids_deleted_before(pool, cutoff)?,
+                TrashKind::Budget => BudgetRepository.list_ids_deleted_before(pool, cutoff)?,
+                TrashKind::Expense => ExpenseRepository.list_ids_deleted_before(pool, cutoff)?,
+            };
+
+            self.purge_all(pool, kind, ids, &mut summary);
+        }
+
+        Ok(summary)
+    }
+
+    /// Purges every item it can, one failing item doesn't keep the rest in the trash
+    fn purge_all(
+        &mut self,
+        pool: &DBPool,
+        kind: TrashKind,
+        ids: Vec<Uuid>,
+        summary: &mut TrashPurgeSummary,
+    ) {
+        for id in ids {
+            match self.purge_by_id(pool, kind, id) {
+                Ok(_) => summary.purged += 1,
+                Err(AppError::Conflict(_)) => summary.skipped += 1,
+                Err(err) => {
+                    log::error!("Failed to purge {} {}: {}", kind.as_str(), id, err);
+                    summary.failed += 1;
+                }
+            }
+        }
+    }
+
+    fn purge_by_id(&mut self, pool: &DBPool, kind: TrashKind, id: Uuid) -> AppResult<()> {
+        match kind {
+            TrashKind::Project => ProjectRepository.purge(pool, id),
+            TrashKind::Budget => BudgetRepository.purge(pool, id),
+            TrashKind::Expense => {
+                let attachments = ExpenseRepository.purge(pool, id)?;
+                remove_stored_files(&attachments);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Stored files outlive their records when removing them fails, which is only worth a warning
+fn remove_stored_files(attachments: &[Attachment]) {
+    let storage = make_storage();
+    for attachment in attachments {
+        for key in [
+            Some(&attachment.storage_key),
+            attachment.thumbnail_key.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if let Err(err) = storage.delete(key.as_str()) {
+                log::warn!("Failed to remove stored file {}: {}", key, err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::helpers::testing::{create_budget, create_user, test_pool};
+    use crate::models::budget::{Budget, BudgetForm, OverspendPolicy};
+    use crate::models::expense::{ExpenseCascade, ExpenseForm};
+    use crate::models::project::ProjectForm;
+
+    fn create_project(pool: &DBPool, user_id: Uuid) -> Uuid {
+        ProjectRepository
+            .create(
+                pool,
+                user_id,
+                ProjectForm {
+                    name: String::from("Household"),
+                    description: String::new(),
+                },
+            )
+            .unwrap()
+            .project_id
+    }
+
+    fn spend(pool: &DBPool, user_id: Uuid, budget_id: Uuid, project_id: Uuid, amount: i64) -> Uuid {
+        let form = ExpenseForm {
+            project_id: project_id.to_string(),
+            amount,
+            narration: String::from("Groceries"),
+            spent_at: None,
+            category_id: None,
+        };
+        let (expense, _, _) = ExpenseRepository
+            .create(pool, user_id, "UTC", budget_id, form)
+            .unwrap();
+        expense.expense_id
+    }
+
+    fn is_live(pool: &DBPool, user_id: Uuid, expense_id: Uuid) -> bool {
+        ExpenseRepository
+            .find_owned_by_id(pool, expense_id, user_id)
+            .is_ok()
+    }
+
+    fn amount_used(pool: &DBPool, budget: &Budget) -> i64 {
+        BudgetRepository
+            .find_owned_by_id(pool, budget.budget_id, budget.user_id)
+            .unwrap()
+            .amount_used
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn cascaded_expenses_come_back_with_their_budget() {
+        let pool = test_pool();
+        let user_id = create_user(&pool).user_id;
+        let budget = create_budget(&pool, user_id, 1000, OverspendPolicy::Reject);
+        let project_id = create_project(&pool, user_id);
+        let cascaded = [
+            spend(&pool, user_id, budget.budget_id, project_id, 100),
+            spend(&pool, user_id, budget.budget_id, project_id, 200),
+        ];
+        // deleted on its own, so it stays in the trash
+        let deleted = spend(&pool, user_id, budget.budget_id, project_id, 400);
+        ExpenseRepository.delete(&pool, deleted, user_id).unwrap();
+
+        BudgetRepository
+            .delete(&pool, budget.budget_id, user_id, ExpenseCascade::Cascade)
+            .unwrap();
+        TrashService
+            .restore(&pool, user_id, TrashKind::Budget, budget.budget_id)
+            .unwrap();
+
+        assert!(cascaded.iter().all(|id| is_live(&pool, user_id, *id)));
+        assert!(!is_live(&pool, user_id, deleted));
+        assert_eq!(amount_used(&pool, &budget), 300);
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn cascaded_expenses_come_back_with_their_project() {
+        let pool = test_pool();
+        let user_id = create_user(&pool).user_id;
+        let budget = create_budget(&pool, user_id, 1000, OverspendPolicy::Reject);
+        let project_id = create_project(&pool, user_id);
+        let expense_id = spend(&pool, user_id, budget.budget_id, project_id, 600);
+
+        ProjectRepository
+            .delete(&pool, project_id, user_id, ExpenseCascade::Cascade)
+            .unwrap();
+        assert_eq!(amount_used(&pool, &budget), 0);
+
+        // the budget filled up meanwhile and rejects overspending
+        let other_project_id = create_project(&pool, user_id);
+        spend(&pool, user_id, budget.budget_id, other_project_id, 500);
+        let restored = TrashService.restore(&pool, user_id, TrashKind::Project, project_id);
+        assert!(matches!(restored, Err(AppError::BadRequest(_))));
+        ProjectRepository
+            .find_trashed_by_id(&pool, project_id, user_id)
+            .unwrap();
+
+        BudgetRepository
+            .update(
+                &pool,
+                budget.budget_id,
+                user_id,
+                BudgetForm {
+                    amount: 2000,
+                    month: budget.month,
+                    year: budget.year,
+                    comment: None,
+                    alert_thresholds: None,
+                    overspend_policy: Some(OverspendPolicy::Reject),
+                },
+            )
+            .unwrap();
+        TrashService
+            .restore(&pool, user_id, TrashKind::Project, project_id)
+            .unwrap();
+        assert!(is_live(&pool, user_id, expense_id));
+        assert_eq!(amount_used(&pool, &budget), 1100);
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn expenses_of_a_deleted_budget_wait_in_the_trash() {
+        let pool = test_pool();
+        let user_id = create_user(&pool).user_id;
+        let budget = create_budget(&pool, user_id, 1000, OverspendPolicy::Allow);
+        let project_id = create_project(&pool, user_id);
+        let expense_id = spend(&pool, user_id, budget.budget_id, project_id, 100);
+
+        ProjectRepository
+            .delete(&pool, project_id, user_id, ExpenseCascade::Cascade)
+            .unwrap();
+        BudgetRepository
+            .delete(&pool, budget.budget_id, user_id, ExpenseCascade::Refuse)
+            .unwrap();
+
+        TrashService
+            .restore(&pool, user_id, TrashKind::Project, project_id)
+            .unwrap();
+        assert!(!is_live(&pool, user_id, expense_id));
+
+        // it was not deleted along with the budget, so it is restored on its own
+        TrashService
+            .restore(&pool, user_id, TrashKind::Budget, budget.budget_id)
+            .unwrap();
+        assert!(!is_live(&pool, user_id, expense_id));
+        TrashService
+            .restore(&pool, user_id, TrashKind::Expense, expense_id)
+            .unwrap();
+        assert!(is_live(&pool, user_id, expense_id));
+        assert_eq!(amount_used(&pool, &budget), 100);
+    }
+
+    #[test]
+    #[ignore = "needs the migrated database configured in .env"]
+    fn only_the_owner_purges_their_trash() {
+        let pool = test_pool();
+        let owner_id = create_user(&pool).user_id;
+        let other_id = create_user(&pool).user_id;
+        let project_id = create_project(&pool, owner_id);
+        ProjectRepository
+            .delete(&pool, project_id, owner_id, ExpenseCascade::Refuse)
+            .unwrap();
+
+        let purged = TrashService.purge(&pool, other_id, TrashKind::Project, project_id);
+        assert!(matches!(purged, Err(AppError::EntityNotFound(_))));
+        ProjectRepository
+            .find_trashed_by_id(&pool, project_id, owner_id)
+            .unwrap();
+
+        TrashService
+            .purge(&pool, owner_id, TrashKind::Project, project_id)
+            .unwrap();
+        assert!(ProjectRepository
+            .find_trashed_by_id(&pool, project_id, owner_id)
+            .is_err());
+    }
+}